@@ -0,0 +1,8 @@
+ALTER TABLE integration_tokens ADD COLUMN IF NOT EXISTS previous_token_hash TEXT;
+ALTER TABLE integration_tokens ADD COLUMN IF NOT EXISTS previous_token_expires_at TIMESTAMPTZ;
+ALTER TABLE integration_tokens ADD COLUMN IF NOT EXISTS last_used_ip TEXT;
+ALTER TABLE integration_tokens ADD COLUMN IF NOT EXISTS last_used_user_agent TEXT;
+ALTER TABLE integration_tokens ADD COLUMN IF NOT EXISTS expiry_notified_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_integration_tokens_previous_hash
+  ON integration_tokens(previous_token_hash);
@@ -0,0 +1,22 @@
+CREATE TABLE IF NOT EXISTS passkey_credentials (
+  id BIGSERIAL PRIMARY KEY,
+  user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  credential_id TEXT NOT NULL UNIQUE,
+  public_key BYTEA NOT NULL,
+  algorithm BIGINT NOT NULL,
+  sign_count BIGINT NOT NULL DEFAULT 0,
+  label TEXT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  last_used_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_passkey_credentials_user_id ON passkey_credentials(user_id);
+
+CREATE TABLE IF NOT EXISTS webauthn_challenges (
+  id BIGSERIAL PRIMARY KEY,
+  challenge_hash TEXT NOT NULL UNIQUE,
+  ceremony TEXT NOT NULL,
+  user_id BIGINT REFERENCES users(id) ON DELETE CASCADE,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  expires_at TIMESTAMPTZ NOT NULL
+);
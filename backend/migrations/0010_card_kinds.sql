@@ -0,0 +1,5 @@
+-- Cards are no longer only coding problems: authored front/back and cloze
+-- cards share the table, scheduling and review history.
+ALTER TABLE problem_cards ADD COLUMN IF NOT EXISTS kind TEXT NOT NULL DEFAULT 'problem';
+ALTER TABLE problem_cards ADD COLUMN IF NOT EXISTS front TEXT;
+ALTER TABLE problem_cards ADD COLUMN IF NOT EXISTS back TEXT;
@@ -0,0 +1,2 @@
+-- Tags carried over from imported notes (markdown front matter, CSV columns).
+ALTER TABLE problem_cards ADD COLUMN IF NOT EXISTS tags TEXT[] NOT NULL DEFAULT ARRAY[]::TEXT[];
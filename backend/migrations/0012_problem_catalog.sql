@@ -0,0 +1,33 @@
+-- One row per problem across all users, keyed like the source registry.
+-- Ingestion adds the source, slug and canonical url; title and metadata only
+-- come from the seeded dataset. Cards link to it so metadata is stored once;
+-- their own title and url stay as a fallback and for authored cards.
+CREATE TABLE IF NOT EXISTS problems (
+  id BIGSERIAL PRIMARY KEY,
+  source TEXT NOT NULL,
+  slug TEXT NOT NULL,
+  title TEXT,
+  url TEXT NOT NULL,
+  difficulty TEXT,
+  tags TEXT[] NOT NULL DEFAULT ARRAY[]::TEXT[],
+  -- Other slugs the platform has used for the same problem.
+  aliases TEXT[] NOT NULL DEFAULT ARRAY[]::TEXT[],
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  UNIQUE (source, slug)
+);
+
+CREATE INDEX IF NOT EXISTS problems_aliases_idx ON problems USING GIN (aliases);
+
+ALTER TABLE problem_cards
+  ADD COLUMN IF NOT EXISTS problem_id BIGINT REFERENCES problems(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS problem_cards_problem_id_idx ON problem_cards (problem_id);
+
+UPDATE problem_cards c
+SET problem_id = p.id
+FROM problems p
+WHERE c.problem_id IS NULL
+  AND c.kind = 'problem'
+  AND p.source = c.source
+  AND p.slug = c.problem_slug;
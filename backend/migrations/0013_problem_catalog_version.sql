@@ -0,0 +1,3 @@
+-- Set on entries written by the bundled catalog dataset, so problems that
+-- are only known from ingestion can be reported.
+ALTER TABLE problems ADD COLUMN IF NOT EXISTS catalog_version BIGINT;
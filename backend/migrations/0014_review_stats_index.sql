@@ -0,0 +1,3 @@
+-- Stats walk each card's reviews in order to find the previous review.
+CREATE INDEX IF NOT EXISTS idx_review_events_user_card_reviewed
+  ON review_events(user_id, card_id, reviewed_at);
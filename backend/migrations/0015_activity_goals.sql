@@ -0,0 +1,31 @@
+-- Activity is bucketed into calendar days in each user's timezone.
+ALTER TABLE notification_preferences ADD COLUMN IF NOT EXISTS timezone TEXT NOT NULL DEFAULT 'UTC';
+
+-- Cards from before this column count as created at their first event or
+-- review, so existing decks do not show up as new today.
+ALTER TABLE problem_cards ADD COLUMN IF NOT EXISTS created_at TIMESTAMPTZ;
+UPDATE problem_cards c
+SET created_at = COALESCE(
+  (SELECT MIN(e.occurred_at) FROM problem_events e
+   WHERE e.user_id = c.user_id AND e.source = c.source AND e.problem_slug = c.problem_slug),
+  (SELECT MIN(r.reviewed_at) FROM review_events r WHERE r.card_id = c.id),
+  NOW()
+)
+WHERE c.created_at IS NULL;
+ALTER TABLE problem_cards ALTER COLUMN created_at SET DEFAULT NOW();
+ALTER TABLE problem_cards ALTER COLUMN created_at SET NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_problem_events_user_occurred
+  ON problem_events(user_id, occurred_at);
+CREATE INDEX IF NOT EXISTS idx_problem_cards_user_created
+  ON problem_cards(user_id, created_at);
+
+CREATE TABLE IF NOT EXISTS goals (
+  id BIGSERIAL PRIMARY KEY,
+  user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  metric TEXT NOT NULL,
+  period TEXT NOT NULL,
+  target BIGINT NOT NULL CHECK (target > 0),
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  UNIQUE (user_id, metric, period)
+);
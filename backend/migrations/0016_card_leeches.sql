@@ -0,0 +1,14 @@
+-- Every `again` grade counts as a lapse. Cards from before this column get
+-- their lapses from review history.
+ALTER TABLE problem_cards ADD COLUMN IF NOT EXISTS lapses INTEGER;
+UPDATE problem_cards c
+SET lapses = (SELECT COUNT(*) FROM review_events r WHERE r.card_id = c.id AND r.grade = 'again')
+WHERE c.lapses IS NULL;
+ALTER TABLE problem_cards ALTER COLUMN lapses SET DEFAULT 0;
+ALTER TABLE problem_cards ALTER COLUMN lapses SET NOT NULL;
+
+ALTER TABLE problem_cards ADD COLUMN IF NOT EXISTS leech_at TIMESTAMPTZ;
+ALTER TABLE problem_cards ADD COLUMN IF NOT EXISTS suspended_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_problem_cards_user_leech
+  ON problem_cards(user_id, leech_at) WHERE leech_at IS NOT NULL;
@@ -0,0 +1,109 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+
+use crate::models::GoalPeriod;
+
+pub const DEFAULT_TIMEZONE: &str = "UTC";
+
+/// Accepts IANA names such as `Europe/Berlin`.
+pub fn parse_timezone(name: &str) -> Option<Tz> {
+    name.trim().parse().ok()
+}
+
+pub fn local_date(at: DateTime<Utc>, timezone: Tz) -> NaiveDate {
+    at.with_timezone(&timezone).date_naive()
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq, Eq)]
+pub struct Streak {
+    /// Consecutive active days ending today, or yesterday while today is
+    /// still open.
+    pub current: u32,
+    pub longest: u32,
+    pub last_active_on: Option<NaiveDate>,
+}
+
+/// Computes streaks from active days sorted ascending; days after `today`
+/// are ignored.
+pub fn streaks(active_days: &[NaiveDate], today: NaiveDate) -> Streak {
+    let mut run = 0;
+    let mut longest = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for &day in active_days.iter().take_while(|day| **day <= today) {
+        run = match previous {
+            Some(last) if last == day => run,
+            Some(last) if day - last == Duration::days(1) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(day);
+    }
+    let current = match previous {
+        Some(last) if today - last <= Duration::days(1) => run,
+        _ => 0,
+    };
+    Streak {
+        current,
+        longest,
+        last_active_on: previous,
+    }
+}
+
+/// First day of the period containing `today`.
+pub fn period_start(period: GoalPeriod, today: NaiveDate) -> NaiveDate {
+    match period {
+        GoalPeriod::Day => today,
+        GoalPeriod::Week => {
+            today - Duration::days(i64::from(today.weekday().num_days_from_monday()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeZone, Utc};
+
+    use super::{Streak, local_date, parse_timezone, period_start, streaks};
+    use crate::models::GoalPeriod;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, d).expect("valid date")
+    }
+
+    #[test]
+    fn current_streak_survives_until_the_day_is_over() {
+        let active = [day(1), day(2), day(3), day(5), day(6), day(6), day(7)];
+        assert_eq!(
+            streaks(&active, day(7)),
+            Streak {
+                current: 3,
+                longest: 3,
+                last_active_on: Some(day(7)),
+            }
+        );
+        assert_eq!(streaks(&active, day(8)).current, 3);
+        assert_eq!(streaks(&active, day(9)).current, 0);
+        assert_eq!(streaks(&active, day(9)).longest, 3);
+        assert_eq!(streaks(&active, day(3)).current, 3);
+        assert_eq!(streaks(&[], day(3)), Streak::default());
+    }
+
+    #[test]
+    fn weeks_start_on_monday() {
+        // 2026-03-04 is a Wednesday.
+        assert_eq!(period_start(GoalPeriod::Week, day(4)), day(2));
+        assert_eq!(period_start(GoalPeriod::Week, day(2)), day(2));
+        assert_eq!(period_start(GoalPeriod::Week, day(8)), day(2));
+        assert_eq!(period_start(GoalPeriod::Day, day(4)), day(4));
+    }
+
+    #[test]
+    fn dates_follow_the_timezone() {
+        let tokyo = parse_timezone(" Asia/Tokyo ").expect("known zone");
+        let at = Utc.with_ymd_and_hms(2026, 3, 4, 20, 0, 0).unwrap();
+        assert_eq!(local_date(at, tokyo), day(5));
+        assert_eq!(local_date(at, chrono_tz::UTC), day(4));
+        assert!(parse_timezone("Mars/Olympus").is_none());
+    }
+}
@@ -4,55 +4,143 @@ use sea_orm::DatabaseConnection;
 use std::sync::Arc;
 
 use crate::{
-    app::state::AppState,
+    app::{
+        rate_limit::{RateLimitConfig, RateLimiter},
+        state::AppState,
+    },
+    catalog::{CatalogSeedReport, load_catalog},
+    models::LeechPolicy,
     ports::{
-        notification_sender::{NotificationSender, StoreNotificationSender},
+        notification_sender::{
+            FanoutNotificationSender, NotificationSender, StoreNotificationSender,
+        },
+        oidc::OidcConfig,
         schedule_provider::ScheduleProvider,
+        web_push::{PushEndpoints, VapidKeys, WebPushNotificationSender},
     },
     repositories::{
         postgres::PostgresRepository,
         traits::{
-            AuthRepository, EventRepository, IntegrationTokenRepository, ReviewRepository,
-            SettingsRepository,
+            ActivityRepository, ArchiveRepository, AuthRepository, CalendarFeedRepository,
+            CardRepository, EventRepository, IdempotencyRepository, IntegrationTokenRepository,
+            LeechRepository, ProblemRepository, PushSubscriptionRepository, ReviewRepository,
+            SettingsRepository, StatsRepository,
         },
     },
     services::{
+        activity::ActivityService,
+        archive::ArchiveService,
         auth::{AuthConfig, AuthService},
+        calendar::CalendarService,
+        cards::CardService,
         dashboard::DashboardService,
         event::EventService,
+        forecast::ForecastService,
+        idempotency::IdempotencyService,
         integrations::IntegrationsService,
+        leech::LeechService,
         notification::NotificationService,
+        problems::ProblemService,
+        push::PushService,
         review::ReviewService,
         settings::SettingsService,
+        stats::StatsService,
     },
+    sources::load_source_registry,
     srs::SrsSchedule,
 };
 
 pub fn build_state_with_schedule(schedule: SrsSchedule, db: DatabaseConnection) -> AppState {
-    let repo = Arc::new(PostgresRepository::new(db, schedule));
+    let repo = Arc::new(PostgresRepository::new(db, schedule.clone()));
     let auth_repo: Arc<dyn AuthRepository> = repo.clone();
     let event_repo: Arc<dyn EventRepository> = repo.clone();
     let review_repo: Arc<dyn ReviewRepository> = repo.clone();
+    let leech_repo: Arc<dyn LeechRepository> = repo.clone();
+    let card_repo: Arc<dyn CardRepository> = repo.clone();
+    let problem_repo: Arc<dyn ProblemRepository> = repo.clone();
+    let stats_repo: Arc<dyn StatsRepository> = repo.clone();
+    let activity_repo: Arc<dyn ActivityRepository> = repo.clone();
     let settings_repo: Arc<dyn SettingsRepository> = repo.clone();
     let integration_repo: Arc<dyn IntegrationTokenRepository> = repo.clone();
+    let push_repo: Arc<dyn PushSubscriptionRepository> = repo.clone();
+    let calendar_repo: Arc<dyn CalendarFeedRepository> = repo.clone();
+    let archive_repo: Arc<dyn ArchiveRepository> = repo.clone();
+    let idempotency_repo: Arc<dyn IdempotencyRepository> = repo.clone();
+    let vapid_keys = VapidKeys::from_env();
+    let push_endpoints = PushEndpoints::from_env();
     let notification_sender: Arc<dyn NotificationSender> =
-        Arc::new(StoreNotificationSender::new(settings_repo.clone()));
+        Arc::new(FanoutNotificationSender::new(vec![
+            Arc::new(StoreNotificationSender::new(settings_repo.clone())),
+            Arc::new(WebPushNotificationSender::new(
+                push_repo.clone(),
+                vapid_keys.clone(),
+                push_endpoints.clone(),
+            )),
+        ]));
 
-    let auth_service = AuthService::new(auth_repo, AuthConfig::from_env());
-    let event_service = EventService::new(event_repo);
-    let review_service = ReviewService::new(review_repo);
-    let dashboard_service = DashboardService::new(review_service.clone(), event_service.clone());
+    let mut auth_service = AuthService::new(
+        auth_repo,
+        AuthConfig::from_env(),
+        notification_sender.clone(),
+    );
+    if let Some(oidc_config) = OidcConfig::from_env() {
+        auth_service = auth_service.with_oidc(oidc_config);
+    }
+    let sources = Arc::new(
+        load_source_registry(env::var("SOURCES_CONFIG_PATH").ok().as_deref())
+            .unwrap_or_else(|err| panic!("SOURCES_CONFIG_PATH: {err}")),
+    );
+    let event_service = EventService::new(event_repo, sources.clone());
+    let leech_service = LeechService::new(
+        leech_repo,
+        LeechPolicy::from_env(),
+        notification_sender.clone(),
+    );
+    let review_service = ReviewService::new(review_repo, leech_service.clone());
+    let card_service = CardService::new(card_repo);
+    let problem_service = ProblemService::new(problem_repo, sources);
+    let stats_service = StatsService::new(stats_repo);
+    let forecast_service = ForecastService::new(review_service.clone(), schedule.clone());
     let settings_service = SettingsService::new(settings_repo.clone());
+    let activity_service = ActivityService::new(activity_repo, settings_service.clone());
+    let dashboard_service = DashboardService::new(
+        review_service.clone(),
+        event_service.clone(),
+        activity_service.clone(),
+    );
     let integrations_service = IntegrationsService::new(event_service.clone(), integration_repo);
     let notification_service = NotificationService::new(
         settings_service.clone(),
         review_service.clone(),
+        activity_service.clone(),
+        leech_service.clone(),
+        integrations_service.clone(),
         notification_sender,
     );
+    let push_service = PushService::new(push_repo, vapid_keys, push_endpoints);
+    let calendar_service = CalendarService::new(
+        review_service.clone(),
+        activity_service.clone(),
+        calendar_repo,
+    );
+    let archive_service = ArchiveService::new(
+        archive_repo,
+        review_service.clone(),
+        event_service.clone(),
+        settings_service.clone(),
+        activity_service.clone(),
+        integrations_service.clone(),
+        schedule,
+    );
+    let idempotency_service = IdempotencyService::new(idempotency_repo);
     let cookie_secure = env::var("COOKIE_SECURE")
         .ok()
         .map(|value| matches!(value.as_str(), "1" | "true" | "TRUE"))
         .unwrap_or(false);
+    let trust_proxy_headers = env::var("TRUST_PROXY_HEADERS")
+        .ok()
+        .map(|value| matches!(value.as_str(), "1" | "true" | "TRUE"))
+        .unwrap_or(false);
     let allowed_origins = env::var("ALLOWED_ORIGINS")
         .ok()
         .map(|raw| {
@@ -67,15 +155,27 @@ pub fn build_state_with_schedule(schedule: SrsSchedule, db: DatabaseConnection)
 
     AppState {
         auth_service,
+        archive_service,
         event_service,
         review_service,
+        leech_service,
+        card_service,
+        problem_service,
         dashboard_service,
+        stats_service,
+        forecast_service,
+        activity_service,
         settings_service,
         integrations_service,
         notification_service,
+        push_service,
+        calendar_service,
+        idempotency_service,
+        rate_limiter: RateLimiter::in_memory(RateLimitConfig::from_env()),
         security: crate::app::state::SecurityConfig {
             cookie_secure,
             allowed_origins,
+            trust_proxy_headers,
         },
     }
 }
@@ -86,3 +186,15 @@ pub fn build_state_from_provider(
 ) -> AppState {
     build_state_with_schedule(provider.load_schedule(), db)
 }
+
+/// Seeds the problem catalog from `PROBLEM_CATALOG_PATH`, or the bundled
+/// `config/problem_catalog.yaml`.
+pub async fn seed_problem_catalog(state: &AppState) -> Result<CatalogSeedReport, String> {
+    let catalog = load_catalog(env::var("PROBLEM_CATALOG_PATH").ok().as_deref())
+        .map_err(|err| err.to_string())?;
+    state
+        .problem_service
+        .seed_catalog(&catalog)
+        .await
+        .map_err(|err| err.to_string())
+}
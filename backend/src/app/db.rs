@@ -3,7 +3,7 @@ use std::{env, fs, path::Path};
 use sea_orm::{ConnectionTrait, Database, DatabaseConnection, DbBackend, Statement};
 use thiserror::Error;
 
-const DEFAULT_SCHEMA_PATH: &str = "migrations/0001_init.sql";
+const DEFAULT_MIGRATIONS_DIR: &str = "migrations";
 
 #[derive(Debug, Error)]
 pub enum DbBootstrapError {
@@ -27,11 +27,33 @@ pub async fn connect_and_verify(
     database_url: &str,
 ) -> Result<DatabaseConnection, DbBootstrapError> {
     let db = Database::connect(database_url).await?;
-    ensure_schema(&db, DEFAULT_SCHEMA_PATH).await?;
+    ensure_migrations(&db, DEFAULT_MIGRATIONS_DIR).await?;
     verify_connection(&db).await?;
     Ok(db)
 }
 
+pub async fn ensure_migrations(
+    db: &DatabaseConnection,
+    migrations_dir: impl AsRef<Path>,
+) -> Result<(), DbBootstrapError> {
+    let migrations_dir = migrations_dir.as_ref();
+    let read_error = |source| DbBootstrapError::ReadSchema {
+        path: migrations_dir.display().to_string(),
+        source,
+    };
+    let mut schema_paths = fs::read_dir(migrations_dir)
+        .map_err(read_error)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(read_error)?;
+    schema_paths.retain(|path| path.extension().is_some_and(|ext| ext == "sql"));
+    schema_paths.sort();
+    for schema_path in schema_paths {
+        ensure_schema(db, schema_path).await?;
+    }
+    Ok(())
+}
+
 pub async fn ensure_schema(
     db: &DatabaseConnection,
     schema_path: impl AsRef<Path>,
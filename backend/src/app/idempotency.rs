@@ -0,0 +1,218 @@
+use axum::{
+    body::{Body, to_bytes},
+    extract::{MatchedPath, Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::{
+    app::{routes::body_limit, state::AppState},
+    controllers::{api_key, auth_cookie},
+    errors::AppError,
+    models::StoredResponse,
+    services::idempotency::IdempotencyDecision,
+};
+
+pub const IDEMPOTENCY_KEY_HEADER: HeaderName = HeaderName::from_static("idempotency-key");
+pub const IDEMPOTENT_REPLAYED_HEADER: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+const MAX_KEY_LEN: usize = 255;
+
+/// Makes mutating requests that carry an `Idempotency-Key` safe to retry: the
+/// first response is stored per user and replayed for later requests with the
+/// same key and body, while reusing the key for a different request is a 409.
+/// Requests without the header, or from callers we cannot identify, pass
+/// straight through to the handler, which does its own authentication.
+pub async fn enforce_idempotency_keys(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+    ) {
+        return next.run(request).await;
+    }
+    let Some(raw_key) = request.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return next.run(request).await;
+    };
+    let Some(key) = raw_key
+        .to_str()
+        .ok()
+        .filter(|key| is_valid_key(key))
+        .map(ToOwned::to_owned)
+    else {
+        return AppError::InvalidInput(format!(
+            "Idempotency-Key must be 1-{MAX_KEY_LEN} visible ASCII characters"
+        ))
+        .to_http()
+        .into_response();
+    };
+    let Some(user_id) = caller_id(&state, request.headers()).await else {
+        return next.run(request).await;
+    };
+
+    let limit = body_limit(
+        request
+            .extensions()
+            .get::<MatchedPath>()
+            .map_or(request.uri().path(), MatchedPath::as_str),
+    );
+    let (parts, body) = request.into_parts();
+    let Ok(bytes) = to_bytes(body, limit).await else {
+        return AppError::InvalidInput("request body too large".to_owned())
+            .to_http()
+            .into_response();
+    };
+    let request_hash = request_hash(
+        &parts.method,
+        parts
+            .uri
+            .path_and_query()
+            .map_or(parts.uri.path(), |path| path.as_str()),
+        &bytes,
+    );
+
+    let service = &state.idempotency_service;
+    match service
+        .begin(user_id, &key, &request_hash, chrono::Utc::now())
+        .await
+    {
+        Ok(IdempotencyDecision::Proceed) => {}
+        Ok(IdempotencyDecision::Replay(stored)) => return replay(stored),
+        Err(err @ AppError::IdempotencyRequestInProgress) => {
+            let mut response = err.to_http().into_response();
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(1));
+            return response;
+        }
+        Err(err) => return err.to_http().into_response(),
+    }
+
+    let response = next
+        .run(Request::from_parts(parts, Body::from(bytes)))
+        .await;
+    let status = response.status();
+    // Failures that say nothing about the request itself leave the key free
+    // so the client's retry actually runs.
+    if status.is_server_error()
+        || matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)
+    {
+        if let Err(err) = service.release(user_id, &key).await {
+            warn!(user_id, error = %err, "idempotency_key_release_failed");
+        }
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(err) => {
+            warn!(user_id, error = %err, "idempotent_response_read_failed");
+            let _ = service.release(user_id, &key).await;
+            return AppError::Internal("failed to read response body".to_owned())
+                .to_http()
+                .into_response();
+        }
+    };
+    let stored = StoredResponse {
+        status: parts.status.as_u16(),
+        content_type: parts
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(ToOwned::to_owned),
+        body: body.to_vec(),
+    };
+    if let Err(err) = service.complete(user_id, &key, stored).await {
+        warn!(user_id, error = %err, "idempotent_response_store_failed");
+    }
+    Response::from_parts(parts, Body::from(body))
+}
+
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty() && key.len() <= MAX_KEY_LEN && key.bytes().all(|byte| byte.is_ascii_graphic())
+}
+
+/// Identifies the caller the same way the extractors do, but without scope or
+/// origin checks: a rejected request is answered by the handler and its key
+/// released above.
+async fn caller_id(state: &AppState, headers: &HeaderMap) -> Option<i64> {
+    if let Some(token) = api_key(headers) {
+        return state
+            .integrations_service
+            .owner_of_api_key(token)
+            .await
+            .ok()
+            .flatten()
+            .map(|user| user.id);
+    }
+    state
+        .auth_service
+        .user_from_jwt_cookie(auth_cookie(headers))
+        .await
+        .ok()
+        .map(|(user, _)| user.id)
+}
+
+fn request_hash(method: &Method, path: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_str().as_bytes());
+    hasher.update(b"\n");
+    hasher.update(path.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(body);
+    format!("{:x}", hasher.finalize())
+}
+
+fn replay(stored: StoredResponse) -> Response {
+    let status = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK);
+    let mut response = (status, stored.body).into_response();
+    let headers = response.headers_mut();
+    headers.remove(header::CONTENT_TYPE);
+    if let Some(content_type) = stored
+        .content_type
+        .and_then(|value| HeaderValue::from_str(&value).ok())
+    {
+        headers.insert(header::CONTENT_TYPE, content_type);
+    }
+    headers.insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Method;
+
+    use super::{is_valid_key, request_hash};
+
+    #[test]
+    fn keys_must_be_visible_ascii() {
+        assert!(is_valid_key("3f1c9a2e-retry"));
+        assert!(!is_valid_key(""));
+        assert!(!is_valid_key("has space"));
+        assert!(!is_valid_key(&"k".repeat(256)));
+    }
+
+    #[test]
+    fn hash_covers_method_path_and_body() {
+        let base = request_hash(&Method::POST, "/reviews/1/grade", b"{\"grade\":\"good\"}");
+        assert_eq!(
+            base,
+            request_hash(&Method::POST, "/reviews/1/grade", b"{\"grade\":\"good\"}")
+        );
+        assert_ne!(
+            base,
+            request_hash(&Method::POST, "/reviews/2/grade", b"{\"grade\":\"good\"}")
+        );
+        assert_ne!(
+            base,
+            request_hash(&Method::POST, "/reviews/1/grade", b"{\"grade\":\"hard\"}")
+        );
+    }
+}
@@ -1,4 +1,6 @@
 pub mod bootstrap;
 pub mod db;
+pub mod idempotency;
+pub mod rate_limit;
 pub mod routes;
 pub mod state;
@@ -0,0 +1,221 @@
+use std::{env, sync::Arc, time::Duration};
+
+use axum::{
+    body::{Body, to_bytes},
+    extract::{Request, State},
+    http::{HeaderValue, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::warn;
+
+use crate::{
+    app::state::AppState,
+    controllers::{api_key, client_ip},
+    dto::auth::RequestMagicLink,
+    errors::AppError,
+    models::hash_token,
+    ports::rate_limit_store::{
+        InMemoryRateLimitStore, RateLimitDecision, RateLimitRule, RateLimitStore,
+    },
+};
+
+/// Magic-link request bodies are tiny; anything larger is rejected before parsing.
+const MAX_AUTH_BODY_BYTES: usize = 16 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    /// Auth endpoints, keyed by client IP.
+    pub ip: Option<RateLimitRule>,
+    /// Magic-link requests, keyed by the lowercased email address.
+    pub email: Option<RateLimitRule>,
+    /// Requests authenticated with `X-API-Key`, keyed by token hash.
+    pub api_token: Option<RateLimitRule>,
+}
+
+impl RateLimitConfig {
+    /// Reads `RATE_LIMIT_IP`, `RATE_LIMIT_EMAIL` and `RATE_LIMIT_API_TOKEN` as
+    /// `<requests>/<window seconds>`; `off` disables a bucket.
+    pub fn from_env() -> Self {
+        Self {
+            ip: rule_from_env("RATE_LIMIT_IP", 20, 60),
+            email: rule_from_env("RATE_LIMIT_EMAIL", 5, 15 * 60),
+            api_token: rule_from_env("RATE_LIMIT_API_TOKEN", 120, 60),
+        }
+    }
+}
+
+fn rule_from_env(
+    name: &str,
+    default_limit: u32,
+    default_window_secs: u64,
+) -> Option<RateLimitRule> {
+    let default = Some(RateLimitRule {
+        limit: default_limit,
+        window: Duration::from_secs(default_window_secs),
+    });
+    let Ok(raw) = env::var(name) else {
+        return default;
+    };
+    if raw.trim().eq_ignore_ascii_case("off") {
+        return None;
+    }
+    match parse_rule(&raw) {
+        Some(rule) => Some(rule),
+        None => {
+            warn!(variable = name, value = %raw, "rate_limit_config_invalid");
+            default
+        }
+    }
+}
+
+fn parse_rule(raw: &str) -> Option<RateLimitRule> {
+    let (limit, window) = raw.trim().split_once('/')?;
+    let limit = limit
+        .trim()
+        .parse::<u32>()
+        .ok()
+        .filter(|limit| *limit > 0)?;
+    let window_secs = window.trim().parse::<u64>().ok().filter(|secs| *secs > 0)?;
+    Some(RateLimitRule {
+        limit,
+        window: Duration::from_secs(window_secs),
+    })
+}
+
+#[derive(Clone)]
+pub struct RateLimiter {
+    store: Arc<dyn RateLimitStore>,
+    config: RateLimitConfig,
+}
+
+impl RateLimiter {
+    pub fn new(store: Arc<dyn RateLimitStore>, config: RateLimitConfig) -> Self {
+        Self { store, config }
+    }
+
+    pub fn in_memory(config: RateLimitConfig) -> Self {
+        Self::new(Arc::new(InMemoryRateLimitStore::new()), config)
+    }
+
+    /// Consumes one request from `bucket:key`. Store failures fail open so a
+    /// flaky shared backend cannot take the API down.
+    async fn check(
+        &self,
+        bucket: &'static str,
+        key: &str,
+        rule: Option<RateLimitRule>,
+    ) -> Result<(), AppError> {
+        let Some(rule) = rule else {
+            return Ok(());
+        };
+        match self.store.hit(&format!("{bucket}:{key}"), rule).await {
+            Ok(RateLimitDecision::Allowed) => Ok(()),
+            Ok(RateLimitDecision::Limited { retry_after }) => {
+                let retry_after_secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+                warn!(
+                    bucket,
+                    limit = rule.limit,
+                    window_secs = rule.window.as_secs(),
+                    retry_after_secs,
+                    "rate_limit_exceeded"
+                );
+                Err(AppError::RateLimited(retry_after_secs))
+            }
+            Err(err) => {
+                warn!(bucket, error = %err, "rate_limit_store_failed");
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Guards the magic-link endpoints with the per-IP bucket and, for link
+/// requests, the per-email bucket.
+pub async fn limit_auth_requests(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let limiter = &state.rate_limiter;
+    let ip = client_ip(
+        request.headers(),
+        request.extensions(),
+        state.security.trust_proxy_headers,
+    );
+    if let Some(ip) = ip
+        && let Err(err) = limiter.check("ip", &ip, limiter.config.ip).await
+    {
+        return rate_limited_response(err);
+    }
+
+    if limiter.config.email.is_none() || !request.uri().path().ends_with("/request-magic-link") {
+        return next.run(request).await;
+    }
+    let (parts, body) = request.into_parts();
+    let Ok(bytes) = to_bytes(body, MAX_AUTH_BODY_BYTES).await else {
+        return AppError::InvalidInput("request body too large".to_owned())
+            .to_http()
+            .into_response();
+    };
+    if let Ok(payload) = serde_json::from_slice::<RequestMagicLink>(&bytes) {
+        let email = payload.email.trim().to_lowercase();
+        if let Err(err) = limiter
+            .check("email", &hash_token(&email), limiter.config.email)
+            .await
+        {
+            return rate_limited_response(err);
+        }
+    }
+    next.run(Request::from_parts(parts, Body::from(bytes)))
+        .await
+}
+
+/// Guards every request that presents an `X-API-Key` with the per-token bucket.
+pub async fn limit_api_token_requests(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if let Some(token) = api_key(request.headers()) {
+        let limiter = &state.rate_limiter;
+        if let Err(err) = limiter
+            .check(
+                "api_token",
+                &hash_token(token.trim()),
+                limiter.config.api_token,
+            )
+            .await
+        {
+            return rate_limited_response(err);
+        }
+    }
+    next.run(request).await
+}
+
+fn rate_limited_response(err: AppError) -> Response {
+    let mut response = err.to_http().into_response();
+    if let AppError::RateLimited(retry_after_secs) = err {
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::parse_rule;
+
+    #[test]
+    fn parses_requests_per_window() {
+        let rule = parse_rule(" 10 / 60 ").expect("valid rule");
+        assert_eq!(rule.limit, 10);
+        assert_eq!(rule.window, Duration::from_secs(60));
+        assert!(parse_rule("10").is_none());
+        assert!(parse_rule("0/60").is_none());
+        assert!(parse_rule("10/0").is_none());
+    }
+}
@@ -2,9 +2,10 @@ use std::time::Duration;
 
 use axum::{
     Router,
-    extract::MatchedPath,
+    extract::{DefaultBodyLimit, MatchedPath},
     http::{HeaderValue, Method, Request, header},
-    routing::{get, post},
+    middleware,
+    routing::{MethodRouter, get, post},
 };
 use tower_http::{
     cors::CorsLayer,
@@ -14,17 +15,71 @@ use tower_http::{
 use tracing::{Span, field};
 
 use crate::{
-    app::state::AppState,
+    app::{
+        idempotency::{
+            IDEMPOTENCY_KEY_HEADER, IDEMPOTENT_REPLAYED_HEADER, enforce_idempotency_keys,
+        },
+        rate_limit::{limit_api_token_requests, limit_auth_requests},
+        state::AppState,
+    },
     controllers::{
-        auth::{logout, me, request_magic_link, verify_magic_link},
+        activity::{activity_heatmap, delete_goal, goals, save_goal},
+        archive::{
+            export_anki, export_archive, import_anki, import_archive, import_leetcode_history,
+        },
+        auth::{
+            delete_account, delete_passkey, jwks, logout, me, oidc_callback, oidc_login,
+            passkey_login, passkey_login_options, passkey_registration_options, passkeys,
+            register_passkey, request_account_deletion, request_magic_link, revoke_other_sessions,
+            revoke_session, sessions, verify_magic_link,
+        },
+        calendar::{calendar_feeds, create_calendar_feed, review_calendar, revoke_calendar_feed},
+        cards::{
+            create_card, delete_card, get_card, import_csv_cards, import_markdown_cards,
+            update_card,
+        },
         dashboard::dashboard,
-        events::ingest_problem_event,
-        integrations::{create_integration_token, integrations, revoke_integration_token},
-        reviews::{due_reviews, grade_review, history},
+        events::{ingest_problem_event, ingest_problem_event_batch},
+        forecast::forecast,
+        integrations::{
+            create_integration_token, integrations, revoke_integration_token,
+            rotate_integration_token,
+        },
+        leeches::{clear_leech, leeches},
+        problems::get_problem,
+        push::{push_subscriptions, subscribe_push, unsubscribe_push, vapid_public_key},
+        reviews::{cards, due_reviews, grade_review, history},
         settings::{get_settings, save_settings},
+        stats::stats,
     },
+    services::calendar::CALENDAR_FEED_PATH,
 };
 
+/// Archives carry a user's whole history, well past axum's 2 MB default.
+pub(crate) const IMPORT_BODY_LIMIT_BYTES: usize = 50 * 1024 * 1024;
+/// axum's own default, kept by every route that isn't an import.
+const DEFAULT_BODY_LIMIT_BYTES: usize = 2 * 1024 * 1024;
+type ImportRoute = (&'static str, fn() -> MethodRouter<AppState>);
+/// The routes given `IMPORT_BODY_LIMIT_BYTES`. The router and `body_limit`
+/// both read this table, so the two can't disagree.
+const IMPORT_ROUTES: [ImportRoute; 5] = [
+    ("/me/import", || post(import_archive)),
+    ("/me/import/anki", || post(import_anki)),
+    ("/me/import/leetcode", || post(import_leetcode_history)),
+    ("/cards/import/markdown", || post(import_markdown_cards)),
+    ("/cards/import/csv", || post(import_csv_cards)),
+];
+
+/// The largest body `route` accepts, for middleware that reads the body
+/// before the handler does.
+pub(crate) fn body_limit(route: &str) -> usize {
+    if IMPORT_ROUTES.iter().any(|(path, _)| *path == route) {
+        IMPORT_BODY_LIMIT_BYTES
+    } else {
+        DEFAULT_BODY_LIMIT_BYTES
+    }
+}
+
 pub fn app_router(state: AppState) -> Router {
     let allowed_origins = state
         .security
@@ -32,18 +87,86 @@ pub fn app_router(state: AppState) -> Router {
         .iter()
         .filter_map(|origin| HeaderValue::from_str(origin).ok())
         .collect::<Vec<_>>();
+    let auth_rate_limit = middleware::from_fn_with_state(state.clone(), limit_auth_requests);
+    let api_token_rate_limit =
+        middleware::from_fn_with_state(state.clone(), limit_api_token_requests);
+    let idempotency = middleware::from_fn_with_state(state.clone(), enforce_idempotency_keys);
+    let import_routes = IMPORT_ROUTES
+        .into_iter()
+        .fold(Router::new(), |router, (path, route)| {
+            router.route(
+                path,
+                route().layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT_BYTES)),
+            )
+        });
 
     Router::new()
         .route("/health", get(|| async { "ok" }))
-        .route("/auth/request-magic-link", post(request_magic_link))
-        .route("/auth/verify-magic-link", post(verify_magic_link))
+        .route("/.well-known/jwks.json", get(jwks))
+        .route(
+            "/auth/request-magic-link",
+            post(request_magic_link).layer(auth_rate_limit.clone()),
+        )
+        .route(
+            "/auth/verify-magic-link",
+            post(verify_magic_link).layer(auth_rate_limit.clone()),
+        )
+        .route(
+            "/auth/oidc/login",
+            get(oidc_login).layer(auth_rate_limit.clone()),
+        )
+        .route(
+            "/auth/oidc/callback",
+            get(oidc_callback).layer(auth_rate_limit.clone()),
+        )
+        .route(
+            "/auth/passkeys/login/options",
+            post(passkey_login_options).layer(auth_rate_limit.clone()),
+        )
+        .route(
+            "/auth/passkeys/login",
+            post(passkey_login).layer(auth_rate_limit),
+        )
+        .route("/auth/passkeys", get(passkeys))
+        .route(
+            "/auth/passkeys/register/options",
+            post(passkey_registration_options),
+        )
+        .route("/auth/passkeys/register", post(register_passkey))
+        .route(
+            "/auth/passkeys/{passkey_id}",
+            axum::routing::delete(delete_passkey),
+        )
         .route("/auth/logout", post(logout))
-        .route("/me", get(me))
+        .route("/auth/sessions", get(sessions))
+        .route(
+            "/auth/sessions/{session_id}",
+            axum::routing::delete(revoke_session),
+        )
+        .route("/auth/sessions/revoke-others", post(revoke_other_sessions))
+        .route("/me", get(me).delete(delete_account))
+        .route("/me/deletion-request", post(request_account_deletion))
+        .route("/me/export", get(export_archive))
+        .route("/me/export/anki", get(export_anki))
         .route("/events/problem-status", post(ingest_problem_event))
+        .route("/events/batch", post(ingest_problem_event_batch))
         .route("/reviews/due", get(due_reviews))
         .route("/reviews/{card_id}/grade", post(grade_review))
         .route("/history", get(history))
+        .route("/cards", get(cards).post(create_card))
+        .route("/cards/leeches", get(leeches))
+        .route("/cards/{card_id}/leech", axum::routing::delete(clear_leech))
+        .route(
+            "/cards/{card_id}",
+            get(get_card).patch(update_card).delete(delete_card),
+        )
+        .route("/problems/{source}/{slug}", get(get_problem))
         .route("/dashboard", get(dashboard))
+        .route("/stats", get(stats))
+        .route("/forecast", get(forecast))
+        .route("/activity/heatmap", get(activity_heatmap))
+        .route("/goals", get(goals).post(save_goal))
+        .route("/goals/{goal_id}", axum::routing::delete(delete_goal))
         .route("/settings", get(get_settings).post(save_settings))
         .route("/integrations", get(integrations))
         .route("/integrations/tokens", post(create_integration_token))
@@ -51,6 +174,31 @@ pub fn app_router(state: AppState) -> Router {
             "/integrations/tokens/{token_id}",
             axum::routing::delete(revoke_integration_token),
         )
+        .route(
+            "/integrations/tokens/{token_id}/rotate",
+            post(rotate_integration_token),
+        )
+        .route("/push/vapid-public-key", get(vapid_public_key))
+        .route(
+            "/push/subscriptions",
+            get(push_subscriptions).post(subscribe_push),
+        )
+        .route(
+            "/push/subscriptions/{subscription_id}",
+            axum::routing::delete(unsubscribe_push),
+        )
+        .route(
+            "/calendar/feeds",
+            get(calendar_feeds).post(create_calendar_feed),
+        )
+        .route(
+            "/calendar/feeds/{feed_id}",
+            axum::routing::delete(revoke_calendar_feed),
+        )
+        .route(CALENDAR_FEED_PATH, get(review_calendar))
+        .merge(import_routes)
+        .route_layer(idempotency)
+        .route_layer(api_token_rate_limit)
         .with_state(state)
         .layer(PropagateRequestIdLayer::x_request_id())
         .layer(SetRequestIdLayer::x_request_id(GenerateRequestId))
@@ -58,13 +206,21 @@ pub fn app_router(state: AppState) -> Router {
             CorsLayer::new()
                 .allow_origin(allowed_origins)
                 .allow_credentials(true)
-                .allow_methods([Method::GET, Method::POST, Method::DELETE, Method::OPTIONS])
+                .allow_methods([
+                    Method::GET,
+                    Method::POST,
+                    Method::PATCH,
+                    Method::DELETE,
+                    Method::OPTIONS,
+                ])
                 .allow_headers([
                     header::CONTENT_TYPE,
                     header::AUTHORIZATION,
                     header::COOKIE,
                     header::HeaderName::from_static("x-api-key"),
-                ]),
+                    IDEMPOTENCY_KEY_HEADER,
+                ])
+                .expose_headers([header::RETRY_AFTER, IDEMPOTENT_REPLAYED_HEADER]),
         )
         .layer(
             TraceLayer::new_for_http()
@@ -1,25 +1,42 @@
 use std::collections::HashSet;
 
+use crate::app::rate_limit::RateLimiter;
+
 use crate::services::{
-    auth::AuthService, dashboard::DashboardService, event::EventService,
-    integrations::IntegrationsService, notification::NotificationService, review::ReviewService,
-    settings::SettingsService,
+    activity::ActivityService, archive::ArchiveService, auth::AuthService,
+    calendar::CalendarService, cards::CardService, dashboard::DashboardService,
+    event::EventService, forecast::ForecastService, idempotency::IdempotencyService,
+    integrations::IntegrationsService, leech::LeechService, notification::NotificationService,
+    problems::ProblemService, push::PushService, review::ReviewService, settings::SettingsService,
+    stats::StatsService,
 };
 
 #[derive(Clone)]
 pub struct SecurityConfig {
     pub cookie_secure: bool,
     pub allowed_origins: HashSet<String>,
+    pub trust_proxy_headers: bool,
 }
 
 #[derive(Clone)]
 pub struct AppState {
     pub auth_service: AuthService,
+    pub archive_service: ArchiveService,
     pub event_service: EventService,
     pub review_service: ReviewService,
+    pub leech_service: LeechService,
+    pub card_service: CardService,
+    pub problem_service: ProblemService,
     pub dashboard_service: DashboardService,
+    pub stats_service: StatsService,
+    pub forecast_service: ForecastService,
+    pub activity_service: ActivityService,
     pub settings_service: SettingsService,
     pub integrations_service: IntegrationsService,
     pub notification_service: NotificationService,
+    pub push_service: PushService,
+    pub calendar_service: CalendarService,
+    pub idempotency_service: IdempotencyService,
+    pub rate_limiter: RateLimiter,
     pub security: SecurityConfig,
 }
@@ -0,0 +1,221 @@
+use std::{collections::HashSet, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    models::{CatalogProblemInput, CatalogSeedSummary, UncataloguedProblem},
+    sources::SourceRegistry,
+};
+
+pub const DEFAULT_CATALOG_PATH: &str = "config/problem_catalog.yaml";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CatalogEntry {
+    pub source: String,
+    pub slug: String,
+    pub title: String,
+    #[serde(default)]
+    pub difficulty: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CatalogFile {
+    pub version: i64,
+    pub problems: Vec<CatalogEntry>,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CatalogError {
+    #[error("cannot read {path}: {message}")]
+    Read { path: String, message: String },
+    #[error("invalid catalog {path}: {message}")]
+    Parse { path: String, message: String },
+}
+
+/// What a seed run did, plus the problems it has no metadata for.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct CatalogSeedReport {
+    pub version: i64,
+    #[serde(flatten)]
+    pub summary: CatalogSeedSummary,
+    /// Dataset entries that were not written, with the reason.
+    pub rejected: Vec<String>,
+    pub uncatalogued: Vec<UncataloguedProblem>,
+}
+
+impl CatalogFile {
+    /// Normalizes every entry against the source registry, so slugs and
+    /// URLs match what ingestion stores. Entries with an unknown source, a
+    /// bad slug or alias, or a slug listed earlier are returned as rejections.
+    pub fn validate(&self, registry: &SourceRegistry) -> (Vec<CatalogProblemInput>, Vec<String>) {
+        let mut problems = Vec::with_capacity(self.problems.len());
+        let mut rejected = Vec::new();
+        let mut seen = HashSet::new();
+        for entry in &self.problems {
+            let label = format!("{}/{}", entry.source.trim(), entry.slug.trim());
+            match validate_entry(entry, registry) {
+                Ok(problem) if !seen.insert((problem.source.clone(), problem.slug.clone())) => {
+                    rejected.push(format!("{label}: listed more than once"));
+                }
+                Ok(problem) => problems.push(problem),
+                Err(reason) => rejected.push(format!("{label}: {reason}")),
+            }
+        }
+        (problems, rejected)
+    }
+}
+
+fn validate_entry(
+    entry: &CatalogEntry,
+    registry: &SourceRegistry,
+) -> Result<CatalogProblemInput, String> {
+    let normalized = registry
+        .normalize(&entry.source, &entry.slug, "")
+        .map_err(|err| err.to_string())?;
+    let title = entry.title.trim();
+    if title.is_empty() {
+        return Err("title is empty".to_owned());
+    }
+    let mut aliases = Vec::new();
+    for alias in &entry.aliases {
+        let alias = registry
+            .normalize(&normalized.source, alias, "")
+            .map_err(|err| err.to_string())?
+            .problem_slug;
+        if alias != normalized.problem_slug && !aliases.contains(&alias) {
+            aliases.push(alias);
+        }
+    }
+    let mut tags: Vec<String> = Vec::new();
+    for tag in &entry.tags {
+        let tag = tag.trim().to_lowercase().replace(char::is_whitespace, "-");
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    Ok(CatalogProblemInput {
+        source: normalized.source,
+        slug: normalized.problem_slug,
+        title: title.to_owned(),
+        url: normalized.url,
+        difficulty: entry
+            .difficulty
+            .as_deref()
+            .map(str::trim)
+            .filter(|difficulty| !difficulty.is_empty())
+            .map(ToOwned::to_owned),
+        tags,
+        aliases,
+    })
+}
+
+/// Reads the dataset; `.json` files are parsed as JSON, anything else as YAML.
+pub fn load_catalog(config_path: Option<&str>) -> Result<CatalogFile, CatalogError> {
+    let path = config_path.unwrap_or(DEFAULT_CATALOG_PATH);
+    let contents = fs::read_to_string(path).map_err(|err| CatalogError::Read {
+        path: path.to_owned(),
+        message: err.to_string(),
+    })?;
+    let parsed = if Path::new(path)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+    {
+        serde_json::from_str(&contents).map_err(|err| err.to_string())
+    } else {
+        serde_yaml::from_str(&contents).map_err(|err| err.to_string())
+    };
+    parsed.map_err(|message| CatalogError::Parse {
+        path: path.to_owned(),
+        message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{CatalogEntry, CatalogError, CatalogFile, load_catalog};
+    use crate::sources::SourceRegistry;
+
+    fn entry(source: &str, slug: &str, aliases: &[&str]) -> CatalogEntry {
+        CatalogEntry {
+            source: source.to_owned(),
+            slug: slug.to_owned(),
+            title: "Problem".to_owned(),
+            difficulty: Some(" Easy ".to_owned()),
+            tags: vec!["Hash Table".to_owned(), "hash-table".to_owned()],
+            aliases: aliases.iter().map(|alias| (*alias).to_owned()).collect(),
+        }
+    }
+
+    #[test]
+    fn bundled_catalog_is_valid() {
+        let catalog = load_catalog(None).expect("bundled catalog parses");
+        let (problems, rejected) = catalog.validate(&SourceRegistry::builtin());
+        assert_eq!(rejected, Vec::<String>::new());
+        assert_eq!(problems.len(), catalog.problems.len());
+        let two_sum = problems
+            .iter()
+            .find(|problem| problem.slug == "two-sum")
+            .expect("two-sum listed");
+        assert_eq!(two_sum.url, "https://leetcode.com/problems/two-sum/");
+    }
+
+    #[test]
+    fn entries_are_normalized_and_rejected_with_reasons() {
+        let catalog = CatalogFile {
+            version: 3,
+            problems: vec![
+                entry("LeetCode", "Two-Sum", &["2-Sum", "two-sum"]),
+                entry("codeforces", "4/A", &[]),
+                entry("leetcode", "two-sum", &[]),
+                entry("kattis", "hello", &[]),
+                entry("leetcode", "bad slug!", &[]),
+            ],
+        };
+        let (problems, rejected) = catalog.validate(&SourceRegistry::builtin());
+        assert_eq!(problems.len(), 2);
+        assert_eq!(problems[0].source, "leetcode");
+        assert_eq!(problems[0].slug, "two-sum");
+        assert_eq!(problems[0].aliases, ["2-sum"]);
+        assert_eq!(problems[0].tags, ["hash-table"]);
+        assert_eq!(problems[0].difficulty.as_deref(), Some("Easy"));
+        assert_eq!(
+            problems[1].url,
+            "https://codeforces.com/problemset/problem/4/a"
+        );
+        assert_eq!(rejected.len(), 3);
+        assert_eq!(rejected[0], "leetcode/two-sum: listed more than once");
+        assert!(rejected[1].starts_with("kattis/hello: unknown source"));
+        assert!(rejected[2].starts_with("leetcode/bad slug!:"));
+    }
+
+    #[test]
+    fn loads_json_and_reports_unreadable_files() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("catalog.json");
+        fs::write(
+            &path,
+            r#"{"version":2,"problems":[{"source":"leetcode","slug":"lru-cache","title":"LRU Cache"}]}"#,
+        )
+        .expect("write catalog");
+        let catalog = load_catalog(path.to_str()).expect("json catalog");
+        assert_eq!(catalog.version, 2);
+        assert_eq!(catalog.problems[0].tags, Vec::<String>::new());
+
+        fs::write(&path, "version: 2").expect("write catalog");
+        assert!(matches!(
+            load_catalog(path.to_str()),
+            Err(CatalogError::Parse { .. })
+        ));
+        assert!(matches!(
+            load_catalog(dir.path().join("missing.yaml").to_str()),
+            Err(CatalogError::Read { .. })
+        ));
+    }
+}
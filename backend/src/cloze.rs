@@ -0,0 +1,76 @@
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+
+/// `{{c1::answer}}` or `{{c1::answer::hint}}`, as in Anki.
+static DELETION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\{c(\d+)::(.*?)(?:::(.*?))?\}\}").expect("valid cloze pattern")
+});
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClozeDeletion {
+    pub number: u32,
+    pub answer: String,
+    pub hint: Option<String>,
+}
+
+pub fn deletions(text: &str) -> Vec<ClozeDeletion> {
+    DELETION
+        .captures_iter(text)
+        .filter_map(|captures| {
+            Some(ClozeDeletion {
+                number: captures[1].parse().ok()?,
+                answer: captures[2].to_owned(),
+                hint: captures.get(3).map(|hint| hint.as_str().to_owned()),
+            })
+        })
+        .collect()
+}
+
+pub fn has_deletions(text: &str) -> bool {
+    DELETION.is_match(text)
+}
+
+/// The text with every deletion hidden, showing its hint when there is one.
+pub fn render_prompt(text: &str) -> String {
+    DELETION
+        .replace_all(text, |captures: &Captures| match captures.get(3) {
+            Some(hint) => format!("[{}]", hint.as_str()),
+            None => "[...]".to_owned(),
+        })
+        .into_owned()
+}
+
+/// The text with every deletion filled in.
+pub fn render_answer(text: &str) -> String {
+    DELETION.replace_all(text, "$2").into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClozeDeletion, deletions, has_deletions, render_answer, render_prompt};
+
+    #[test]
+    fn parses_deletions_with_and_without_hints() {
+        let text = "{{c1::Paris}} is the capital of {{c2::France::country}}";
+        assert!(has_deletions(text));
+        assert!(!has_deletions("no deletions {{here}}"));
+        assert_eq!(
+            deletions(text),
+            vec![
+                ClozeDeletion {
+                    number: 1,
+                    answer: "Paris".to_owned(),
+                    hint: None,
+                },
+                ClozeDeletion {
+                    number: 2,
+                    answer: "France".to_owned(),
+                    hint: Some("country".to_owned()),
+                },
+            ]
+        );
+        assert_eq!(render_prompt(text), "[...] is the capital of [country]");
+        assert_eq!(render_answer(text), "Paris is the capital of France");
+    }
+}
@@ -0,0 +1,74 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use tracing::info;
+
+use crate::{
+    app::state::AppState,
+    dto::activity::{GoalProgress, HeatmapQuery, HeatmapResponse, SaveGoalRequest},
+    extractors::{
+        api_key_user::ReviewsRead, authenticated_user::AuthenticatedUser,
+        session_or_api_key_user::SessionOrApiKeyUser,
+    },
+};
+
+pub async fn activity_heatmap(
+    State(state): State<AppState>,
+    SessionOrApiKeyUser(user, _): SessionOrApiKeyUser<ReviewsRead>,
+    Query(query): Query<HeatmapQuery>,
+) -> Result<Json<HeatmapResponse>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let heatmap = state
+        .activity_service
+        .heatmap(user.id, query, chrono::Utc::now())
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(
+        user_id = user.id,
+        day_count = heatmap.days.len(),
+        "activity_heatmap_get"
+    );
+    Ok(Json(heatmap))
+}
+
+pub async fn goals(
+    State(state): State<AppState>,
+    SessionOrApiKeyUser(user, _): SessionOrApiKeyUser<ReviewsRead>,
+) -> Result<Json<Vec<GoalProgress>>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let goals = state
+        .activity_service
+        .goals(user.id, chrono::Utc::now())
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(user_id = user.id, goal_count = goals.len(), "goals_get");
+    Ok(Json(goals))
+}
+
+pub async fn save_goal(
+    State(state): State<AppState>,
+    AuthenticatedUser(user): AuthenticatedUser,
+    Json(payload): Json<SaveGoalRequest>,
+) -> Result<Json<GoalProgress>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let goal = state
+        .activity_service
+        .save_goal(user.id, payload, chrono::Utc::now())
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(user_id = user.id, goal_id = goal.goal.id, "goal_save");
+    Ok(Json(goal))
+}
+
+pub async fn delete_goal(
+    State(state): State<AppState>,
+    AuthenticatedUser(user): AuthenticatedUser,
+    Path(goal_id): Path<i64>,
+) -> Result<StatusCode, (StatusCode, Json<crate::errors::ApiError>)> {
+    state
+        .activity_service
+        .delete_goal(user.id, goal_id)
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(user_id = user.id, goal_id, "goal_delete");
+    Ok(StatusCode::NO_CONTENT)
+}
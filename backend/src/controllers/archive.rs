@@ -0,0 +1,123 @@
+use axum::{
+    Json,
+    body::{Body, Bytes},
+    extract::{Query, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use futures_util::{StreamExt, TryStreamExt, stream};
+use tracing::{error, info};
+
+use crate::{
+    app::state::AppState,
+    dto::archive::{ExportArchive, ImportArchiveQuery},
+    extractors::{
+        api_key_user::ExportRead, authenticated_user::AuthenticatedUser,
+        session_or_api_key_user::SessionOrApiKeyUser,
+    },
+    models::{ArchiveImportSummary, ImportMode},
+};
+
+pub async fn export_archive(
+    State(state): State<AppState>,
+    SessionOrApiKeyUser(user, _): SessionOrApiKeyUser<ExportRead>,
+) -> Result<impl IntoResponse, (StatusCode, Json<crate::errors::ApiError>)> {
+    let user_id = user.id;
+    let now = chrono::Utc::now();
+    let mut chunks = Box::pin(state.archive_service.export(user, now));
+    // Pull the first section before answering so an early failure still gets
+    // a proper error status instead of a truncated 200.
+    let first = chunks
+        .next()
+        .await
+        .transpose()
+        .map_err(|err| err.to_http())?;
+    info!(user_id, "archive_export_started");
+    let body = stream::iter(first.map(Ok))
+        .chain(chunks)
+        .inspect_err(move |err| {
+            error!(user_id, error = %err, "archive_export_failed");
+        });
+    let disposition = format!(
+        "attachment; filename=\"srs-anything-export-{}.json\"",
+        now.format("%Y-%m-%d")
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json".to_owned()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        Body::from_stream(body),
+    ))
+}
+
+pub async fn import_archive(
+    State(state): State<AppState>,
+    AuthenticatedUser(user): AuthenticatedUser,
+    Query(query): Query<ImportArchiveQuery>,
+    Json(archive): Json<ExportArchive>,
+) -> Result<Json<ArchiveImportSummary>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let summary = state
+        .archive_service
+        .import(user.id, archive, query.mode.unwrap_or(ImportMode::Merge))
+        .await
+        .map_err(|err| err.to_http())?;
+    Ok(Json(summary))
+}
+
+pub async fn export_anki(
+    State(state): State<AppState>,
+    SessionOrApiKeyUser(user, _): SessionOrApiKeyUser<ExportRead>,
+) -> Result<impl IntoResponse, (StatusCode, Json<crate::errors::ApiError>)> {
+    let now = chrono::Utc::now();
+    let package = state
+        .archive_service
+        .export_anki(&user, now)
+        .await
+        .map_err(|err| err.to_http())?;
+    let disposition = format!(
+        "attachment; filename=\"srs-anything-{}.apkg\"",
+        now.format("%Y-%m-%d")
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/octet-stream".to_owned()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        package,
+    ))
+}
+
+/// Takes the raw `.apkg` file as the request body.
+pub async fn import_anki(
+    State(state): State<AppState>,
+    AuthenticatedUser(user): AuthenticatedUser,
+    Query(query): Query<ImportArchiveQuery>,
+    package: Bytes,
+) -> Result<Json<ArchiveImportSummary>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let summary = state
+        .archive_service
+        .import_anki(
+            user.id,
+            &package,
+            query.mode.unwrap_or(ImportMode::Merge),
+            chrono::Utc::now(),
+        )
+        .await
+        .map_err(|err| err.to_http())?;
+    Ok(Json(summary))
+}
+
+/// Takes a LeetCode submission history dump, JSON or CSV, as the body.
+pub async fn import_leetcode_history(
+    State(state): State<AppState>,
+    AuthenticatedUser(user): AuthenticatedUser,
+    body: String,
+) -> Result<Json<ArchiveImportSummary>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let summary = state
+        .archive_service
+        .import_leetcode_history(user.id, &body, chrono::Utc::now())
+        .await
+        .map_err(|err| err.to_http())?;
+    Ok(Json(summary))
+}
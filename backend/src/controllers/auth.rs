@@ -1,19 +1,28 @@
 use axum::{
     Json,
-    extract::State,
-    http::{HeaderValue, StatusCode, header},
-    response::IntoResponse,
+    extract::{Path, Query, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{AppendHeaders, IntoResponse},
 };
 use tracing::info;
 
 use crate::{
     app::state::AppState,
+    controllers::cookie_value,
     dto::auth::{
-        RequestMagicLink, RequestMagicLinkResponse, VerifyMagicLink, VerifyMagicLinkResponse,
+        AccountDeletionResponse, DeleteAccount, OidcCallbackQuery, PasskeyAssertionCredential,
+        PasskeyLoginOptions, PasskeyRegistrationOptions, PasskeySummary, RegisterPasskey,
+        RequestMagicLink, RequestMagicLinkResponse, RevokeOtherSessionsResponse, SessionSummary,
+        VerifyMagicLink, VerifyMagicLinkResponse,
     },
-    extractors::authenticated_user::AuthenticatedUser,
+    errors::AppError,
+    extractors::authenticated_user::{AuthenticatedSession, AuthenticatedUser},
+    models::ClientInfo,
 };
 
+const OIDC_STATE_COOKIE: &str = "srs_oidc_state";
+const OIDC_STATE_MAX_AGE_SECS: i64 = 600;
+
 pub async fn request_magic_link(
     State(state): State<AppState>,
     Json(payload): Json<RequestMagicLink>,
@@ -32,12 +41,13 @@ pub async fn request_magic_link(
 
 pub async fn verify_magic_link(
     State(state): State<AppState>,
+    client: ClientInfo,
     Json(payload): Json<VerifyMagicLink>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<crate::errors::ApiError>)> {
     info!("auth_verify_magic_link");
     let (user, jwt) = state
         .auth_service
-        .verify_magic_link(&payload.token)
+        .verify_magic_link(&payload.token, client)
         .await
         .map_err(|err| err.to_http())?;
     let cookie = build_auth_cookie(
@@ -52,16 +62,248 @@ pub async fn verify_magic_link(
     ))
 }
 
-pub async fn logout(
+pub async fn oidc_login(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, (StatusCode, Json<crate::errors::ApiError>)> {
+    info!("auth_oidc_login");
+    let start = state
+        .auth_service
+        .begin_oidc_login()
+        .await
+        .map_err(|err| err.to_http())?;
+    let location = header_value(&start.authorization_url)?;
+    let state_cookie = build_cookie(
+        OIDC_STATE_COOKIE,
+        &start.state,
+        OIDC_STATE_MAX_AGE_SECS,
+        state.security.cookie_secure,
+    )?;
+    Ok((
+        StatusCode::SEE_OTHER,
+        [
+            (header::LOCATION, location),
+            (header::SET_COOKIE, state_cookie),
+        ],
+    ))
+}
+
+pub async fn oidc_callback(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    client: ClientInfo,
+    Query(query): Query<OidcCallbackQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<crate::errors::ApiError>)> {
+    info!("auth_oidc_callback");
+    if let Some(error) = query.error {
+        return Err(AppError::OidcLoginFailed(format!("provider returned {error}")).to_http());
+    }
+    let (Some(code), Some(oidc_state)) = (query.code, query.state) else {
+        return Err(AppError::InvalidInput("code and state are required".to_owned()).to_http());
+    };
+    let (user, jwt) = state
+        .auth_service
+        .complete_oidc_login(
+            &code,
+            &oidc_state,
+            cookie_value(&headers, OIDC_STATE_COOKIE),
+            client,
+        )
+        .await
+        .map_err(|err| err.to_http())?;
+    let redirect = state
+        .auth_service
+        .oidc_post_login_redirect()
+        .unwrap_or("/")
+        .to_owned();
+    let auth_cookie = build_auth_cookie(
+        &jwt,
+        state.auth_service.session_max_age_secs(),
+        state.security.cookie_secure,
+    )?;
+    let clear_state_cookie = build_cookie(OIDC_STATE_COOKIE, "", 0, state.security.cookie_secure)?;
+    info!(user_id = user.id, "auth_oidc_callback_success");
+    Ok((
+        StatusCode::SEE_OTHER,
+        AppendHeaders([
+            (header::LOCATION, header_value(&redirect)?),
+            (header::SET_COOKIE, auth_cookie),
+            (header::SET_COOKIE, clear_state_cookie),
+        ]),
+    ))
+}
+
+pub async fn passkey_registration_options(
     State(state): State<AppState>,
     AuthenticatedUser(user): AuthenticatedUser,
+) -> Result<Json<PasskeyRegistrationOptions>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let options = state
+        .auth_service
+        .begin_passkey_registration(&user)
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(user_id = user.id, "auth_passkey_registration_options");
+    Ok(Json(options))
+}
+
+pub async fn register_passkey(
+    State(state): State<AppState>,
+    AuthenticatedUser(user): AuthenticatedUser,
+    Json(payload): Json<RegisterPasskey>,
+) -> Result<Json<PasskeySummary>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let passkey = state
+        .auth_service
+        .finish_passkey_registration(&user, payload.label, payload.credential)
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(
+        user_id = user.id,
+        passkey_id = passkey.id,
+        "auth_passkey_register"
+    );
+    Ok(Json(passkey))
+}
+
+pub async fn passkeys(
+    State(state): State<AppState>,
+    AuthenticatedUser(user): AuthenticatedUser,
+) -> Result<Json<Vec<PasskeySummary>>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let passkeys = state
+        .auth_service
+        .list_passkeys(user.id)
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(
+        user_id = user.id,
+        passkey_count = passkeys.len(),
+        "auth_passkeys"
+    );
+    Ok(Json(passkeys))
+}
+
+pub async fn delete_passkey(
+    State(state): State<AppState>,
+    AuthenticatedUser(user): AuthenticatedUser,
+    Path(passkey_id): Path<i64>,
+) -> Result<StatusCode, (StatusCode, Json<crate::errors::ApiError>)> {
+    state
+        .auth_service
+        .delete_passkey(user.id, passkey_id)
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(user_id = user.id, passkey_id, "auth_passkey_delete");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn passkey_login_options(
+    State(state): State<AppState>,
+) -> Result<Json<PasskeyLoginOptions>, (StatusCode, Json<crate::errors::ApiError>)> {
+    info!("auth_passkey_login_options");
+    let options = state
+        .auth_service
+        .begin_passkey_login()
+        .await
+        .map_err(|err| err.to_http())?;
+    Ok(Json(options))
+}
+
+pub async fn passkey_login(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Json(payload): Json<PasskeyAssertionCredential>,
+) -> Result<impl IntoResponse, (StatusCode, Json<crate::errors::ApiError>)> {
+    info!("auth_passkey_login");
+    let (user, jwt) = state
+        .auth_service
+        .finish_passkey_login(payload, client)
+        .await
+        .map_err(|err| err.to_http())?;
+    let cookie = build_auth_cookie(
+        &jwt,
+        state.auth_service.session_max_age_secs(),
+        state.security.cookie_secure,
+    )?;
+    info!(user_id = user.id, "auth_passkey_login_success");
+    Ok((
+        [(header::SET_COOKIE, cookie)],
+        Json(VerifyMagicLinkResponse { user }),
+    ))
+}
+
+pub async fn logout(
+    State(state): State<AppState>,
+    AuthenticatedSession(user, session): AuthenticatedSession,
 ) -> Result<impl IntoResponse, (StatusCode, Json<crate::errors::ApiError>)> {
     info!("auth_logout");
+    state
+        .auth_service
+        .revoke_session(user.id, session.id)
+        .await
+        .map_err(|err| err.to_http())?;
     let clear_cookie = clear_auth_cookie(state.security.cookie_secure)?;
     info!(user_id = user.id, "auth_logout_success");
     Ok(([(header::SET_COOKIE, clear_cookie)], StatusCode::NO_CONTENT))
 }
 
+pub async fn sessions(
+    State(state): State<AppState>,
+    AuthenticatedSession(user, session): AuthenticatedSession,
+) -> Result<Json<Vec<SessionSummary>>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let sessions = state
+        .auth_service
+        .list_sessions(user.id, session.id)
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(
+        user_id = user.id,
+        session_count = sessions.len(),
+        "auth_sessions"
+    );
+    Ok(Json(sessions))
+}
+
+pub async fn revoke_session(
+    State(state): State<AppState>,
+    AuthenticatedSession(user, current): AuthenticatedSession,
+    Path(session_id): Path<i64>,
+) -> Result<axum::response::Response, (StatusCode, Json<crate::errors::ApiError>)> {
+    state
+        .auth_service
+        .revoke_session(user.id, session_id)
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(user_id = user.id, session_id, "auth_session_revoke");
+    if session_id == current.id {
+        let clear_cookie = clear_auth_cookie(state.security.cookie_secure)?;
+        return Ok(([(header::SET_COOKIE, clear_cookie)], StatusCode::NO_CONTENT).into_response());
+    }
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+pub async fn revoke_other_sessions(
+    State(state): State<AppState>,
+    AuthenticatedSession(user, session): AuthenticatedSession,
+) -> Result<Json<RevokeOtherSessionsResponse>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let revoked_count = state
+        .auth_service
+        .revoke_other_sessions(user.id, session.id)
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(
+        user_id = user.id,
+        revoked_count, "auth_other_sessions_revoke"
+    );
+    Ok(Json(RevokeOtherSessionsResponse { revoked_count }))
+}
+
+/// Public keys for verifying session tokens, for internal tools that must not
+/// hold the signing secret.
+pub async fn jwks(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(header::CACHE_CONTROL, "public, max-age=300")],
+        Json(state.auth_service.jwks()),
+    )
+}
+
 pub async fn me(
     AuthenticatedUser(user): AuthenticatedUser,
 ) -> Result<Json<crate::models::User>, (StatusCode, Json<crate::errors::ApiError>)> {
@@ -69,17 +311,60 @@ pub async fn me(
     Ok(Json(user))
 }
 
+pub async fn request_account_deletion(
+    State(state): State<AppState>,
+    AuthenticatedUser(user): AuthenticatedUser,
+) -> Result<Json<AccountDeletionResponse>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let requested = state
+        .auth_service
+        .request_account_deletion(user.id)
+        .await
+        .map_err(|err| err.to_http())?;
+    Ok(Json(AccountDeletionResponse {
+        sent: true,
+        expires_at: requested.expires_at,
+        dev_confirmation_token: requested.dev_token,
+    }))
+}
+
+pub async fn delete_account(
+    State(state): State<AppState>,
+    AuthenticatedUser(user): AuthenticatedUser,
+    Json(payload): Json<DeleteAccount>,
+) -> Result<impl IntoResponse, (StatusCode, Json<crate::errors::ApiError>)> {
+    state
+        .auth_service
+        .delete_account(user.id, &payload.confirmation_token)
+        .await
+        .map_err(|err| err.to_http())?;
+    let clear_cookie = clear_auth_cookie(state.security.cookie_secure)?;
+    Ok(([(header::SET_COOKIE, clear_cookie)], StatusCode::NO_CONTENT))
+}
+
 fn build_auth_cookie(
     jwt: &str,
     max_age_secs: i64,
     secure: bool,
+) -> Result<HeaderValue, (StatusCode, Json<crate::errors::ApiError>)> {
+    build_cookie("srs_auth", jwt, max_age_secs, secure)
+}
+
+fn build_cookie(
+    name: &str,
+    value: &str,
+    max_age_secs: i64,
+    secure: bool,
 ) -> Result<HeaderValue, (StatusCode, Json<crate::errors::ApiError>)> {
     let secure_part = if secure { "; Secure" } else { "" };
     let value = format!(
-        "srs_auth={jwt}; Path=/; HttpOnly; SameSite=Lax; Max-Age={max_age_secs}{secure_part}"
+        "{name}={value}; Path=/; HttpOnly; SameSite=Lax; Max-Age={max_age_secs}{secure_part}"
     );
-    HeaderValue::from_str(&value).map_err(|err| {
-        crate::errors::AppError::Internal(format!("invalid cookie header: {err}")).to_http()
+    header_value(&value)
+}
+
+fn header_value(value: &str) -> Result<HeaderValue, (StatusCode, Json<crate::errors::ApiError>)> {
+    HeaderValue::from_str(value).map_err(|err| {
+        crate::errors::AppError::Internal(format!("invalid header value: {err}")).to_http()
     })
 }
 
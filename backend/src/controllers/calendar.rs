@@ -0,0 +1,89 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use tracing::info;
+
+use crate::{
+    app::state::AppState,
+    dto::calendar::{
+        CalendarFeedQuery, CalendarFeedSummary, CreateCalendarFeedRequest,
+        CreateCalendarFeedResponse,
+    },
+    extractors::authenticated_user::AuthenticatedUser,
+};
+
+pub async fn calendar_feeds(
+    State(state): State<AppState>,
+    AuthenticatedUser(user): AuthenticatedUser,
+) -> Result<Json<Vec<CalendarFeedSummary>>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let feeds = state
+        .calendar_service
+        .list_feeds(user.id)
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(
+        user_id = user.id,
+        feed_count = feeds.len(),
+        "calendar_feeds_get"
+    );
+    Ok(Json(feeds))
+}
+
+pub async fn create_calendar_feed(
+    State(state): State<AppState>,
+    AuthenticatedUser(user): AuthenticatedUser,
+    Json(payload): Json<CreateCalendarFeedRequest>,
+) -> Result<Json<CreateCalendarFeedResponse>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let created = state
+        .calendar_service
+        .create_feed(user.id, &payload.label)
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(
+        user_id = user.id,
+        feed_id = created.feed_summary.id,
+        "calendar_feed_created"
+    );
+    Ok(Json(created))
+}
+
+pub async fn revoke_calendar_feed(
+    State(state): State<AppState>,
+    AuthenticatedUser(user): AuthenticatedUser,
+    Path(feed_id): Path<i64>,
+) -> Result<StatusCode, (StatusCode, Json<crate::errors::ApiError>)> {
+    state
+        .calendar_service
+        .revoke_feed(user.id, feed_id)
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(user_id = user.id, feed_id, "calendar_feed_revoked");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn review_calendar(
+    State(state): State<AppState>,
+    Query(query): Query<CalendarFeedQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<crate::errors::ApiError>)> {
+    let user = state
+        .calendar_service
+        .user_from_feed_token(query.token.as_deref())
+        .await
+        .map_err(|err| err.to_http())?;
+    let calendar = state
+        .calendar_service
+        .render_feed(user.id, chrono::Utc::now())
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(user_id = user.id, "calendar_feed_served");
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/calendar; charset=utf-8"),
+            (header::CACHE_CONTROL, "private, max-age=300"),
+        ],
+        calendar,
+    ))
+}
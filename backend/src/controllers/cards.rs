@@ -0,0 +1,113 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use tracing::info;
+
+use crate::{
+    app::state::AppState,
+    dto::cards::{
+        CardImportResponse, CreateCardRequest, ImportCardsCsvQuery, ImportCardsQuery,
+        UpdateCardRequest,
+    },
+    extractors::{
+        api_key_user::{CardsRead, CardsWrite},
+        session_or_api_key_user::SessionOrApiKeyUser,
+    },
+    models::ProblemCard,
+};
+
+pub async fn create_card(
+    State(state): State<AppState>,
+    SessionOrApiKeyUser(user, _): SessionOrApiKeyUser<CardsWrite>,
+    Json(payload): Json<CreateCardRequest>,
+) -> Result<(StatusCode, Json<ProblemCard>), (StatusCode, Json<crate::errors::ApiError>)> {
+    info!(kind = payload.kind.as_str(), "cards_create_request");
+    let card = state
+        .card_service
+        .create(user.id, payload, chrono::Utc::now())
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(user_id = user.id, card_id = card.id, "cards_created");
+    Ok((StatusCode::CREATED, Json(card)))
+}
+
+pub async fn get_card(
+    State(state): State<AppState>,
+    SessionOrApiKeyUser(user, _): SessionOrApiKeyUser<CardsRead>,
+    Path(card_id): Path<i64>,
+) -> Result<Json<ProblemCard>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let card = state
+        .card_service
+        .get(user.id, card_id)
+        .await
+        .map_err(|err| err.to_http())?;
+    Ok(Json(card))
+}
+
+pub async fn update_card(
+    State(state): State<AppState>,
+    SessionOrApiKeyUser(user, _): SessionOrApiKeyUser<CardsWrite>,
+    Path(card_id): Path<i64>,
+    Json(payload): Json<UpdateCardRequest>,
+) -> Result<Json<ProblemCard>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let card = state
+        .card_service
+        .update(user.id, card_id, payload)
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(user_id = user.id, card_id, "cards_updated");
+    Ok(Json(card))
+}
+
+pub async fn delete_card(
+    State(state): State<AppState>,
+    SessionOrApiKeyUser(user, _): SessionOrApiKeyUser<CardsWrite>,
+    Path(card_id): Path<i64>,
+) -> Result<StatusCode, (StatusCode, Json<crate::errors::ApiError>)> {
+    state
+        .card_service
+        .delete(user.id, card_id)
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(user_id = user.id, card_id, "cards_deleted");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Takes a markdown file as the request body.
+pub async fn import_markdown_cards(
+    State(state): State<AppState>,
+    SessionOrApiKeyUser(user, _): SessionOrApiKeyUser<CardsWrite>,
+    Query(query): Query<ImportCardsQuery>,
+    body: String,
+) -> Result<Json<CardImportResponse>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let response = state
+        .card_service
+        .import_markdown(user.id, &body, query.dry_run, chrono::Utc::now())
+        .await
+        .map_err(|err| err.to_http())?;
+    Ok(Json(response))
+}
+
+/// Takes a CSV file with a header row as the request body; the query maps
+/// its columns to card fields.
+pub async fn import_csv_cards(
+    State(state): State<AppState>,
+    SessionOrApiKeyUser(user, _): SessionOrApiKeyUser<CardsWrite>,
+    Query(query): Query<ImportCardsCsvQuery>,
+    body: String,
+) -> Result<Json<CardImportResponse>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let response = state
+        .card_service
+        .import_csv(
+            user.id,
+            &body,
+            &query.columns(),
+            query.dry_run,
+            chrono::Utc::now(),
+        )
+        .await
+        .map_err(|err| err.to_http())?;
+    Ok(Json(response))
+}
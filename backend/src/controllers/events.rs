@@ -2,13 +2,17 @@ use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
 use tracing::info;
 
 use crate::{
-    app::state::AppState, dto::events::IngestProblemEventRequest,
-    extractors::api_key_user::ApiKeyUser, models::IngestProblemInput,
+    app::state::AppState,
+    dto::events::{
+        IngestProblemEventBatchRequest, IngestProblemEventBatchResponse, IngestProblemEventRequest,
+    },
+    errors::AppError,
+    extractors::api_key_user::{ApiKeyUser, EventsWrite},
 };
 
 pub async fn ingest_problem_event(
     State(state): State<AppState>,
-    ApiKeyUser(user): ApiKeyUser,
+    ApiKeyUser(user, _): ApiKeyUser<EventsWrite>,
     Json(payload): Json<IngestProblemEventRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<crate::errors::ApiError>)> {
     info!(
@@ -16,17 +20,12 @@ pub async fn ingest_problem_event(
         problem_slug = %payload.problem_slug,
         "events_ingest_request"
     );
+    let input = payload
+        .into_input(user.id, state.event_service.sources())
+        .map_err(|reason| AppError::InvalidInput(reason).to_http())?;
     let event = state
         .event_service
-        .ingest(IngestProblemInput {
-            user_id: user.id,
-            source: payload.source.to_lowercase(),
-            problem_slug: payload.problem_slug,
-            title: payload.title,
-            url: payload.url,
-            status: payload.status,
-            occurred_at: payload.occurred_at,
-        })
+        .ingest(input)
         .await
         .map_err(|err| err.to_http())?;
     info!(
@@ -38,3 +37,32 @@ pub async fn ingest_problem_event(
     );
     Ok((StatusCode::CREATED, Json(event)))
 }
+
+/// Ingests several events at once, e.g. an offline queue being flushed.
+/// Each item gets its own status, so one bad event does not sink the rest.
+pub async fn ingest_problem_event_batch(
+    State(state): State<AppState>,
+    ApiKeyUser(user, _): ApiKeyUser<EventsWrite>,
+    Json(payload): Json<IngestProblemEventBatchRequest>,
+) -> Result<Json<IngestProblemEventBatchResponse>, (StatusCode, Json<crate::errors::ApiError>)> {
+    info!(
+        user_id = user.id,
+        events = payload.events.len(),
+        "events_batch_request"
+    );
+    let items = payload
+        .events
+        .into_iter()
+        .map(|item| {
+            serde_json::from_value::<IngestProblemEventRequest>(item)
+                .map_err(|err| err.to_string())
+                .and_then(|event| event.into_input(user.id, state.event_service.sources()))
+        })
+        .collect();
+    let outcomes = state
+        .event_service
+        .ingest_batch(user.id, items)
+        .await
+        .map_err(|err| err.to_http())?;
+    Ok(Json(outcomes.into()))
+}
@@ -0,0 +1,24 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
+
+use crate::{
+    app::state::AppState,
+    dto::forecast::{ForecastQuery, ForecastResponse},
+    extractors::{api_key_user::ReviewsRead, session_or_api_key_user::SessionOrApiKeyUser},
+};
+
+pub async fn forecast(
+    State(state): State<AppState>,
+    SessionOrApiKeyUser(user, _): SessionOrApiKeyUser<ReviewsRead>,
+    Query(query): Query<ForecastQuery>,
+) -> Result<Json<ForecastResponse>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let forecast = state
+        .forecast_service
+        .forecast(user.id, query, chrono::Utc::now())
+        .await
+        .map_err(|err| err.to_http())?;
+    Ok(Json(forecast))
+}
@@ -9,6 +9,7 @@ use crate::{
     app::state::AppState,
     dto::integrations::{
         CreateIntegrationTokenRequest, CreateIntegrationTokenResponse, IntegrationsResponse,
+        RotateIntegrationTokenRequest,
     },
     extractors::authenticated_user::AuthenticatedUser,
 };
@@ -37,7 +38,12 @@ pub async fn create_integration_token(
 ) -> Result<Json<CreateIntegrationTokenResponse>, (StatusCode, Json<crate::errors::ApiError>)> {
     let created = state
         .integrations_service
-        .create_token(user.id, &payload.label, payload.expires_in_days)
+        .create_token(
+            user.id,
+            &payload.label,
+            payload.expires_in_days,
+            payload.scopes,
+        )
         .await
         .map_err(|err| err.to_http())?;
     info!(
@@ -61,3 +67,24 @@ pub async fn revoke_integration_token(
     info!(user_id = user.id, token_id, "integrations_token_revoked");
     Ok(StatusCode::NO_CONTENT)
 }
+
+pub async fn rotate_integration_token(
+    State(state): State<AppState>,
+    AuthenticatedUser(user): AuthenticatedUser,
+    Path(token_id): Path<i64>,
+    payload: Option<Json<RotateIntegrationTokenRequest>>,
+) -> Result<Json<CreateIntegrationTokenResponse>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let Json(payload) = payload.unwrap_or_default();
+    let rotated = state
+        .integrations_service
+        .rotate_token(
+            user.id,
+            token_id,
+            payload.grace_period_minutes,
+            payload.expires_in_days,
+        )
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(user_id = user.id, token_id, "integrations_token_rotated");
+    Ok(Json(rotated))
+}
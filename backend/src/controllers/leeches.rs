@@ -0,0 +1,48 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use tracing::info;
+
+use crate::{
+    app::state::AppState,
+    dto::leeches::LeechesResponse,
+    extractors::{
+        api_key_user::{CardsRead, CardsWrite},
+        session_or_api_key_user::SessionOrApiKeyUser,
+    },
+    models::ProblemCard,
+};
+
+pub async fn leeches(
+    State(state): State<AppState>,
+    SessionOrApiKeyUser(user, _): SessionOrApiKeyUser<CardsRead>,
+) -> Result<Json<LeechesResponse>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let cards = state
+        .leech_service
+        .list(user.id)
+        .await
+        .map_err(|err| err.to_http())?;
+    let policy = state.leech_service.policy();
+    Ok(Json(LeechesResponse {
+        threshold: policy.threshold,
+        action: policy.action,
+        cards,
+    }))
+}
+
+/// Marks the card as restudied, removing its leech flag.
+pub async fn clear_leech(
+    State(state): State<AppState>,
+    SessionOrApiKeyUser(user, _): SessionOrApiKeyUser<CardsWrite>,
+    Path(card_id): Path<i64>,
+) -> Result<Json<ProblemCard>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let card = state
+        .leech_service
+        .clear(user.id, card_id)
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(user_id = user.id, card_id, "leech_clear_request");
+    Ok(Json(card))
+}
@@ -1,13 +1,31 @@
+pub mod activity;
+pub mod archive;
 pub mod auth;
+pub mod calendar;
+pub mod cards;
 pub mod dashboard;
 pub mod events;
+pub mod forecast;
 pub mod integrations;
+pub mod leeches;
+pub mod problems;
+pub mod push;
 pub mod reviews;
 pub mod settings;
+pub mod stats;
 
-use axum::http::HeaderMap;
+use std::net::SocketAddr;
+
+use axum::{
+    extract::ConnectInfo,
+    http::{Extensions, HeaderMap, header},
+};
 
 pub fn auth_cookie(headers: &HeaderMap) -> Option<&str> {
+    cookie_value(headers, "srs_auth")
+}
+
+pub fn cookie_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
     headers
         .get("cookie")
         .and_then(|value| value.to_str().ok())
@@ -15,7 +33,7 @@ pub fn auth_cookie(headers: &HeaderMap) -> Option<&str> {
             raw.split(';').find_map(|chunk| {
                 let mut parts = chunk.trim().splitn(2, '=');
                 match (parts.next(), parts.next()) {
-                    (Some("srs_auth"), Some(value)) if !value.is_empty() => Some(value),
+                    (Some(key), Some(value)) if key == name && !value.is_empty() => Some(value),
                     _ => None,
                 }
             })
@@ -28,3 +46,35 @@ pub fn api_key(headers: &HeaderMap) -> Option<&str> {
         .and_then(|value| value.to_str().ok())
         .filter(|value| !value.trim().is_empty())
 }
+
+pub fn user_agent(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.trim().is_empty())
+}
+
+/// Resolves the caller's address. `X-Forwarded-For` is only honoured when the
+/// deployment sits behind a proxy that overwrites it (`TRUST_PROXY_HEADERS`).
+pub fn client_ip(
+    headers: &HeaderMap,
+    extensions: &Extensions,
+    trust_proxy_headers: bool,
+) -> Option<String> {
+    let forwarded = trust_proxy_headers
+        .then(|| {
+            headers
+                .get("x-forwarded-for")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|raw| raw.split(',').next())
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(ToOwned::to_owned)
+        })
+        .flatten();
+    forwarded.or_else(|| {
+        extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string())
+    })
+}
@@ -0,0 +1,24 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+
+use crate::{
+    app::state::AppState,
+    dto::problems::ProblemResponse,
+    extractors::{api_key_user::CardsRead, session_or_api_key_user::SessionOrApiKeyUser},
+};
+
+pub async fn get_problem(
+    State(state): State<AppState>,
+    SessionOrApiKeyUser(_, _): SessionOrApiKeyUser<CardsRead>,
+    Path((source, slug)): Path<(String, String)>,
+) -> Result<Json<ProblemResponse>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let problem = state
+        .problem_service
+        .get(&source, &slug)
+        .await
+        .map_err(|err| err.to_http())?;
+    Ok(Json(problem))
+}
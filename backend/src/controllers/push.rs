@@ -0,0 +1,85 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use tracing::info;
+
+use crate::{
+    app::state::AppState,
+    controllers::user_agent,
+    dto::push::{SubscribePushRequest, VapidPublicKeyResponse},
+    extractors::authenticated_user::AuthenticatedUser,
+    models::PushSubscription,
+};
+
+pub async fn vapid_public_key(State(state): State<AppState>) -> Json<VapidPublicKeyResponse> {
+    Json(VapidPublicKeyResponse {
+        public_key: state.push_service.vapid_public_key(),
+    })
+}
+
+pub async fn push_subscriptions(
+    State(state): State<AppState>,
+    AuthenticatedUser(user): AuthenticatedUser,
+) -> Result<Json<Vec<PushSubscription>>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let subscriptions = state
+        .push_service
+        .list(user.id)
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(
+        user_id = user.id,
+        subscription_count = subscriptions.len(),
+        "push_subscriptions_get"
+    );
+    Ok(Json(subscriptions))
+}
+
+pub async fn subscribe_push(
+    State(state): State<AppState>,
+    AuthenticatedUser(user): AuthenticatedUser,
+    headers: HeaderMap,
+    Json(payload): Json<SubscribePushRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<crate::errors::ApiError>)> {
+    let device_label = payload
+        .device_label
+        .as_deref()
+        .or_else(|| user_agent(&headers))
+        .unwrap_or_default();
+    let subscription = state
+        .push_service
+        .subscribe(
+            user.id,
+            &payload.endpoint,
+            &payload.keys.p256dh,
+            &payload.keys.auth,
+            device_label,
+        )
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(
+        user_id = user.id,
+        subscription_id = subscription.id,
+        "push_subscription_created"
+    );
+    Ok((StatusCode::CREATED, Json(subscription)))
+}
+
+pub async fn unsubscribe_push(
+    State(state): State<AppState>,
+    AuthenticatedUser(user): AuthenticatedUser,
+    Path(subscription_id): Path<i64>,
+) -> Result<StatusCode, (StatusCode, Json<crate::errors::ApiError>)> {
+    state
+        .push_service
+        .unsubscribe(user.id, subscription_id)
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(
+        user_id = user.id,
+        subscription_id, "push_subscription_deleted"
+    );
+    Ok(StatusCode::NO_CONTENT)
+}
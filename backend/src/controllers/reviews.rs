@@ -8,13 +8,16 @@ use tracing::info;
 use crate::{
     app::state::AppState,
     dto::reviews::GradeRequest,
-    extractors::authenticated_user::AuthenticatedUser,
+    extractors::{
+        api_key_user::{CardsRead, ReviewsRead, ReviewsWrite},
+        session_or_api_key_user::SessionOrApiKeyUser,
+    },
     models::{ProblemCard, ReviewEvent},
 };
 
 pub async fn due_reviews(
     State(state): State<AppState>,
-    AuthenticatedUser(user): AuthenticatedUser,
+    SessionOrApiKeyUser(user, _): SessionOrApiKeyUser<ReviewsRead>,
 ) -> Result<Json<Vec<ProblemCard>>, (StatusCode, Json<crate::errors::ApiError>)> {
     let cards = state
         .review_service
@@ -27,7 +30,7 @@ pub async fn due_reviews(
 
 pub async fn grade_review(
     State(state): State<AppState>,
-    AuthenticatedUser(user): AuthenticatedUser,
+    SessionOrApiKeyUser(user, _): SessionOrApiKeyUser<ReviewsWrite>,
     Path(card_id): Path<i64>,
     Json(payload): Json<GradeRequest>,
 ) -> Result<Json<ReviewEvent>, (StatusCode, Json<crate::errors::ApiError>)> {
@@ -48,7 +51,7 @@ pub async fn grade_review(
 
 pub async fn history(
     State(state): State<AppState>,
-    AuthenticatedUser(user): AuthenticatedUser,
+    SessionOrApiKeyUser(user, _): SessionOrApiKeyUser<ReviewsRead>,
 ) -> Result<Json<Vec<ReviewEvent>>, (StatusCode, Json<crate::errors::ApiError>)> {
     let history = state
         .review_service
@@ -62,3 +65,16 @@ pub async fn history(
     );
     Ok(Json(history))
 }
+
+pub async fn cards(
+    State(state): State<AppState>,
+    SessionOrApiKeyUser(user, _): SessionOrApiKeyUser<CardsRead>,
+) -> Result<Json<Vec<ProblemCard>>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let cards = state
+        .review_service
+        .list_cards(user.id)
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(user_id = user.id, card_count = cards.len(), "cards_list");
+    Ok(Json(cards))
+}
@@ -36,7 +36,12 @@ pub async fn save_settings(
     );
     let pref = state
         .settings_service
-        .save(user.id, payload.email_enabled, payload.digest_hour_utc)
+        .save(
+            user.id,
+            payload.email_enabled,
+            payload.digest_hour_utc,
+            payload.timezone.as_deref(),
+        )
         .await
         .map_err(|err| err.to_http())?;
     info!(
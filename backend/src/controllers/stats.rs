@@ -0,0 +1,30 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
+use tracing::info;
+
+use crate::{
+    app::state::AppState,
+    dto::stats::{StatsQuery, StatsResponse},
+    extractors::{api_key_user::ReviewsRead, session_or_api_key_user::SessionOrApiKeyUser},
+};
+
+pub async fn stats(
+    State(state): State<AppState>,
+    SessionOrApiKeyUser(user, _): SessionOrApiKeyUser<ReviewsRead>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Json<StatsResponse>, (StatusCode, Json<crate::errors::ApiError>)> {
+    let stats = state
+        .stats_service
+        .stats(user.id, query, chrono::Utc::now())
+        .await
+        .map_err(|err| err.to_http())?;
+    info!(
+        user_id = user.id,
+        reviews = stats.totals.reviews,
+        "stats_get"
+    );
+    Ok(Json(stats))
+}
@@ -0,0 +1,58 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    activity::Streak,
+    models::{DailyActivity, Goal, GoalMetric, GoalPeriod},
+};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct HeatmapQuery {
+    /// Calendar year to show; the trailing 365 days when absent.
+    #[serde(default)]
+    pub year: Option<i32>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ActivityTotals {
+    pub reviews: i64,
+    pub solved: i64,
+    pub new_cards: i64,
+    pub active_days: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HeatmapResponse {
+    pub timezone: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub totals: ActivityTotals,
+    pub streak: Streak,
+    /// Every day from `from` through `to`, including empty ones.
+    pub days: Vec<DailyActivity>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SaveGoalRequest {
+    pub metric: GoalMetric,
+    pub period: GoalPeriod,
+    pub target: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GoalProgress {
+    #[serde(flatten)]
+    pub goal: Goal,
+    /// First and last local day of the current period.
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub progress: i64,
+    pub met: bool,
+}
+
+/// Streak and goal status, as shown on the dashboard and in digests.
+#[derive(Debug, Serialize)]
+pub struct ActivitySummary {
+    pub streak: Streak,
+    pub goals: Vec<GoalProgress>,
+}
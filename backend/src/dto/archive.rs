@@ -0,0 +1,112 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dto::integrations::IntegrationTokenSummary,
+    models::{CardKind, GoalMetric, GoalPeriod, ImportMode, ProblemStatus},
+    srs::Grade,
+};
+
+pub const ARCHIVE_FORMAT: &str = "srs-anything-export";
+/// Bumped whenever a field is removed or changes meaning.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Shape of the `/me/export` document. The endpoint streams it section by
+/// section, in this field order.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportArchive {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub user: ExportUser,
+    pub settings: ExportSettings,
+    /// Absent in archives from before goals; importing those leaves the
+    /// account's goals alone.
+    #[serde(default)]
+    pub goals: Option<Vec<ExportGoal>>,
+    /// Metadata only; token secrets are never stored and cannot be exported.
+    /// Ignored on import.
+    #[serde(default)]
+    pub integration_tokens: Vec<IntegrationTokenSummary>,
+    pub cards: Vec<ExportCard>,
+    pub problem_events: Vec<ExportProblemEvent>,
+    pub reviews: Vec<ExportReview>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportUser {
+    pub email: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportSettings {
+    pub email_enabled: bool,
+    pub digest_hour_utc: u8,
+    /// Absent in archives from before per-user timezones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportGoal {
+    pub metric: GoalMetric,
+    pub period: GoalPeriod,
+    pub target: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportCard {
+    /// Only meaningful within one archive, to link `reviews` to cards.
+    pub id: i64,
+    /// Absent in archives from before authored cards; those are all problems.
+    #[serde(default)]
+    pub kind: CardKind,
+    pub source: String,
+    pub problem_slug: String,
+    pub title: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub front: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub back: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    pub interval_index: usize,
+    pub next_due_at: DateTime<Utc>,
+    /// Absent in archives from before card creation times were kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    /// Absent in archives from before lapses were tracked; the imported
+    /// `again` reviews are counted instead.
+    #[serde(default)]
+    pub lapses: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leech_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suspended_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportProblemEvent {
+    pub source: String,
+    pub problem_slug: String,
+    pub title: String,
+    pub url: String,
+    pub status: ProblemStatus,
+    pub occurred_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportReview {
+    pub card_id: i64,
+    pub grade: Grade,
+    pub reviewed_at: DateTime<Utc>,
+    pub next_due_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportArchiveQuery {
+    /// Defaults to `merge`.
+    pub mode: Option<ImportMode>,
+}
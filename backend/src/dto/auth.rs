@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::models::User;
@@ -22,3 +23,147 @@ pub struct VerifyMagicLink {
 pub struct VerifyMagicLinkResponse {
     pub user: User,
 }
+
+#[derive(Debug, Serialize)]
+pub struct AccountDeletionResponse {
+    pub sent: bool,
+    pub expires_at: DateTime<Utc>,
+    /// Only outside production; otherwise the token is only sent to the user.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dev_confirmation_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteAccount {
+    pub confirmation_token: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionSummary {
+    pub id: i64,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub current: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RevokeOtherSessionsResponse {
+    pub revoked_count: u64,
+}
+
+/// Query string the identity provider appends to the OIDC redirect URL.
+#[derive(Debug, Deserialize)]
+pub struct OidcCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+/// `PublicKeyCredentialCreationOptions` with binary fields base64url-encoded.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyRegistrationOptions {
+    pub challenge: String,
+    pub rp: PasskeyRelyingParty,
+    pub user: PasskeyUserEntity,
+    pub pub_key_cred_params: Vec<PasskeyCredentialParameters>,
+    pub timeout: u64,
+    pub attestation: &'static str,
+    pub exclude_credentials: Vec<PasskeyCredentialDescriptor>,
+    pub authenticator_selection: PasskeyAuthenticatorSelection,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PasskeyRelyingParty {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyUserEntity {
+    pub id: String,
+    pub name: String,
+    pub display_name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PasskeyCredentialParameters {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub alg: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PasskeyCredentialDescriptor {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub id: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyAuthenticatorSelection {
+    pub resident_key: &'static str,
+    pub require_resident_key: bool,
+    pub user_verification: &'static str,
+}
+
+/// `PublicKeyCredentialRequestOptions` for a usernameless login.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyLoginOptions {
+    pub challenge: String,
+    pub rp_id: String,
+    pub timeout: u64,
+    pub user_verification: &'static str,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RegisterPasskey {
+    pub label: Option<String>,
+    pub credential: PasskeyAttestationCredential,
+}
+
+/// `PublicKeyCredential` returned by `navigator.credentials.create()`.
+#[derive(Debug, Deserialize)]
+pub struct PasskeyAttestationCredential {
+    pub id: String,
+    pub response: PasskeyAttestationResponse,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyAttestationResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    pub attestation_object: String,
+}
+
+/// `PublicKeyCredential` returned by `navigator.credentials.get()`.
+#[derive(Debug, Deserialize)]
+pub struct PasskeyAssertionCredential {
+    pub id: String,
+    pub response: PasskeyAssertionResponse,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyAssertionResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    pub authenticator_data: String,
+    pub signature: String,
+    pub user_handle: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PasskeySummary {
+    pub id: i64,
+    pub label: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
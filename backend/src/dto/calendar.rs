@@ -0,0 +1,28 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize)]
+pub struct CalendarFeedSummary {
+    pub id: i64,
+    pub label: String,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCalendarFeedRequest {
+    pub label: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateCalendarFeedResponse {
+    pub token: String,
+    pub feed_path: String,
+    pub feed_summary: CalendarFeedSummary,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CalendarFeedQuery {
+    pub token: Option<String>,
+}
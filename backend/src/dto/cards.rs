@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{models::CardKind, ports::card_notes::CsvColumns};
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCardRequest {
+    #[serde(default = "default_kind")]
+    pub kind: CardKind,
+    /// Stable per-user identifier, e.g. for re-importing the same notes.
+    /// Generated when omitted.
+    pub key: Option<String>,
+    pub front: String,
+    pub back: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Partial update: omitted fields keep their current value.
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateCardRequest {
+    pub kind: Option<CardKind>,
+    pub front: Option<String>,
+    pub back: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+fn default_kind() -> CardKind {
+    CardKind::Basic
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportCardsQuery {
+    /// Report what would change without writing anything.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Column mapping for CSV imports; each names the header to read a field from.
+#[derive(Debug, Deserialize)]
+pub struct ImportCardsCsvQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+    pub front: Option<String>,
+    pub back: Option<String>,
+    pub kind: Option<String>,
+    pub key: Option<String>,
+    pub tags: Option<String>,
+}
+
+impl ImportCardsCsvQuery {
+    pub fn columns(&self) -> CsvColumns {
+        let defaults = CsvColumns::default();
+        let pick = |column: &Option<String>, default: String| column.clone().unwrap_or(default);
+        CsvColumns {
+            front: pick(&self.front, defaults.front),
+            back: pick(&self.back, defaults.back),
+            kind: pick(&self.kind, defaults.kind),
+            key: pick(&self.key, defaults.key),
+            tags: pick(&self.tags, defaults.tags),
+        }
+    }
+}
+
+/// `card_id` is absent for cards a dry run would create.
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum CardImportResult {
+    Created {
+        line: usize,
+        key: String,
+        title: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        card_id: Option<i64>,
+    },
+    Updated {
+        line: usize,
+        key: String,
+        title: String,
+        card_id: i64,
+    },
+    Skipped {
+        line: usize,
+        key: String,
+        reason: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub struct CardImportResponse {
+    pub dry_run: bool,
+    pub created: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    pub results: Vec<CardImportResult>,
+}
+
+impl CardImportResponse {
+    pub fn new(dry_run: bool, results: Vec<CardImportResult>) -> Self {
+        let count = |matches: fn(&CardImportResult) -> bool| {
+            results.iter().filter(|result| matches(result)).count()
+        };
+        Self {
+            dry_run,
+            created: count(|result| matches!(result, CardImportResult::Created { .. })),
+            updated: count(|result| matches!(result, CardImportResult::Updated { .. })),
+            skipped: count(|result| matches!(result, CardImportResult::Skipped { .. })),
+            results,
+        }
+    }
+}
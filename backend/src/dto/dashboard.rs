@@ -1,12 +1,20 @@
 use serde::Serialize;
 
-use crate::models::ProblemEvent;
+use crate::{dto::activity::ActivitySummary, models::ProblemEvent};
 
 #[derive(Debug, Serialize)]
 pub struct DashboardResponse {
     pub due_count: usize,
     pub upcoming_count: usize,
-    pub leetcode_count: usize,
-    pub neetcode_count: usize,
+    /// Upcoming cards per registered source, in registry order.
+    pub source_counts: Vec<SourceCount>,
     pub latest_ingestion: Option<ProblemEvent>,
+    pub activity: ActivitySummary,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SourceCount {
+    pub source: String,
+    pub display_name: String,
+    pub count: usize,
 }
@@ -1,14 +1,96 @@
 use chrono::{DateTime, Utc};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-use crate::models::ProblemStatus;
+use crate::{
+    models::{BatchIngestOutcome, IngestProblemInput, ProblemEvent, ProblemStatus},
+    sources::SourceRegistry,
+};
 
+/// Either `status` or a platform `verdict` (e.g. Codeforces `WRONG_ANSWER`)
+/// must be given; `status` wins when both are.
 #[derive(Debug, Deserialize)]
 pub struct IngestProblemEventRequest {
     pub source: String,
     pub problem_slug: String,
     pub title: String,
     pub url: String,
-    pub status: ProblemStatus,
+    #[serde(default)]
+    pub status: Option<ProblemStatus>,
+    #[serde(default)]
+    pub verdict: Option<String>,
     pub occurred_at: DateTime<Utc>,
 }
+
+impl IngestProblemEventRequest {
+    pub fn into_input(
+        self,
+        user_id: i64,
+        sources: &SourceRegistry,
+    ) -> Result<IngestProblemInput, String> {
+        let status = match (self.status, self.verdict) {
+            (Some(status), _) => status,
+            (None, Some(verdict)) => sources
+                .status_for_verdict(&self.source, &verdict)
+                .map_err(|err| err.to_string())?,
+            (None, None) => return Err("either status or verdict is required".to_owned()),
+        };
+        Ok(IngestProblemInput {
+            user_id,
+            source: self.source.to_lowercase(),
+            problem_slug: self.problem_slug,
+            title: self.title,
+            url: self.url,
+            status,
+            occurred_at: self.occurred_at,
+        })
+    }
+}
+
+/// Items are kept as raw JSON so one malformed event is rejected on its own
+/// instead of failing the whole batch.
+#[derive(Debug, Deserialize)]
+pub struct IngestProblemEventBatchRequest {
+    pub events: Vec<Value>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum BatchItemResult {
+    Created { index: usize, event: ProblemEvent },
+    Deduplicated { index: usize, event: ProblemEvent },
+    Rejected { index: usize, reason: String },
+}
+
+#[derive(Debug, Serialize)]
+pub struct IngestProblemEventBatchResponse {
+    pub created: usize,
+    pub deduplicated: usize,
+    pub rejected: usize,
+    pub results: Vec<BatchItemResult>,
+}
+
+impl From<Vec<BatchIngestOutcome>> for IngestProblemEventBatchResponse {
+    fn from(outcomes: Vec<BatchIngestOutcome>) -> Self {
+        let results = outcomes
+            .into_iter()
+            .enumerate()
+            .map(|(index, outcome)| match outcome {
+                BatchIngestOutcome::Created(event) => BatchItemResult::Created { index, event },
+                BatchIngestOutcome::Deduplicated(event) => {
+                    BatchItemResult::Deduplicated { index, event }
+                }
+                BatchIngestOutcome::Rejected(reason) => BatchItemResult::Rejected { index, reason },
+            })
+            .collect::<Vec<_>>();
+        let count = |matches: fn(&BatchItemResult) -> bool| {
+            results.iter().filter(|result| matches(result)).count()
+        };
+        Self {
+            created: count(|result| matches!(result, BatchItemResult::Created { .. })),
+            deduplicated: count(|result| matches!(result, BatchItemResult::Deduplicated { .. })),
+            rejected: count(|result| matches!(result, BatchItemResult::Rejected { .. })),
+            results,
+        }
+    }
+}
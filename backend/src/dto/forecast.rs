@@ -0,0 +1,41 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+pub struct ForecastQuery {
+    #[serde(default = "default_days")]
+    pub days: u32,
+    /// Offset of the caller's local time from UTC, e.g. `120` for UTC+2.
+    /// Days are bucketed by local date.
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+    /// Also project the reviews that today's due cards will lead to.
+    #[serde(default)]
+    pub simulate: bool,
+    /// Share of simulated reviews that pass; the rest are graded `again`.
+    pub pass_rate: Option<f64>,
+}
+
+fn default_days() -> u32 {
+    30
+}
+
+#[derive(Debug, Serialize)]
+pub struct ForecastDay {
+    pub date: NaiveDate,
+    /// Cards whose `next_due_at` falls on this day; overdue cards count today.
+    pub due: usize,
+    /// Expected reviews including simulated follow-ups.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub projected: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ForecastResponse {
+    pub days: u32,
+    pub utc_offset_minutes: i32,
+    pub overdue: usize,
+    /// Set when the forecast was simulated.
+    pub pass_rate: Option<f64>,
+    pub forecast: Vec<ForecastDay>,
+}
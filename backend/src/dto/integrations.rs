@@ -11,7 +11,7 @@ pub struct IntegrationsResponse {
     pub tokens: Vec<IntegrationTokenSummary>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct IntegrationTokenSummary {
     pub id: i64,
     pub label: String,
@@ -20,12 +20,22 @@ pub struct IntegrationTokenSummary {
     pub expires_at: Option<DateTime<Utc>>,
     pub revoked_at: Option<DateTime<Utc>>,
     pub last_used_at: Option<DateTime<Utc>>,
+    pub last_used_ip: Option<String>,
+    pub last_used_user_agent: Option<String>,
+    pub previous_secret_expires_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CreateIntegrationTokenRequest {
     pub label: String,
     pub expires_in_days: Option<i64>,
+    pub scopes: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RotateIntegrationTokenRequest {
+    pub grace_period_minutes: Option<i64>,
+    pub expires_in_days: Option<i64>,
 }
 
 #[derive(Debug, Serialize)]
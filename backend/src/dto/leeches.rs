@@ -0,0 +1,13 @@
+use serde::Serialize;
+
+use crate::models::{LeechAction, ProblemCard};
+
+#[derive(Debug, Serialize)]
+pub struct LeechesResponse {
+    /// Lapses after which a card is flagged.
+    pub threshold: u32,
+    /// What happens to newly flagged cards.
+    pub action: LeechAction,
+    /// Flagged cards, most lapses first.
+    pub cards: Vec<ProblemCard>,
+}
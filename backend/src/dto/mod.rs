@@ -1,6 +1,15 @@
+pub mod activity;
+pub mod archive;
 pub mod auth;
+pub mod calendar;
+pub mod cards;
 pub mod dashboard;
 pub mod events;
+pub mod forecast;
 pub mod integrations;
+pub mod leeches;
+pub mod problems;
+pub mod push;
 pub mod reviews;
 pub mod settings;
+pub mod stats;
@@ -0,0 +1,11 @@
+use serde::Serialize;
+
+use crate::models::Problem;
+
+#[derive(Debug, Serialize)]
+pub struct ProblemResponse {
+    #[serde(flatten)]
+    pub problem: Problem,
+    /// Users with a card for this problem.
+    pub learners: i64,
+}
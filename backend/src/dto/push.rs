@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize)]
+pub struct VapidPublicKeyResponse {
+    pub public_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PushSubscriptionKeys {
+    pub p256dh: String,
+    pub auth: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubscribePushRequest {
+    pub endpoint: String,
+    pub keys: PushSubscriptionKeys,
+    pub device_label: Option<String>,
+}
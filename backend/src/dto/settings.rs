@@ -4,4 +4,7 @@ use serde::Deserialize;
 pub struct SaveSettingsRequest {
     pub email_enabled: bool,
     pub digest_hour_utc: u8,
+    /// IANA name such as `Europe/Berlin`; absent keeps the current one.
+    #[serde(default)]
+    pub timezone: Option<String>,
 }
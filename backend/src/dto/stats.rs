@@ -0,0 +1,97 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::RetentionCounts;
+
+#[derive(Debug, Deserialize)]
+pub struct StatsQuery {
+    /// Whole weeks to cover, the current one included.
+    #[serde(default = "default_weeks")]
+    pub weeks: u32,
+    /// Interval a card must have been scheduled at for its next review to
+    /// count as mature.
+    #[serde(default = "default_mature_days")]
+    pub mature_days: u32,
+}
+
+fn default_weeks() -> u32 {
+    12
+}
+
+fn default_mature_days() -> u32 {
+    21
+}
+
+#[derive(Debug, Serialize)]
+pub struct RetentionStats {
+    pub reviews: i64,
+    pub mature_reviews: i64,
+    pub mature_passed: i64,
+    /// `mature_passed / mature_reviews`, or `null` without mature reviews.
+    pub retention: Option<f64>,
+    pub lapses: i64,
+}
+
+impl From<RetentionCounts> for RetentionStats {
+    fn from(counts: RetentionCounts) -> Self {
+        Self {
+            reviews: counts.reviews,
+            mature_reviews: counts.mature_reviews,
+            mature_passed: counts.mature_passed,
+            retention: counts.retention(),
+            lapses: counts.lapses,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct WeeklyStats {
+    /// Monday, UTC.
+    pub week_start: NaiveDate,
+    #[serde(flatten)]
+    pub stats: RetentionStats,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct GradeCounts {
+    pub again: i64,
+    pub hard: i64,
+    pub good: i64,
+    pub easy: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IntervalBucket {
+    pub interval_index: usize,
+    pub cards: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SourceStats {
+    pub source: String,
+    #[serde(flatten)]
+    pub stats: RetentionStats,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagStats {
+    pub tag: String,
+    #[serde(flatten)]
+    pub stats: RetentionStats,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatsResponse {
+    pub since: DateTime<Utc>,
+    pub mature_days: u32,
+    #[serde(flatten)]
+    pub totals: RetentionStats,
+    pub average_review_gap_seconds: Option<f64>,
+    /// Every week since `since`, oldest first, including weeks without reviews.
+    pub weekly: Vec<WeeklyStats>,
+    pub grades: GradeCounts,
+    /// Current cards per interval index.
+    pub interval_histogram: Vec<IntervalBucket>,
+    pub sources: Vec<SourceStats>,
+    pub tags: Vec<TagStats>,
+}
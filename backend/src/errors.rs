@@ -24,24 +24,54 @@ pub enum AppError {
     MissingAuthCookie,
     #[error("Invalid session")]
     InvalidSession,
+    #[error("Session not found")]
+    SessionNotFound,
     #[error("Missing API token")]
     MissingApiToken,
     #[error("Invalid API token")]
     InvalidApiToken,
     #[error("Integration token not found")]
     IntegrationTokenNotFound,
+    #[error("Calendar feed not found")]
+    CalendarFeedNotFound,
+    #[error("Push subscription not found")]
+    PushSubscriptionNotFound,
     #[error("Invalid email")]
     InvalidEmail,
     #[error("Invalid input: {0}")]
     InvalidInput(String),
     #[error("Invalid or expired magic link")]
     InvalidOrExpiredMagicLink,
+    #[error("Invalid or expired account deletion token")]
+    InvalidOrExpiredDeletionToken,
+    #[error("Single sign-on is not configured")]
+    OidcNotConfigured,
+    #[error("Single sign-on failed: {0}")]
+    OidcLoginFailed(String),
+    #[error("Passkey rejected: {0}")]
+    PasskeyRejected(String),
+    #[error("Passkey not found")]
+    PasskeyNotFound,
     #[error("Card not found")]
     CardNotFound,
+    #[error("A card with key {0} already exists")]
+    CardKeyTaken(String),
+    #[error("Problem not found")]
+    ProblemNotFound,
     #[error("Settings not found")]
     SettingsNotFound,
+    #[error("Goal not found")]
+    GoalNotFound,
     #[error("Forbidden")]
     Forbidden,
+    #[error("API token is missing the {0} scope")]
+    InsufficientScope(String),
+    #[error("Idempotency key was already used with a different request")]
+    IdempotencyKeyReused,
+    #[error("A request with this idempotency key is still in progress")]
+    IdempotencyRequestInProgress,
+    #[error("Too many requests, retry in {0} seconds")]
+    RateLimited(u64),
     #[error("Internal error: {0}")]
     Internal(String),
 }
@@ -53,13 +83,28 @@ impl AppError {
             | Self::MissingAuthCookie
             | Self::InvalidSession
             | Self::InvalidOrExpiredMagicLink
+            | Self::OidcLoginFailed(_)
+            | Self::PasskeyRejected(_)
             | Self::MissingApiToken
             | Self::InvalidApiToken => StatusCode::UNAUTHORIZED,
-            Self::Forbidden => StatusCode::FORBIDDEN,
-            Self::InvalidEmail | Self::InvalidInput(_) => StatusCode::BAD_REQUEST,
-            Self::CardNotFound | Self::SettingsNotFound | Self::IntegrationTokenNotFound => {
-                StatusCode::NOT_FOUND
+            Self::Forbidden | Self::InsufficientScope(_) => StatusCode::FORBIDDEN,
+            Self::InvalidEmail | Self::InvalidInput(_) | Self::InvalidOrExpiredDeletionToken => {
+                StatusCode::BAD_REQUEST
             }
+            Self::CardNotFound
+            | Self::ProblemNotFound
+            | Self::SettingsNotFound
+            | Self::GoalNotFound
+            | Self::IntegrationTokenNotFound
+            | Self::CalendarFeedNotFound
+            | Self::PushSubscriptionNotFound
+            | Self::SessionNotFound
+            | Self::OidcNotConfigured
+            | Self::PasskeyNotFound => StatusCode::NOT_FOUND,
+            Self::CardKeyTaken(_)
+            | Self::IdempotencyKeyReused
+            | Self::IdempotencyRequestInProgress => StatusCode::CONFLICT,
+            Self::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
             Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
         };
         if status.is_server_error() {
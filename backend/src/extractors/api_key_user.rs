@@ -1,27 +1,85 @@
+use std::marker::PhantomData;
+
 use axum::{
     extract::{FromRef, FromRequestParts},
     http::request::Parts,
 };
 
-use crate::{app::state::AppState, controllers::api_key, models::User};
+use crate::{
+    app::state::AppState,
+    controllers::api_key,
+    models::{
+        ClientInfo, SCOPE_CARDS_READ, SCOPE_CARDS_WRITE, SCOPE_EVENTS_WRITE, SCOPE_EXPORT_READ,
+        SCOPE_REVIEWS_READ, SCOPE_REVIEWS_WRITE, User,
+    },
+};
+
+/// Marker for the integration token scope an extractor requires.
+pub trait ApiScope: Send + Sync + 'static {
+    const NAME: &'static str;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct EventsWrite;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ReviewsRead;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ReviewsWrite;
+
+#[derive(Debug, Clone, Copy)]
+pub struct CardsRead;
+
+#[derive(Debug, Clone, Copy)]
+pub struct CardsWrite;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ExportRead;
+
+impl ApiScope for EventsWrite {
+    const NAME: &'static str = SCOPE_EVENTS_WRITE;
+}
+
+impl ApiScope for ReviewsRead {
+    const NAME: &'static str = SCOPE_REVIEWS_READ;
+}
+
+impl ApiScope for ReviewsWrite {
+    const NAME: &'static str = SCOPE_REVIEWS_WRITE;
+}
+
+impl ApiScope for CardsRead {
+    const NAME: &'static str = SCOPE_CARDS_READ;
+}
+
+impl ApiScope for CardsWrite {
+    const NAME: &'static str = SCOPE_CARDS_WRITE;
+}
+
+impl ApiScope for ExportRead {
+    const NAME: &'static str = SCOPE_EXPORT_READ;
+}
 
 #[derive(Debug, Clone)]
-pub struct ApiKeyUser(pub User);
+pub struct ApiKeyUser<S: ApiScope>(pub User, pub PhantomData<S>);
 
-impl<S> FromRequestParts<S> for ApiKeyUser
+impl<S, St> FromRequestParts<St> for ApiKeyUser<S>
 where
-    S: Send + Sync,
-    AppState: FromRef<S>,
+    S: ApiScope,
+    St: Send + Sync,
+    AppState: FromRef<St>,
 {
     type Rejection = (axum::http::StatusCode, axum::Json<crate::errors::ApiError>);
 
-    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+    async fn from_request_parts(parts: &mut Parts, state: &St) -> Result<Self, Self::Rejection> {
         let app_state = AppState::from_ref(state);
+        let Ok(client) = ClientInfo::from_request_parts(parts, state).await;
         let user = app_state
             .integrations_service
-            .user_from_api_key(api_key(&parts.headers))
+            .user_from_api_key(api_key(&parts.headers), S::NAME, client)
             .await
             .map_err(|err| err.to_http())?;
-        Ok(Self(user))
+        Ok(Self(user, PhantomData))
     }
 }
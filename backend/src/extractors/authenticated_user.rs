@@ -3,12 +3,22 @@ use axum::{
     http::{HeaderMap, Method, request::Parts},
 };
 
-use crate::{app::state::AppState, controllers::auth_cookie, errors::AppError, models::User};
+use crate::{
+    app::state::AppState,
+    controllers::auth_cookie,
+    errors::AppError,
+    models::{Session, User},
+};
 
 #[derive(Debug, Clone)]
 pub struct AuthenticatedUser(pub User);
 
-impl<S> FromRequestParts<S> for AuthenticatedUser
+/// Like [`AuthenticatedUser`], but also exposes the session row behind the
+/// cookie for handlers that manage sessions.
+#[derive(Debug, Clone)]
+pub struct AuthenticatedSession(pub User, pub Session);
+
+impl<S> FromRequestParts<S> for AuthenticatedSession
 where
     S: Send + Sync,
     AppState: FromRef<S>,
@@ -21,11 +31,25 @@ where
             validate_origin(&parts.headers, &app_state.security.allowed_origins)
                 .map_err(|err| err.to_http())?;
         }
-        let user = app_state
+        let (user, session) = app_state
             .auth_service
             .user_from_jwt_cookie(auth_cookie(&parts.headers))
             .await
             .map_err(|err| err.to_http())?;
+        Ok(Self(user, session))
+    }
+}
+
+impl<S> FromRequestParts<S> for AuthenticatedUser
+where
+    S: Send + Sync,
+    AppState: FromRef<S>,
+{
+    type Rejection = (axum::http::StatusCode, axum::Json<crate::errors::ApiError>);
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let AuthenticatedSession(user, _) =
+            AuthenticatedSession::from_request_parts(parts, state).await?;
         Ok(Self(user))
     }
 }
@@ -0,0 +1,32 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::request::Parts,
+};
+
+use crate::{
+    app::state::AppState,
+    controllers::{client_ip, user_agent},
+    models::ClientInfo,
+};
+
+impl<S> FromRequestParts<S> for ClientInfo
+where
+    S: Send + Sync,
+    AppState: FromRef<S>,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let app_state = AppState::from_ref(state);
+        Ok(Self {
+            ip: client_ip(
+                &parts.headers,
+                &parts.extensions,
+                app_state.security.trust_proxy_headers,
+            ),
+            user_agent: user_agent(&parts.headers).map(ToOwned::to_owned),
+        })
+    }
+}
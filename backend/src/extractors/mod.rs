@@ -1,2 +1,4 @@
 pub mod api_key_user;
 pub mod authenticated_user;
+pub mod client_info;
+pub mod session_or_api_key_user;
@@ -0,0 +1,39 @@
+use std::marker::PhantomData;
+
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::request::Parts,
+};
+
+use crate::{
+    app::state::AppState,
+    controllers::api_key,
+    extractors::{
+        api_key_user::{ApiKeyUser, ApiScope},
+        authenticated_user::AuthenticatedUser,
+    },
+    models::User,
+};
+
+/// Accepts either the browser session cookie or an `X-API-Key` carrying the
+/// scope `S`, so CLI and editor integrations can share the web endpoints.
+#[derive(Debug, Clone)]
+pub struct SessionOrApiKeyUser<S: ApiScope>(pub User, pub PhantomData<S>);
+
+impl<S, St> FromRequestParts<St> for SessionOrApiKeyUser<S>
+where
+    S: ApiScope,
+    St: Send + Sync,
+    AppState: FromRef<St>,
+{
+    type Rejection = (axum::http::StatusCode, axum::Json<crate::errors::ApiError>);
+
+    async fn from_request_parts(parts: &mut Parts, state: &St) -> Result<Self, Self::Rejection> {
+        if api_key(&parts.headers).is_some() {
+            let ApiKeyUser(user, _) = ApiKeyUser::<S>::from_request_parts(parts, state).await?;
+            return Ok(Self(user, PhantomData));
+        }
+        let AuthenticatedUser(user) = AuthenticatedUser::from_request_parts(parts, state).await?;
+        Ok(Self(user, PhantomData))
+    }
+}
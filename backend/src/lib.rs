@@ -1,4 +1,7 @@
+pub mod activity;
 pub mod app;
+pub mod catalog;
+pub mod cloze;
 pub mod controllers;
 pub mod dto;
 pub mod errors;
@@ -7,6 +10,7 @@ pub mod models;
 pub mod ports;
 pub mod repositories;
 pub mod services;
+pub mod sources;
 pub mod srs;
 pub mod store;
 pub mod workers;
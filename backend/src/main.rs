@@ -1,9 +1,17 @@
-use tracing::info;
+use std::net::SocketAddr;
+
+use tracing::{info, warn};
 
 use srs_anything_backend::{
-    app::{bootstrap::build_state_from_provider, db::connect_from_env, routes::app_router},
+    app::{
+        bootstrap::{build_state_from_provider, seed_problem_catalog},
+        db::connect_from_env,
+        routes::app_router,
+    },
     ports::schedule_provider::EnvScheduleProvider,
-    workers::email_digest_worker::email_digest_worker,
+    workers::{
+        email_digest_worker::email_digest_worker, jwt_key_reload_worker::jwt_key_reload_worker,
+    },
 };
 
 #[tokio::main]
@@ -21,7 +29,29 @@ async fn main() {
     let db = connect_from_env().await.expect("connect to postgres");
     let schedule_provider = EnvScheduleProvider;
     let state = build_state_from_provider(&schedule_provider, db);
+    if std::env::args().nth(1).as_deref() == Some("seed-catalog") {
+        match seed_problem_catalog(&state).await {
+            Ok(report) => println!(
+                "{}",
+                serde_json::to_string_pretty(&report).expect("serialize seed report")
+            ),
+            Err(err) => {
+                eprintln!("seeding the problem catalog failed: {err}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    let seed_on_startup = std::env::var("SEED_PROBLEM_CATALOG")
+        .map(|value| !matches!(value.as_str(), "0" | "false" | "FALSE"))
+        .unwrap_or(true);
+    if seed_on_startup && let Err(err) = seed_problem_catalog(&state).await {
+        warn!(error = %err, "problem_catalog_seed_failed");
+    }
     tokio::spawn(email_digest_worker(state.clone()));
+    if let Some(interval) = state.auth_service.jwt_keys_reload_interval() {
+        tokio::spawn(jwt_key_reload_worker(state.clone(), interval));
+    }
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000")
         .await
@@ -31,7 +61,10 @@ async fn main() {
         listen_addr = "0.0.0.0:3000",
         "backend_started"
     );
-    axum::serve(listener, app_router(state))
-        .await
-        .expect("serve app");
+    axum::serve(
+        listener,
+        app_router(state).into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .expect("serve app");
 }
@@ -1,4 +1,4 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
@@ -24,10 +24,124 @@ pub struct MagicLinkToken {
 pub struct Session {
     pub id: i64,
     pub user_id: i64,
+    /// Hash of the `jti` claim carried by the session's JWT.
     pub session_token_hash: String,
     pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+}
+
+/// Links an external OpenID Connect account (`issuer`, `subject`) to a user.
+#[derive(Debug, Clone)]
+pub struct UserIdentity {
+    pub id: i64,
+    pub user_id: i64,
+    pub issuer: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_login_at: DateTime<Utc>,
 }
 
+/// Pending OIDC authorization-code login, keyed by the hashed `state`.
+#[derive(Debug, Clone)]
+pub struct OidcAuthRequest {
+    pub id: i64,
+    pub state_hash: String,
+    pub nonce: String,
+    pub pkce_verifier: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A WebAuthn credential registered by a user. `public_key` holds the raw
+/// COSE_Key from the authenticator.
+#[derive(Debug, Clone)]
+pub struct PasskeyCredential {
+    pub id: i64,
+    pub user_id: i64,
+    /// Base64url credential id, as browsers report it.
+    pub credential_id: String,
+    pub public_key: Vec<u8>,
+    pub algorithm: i64,
+    pub sign_count: i64,
+    pub label: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebauthnCeremony {
+    Registration,
+    Authentication,
+}
+
+impl WebauthnCeremony {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Registration => "registration",
+            Self::Authentication => "authentication",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "registration" => Some(Self::Registration),
+            "authentication" => Some(Self::Authentication),
+            _ => None,
+        }
+    }
+}
+
+/// Single-use challenge for a pending WebAuthn ceremony. Registration
+/// challenges are bound to the user adding the passkey.
+#[derive(Debug, Clone)]
+pub struct WebauthnChallenge {
+    pub id: i64,
+    pub challenge_hash: String,
+    pub ceremony: WebauthnCeremony,
+    pub user_id: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Pending confirmation for deleting an account, keyed by the hashed token
+/// the user has to send back.
+#[derive(Debug, Clone)]
+pub struct AccountDeletionRequest {
+    pub id: i64,
+    pub user_id: i64,
+    pub token_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Where a request came from, recorded on sessions and API token usage.
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+pub const SCOPE_EVENTS_WRITE: &str = "events:write";
+pub const SCOPE_REVIEWS_READ: &str = "reviews:read";
+pub const SCOPE_REVIEWS_WRITE: &str = "reviews:write";
+pub const SCOPE_CARDS_READ: &str = "cards:read";
+pub const SCOPE_CARDS_WRITE: &str = "cards:write";
+pub const SCOPE_EXPORT_READ: &str = "export:read";
+
+pub const INTEGRATION_SCOPES: [&str; 6] = [
+    SCOPE_EVENTS_WRITE,
+    SCOPE_REVIEWS_READ,
+    SCOPE_REVIEWS_WRITE,
+    SCOPE_CARDS_READ,
+    SCOPE_CARDS_WRITE,
+    SCOPE_EXPORT_READ,
+];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IntegrationToken {
     pub id: i64,
@@ -39,6 +153,24 @@ pub struct IntegrationToken {
     pub expires_at: Option<DateTime<Utc>>,
     pub revoked_at: Option<DateTime<Utc>>,
     pub last_used_at: Option<DateTime<Utc>>,
+    pub last_used_ip: Option<String>,
+    pub last_used_user_agent: Option<String>,
+    /// Hash of the secret replaced by the latest rotation, accepted until
+    /// `previous_token_expires_at` so clients can roll over without downtime.
+    pub previous_token_hash: Option<String>,
+    pub previous_token_expires_at: Option<DateTime<Utc>>,
+    pub expiry_notified_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalendarFeedToken {
+    pub id: i64,
+    pub user_id: i64,
+    pub token_hash: String,
+    pub label: String,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -61,16 +193,192 @@ pub struct ProblemEvent {
     pub dedup_key: String,
 }
 
+/// Source recorded on user-authored cards, which have no coding platform.
+pub const CUSTOM_CARD_SOURCE: &str = "custom";
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CardKind {
+    /// Created from a coding-platform event; the problem itself is the prompt.
+    #[default]
+    Problem,
+    /// Question on the front, answer on the back.
+    Basic,
+    /// Text on the front with `{{c1::...}}` deletions to recall.
+    Cloze,
+}
+
+impl CardKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Problem => "problem",
+            Self::Basic => "basic",
+            Self::Cloze => "cloze",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "problem" => Some(Self::Problem),
+            "basic" => Some(Self::Basic),
+            "cloze" => Some(Self::Cloze),
+            _ => None,
+        }
+    }
+}
+
+/// Catalog entry shared by every user's card for the same problem.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Problem {
+    pub id: i64,
+    pub source: String,
+    pub slug: String,
+    /// `None` until the seeded dataset covers the problem.
+    pub title: Option<String>,
+    /// Canonical URL, as built by the source registry.
+    pub url: String,
+    /// In the platform's own terms, e.g. `Easy` or a Codeforces rating.
+    pub difficulty: Option<String>,
+    pub tags: Vec<String>,
+    pub aliases: Vec<String>,
+    /// Version of the bundled dataset that last wrote this entry; `None` for
+    /// problems only known from ingestion.
+    pub catalog_version: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A validated entry of the bundled catalog dataset, ready to upsert.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatalogProblemInput {
+    pub source: String,
+    pub slug: String,
+    pub title: String,
+    pub url: String,
+    pub difficulty: Option<String>,
+    pub tags: Vec<String>,
+    pub aliases: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CatalogSeedSummary {
+    pub inserted: usize,
+    pub updated: usize,
+    /// Entries already stored exactly as in the dataset.
+    pub unchanged: usize,
+}
+
+/// A problem users have cards for that the dataset has no entry for.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UncataloguedProblem {
+    pub source: String,
+    pub slug: String,
+    pub title: String,
+    pub learners: i64,
+}
+
+/// Any schedulable card. Problem cards carry `source`/`problem_slug`/`url`
+/// and link to the catalog, whose title, URL, tags and difficulty they show;
+/// authored cards use `CUSTOM_CARD_SOURCE`, a per-user key as the slug, an
+/// empty URL and their content in `front`/`back`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProblemCard {
     pub id: i64,
     pub user_id: i64,
+    #[serde(default)]
+    pub kind: CardKind,
     pub source: String,
     pub problem_slug: String,
     pub title: String,
     pub url: String,
+    #[serde(default)]
+    pub front: Option<String>,
+    #[serde(default)]
+    pub back: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub problem_id: Option<i64>,
+    #[serde(default)]
+    pub difficulty: Option<String>,
     pub interval_index: usize,
     pub next_due_at: DateTime<Utc>,
+    /// When the card entered the deck: first ingestion, creation or import.
+    pub created_at: DateTime<Utc>,
+    /// `again` grades since the card was created or last restudied.
+    #[serde(default)]
+    pub lapses: u32,
+    /// Set when `lapses` reached the leech threshold.
+    #[serde(default)]
+    pub leech_at: Option<DateTime<Utc>>,
+    /// Suspended cards stay out of review queues until restudied.
+    #[serde(default)]
+    pub suspended_at: Option<DateTime<Utc>>,
+}
+
+/// Tag added to cards flagged as leeches by [`LeechAction::Tag`].
+pub const LEECH_TAG: &str = "leech";
+
+/// What happens to a card when it becomes a leech. Every leech is listed
+/// under `/cards/leeches` whatever the action.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LeechAction {
+    /// Adds [`LEECH_TAG`] to the card.
+    #[default]
+    Tag,
+    /// Takes the card out of review queues.
+    Suspend,
+    /// Sends the owner a notification.
+    Notify,
+}
+
+impl LeechAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Tag => "tag",
+            Self::Suspend => "suspend",
+            Self::Notify => "notify",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "tag" => Some(Self::Tag),
+            "suspend" => Some(Self::Suspend),
+            "notify" => Some(Self::Notify),
+            _ => None,
+        }
+    }
+}
+
+pub const DEFAULT_LEECH_THRESHOLD: u32 = 8;
+
+/// When a card becomes a leech and what happens to it then.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LeechPolicy {
+    /// Lapses after which a card is flagged.
+    pub threshold: u32,
+    pub action: LeechAction,
+}
+
+impl Default for LeechPolicy {
+    fn default() -> Self {
+        Self {
+            threshold: DEFAULT_LEECH_THRESHOLD,
+            action: LeechAction::default(),
+        }
+    }
+}
+
+/// Content of an authored card, as created or edited through `/cards`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CardContent {
+    pub kind: CardKind,
+    pub title: String,
+    pub front: String,
+    pub back: Option<String>,
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -83,11 +391,164 @@ pub struct ReviewEvent {
     pub next_due_at: DateTime<Utc>,
 }
 
+/// A saved grade, plus the card when that grade made it a leech.
+#[derive(Debug, Clone)]
+pub struct GradedReview {
+    pub review: ReviewEvent,
+    pub leech: Option<ProblemCard>,
+}
+
+/// Review counts for one slice of the stats. A review is mature when the
+/// card's previous review had scheduled it at least the maturity threshold
+/// ahead, and a lapse is an `again` right after a passing review.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetentionCounts {
+    pub reviews: i64,
+    pub mature_reviews: i64,
+    /// Mature reviews graded anything but `again`.
+    pub mature_passed: i64,
+    pub lapses: i64,
+}
+
+impl RetentionCounts {
+    /// Share of mature reviews that passed; `None` without mature reviews.
+    pub fn retention(&self) -> Option<f64> {
+        (self.mature_reviews > 0).then(|| self.mature_passed as f64 / self.mature_reviews as f64)
+    }
+}
+
+/// A user's reviews since some instant, aggregated. Weeks start on Monday
+/// (UTC) and only weeks with reviews are listed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReviewStats {
+    pub totals: RetentionCounts,
+    /// Mean time since the same card's previous review.
+    pub average_review_gap_seconds: Option<f64>,
+    pub weeks: Vec<(NaiveDate, RetentionCounts)>,
+    pub grades: Vec<(Grade, i64)>,
+    pub sources: Vec<(String, RetentionCounts)>,
+    pub tags: Vec<(String, RetentionCounts)>,
+    /// Current cards per interval index, regardless of `since`.
+    pub interval_indexes: Vec<(usize, i64)>,
+}
+
+/// What a goal counts, per calendar day in the user's timezone.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalMetric {
+    Reviews,
+    /// Distinct problems with a solved event.
+    Solved,
+    NewCards,
+}
+
+impl GoalMetric {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Reviews => "reviews",
+            Self::Solved => "solved",
+            Self::NewCards => "new_cards",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "reviews" => Some(Self::Reviews),
+            "solved" => Some(Self::Solved),
+            "new_cards" => Some(Self::NewCards),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GoalPeriod {
+    Day,
+    /// Monday to Sunday.
+    Week,
+}
+
+impl GoalPeriod {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Day => "day",
+            Self::Week => "week",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "day" => Some(Self::Day),
+            "week" => Some(Self::Week),
+            _ => None,
+        }
+    }
+}
+
+/// At most one goal per metric and period for each user.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Goal {
+    pub id: i64,
+    pub user_id: i64,
+    pub metric: GoalMetric,
+    pub period: GoalPeriod,
+    pub target: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Activity on one local calendar day; days without any are not stored.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DailyActivity {
+    pub date: NaiveDate,
+    pub reviews: i64,
+    pub solved: i64,
+    pub new_cards: i64,
+}
+
+impl DailyActivity {
+    pub fn empty(date: NaiveDate) -> Self {
+        Self {
+            date,
+            reviews: 0,
+            solved: 0,
+            new_cards: 0,
+        }
+    }
+
+    pub fn count(&self, metric: GoalMetric) -> i64 {
+        match metric {
+            GoalMetric::Reviews => self.reviews,
+            GoalMetric::Solved => self.solved,
+            GoalMetric::NewCards => self.new_cards,
+        }
+    }
+
+    /// Reviews and solves keep a streak alive; new cards alone do not.
+    pub fn is_active(&self) -> bool {
+        self.reviews > 0 || self.solved > 0
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NotificationPreference {
     pub user_id: i64,
     pub email_enabled: bool,
     pub digest_hour_utc: u8,
+    /// IANA name; calendar days for activity and goals are counted in it.
+    pub timezone: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushSubscription {
+    pub id: i64,
+    pub user_id: i64,
+    pub endpoint: String,
+    pub p256dh: String,
+    pub auth: String,
+    pub device_label: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -110,6 +571,148 @@ pub struct IngestProblemInput {
     pub occurred_at: DateTime<Utc>,
 }
 
+/// Response recorded for an `Idempotency-Key` so retries can be answered
+/// without running the handler again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct IdempotencyRecord {
+    pub user_id: i64,
+    pub key: String,
+    pub request_hash: String,
+    /// `None` while the first request is still being handled.
+    pub response: Option<StoredResponse>,
+    pub claimed_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub enum IdempotencyClaim {
+    /// The key was free (or had expired) and now belongs to this request.
+    Claimed,
+    Existing(IdempotencyRecord),
+    /// Another request took the key or let it go while this one was looking;
+    /// trying again settles it.
+    Contended,
+}
+
+/// Result of writing one event of a batch.
+#[derive(Debug, Clone)]
+pub struct IngestedEvent {
+    pub event: ProblemEvent,
+    /// `false` when an event with the same dedup key already existed.
+    pub created: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum BatchIngestOutcome {
+    Created(ProblemEvent),
+    Deduplicated(ProblemEvent),
+    Rejected(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    /// Keep the account's data and fold the archive into it.
+    Merge,
+    /// Drop the account's cards, events and reviews before importing, and its
+    /// goals when the archive has them.
+    Replace,
+}
+
+/// Validated archive contents ready to be written for one user. Cards are
+/// referenced by their id inside the archive; the repository assigns real ids.
+#[derive(Debug, Clone)]
+pub struct ArchiveImport {
+    pub mode: ImportMode,
+    /// `None` leaves the account's notification settings untouched.
+    pub settings: Option<ImportedSettings>,
+    /// `None` leaves the account's goals untouched, even when replacing.
+    pub goals: Option<Vec<ImportedGoal>>,
+    pub cards: Vec<ImportedCard>,
+    pub events: Vec<ImportedEvent>,
+    pub reviews: Vec<ImportedReview>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportedSettings {
+    pub email_enabled: bool,
+    pub digest_hour_utc: u8,
+    /// `None` keeps the account's timezone.
+    pub timezone: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportedCard {
+    pub archive_id: i64,
+    pub kind: CardKind,
+    pub source: String,
+    pub problem_slug: String,
+    pub title: String,
+    pub url: String,
+    /// Canonical URL from the source registry, set when the card is a problem
+    /// on a registered source. The problem is then added to the catalog.
+    pub catalog_url: Option<String>,
+    pub front: Option<String>,
+    pub back: Option<String>,
+    pub tags: Vec<String>,
+    pub interval_index: usize,
+    pub next_due_at: DateTime<Utc>,
+    /// `None` when the archive does not know; the import time is used.
+    pub created_at: Option<DateTime<Utc>>,
+    /// Latest review of this card in the archive; decides merge conflicts.
+    pub last_reviewed_at: Option<DateTime<Utc>>,
+    /// `None` when the archive does not know; the imported `again` reviews
+    /// are counted instead. `leech_at` and `suspended_at` only apply with it.
+    pub lapses: Option<u32>,
+    pub leech_at: Option<DateTime<Utc>>,
+    pub suspended_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportedGoal {
+    pub metric: GoalMetric,
+    pub period: GoalPeriod,
+    pub target: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportedEvent {
+    pub source: String,
+    pub problem_slug: String,
+    pub title: String,
+    pub url: String,
+    pub status: ProblemStatus,
+    pub occurred_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportedReview {
+    pub archive_card_id: i64,
+    pub grade: Grade,
+    pub reviewed_at: DateTime<Utc>,
+    pub next_due_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ArchiveImportSummary {
+    pub cards_created: usize,
+    /// Archive cards that matched an existing `(source, problem_slug)`.
+    pub cards_merged: usize,
+    pub events_imported: usize,
+    pub reviews_imported: usize,
+    /// Reviews already present on the account.
+    pub reviews_skipped: usize,
+    /// Goals the account did not have yet.
+    pub goals_imported: usize,
+}
+
 pub fn hash_token(token: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(token.as_bytes());
@@ -0,0 +1,580 @@
+//! Reads and writes Anki `.apkg` packages: a zip holding a legacy (schema 11)
+//! SQLite collection plus a media manifest.
+
+use std::{collections::HashMap, path::Path};
+
+use sea_orm::{
+    ConnectOptions, ConnectionTrait, Database, DatabaseConnection, DbBackend, QueryResult,
+    Statement, TransactionTrait, Value,
+};
+use serde_json::{Value as JsonValue, json};
+use sha1::{Digest, Sha1};
+use thiserror::Error;
+
+use super::zip::{ZipError, ZipReader, write_archive};
+
+/// Collections are unpacked to disk before SQLite opens them, so cap the
+/// inflated size independently of the request body limit.
+const MAX_COLLECTION_BYTES: usize = 256 * 1024 * 1024;
+/// Field separator inside `notes.flds`.
+const FIELD_SEPARATOR: char = '\x1f';
+
+/// Note type written on export; its fields are what the importer looks for
+/// first when reading a package back.
+pub const SRS_NOTE_TYPE: &str = "SRS Anything";
+pub const SRS_NOTE_FIELDS: [&str; 4] = ["Title", "URL", "Source", "Slug"];
+/// Fixed so repeated exports update the same note type inside Anki.
+const SRS_NOTE_TYPE_ID: i64 = 1_700_000_000_000;
+
+pub const CARD_TYPE_NEW: i64 = 0;
+pub const CARD_TYPE_REVIEW: i64 = 2;
+/// Queue whose `due` is a unix timestamp rather than a day number.
+pub const QUEUE_INTRADAY_LEARNING: i64 = 1;
+
+#[derive(Debug, Error)]
+pub enum AnkiError {
+    #[error(transparent)]
+    Zip(#[from] ZipError),
+    #[error("package has no collection.anki21 or collection.anki2")]
+    MissingCollection,
+    #[error(
+        "package only contains the newer collection.anki21b format, export it again with \"Support older Anki versions\" ticked"
+    )]
+    UnsupportedFormat,
+    #[error("collection database error: {0}")]
+    Database(String),
+    #[error("collection is malformed: {0}")]
+    Malformed(String),
+    #[error("temporary file error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl From<sea_orm::DbErr> for AnkiError {
+    fn from(err: sea_orm::DbErr) -> Self {
+        Self::Database(err.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AnkiCollection {
+    /// Collection creation time in unix seconds; review card `due` values
+    /// count days from here.
+    pub created_at: i64,
+    pub notes: Vec<AnkiNote>,
+    pub cards: Vec<AnkiCard>,
+    pub revlog: Vec<AnkiReview>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AnkiNote {
+    pub id: i64,
+    pub guid: String,
+    pub note_type: String,
+    /// Field names paired with their values, in note type order.
+    pub fields: Vec<(String, String)>,
+}
+
+impl AnkiNote {
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AnkiCard {
+    pub id: i64,
+    pub note_id: i64,
+    pub card_type: i64,
+    pub queue: i64,
+    pub due: i64,
+    /// Days for review cards, negative seconds while learning.
+    pub interval: i64,
+    pub factor: i64,
+    pub reps: i64,
+    pub lapses: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct AnkiReview {
+    /// Review time in unix milliseconds.
+    pub id: i64,
+    pub card_id: i64,
+    /// 1 = again, 2 = hard, 3 = good, 4 = easy.
+    pub ease: i64,
+    /// Positive days or negative seconds, like [`AnkiCard::interval`].
+    pub interval: i64,
+    pub last_interval: i64,
+    pub factor: i64,
+    pub time_ms: i64,
+    pub review_type: i64,
+}
+
+/// Unpacks an `.apkg` and loads its notes, cards and review log.
+pub async fn read_package(package: &[u8]) -> Result<AnkiCollection, AnkiError> {
+    let mut zip = ZipReader::new(package)?;
+    // Newer exports keep a stub `collection.anki2` next to the real one.
+    let collection = match zip.read("collection.anki21", MAX_COLLECTION_BYTES)? {
+        Some(collection) => collection,
+        None => match zip.read("collection.anki2", MAX_COLLECTION_BYTES)? {
+            Some(collection) => collection,
+            None if zip.names().any(|name| name == "collection.anki21b") => {
+                return Err(AnkiError::UnsupportedFormat);
+            }
+            None => return Err(AnkiError::MissingCollection),
+        },
+    };
+
+    let file = tempfile::NamedTempFile::new()?;
+    std::fs::write(file.path(), &collection)?;
+    let db = connect(file.path(), "ro").await?;
+    let result = read_collection(&db).await;
+    db.close().await?;
+    result
+}
+
+/// Builds an `.apkg` with a single deck holding every note in `collection`.
+/// Notes are written with the [`SRS_NOTE_TYPE`] note type regardless of the
+/// type they were read with.
+pub async fn write_package(
+    deck_name: &str,
+    collection: &AnkiCollection,
+    now_ms: i64,
+) -> Result<Vec<u8>, AnkiError> {
+    let file = tempfile::NamedTempFile::new()?;
+    let db = connect(file.path(), "rwc").await?;
+    let result = write_collection(&db, deck_name, collection, now_ms).await;
+    db.close().await?;
+    result?;
+
+    let collection = std::fs::read(file.path())?;
+    Ok(write_archive(&[
+        ("collection.anki2", &collection),
+        ("media", b"{}"),
+    ])?)
+}
+
+async fn connect(path: &Path, mode: &str) -> Result<DatabaseConnection, AnkiError> {
+    let mut options = ConnectOptions::new(format!("sqlite://{}?mode={mode}", path.display()));
+    options.max_connections(1).sqlx_logging(false);
+    Ok(Database::connect(options).await?)
+}
+
+async fn read_collection(db: &DatabaseConnection) -> Result<AnkiCollection, AnkiError> {
+    let col = db
+        .query_one(Statement::from_string(
+            DbBackend::Sqlite,
+            "SELECT crt, models FROM col LIMIT 1",
+        ))
+        .await?
+        .ok_or_else(|| AnkiError::Malformed("col table is empty".to_owned()))?;
+    let created_at: i64 = col.try_get("", "crt")?;
+    let models: String = col.try_get("", "models")?;
+    let note_types = parse_note_types(&models)?;
+
+    let notes = db
+        .query_all(Statement::from_string(
+            DbBackend::Sqlite,
+            "SELECT id, guid, mid, flds FROM notes ORDER BY id",
+        ))
+        .await?
+        .into_iter()
+        .map(|row| note_from_row(&row, &note_types))
+        .collect::<Result<Vec<_>, _>>()?;
+    let cards = db
+        .query_all(Statement::from_string(
+            DbBackend::Sqlite,
+            "SELECT id, nid, type, queue, due, ivl, factor, reps, lapses FROM cards ORDER BY id",
+        ))
+        .await?
+        .iter()
+        .map(card_from_row)
+        .collect::<Result<Vec<_>, _>>()?;
+    let revlog = db
+        .query_all(Statement::from_string(
+            DbBackend::Sqlite,
+            "SELECT id, cid, ease, ivl, lastIvl, factor, time, type FROM revlog ORDER BY id",
+        ))
+        .await?
+        .iter()
+        .map(review_from_row)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(AnkiCollection {
+        created_at,
+        notes,
+        cards,
+        revlog,
+    })
+}
+
+struct NoteType {
+    name: String,
+    fields: Vec<String>,
+}
+
+/// `col.models` is a JSON object keyed by note type id.
+fn parse_note_types(models: &str) -> Result<HashMap<i64, NoteType>, AnkiError> {
+    let models: HashMap<String, JsonValue> = serde_json::from_str(models)
+        .map_err(|err| AnkiError::Malformed(format!("invalid note types: {err}")))?;
+    let mut note_types = HashMap::new();
+    for (id, model) in models {
+        let Ok(id) = id.parse::<i64>() else { continue };
+        let mut fields = model["flds"]
+            .as_array()
+            .map(|fields| {
+                fields
+                    .iter()
+                    .map(|field| {
+                        (
+                            field["ord"].as_i64().unwrap_or_default(),
+                            field["name"].as_str().unwrap_or_default().to_owned(),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        fields.sort_by_key(|(ord, _)| *ord);
+        note_types.insert(
+            id,
+            NoteType {
+                name: model["name"].as_str().unwrap_or_default().to_owned(),
+                fields: fields.into_iter().map(|(_, name)| name).collect(),
+            },
+        );
+    }
+    Ok(note_types)
+}
+
+fn note_from_row(
+    row: &QueryResult,
+    note_types: &HashMap<i64, NoteType>,
+) -> Result<AnkiNote, AnkiError> {
+    let note_type_id: i64 = row.try_get("", "mid")?;
+    let note_type = note_types.get(&note_type_id);
+    let flds: String = row.try_get("", "flds")?;
+    let fields = flds
+        .split(FIELD_SEPARATOR)
+        .enumerate()
+        .map(|(index, value)| {
+            let name = note_type
+                .and_then(|note_type| note_type.fields.get(index).cloned())
+                .unwrap_or_else(|| format!("Field {}", index + 1));
+            (name, value.to_owned())
+        })
+        .collect();
+    Ok(AnkiNote {
+        id: row.try_get("", "id")?,
+        guid: row.try_get("", "guid")?,
+        note_type: note_type
+            .map(|note_type| note_type.name.clone())
+            .unwrap_or_default(),
+        fields,
+    })
+}
+
+fn card_from_row(row: &QueryResult) -> Result<AnkiCard, AnkiError> {
+    Ok(AnkiCard {
+        id: row.try_get("", "id")?,
+        note_id: row.try_get("", "nid")?,
+        card_type: row.try_get("", "type")?,
+        queue: row.try_get("", "queue")?,
+        due: row.try_get("", "due")?,
+        interval: row.try_get("", "ivl")?,
+        factor: row.try_get("", "factor")?,
+        reps: row.try_get("", "reps")?,
+        lapses: row.try_get("", "lapses")?,
+    })
+}
+
+fn review_from_row(row: &QueryResult) -> Result<AnkiReview, AnkiError> {
+    Ok(AnkiReview {
+        id: row.try_get("", "id")?,
+        card_id: row.try_get("", "cid")?,
+        ease: row.try_get("", "ease")?,
+        interval: row.try_get("", "ivl")?,
+        last_interval: row.try_get("", "lastIvl")?,
+        factor: row.try_get("", "factor")?,
+        time_ms: row.try_get("", "time")?,
+        review_type: row.try_get("", "type")?,
+    })
+}
+
+/// Legacy schema 11, which every Anki release since 2.1 can still import.
+const SCHEMA: [&str; 10] = [
+    "CREATE TABLE col (id integer primary key, crt integer not null, mod integer not null, scm integer not null, ver integer not null, dty integer not null, usn integer not null, ls integer not null, conf text not null, models text not null, decks text not null, dconf text not null, tags text not null)",
+    "CREATE TABLE notes (id integer primary key, guid text not null, mid integer not null, mod integer not null, usn integer not null, tags text not null, flds text not null, sfld integer not null, csum integer not null, flags integer not null, data text not null)",
+    "CREATE TABLE cards (id integer primary key, nid integer not null, did integer not null, ord integer not null, mod integer not null, usn integer not null, type integer not null, queue integer not null, due integer not null, ivl integer not null, factor integer not null, reps integer not null, lapses integer not null, left integer not null, odue integer not null, odid integer not null, flags integer not null, data text not null)",
+    "CREATE TABLE revlog (id integer primary key, cid integer not null, usn integer not null, ease integer not null, ivl integer not null, lastIvl integer not null, factor integer not null, time integer not null, type integer not null)",
+    "CREATE TABLE graves (usn integer not null, oid integer not null, type integer not null)",
+    "CREATE INDEX ix_notes_usn ON notes (usn)",
+    "CREATE INDEX ix_cards_usn ON cards (usn)",
+    "CREATE INDEX ix_revlog_usn ON revlog (usn)",
+    "CREATE INDEX ix_cards_nid ON cards (nid)",
+    "CREATE INDEX ix_revlog_cid ON revlog (cid)",
+];
+
+async fn write_collection(
+    db: &DatabaseConnection,
+    deck_name: &str,
+    collection: &AnkiCollection,
+    now_ms: i64,
+) -> Result<(), AnkiError> {
+    let now_secs = now_ms / 1000;
+    let deck_id = now_ms;
+    let tx = db.begin().await?;
+    for statement in SCHEMA {
+        tx.execute_unprepared(statement).await?;
+    }
+
+    tx.execute(Statement::from_sql_and_values(
+        DbBackend::Sqlite,
+        "INSERT INTO col (id, crt, mod, scm, ver, dty, usn, ls, conf, models, decks, dconf, tags) VALUES (1, ?, ?, ?, 11, 0, 0, 0, ?, ?, ?, ?, '{}')",
+        vec![
+            Value::from(collection.created_at),
+            Value::from(now_ms),
+            Value::from(now_ms),
+            Value::from(collection_config(deck_id).to_string()),
+            Value::from(note_type_json(deck_id, now_secs).to_string()),
+            Value::from(decks_json(deck_id, deck_name, now_secs).to_string()),
+            Value::from(deck_config_json(now_secs).to_string()),
+        ],
+    ))
+    .await?;
+
+    for note in &collection.notes {
+        let values = SRS_NOTE_FIELDS
+            .iter()
+            .map(|name| note.field(name).unwrap_or_default())
+            .collect::<Vec<_>>();
+        let sort_field = values[0].to_owned();
+        tx.execute(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            "INSERT INTO notes (id, guid, mid, mod, usn, tags, flds, sfld, csum, flags, data) VALUES (?, ?, ?, ?, -1, '', ?, ?, ?, 0, '')",
+            vec![
+                Value::from(note.id),
+                Value::from(note.guid.clone()),
+                Value::from(SRS_NOTE_TYPE_ID),
+                Value::from(now_secs),
+                Value::from(values.join(&FIELD_SEPARATOR.to_string())),
+                Value::from(sort_field.clone()),
+                Value::from(field_checksum(&sort_field)),
+            ],
+        ))
+        .await?;
+    }
+
+    for card in &collection.cards {
+        tx.execute(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            "INSERT INTO cards (id, nid, did, ord, mod, usn, type, queue, due, ivl, factor, reps, lapses, left, odue, odid, flags, data) VALUES (?, ?, ?, 0, ?, -1, ?, ?, ?, ?, ?, ?, ?, 0, 0, 0, 0, '')",
+            vec![
+                Value::from(card.id),
+                Value::from(card.note_id),
+                Value::from(deck_id),
+                Value::from(now_secs),
+                Value::from(card.card_type),
+                Value::from(card.queue),
+                Value::from(card.due),
+                Value::from(card.interval),
+                Value::from(card.factor),
+                Value::from(card.reps),
+                Value::from(card.lapses),
+            ],
+        ))
+        .await?;
+    }
+
+    for review in &collection.revlog {
+        tx.execute(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            "INSERT INTO revlog (id, cid, usn, ease, ivl, lastIvl, factor, time, type) VALUES (?, ?, -1, ?, ?, ?, ?, ?, ?)",
+            vec![
+                Value::from(review.id),
+                Value::from(review.card_id),
+                Value::from(review.ease),
+                Value::from(review.interval),
+                Value::from(review.last_interval),
+                Value::from(review.factor),
+                Value::from(review.time_ms),
+                Value::from(review.review_type),
+            ],
+        ))
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Anki's duplicate check compares the first 8 hex digits of the SHA-1 of
+/// the sort field.
+fn field_checksum(value: &str) -> i64 {
+    let digest = Sha1::digest(value.as_bytes());
+    i64::from(u32::from_be_bytes([
+        digest[0], digest[1], digest[2], digest[3],
+    ]))
+}
+
+fn collection_config(deck_id: i64) -> JsonValue {
+    json!({
+        "activeDecks": [deck_id],
+        "curDeck": deck_id,
+        "newSpread": 0,
+        "collapseTime": 1200,
+        "timeLim": 0,
+        "estTimes": true,
+        "dueCounts": true,
+        "curModel": SRS_NOTE_TYPE_ID.to_string(),
+        "nextPos": 1,
+        "sortType": "noteFld",
+        "sortBackwards": false,
+        "addToCur": true
+    })
+}
+
+fn note_type_json(deck_id: i64, now_secs: i64) -> JsonValue {
+    let fields = SRS_NOTE_FIELDS
+        .iter()
+        .enumerate()
+        .map(|(ord, name)| {
+            json!({
+                "name": name,
+                "ord": ord,
+                "sticky": false,
+                "rtl": false,
+                "font": "Arial",
+                "size": 20,
+                "media": []
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        SRS_NOTE_TYPE_ID.to_string(): {
+            "id": SRS_NOTE_TYPE_ID,
+            "name": SRS_NOTE_TYPE,
+            "type": 0,
+            "mod": now_secs,
+            "usn": -1,
+            "sortf": 0,
+            "did": deck_id,
+            "tmpls": [{
+                "name": "Problem",
+                "ord": 0,
+                "qfmt": "{{Title}}<br><small>{{Source}}</small>",
+                "afmt": "{{FrontSide}}<hr id=answer><a href=\"{{URL}}\">{{URL}}</a>",
+                "bqfmt": "",
+                "bafmt": "",
+                "did": null
+            }],
+            "flds": fields,
+            "css": ".card { font-family: arial; font-size: 20px; text-align: center; }",
+            "latexPre": "\\documentclass[12pt]{article}\n\\begin{document}\n",
+            "latexPost": "\\end{document}",
+            "tags": [],
+            "vers": [],
+            "req": [[0, "any", [0]]]
+        }
+    })
+}
+
+fn decks_json(deck_id: i64, deck_name: &str, now_secs: i64) -> JsonValue {
+    let deck = |id: i64, name: &str| {
+        json!({
+            "id": id,
+            "name": name,
+            "desc": "",
+            "mod": now_secs,
+            "usn": -1,
+            "collapsed": false,
+            "browserCollapsed": false,
+            "newToday": [0, 0],
+            "revToday": [0, 0],
+            "lrnToday": [0, 0],
+            "timeToday": [0, 0],
+            "dyn": 0,
+            "conf": 1,
+            "extendNew": 0,
+            "extendRev": 0
+        })
+    };
+    json!({
+        "1": deck(1, "Default"),
+        deck_id.to_string(): deck(deck_id, deck_name)
+    })
+}
+
+fn deck_config_json(now_secs: i64) -> JsonValue {
+    json!({
+        "1": {
+            "id": 1,
+            "name": "Default",
+            "mod": now_secs,
+            "usn": -1,
+            "maxTaken": 60,
+            "autoplay": true,
+            "timer": 0,
+            "replayq": true,
+            "dyn": false,
+            "new": {
+                "delays": [1.0, 10.0],
+                "ints": [1, 4, 0],
+                "initialFactor": 2500,
+                "order": 1,
+                "perDay": 20,
+                "bury": false
+            },
+            "rev": {
+                "perDay": 200,
+                "ease4": 1.3,
+                "ivlFct": 1.0,
+                "maxIvl": 36500,
+                "hardFactor": 1.2,
+                "bury": false
+            },
+            "lapse": {
+                "delays": [10.0],
+                "mult": 0.0,
+                "minInt": 1,
+                "leechFails": 8,
+                "leechAction": 1
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AnkiError, SRS_NOTE_TYPE, read_package, write_package};
+
+    const FIXTURE: &[u8] = include_bytes!("../../tests/fixtures/anki/basic_leetcode.apkg");
+
+    #[tokio::test]
+    async fn reads_fixture_and_round_trips_through_export() {
+        let collection = read_package(FIXTURE).await.unwrap();
+        assert_eq!(collection.notes.len(), 3);
+        assert_eq!(collection.cards.len(), 3);
+        assert_eq!(collection.revlog.len(), 4);
+        assert_eq!(collection.notes[0].note_type, "Basic");
+        assert_eq!(collection.notes[0].field("front"), Some("Two Sum"));
+
+        let package = write_package("Exported", &collection, 1_767_225_600_000)
+            .await
+            .unwrap();
+        let reread = read_package(&package).await.unwrap();
+        assert_eq!(reread.created_at, collection.created_at);
+        assert_eq!(reread.notes[0].note_type, SRS_NOTE_TYPE);
+        assert_eq!(reread.cards.len(), 3);
+        assert_eq!(reread.revlog.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn rejects_packages_without_a_legacy_collection() {
+        let package = include_bytes!("../../tests/fixtures/anki/anki21b_only.apkg");
+        assert!(matches!(
+            read_package(package).await,
+            Err(AnkiError::UnsupportedFormat)
+        ));
+    }
+}
@@ -0,0 +1,326 @@
+//! Turns study notes into authored cards: markdown files where every heading
+//! starts a card, and CSV files with configurable columns.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use super::csv::{CsvError, CsvRecord, parse_records};
+use crate::{cloze, models::CardKind};
+
+const MAX_KEY_SLUG_CHARS: usize = 80;
+
+static HEADING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^#{1,6}\s+(.*?)(?:\s+#+)?\s*$").expect("valid heading pattern"));
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CardNotesError {
+    #[error(transparent)]
+    Csv(#[from] CsvError),
+    #[error("front matter is not closed with ---")]
+    UnterminatedFrontMatter,
+    #[error("invalid front matter: {0}")]
+    FrontMatter(String),
+    #[error("CSV has no '{0}' column")]
+    MissingColumn(String),
+    #[error("line {line}: unknown card kind '{kind}'")]
+    UnknownKind { line: usize, kind: String },
+}
+
+/// One card as written in the notes, before validation. `key` identifies the
+/// card across imports so a re-import updates instead of duplicating.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteCard {
+    /// 1-based line the card starts on, for the import report.
+    pub line: usize,
+    pub key: String,
+    pub kind: CardKind,
+    pub front: String,
+    pub back: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// Header names to read each card field from. Only `front` has to exist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvColumns {
+    pub front: String,
+    pub back: String,
+    pub kind: String,
+    pub key: String,
+    pub tags: String,
+}
+
+impl Default for CsvColumns {
+    fn default() -> Self {
+        Self {
+            front: "front".to_owned(),
+            back: "back".to_owned(),
+            kind: "kind".to_owned(),
+            key: "key".to_owned(),
+            tags: "tags".to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct FrontMatter {
+    #[serde(default)]
+    tags: Option<TagList>,
+    /// Prefixes every key, so files with overlapping headings don't collide.
+    #[serde(default)]
+    deck: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum TagList {
+    List(Vec<String>),
+    Text(String),
+}
+
+/// Every heading starts a card and the text up to the next heading is its
+/// back. A heading with cloze deletions becomes a cloze card with the body as
+/// extra notes; deletions in the body make a cloze card of heading and body.
+/// Headings inside fenced code blocks are ignored.
+pub fn parse_markdown(text: &str) -> Result<Vec<NoteCard>, CardNotesError> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let lines = text.lines().collect::<Vec<_>>();
+    let (front_matter, body_start) = front_matter(&lines)?;
+    let tags = match front_matter.tags {
+        Some(TagList::List(tags)) => tags,
+        Some(TagList::Text(tags)) => split_tags(&tags),
+        None => Vec::new(),
+    };
+    let deck = front_matter
+        .deck
+        .map(|deck| slugify(&deck))
+        .filter(|deck| !deck.is_empty());
+
+    let mut sections: Vec<(usize, String, Vec<&str>)> = Vec::new();
+    let mut fence: Option<&str> = None;
+    for (index, line) in lines.iter().enumerate().skip(body_start) {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+        } else if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(&trimmed[..3]);
+        } else if let Some(captures) = HEADING.captures(line) {
+            sections.push((index + 1, captures[1].to_owned(), Vec::new()));
+            continue;
+        }
+        if let Some((_, _, body)) = sections.last_mut() {
+            body.push(line);
+        }
+    }
+
+    Ok(sections
+        .into_iter()
+        .map(|(line, heading, body)| {
+            let body = body.join("\n").trim().to_owned();
+            let (kind, front, back) = if cloze::has_deletions(&heading) {
+                (CardKind::Cloze, heading.clone(), Some(body))
+            } else if cloze::has_deletions(&body) {
+                (CardKind::Cloze, format!("{heading}\n\n{body}"), None)
+            } else {
+                (CardKind::Basic, heading.clone(), Some(body))
+            };
+            let slug = derive_key(&heading);
+            NoteCard {
+                line,
+                key: match &deck {
+                    Some(deck) => format!("{deck}/{slug}"),
+                    None => slug,
+                },
+                kind,
+                front,
+                back: back.filter(|back| !back.is_empty()),
+                tags: tags.clone(),
+            }
+        })
+        .collect())
+}
+
+/// Rows without a `kind` are cloze cards when the front has deletions and
+/// basic cards otherwise. Rows without a `key` are keyed by their front.
+pub fn parse_csv(text: &str, columns: &CsvColumns) -> Result<Vec<NoteCard>, CardNotesError> {
+    let records = parse_records(text)?;
+    if let Some(first) = records.first()
+        && !first.has_column(&columns.front)
+    {
+        return Err(CardNotesError::MissingColumn(columns.front.clone()));
+    }
+    records
+        .iter()
+        .map(|record| note_from_record(record, columns))
+        .collect()
+}
+
+fn note_from_record(record: &CsvRecord, columns: &CsvColumns) -> Result<NoteCard, CardNotesError> {
+    let front = record.get(&[&columns.front]).unwrap_or_default().to_owned();
+    let kind = match record.get(&[&columns.kind]) {
+        Some(kind) => CardKind::parse(&kind.to_ascii_lowercase()).ok_or_else(|| {
+            CardNotesError::UnknownKind {
+                line: record.line,
+                kind: kind.to_owned(),
+            }
+        })?,
+        None if cloze::has_deletions(&front) => CardKind::Cloze,
+        None => CardKind::Basic,
+    };
+    Ok(NoteCard {
+        line: record.line,
+        key: record
+            .get(&[&columns.key])
+            .map(ToOwned::to_owned)
+            .unwrap_or_else(|| derive_key(&front)),
+        kind,
+        back: record.get(&[&columns.back]).map(ToOwned::to_owned),
+        tags: record
+            .get(&[&columns.tags])
+            .map(split_tags)
+            .unwrap_or_default(),
+        front,
+    })
+}
+
+/// Returns the parsed front matter and the index of the first body line.
+fn front_matter(lines: &[&str]) -> Result<(FrontMatter, usize), CardNotesError> {
+    if lines.first().map(|line| line.trim_end()) != Some("---") {
+        return Ok((FrontMatter::default(), 0));
+    }
+    let end = lines
+        .iter()
+        .skip(1)
+        .position(|line| matches!(line.trim_end(), "---" | "..."))
+        .map(|offset| offset + 1)
+        .ok_or(CardNotesError::UnterminatedFrontMatter)?;
+    let yaml = lines[1..end].join("\n");
+    if yaml.trim().is_empty() {
+        return Ok((FrontMatter::default(), end + 1));
+    }
+    let parsed =
+        serde_yaml::from_str(&yaml).map_err(|err| CardNotesError::FrontMatter(err.to_string()))?;
+    Ok((parsed, end + 1))
+}
+
+fn split_tags(tags: &str) -> Vec<String> {
+    tags.split(|ch: char| ch == ',' || ch == ';' || ch.is_whitespace())
+        .filter(|tag| !tag.is_empty())
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+/// A readable key from the card's text, falling back to a hash for text with
+/// no letters or digits.
+fn derive_key(text: &str) -> String {
+    let slug = slugify(&cloze::render_answer(text));
+    if !slug.is_empty() {
+        return slug;
+    }
+    let digest = Sha256::digest(text.as_bytes());
+    format!("note-{}", &format!("{digest:x}")[..12])
+}
+
+fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for ch in text.chars().flat_map(char::to_lowercase) {
+        if ch.is_alphanumeric() {
+            slug.push(ch);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+        if slug.chars().count() >= MAX_KEY_SLUG_CHARS {
+            break;
+        }
+    }
+    slug.trim_end_matches('-').to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CardNotesError, CsvColumns, parse_csv, parse_markdown};
+    use crate::models::CardKind;
+
+    #[test]
+    fn markdown_headings_become_cards_with_front_matter_tags() {
+        let notes = parse_markdown(
+            "---\ntags: [rust, ownership]\ndeck: Rust Book\n---\n\
+             # Ownership\n\n\
+             ## What happens on move?\nThe source can no longer be used.\n\n\
+             ```rust\n# not a heading\nlet b = a;\n```\n\
+             ## Each value has {{c1::exactly one}} owner\n\
+             ## Borrowing\nYou can have {{c1::many shared}} or {{c2::one mutable}} reference.\n",
+        )
+        .unwrap();
+        assert_eq!(notes.len(), 4);
+
+        assert_eq!(notes[0].line, 5);
+        assert_eq!(notes[0].key, "rust-book/ownership");
+        assert_eq!(notes[0].back, None);
+
+        assert_eq!(notes[1].kind, CardKind::Basic);
+        assert_eq!(notes[1].front, "What happens on move?");
+        assert_eq!(
+            notes[1].back.as_deref(),
+            Some("The source can no longer be used.\n\n```rust\n# not a heading\nlet b = a;\n```")
+        );
+        assert_eq!(notes[1].tags, ["rust", "ownership"]);
+
+        assert_eq!(notes[2].kind, CardKind::Cloze);
+        assert_eq!(notes[2].key, "rust-book/each-value-has-exactly-one-owner");
+        assert_eq!(notes[2].back, None);
+
+        assert_eq!(notes[3].kind, CardKind::Cloze);
+        assert_eq!(
+            notes[3].front,
+            "Borrowing\n\nYou can have {{c1::many shared}} or {{c2::one mutable}} reference."
+        );
+    }
+
+    #[test]
+    fn markdown_front_matter_must_be_closed() {
+        assert_eq!(
+            parse_markdown("---\ntags: rust\n# Heading\n"),
+            Err(CardNotesError::UnterminatedFrontMatter)
+        );
+        let notes = parse_markdown("---\ntags: rust, traits\n---\n# Q\nA\n").unwrap();
+        assert_eq!(notes[0].tags, ["rust", "traits"]);
+    }
+
+    #[test]
+    fn csv_columns_are_mapped_and_kinds_inferred() {
+        let columns = CsvColumns {
+            front: "Question".to_owned(),
+            back: "Answer".to_owned(),
+            tags: "Labels".to_owned(),
+            ..CsvColumns::default()
+        };
+        let notes = parse_csv(
+            "Question,Answer,Labels\n\
+             What is 2+2?,4,math basics\n\
+             \"The {{c1::mitochondria}} is the powerhouse\",,biology\n",
+            &columns,
+        )
+        .unwrap();
+        assert_eq!(notes[0].key, "what-is-2-2");
+        assert_eq!(notes[0].kind, CardKind::Basic);
+        assert_eq!(notes[0].tags, ["math", "basics"]);
+        assert_eq!(notes[1].line, 3);
+        assert_eq!(notes[1].kind, CardKind::Cloze);
+        assert_eq!(notes[1].back, None);
+
+        assert_eq!(
+            parse_csv("front,back\nQ,A\n", &columns),
+            Err(CardNotesError::MissingColumn("Question".to_owned()))
+        );
+        assert!(matches!(
+            parse_csv("front,kind\nQ,reverse\n", &CsvColumns::default()),
+            Err(CardNotesError::UnknownKind { line: 2, .. })
+        ));
+    }
+}
@@ -0,0 +1,130 @@
+//! Header-keyed CSV records for the import endpoints, read with the `csv`
+//! crate.
+
+use std::collections::HashMap;
+
+use ::csv::{ReaderBuilder, StringRecord};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CsvError {
+    #[error("line {line} has {found} fields, expected {expected}")]
+    FieldCount {
+        line: usize,
+        found: usize,
+        expected: usize,
+    },
+    #[error("missing header row")]
+    MissingHeader,
+    #[error("invalid CSV: {0}")]
+    Malformed(String),
+}
+
+/// One data row keyed by its (trimmed, lowercased) header names.
+#[derive(Debug, Clone)]
+pub struct CsvRecord {
+    /// 1-based line the row starts on, for error messages.
+    pub line: usize,
+    fields: HashMap<String, String>,
+}
+
+impl CsvRecord {
+    /// Returns the first non-empty value among `names`.
+    pub fn get(&self, names: &[&str]) -> Option<&str> {
+        names
+            .iter()
+            .filter_map(|name| self.fields.get(&name.to_ascii_lowercase()))
+            .map(|value| value.trim())
+            .find(|value| !value.is_empty())
+    }
+
+    pub fn has_column(&self, name: &str) -> bool {
+        self.fields.contains_key(&name.trim().to_ascii_lowercase())
+    }
+}
+
+/// Parses a CSV document whose first row holds the column names. Blank lines
+/// are skipped.
+pub fn parse_records(text: &str) -> Result<Vec<CsvRecord>, CsvError> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut reader = ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let mut rows = reader
+        .records()
+        .filter(|row| !row.as_ref().is_ok_and(is_blank));
+    let header = rows
+        .next()
+        .ok_or(CsvError::MissingHeader)?
+        .map_err(|err| CsvError::Malformed(err.to_string()))?
+        .iter()
+        .map(|name| name.trim().to_ascii_lowercase())
+        .collect::<Vec<_>>();
+    rows.map(|row| {
+        let row = row.map_err(|err| CsvError::Malformed(err.to_string()))?;
+        let line = row
+            .position()
+            .map_or(0, |position| line_at(text, position.byte() as usize));
+        if row.len() != header.len() {
+            return Err(CsvError::FieldCount {
+                line,
+                found: row.len(),
+                expected: header.len(),
+            });
+        }
+        Ok(CsvRecord {
+            line,
+            fields: header
+                .iter()
+                .cloned()
+                .zip(row.iter().map(ToOwned::to_owned))
+                .collect(),
+        })
+    })
+    .collect()
+}
+
+/// 1-based line of the record starting at `byte`. The reader reports the
+/// line terminator before a record as its start, and miscounts CRLF lines.
+fn line_at(text: &str, byte: usize) -> usize {
+    let rest = text.get(byte..).unwrap_or_default();
+    let start = byte + rest.len() - rest.trim_start_matches(['\r', '\n']).len();
+    text.as_bytes()[..start]
+        .iter()
+        .filter(|byte| **byte == b'\n')
+        .count()
+        + 1
+}
+
+fn is_blank(row: &StringRecord) -> bool {
+    row.len() == 1 && row[0].trim().is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CsvError, parse_records};
+
+    #[test]
+    fn parses_quoted_fields_and_line_endings() {
+        let records =
+            parse_records("a,b\r\nx,\"b, \"\"c\"\"\"\r\n\r\n\"multi\nline\",d\n").unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].line, 2);
+        assert_eq!(records[0].get(&["b"]), Some("b, \"c\""));
+        assert_eq!(records[1].line, 4);
+        assert_eq!(records[1].get(&["a"]), Some("multi\nline"));
+    }
+
+    #[test]
+    fn records_are_keyed_by_header() {
+        let records = parse_records("\u{feff}Title, Slug\nTwo Sum,two-sum\n,\n").unwrap();
+        assert_eq!(records[0].get(&["slug"]), Some("two-sum"));
+        assert_eq!(records[1].get(&["missing", "title"]), None);
+        assert!(matches!(
+            parse_records("a,b\n1\n"),
+            Err(CsvError::FieldCount { line: 2, .. })
+        ));
+        assert_eq!(parse_records("\n \n").unwrap_err(), CsvError::MissingHeader);
+    }
+}
@@ -0,0 +1,190 @@
+//! Parses LeetCode submission history dumps: the `submissions_dump` JSON
+//! from `/api/submissions/`, GraphQL `submissionList` /
+//! `recentAcSubmissionList` responses, a bare JSON array of submissions, or a
+//! CSV with a header row.
+
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use super::csv::{CsvError, parse_records};
+
+/// LeetCode's numeric status code for an accepted submission.
+const STATUS_ACCEPTED: i64 = 10;
+
+const SLUG_KEYS: [&str; 4] = ["title_slug", "titleSlug", "question_slug", "slug"];
+const TITLE_KEYS: [&str; 3] = ["title", "question_title", "questionTitle"];
+const STATUS_KEYS: [&str; 4] = ["status_display", "statusDisplay", "status", "result"];
+const TIME_KEYS: [&str; 4] = ["timestamp", "submitted_at", "submittedAt", "time"];
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LeetCodeHistoryError {
+    #[error("invalid JSON: {0}")]
+    Json(String),
+    #[error(transparent)]
+    Csv(#[from] CsvError),
+    #[error("no submission list found in the JSON document")]
+    NoSubmissions,
+    #[error("submission {0} has no problem slug")]
+    MissingSlug(usize),
+    #[error("submission {0} has no valid timestamp")]
+    InvalidTimestamp(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeetCodeSubmission {
+    pub problem_slug: String,
+    pub title: String,
+    pub accepted: bool,
+    pub submitted_at: DateTime<Utc>,
+}
+
+/// Detects JSON versus CSV from the first non-blank character.
+pub fn parse_submissions(body: &str) -> Result<Vec<LeetCodeSubmission>, LeetCodeHistoryError> {
+    let body = body.trim_start_matches('\u{feff}');
+    match body.trim_start().chars().next() {
+        Some('[' | '{') => parse_json(body),
+        _ => parse_csv(body),
+    }
+}
+
+fn parse_json(body: &str) -> Result<Vec<LeetCodeSubmission>, LeetCodeHistoryError> {
+    let document: Value =
+        serde_json::from_str(body).map_err(|err| LeetCodeHistoryError::Json(err.to_string()))?;
+    let submissions = match &document {
+        Value::Array(items) => items,
+        Value::Object(_) => [
+            "/submissions_dump",
+            "/submissions",
+            "/data/submissionList/submissions",
+            "/data/recentAcSubmissionList",
+            "/data/recentSubmissionList",
+        ]
+        .iter()
+        .find_map(|path| document.pointer(path).and_then(Value::as_array))
+        .ok_or(LeetCodeHistoryError::NoSubmissions)?,
+        _ => return Err(LeetCodeHistoryError::NoSubmissions),
+    };
+
+    submissions
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let empty = Map::new();
+            let fields = item.as_object().unwrap_or(&empty);
+            let get = |keys: &[&str]| {
+                keys.iter()
+                    .filter_map(|key| fields.get(*key))
+                    .find_map(|value| match value {
+                        Value::String(text) if !text.trim().is_empty() => {
+                            Some(text.trim().to_owned())
+                        }
+                        Value::Number(number) => Some(number.to_string()),
+                        _ => None,
+                    })
+            };
+            submission(index + 1, get)
+        })
+        .collect()
+}
+
+fn parse_csv(body: &str) -> Result<Vec<LeetCodeSubmission>, LeetCodeHistoryError> {
+    parse_records(body)?
+        .iter()
+        .map(|record| submission(record.line, |keys| record.get(keys).map(str::to_owned)))
+        .collect()
+}
+
+fn submission(
+    position: usize,
+    get: impl Fn(&[&str]) -> Option<String>,
+) -> Result<LeetCodeSubmission, LeetCodeHistoryError> {
+    let problem_slug = get(&SLUG_KEYS)
+        .map(|slug| slug.to_ascii_lowercase())
+        .ok_or(LeetCodeHistoryError::MissingSlug(position))?;
+    let submitted_at = get(&TIME_KEYS)
+        .as_deref()
+        .and_then(parse_timestamp)
+        .ok_or(LeetCodeHistoryError::InvalidTimestamp(position))?;
+    // Acceptance-only lists (recentAcSubmissionList) carry no status at all.
+    let accepted = get(&STATUS_KEYS).is_none_or(|status| is_accepted(&status));
+    Ok(LeetCodeSubmission {
+        title: get(&TITLE_KEYS).unwrap_or_else(|| problem_slug.clone()),
+        problem_slug,
+        accepted,
+        submitted_at,
+    })
+}
+
+fn is_accepted(status: &str) -> bool {
+    status.eq_ignore_ascii_case("accepted")
+        || status.eq_ignore_ascii_case("ac")
+        || status.parse::<i64>() == Ok(STATUS_ACCEPTED)
+}
+
+/// Unix seconds (as LeetCode sends them), unix milliseconds from browser
+/// tools, or RFC 3339.
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(number) = value.parse::<i64>() {
+        return if number > 100_000_000_000 {
+            DateTime::from_timestamp_millis(number)
+        } else {
+            DateTime::from_timestamp(number, 0)
+        };
+    }
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LeetCodeHistoryError, parse_submissions};
+
+    #[test]
+    fn reads_api_dump_graphql_and_csv() {
+        let dump = r#"{"submissions_dump":[
+            {"id":1,"title":"Two Sum","title_slug":"two-sum","status":10,
+             "status_display":"Accepted","timestamp":1767225600},
+            {"id":2,"title":"Two Sum","title_slug":"two-sum","status":11,
+             "status_display":"Wrong Answer","timestamp":1767225000}
+        ],"has_next":false}"#;
+        let submissions = parse_submissions(dump).unwrap();
+        assert_eq!(submissions.len(), 2);
+        assert!(submissions[0].accepted);
+        assert!(!submissions[1].accepted);
+        assert_eq!(
+            submissions[0].submitted_at.to_rfc3339(),
+            "2026-01-01T00:00:00+00:00"
+        );
+
+        let graphql = r#"{"data":{"recentAcSubmissionList":[
+            {"id":"9","title":"LRU Cache","titleSlug":"lru-cache","timestamp":"1767225600"}
+        ]}}"#;
+        let submissions = parse_submissions(graphql).unwrap();
+        assert_eq!(submissions[0].problem_slug, "lru-cache");
+        assert!(submissions[0].accepted);
+
+        let csv = "Title,Title Slug,title_slug,Status,Timestamp\n\
+                   Valid Anagram,,valid-anagram,Accepted,2026-01-02T10:00:00Z\n";
+        let submissions = parse_submissions(csv).unwrap();
+        assert_eq!(submissions[0].title, "Valid Anagram");
+        assert_eq!(submissions[0].problem_slug, "valid-anagram");
+    }
+
+    #[test]
+    fn rejects_rows_without_slug_or_time() {
+        assert_eq!(
+            parse_submissions(r#"[{"title":"Two Sum","timestamp":1}]"#),
+            Err(LeetCodeHistoryError::MissingSlug(1))
+        );
+        assert_eq!(
+            parse_submissions("slug,timestamp\ntwo-sum,yesterday\n"),
+            Err(LeetCodeHistoryError::InvalidTimestamp(2))
+        );
+        assert_eq!(
+            parse_submissions(r#"{"has_next":false}"#),
+            Err(LeetCodeHistoryError::NoSubmissions)
+        );
+    }
+}
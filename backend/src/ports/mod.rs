@@ -1,2 +1,11 @@
+pub mod anki;
+pub mod card_notes;
+pub mod csv;
+pub mod leetcode_history;
 pub mod notification_sender;
+pub mod oidc;
+pub mod rate_limit_store;
 pub mod schedule_provider;
+pub mod web_push;
+pub mod webauthn;
+pub mod zip;
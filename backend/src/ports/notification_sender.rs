@@ -7,6 +7,14 @@ use crate::repositories::{error::RepoError, traits::SettingsRepository};
 #[async_trait]
 pub trait NotificationSender: Send + Sync {
     async fn send_digest(&self, user_id: i64, subject: &str, body: &str) -> Result<(), RepoError>;
+    /// Sends an account deletion confirmation. It carries a token that can
+    /// delete the account, so only the account's email may receive it.
+    async fn send_account_deletion(
+        &self,
+        user_id: i64,
+        subject: &str,
+        body: &str,
+    ) -> Result<(), RepoError>;
 }
 
 #[derive(Clone)]
@@ -26,4 +34,47 @@ impl NotificationSender for StoreNotificationSender {
         self.settings_repo.log_email(user_id, subject, body).await?;
         Ok(())
     }
+
+    async fn send_account_deletion(
+        &self,
+        user_id: i64,
+        subject: &str,
+        body: &str,
+    ) -> Result<(), RepoError> {
+        self.settings_repo.log_email(user_id, subject, body).await?;
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct FanoutNotificationSender {
+    senders: Vec<Arc<dyn NotificationSender>>,
+}
+
+impl FanoutNotificationSender {
+    pub fn new(senders: Vec<Arc<dyn NotificationSender>>) -> Self {
+        Self { senders }
+    }
+}
+
+#[async_trait]
+impl NotificationSender for FanoutNotificationSender {
+    async fn send_digest(&self, user_id: i64, subject: &str, body: &str) -> Result<(), RepoError> {
+        for sender in &self.senders {
+            sender.send_digest(user_id, subject, body).await?;
+        }
+        Ok(())
+    }
+
+    async fn send_account_deletion(
+        &self,
+        user_id: i64,
+        subject: &str,
+        body: &str,
+    ) -> Result<(), RepoError> {
+        for sender in &self.senders {
+            sender.send_account_deletion(user_id, subject, body).await?;
+        }
+        Ok(())
+    }
 }
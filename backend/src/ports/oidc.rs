@@ -0,0 +1,338 @@
+use std::{env, sync::Arc, time::Duration};
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, Validation, decode, decode_header,
+    jwk::{Jwk, JwkSet},
+};
+use reqwest::Url;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tokio::{sync::RwLock, time::Instant};
+use tracing::{info, warn};
+
+/// Discovery documents and key sets are refetched after this long.
+const METADATA_TTL: Duration = Duration::from_secs(60 * 60);
+/// An unknown `kid` triggers a JWKS refresh, but never more often than this.
+const JWKS_MIN_REFRESH: Duration = Duration::from_secs(30);
+
+/// Signature algorithms accepted on ID tokens. Symmetric algorithms are
+/// excluded so a published `oct` key can never be used to forge tokens.
+const ALLOWED_ALGORITHMS: [Algorithm; 7] = [
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::EdDSA,
+];
+
+#[derive(Debug, Error)]
+pub enum OidcError {
+    #[error("provider discovery failed: {0}")]
+    Discovery(String),
+    #[error("token exchange failed: {0}")]
+    TokenExchange(String),
+    #[error("invalid id token: {0}")]
+    InvalidIdToken(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct OidcConfig {
+    pub issuer_url: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    /// Backend callback registered with the provider.
+    pub redirect_url: String,
+    pub scopes: String,
+    /// Where the browser lands after a successful login.
+    pub post_login_redirect: String,
+}
+
+impl OidcConfig {
+    /// Returns `None` unless both `OIDC_ISSUER_URL` and `OIDC_CLIENT_ID` are set.
+    pub fn from_env() -> Option<Self> {
+        let non_empty = |name: &str| env::var(name).ok().filter(|value| !value.trim().is_empty());
+        let issuer_url = non_empty("OIDC_ISSUER_URL")?;
+        let Some(client_id) = non_empty("OIDC_CLIENT_ID") else {
+            warn!("OIDC_ISSUER_URL set without OIDC_CLIENT_ID, SSO disabled");
+            return None;
+        };
+        Some(Self {
+            issuer_url,
+            client_id,
+            client_secret: non_empty("OIDC_CLIENT_SECRET"),
+            redirect_url: non_empty("OIDC_REDIRECT_URL")
+                .unwrap_or_else(|| "http://localhost:3000/auth/oidc/callback".to_owned()),
+            scopes: non_empty("OIDC_SCOPES").unwrap_or_else(|| "openid email profile".to_owned()),
+            post_login_redirect: non_empty("OIDC_POST_LOGIN_REDIRECT")
+                .unwrap_or_else(|| "http://localhost:5173/".to_owned()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdTokenClaims {
+    pub iss: String,
+    pub sub: String,
+    pub email: Option<String>,
+    #[serde(default)]
+    email_verified: Option<serde_json::Value>,
+    nonce: Option<String>,
+    azp: Option<String>,
+}
+
+impl IdTokenClaims {
+    /// Some providers send `email_verified` as the string `"true"`.
+    pub fn email_verified(&self) -> bool {
+        match &self.email_verified {
+            Some(serde_json::Value::Bool(verified)) => *verified,
+            Some(serde_json::Value::String(verified)) => verified == "true",
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Cached<T> {
+    value: T,
+    fetched_at: Instant,
+}
+
+#[derive(Clone)]
+pub struct OidcProvider {
+    config: OidcConfig,
+    client: reqwest::Client,
+    metadata: Arc<RwLock<Option<Cached<ProviderMetadata>>>>,
+    jwks: Arc<RwLock<Option<Cached<JwkSet>>>>,
+}
+
+impl OidcProvider {
+    pub fn new(config: OidcConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .expect("build oidc http client"),
+            metadata: Arc::new(RwLock::new(None)),
+            jwks: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    pub fn post_login_redirect(&self) -> &str {
+        &self.config.post_login_redirect
+    }
+
+    pub async fn authorization_url(
+        &self,
+        state: &str,
+        nonce: &str,
+        code_challenge: &str,
+    ) -> Result<String, OidcError> {
+        let metadata = self.metadata().await?;
+        let mut url = Url::parse(&metadata.authorization_endpoint).map_err(|err| {
+            OidcError::Discovery(format!("invalid authorization endpoint: {err}"))
+        })?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.config.client_id)
+            .append_pair("redirect_uri", &self.config.redirect_url)
+            .append_pair("scope", &self.config.scopes)
+            .append_pair("state", state)
+            .append_pair("nonce", nonce)
+            .append_pair("code_challenge", code_challenge)
+            .append_pair("code_challenge_method", "S256");
+        Ok(url.into())
+    }
+
+    /// Redeems an authorization code and returns the raw ID token.
+    pub async fn exchange_code(
+        &self,
+        code: &str,
+        code_verifier: &str,
+    ) -> Result<String, OidcError> {
+        let metadata = self.metadata().await?;
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.config.redirect_url.as_str()),
+            ("client_id", self.config.client_id.as_str()),
+            ("code_verifier", code_verifier),
+        ];
+        if let Some(secret) = &self.config.client_secret {
+            form.push(("client_secret", secret.as_str()));
+        }
+        let response = self
+            .client
+            .post(&metadata.token_endpoint)
+            .form(&form)
+            .send()
+            .await
+            .map_err(|err| OidcError::TokenExchange(err.to_string()))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(OidcError::TokenExchange(format!(
+                "token endpoint returned {status}"
+            )));
+        }
+        response
+            .json::<TokenResponse>()
+            .await
+            .map_err(|err| OidcError::TokenExchange(err.to_string()))?
+            .id_token
+            .ok_or_else(|| OidcError::TokenExchange("response has no id_token".to_owned()))
+    }
+
+    /// Checks signature, issuer, audience, expiry and nonce of an ID token.
+    pub async fn verify_id_token(
+        &self,
+        id_token: &str,
+        expected_nonce: &str,
+    ) -> Result<IdTokenClaims, OidcError> {
+        let metadata = self.metadata().await?;
+        let header =
+            decode_header(id_token).map_err(|err| OidcError::InvalidIdToken(err.to_string()))?;
+        if !ALLOWED_ALGORITHMS.contains(&header.alg) {
+            return Err(OidcError::InvalidIdToken(format!(
+                "algorithm {:?} is not allowed",
+                header.alg
+            )));
+        }
+        let jwk = self.signing_key(header.kid.as_deref()).await?;
+        let key = DecodingKey::from_jwk(&jwk)
+            .map_err(|err| OidcError::InvalidIdToken(err.to_string()))?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[metadata.issuer.as_str()]);
+        validation.set_audience(&[self.config.client_id.as_str()]);
+        validation.set_required_spec_claims(&["exp", "iat", "iss", "aud", "sub"]);
+        let claims = decode::<IdTokenClaims>(id_token, &key, &validation)
+            .map_err(|err| OidcError::InvalidIdToken(err.to_string()))?
+            .claims;
+
+        if claims.nonce.as_deref() != Some(expected_nonce) {
+            return Err(OidcError::InvalidIdToken("nonce mismatch".to_owned()));
+        }
+        if claims
+            .azp
+            .as_deref()
+            .is_some_and(|azp| azp != self.config.client_id)
+        {
+            return Err(OidcError::InvalidIdToken(
+                "token was issued to another client".to_owned(),
+            ));
+        }
+        Ok(claims)
+    }
+
+    async fn metadata(&self) -> Result<ProviderMetadata, OidcError> {
+        if let Some(cached) = self.metadata.read().await.as_ref()
+            && cached.fetched_at.elapsed() < METADATA_TTL
+        {
+            return Ok(cached.value.clone());
+        }
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            self.config.issuer_url.trim_end_matches('/')
+        );
+        let metadata: ProviderMetadata =
+            self.fetch_json(&url).await.map_err(OidcError::Discovery)?;
+        if metadata.issuer.trim_end_matches('/') != self.config.issuer_url.trim_end_matches('/') {
+            return Err(OidcError::Discovery(format!(
+                "issuer {} does not match configured {}",
+                metadata.issuer, self.config.issuer_url
+            )));
+        }
+        info!(issuer = %metadata.issuer, "oidc_discovery_loaded");
+        *self.metadata.write().await = Some(Cached {
+            value: metadata.clone(),
+            fetched_at: Instant::now(),
+        });
+        Ok(metadata)
+    }
+
+    /// Looks `kid` up in the cached key set, refreshing it when the key is
+    /// unknown (the provider rotated keys) or the cache is stale.
+    async fn signing_key(&self, kid: Option<&str>) -> Result<Jwk, OidcError> {
+        let cached = self.jwks.read().await.clone();
+        if let Some(cached) = &cached {
+            let fresh = cached.fetched_at.elapsed() < METADATA_TTL;
+            match find_key(&cached.value, kid) {
+                Some(jwk) if fresh => return Ok(jwk),
+                None if cached.fetched_at.elapsed() < JWKS_MIN_REFRESH => {
+                    return Err(OidcError::InvalidIdToken("unknown signing key".to_owned()));
+                }
+                _ => {}
+            }
+        }
+
+        let metadata = self.metadata().await?;
+        let jwks: JwkSet = self
+            .fetch_json(&metadata.jwks_uri)
+            .await
+            .map_err(OidcError::Discovery)?;
+        info!(key_count = jwks.keys.len(), "oidc_jwks_refreshed");
+        let jwk = find_key(&jwks, kid);
+        *self.jwks.write().await = Some(Cached {
+            value: jwks,
+            fetched_at: Instant::now(),
+        });
+        jwk.ok_or_else(|| OidcError::InvalidIdToken("unknown signing key".to_owned()))
+    }
+
+    async fn fetch_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T, String> {
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|err| err.to_string())?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("{url} returned {status}"));
+        }
+        response.json::<T>().await.map_err(|err| err.to_string())
+    }
+}
+
+fn find_key(jwks: &JwkSet, kid: Option<&str>) -> Option<Jwk> {
+    match kid {
+        Some(kid) => jwks.find(kid).cloned(),
+        None if jwks.keys.len() == 1 => jwks.keys.first().cloned(),
+        None => None,
+    }
+}
+
+/// RFC 7636 `S256` code challenge for a PKCE verifier.
+pub fn pkce_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::pkce_challenge;
+
+    #[test]
+    fn pkce_challenge_matches_rfc_7636_example() {
+        assert_eq!(
+            pkce_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use tokio::time::Instant;
+
+use crate::repositories::error::RepoError;
+
+/// Allows `limit` requests per `window`, refilled continuously.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitRule {
+    pub limit: u32,
+    pub window: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitDecision {
+    Allowed,
+    Limited { retry_after: Duration },
+}
+
+/// Backing store for rate-limit buckets. The in-memory store is per process;
+/// a shared backend (Redis, Postgres) can implement this to limit across
+/// replicas.
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    async fn hit(&self, key: &str, rule: RateLimitRule) -> Result<RateLimitDecision, RepoError>;
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+    window: Duration,
+}
+
+/// Drop idle buckets once the map grows past this many keys.
+const PRUNE_THRESHOLD: usize = 10_000;
+
+#[derive(Debug, Default)]
+pub struct InMemoryRateLimitStore {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl InMemoryRateLimitStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn hit_at(&self, key: &str, rule: RateLimitRule, now: Instant) -> RateLimitDecision {
+        let capacity = f64::from(rule.limit);
+        let refill_per_sec = capacity / rule.window.as_secs_f64().max(f64::EPSILON);
+        let mut buckets = self.buckets.lock().expect("rate limit buckets poisoned");
+        if buckets.len() >= PRUNE_THRESHOLD {
+            buckets.retain(|_, bucket| now.duration_since(bucket.updated_at) < bucket.window);
+        }
+        let bucket = buckets.entry(key.to_owned()).or_insert(Bucket {
+            tokens: capacity,
+            updated_at: now,
+            window: rule.window,
+        });
+        let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * refill_per_sec).min(capacity);
+        bucket.updated_at = now;
+        bucket.window = rule.window;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return RateLimitDecision::Allowed;
+        }
+        let wait_secs = (1.0 - bucket.tokens) / refill_per_sec;
+        RateLimitDecision::Limited {
+            retry_after: Duration::from_secs_f64(wait_secs),
+        }
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for InMemoryRateLimitStore {
+    async fn hit(&self, key: &str, rule: RateLimitRule) -> Result<RateLimitDecision, RepoError> {
+        Ok(self.hit_at(key, rule, Instant::now()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::Instant;
+
+    use super::{InMemoryRateLimitStore, RateLimitDecision, RateLimitRule};
+
+    #[test]
+    fn bucket_refills_after_the_window() {
+        let store = InMemoryRateLimitStore::new();
+        let rule = RateLimitRule {
+            limit: 2,
+            window: Duration::from_secs(60),
+        };
+        let start = Instant::now();
+
+        assert_eq!(
+            store.hit_at("ip:1", rule, start),
+            RateLimitDecision::Allowed
+        );
+        assert_eq!(
+            store.hit_at("ip:1", rule, start),
+            RateLimitDecision::Allowed
+        );
+        let RateLimitDecision::Limited { retry_after } = store.hit_at("ip:1", rule, start) else {
+            panic!("third hit should be limited");
+        };
+        assert_eq!(retry_after.as_secs(), 30);
+        assert_eq!(
+            store.hit_at("ip:2", rule, start),
+            RateLimitDecision::Allowed
+        );
+        assert_eq!(
+            store.hit_at("ip:1", rule, start + Duration::from_secs(30)),
+            RateLimitDecision::Allowed
+        );
+    }
+}
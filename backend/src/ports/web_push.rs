@@ -0,0 +1,484 @@
+use std::{
+    env,
+    sync::{Arc, RwLock},
+    time::Duration as StdDuration,
+};
+
+use aes_gcm::{Aes128Gcm, KeyInit, aead::Aead};
+use async_trait::async_trait;
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{Duration, Utc};
+use hkdf::Hkdf;
+use jsonwebtoken::{Algorithm, EncodingKey, Header, encode};
+use p256::{PublicKey, SecretKey, ecdh::diffie_hellman, pkcs8::EncodePrivateKey};
+use reqwest::{StatusCode, Url, redirect};
+use serde::Serialize;
+use sha2::Sha256;
+use tracing::{info, warn};
+use url::{Host, Origin};
+
+use crate::{
+    models::PushSubscription,
+    ports::notification_sender::NotificationSender,
+    repositories::{error::RepoError, traits::PushSubscriptionRepository},
+};
+
+const RECORD_SIZE: u32 = 4096;
+/// Push services of the major browsers, each matching itself and its
+/// subdomains: FCM (Chrome, Edge on Android), Mozilla, Apple and WNS (Edge
+/// on Windows).
+const PUSH_SERVICE_DOMAINS: [&str; 5] = [
+    "fcm.googleapis.com",
+    "android.googleapis.com",
+    "push.services.mozilla.com",
+    "push.apple.com",
+    "notify.windows.com",
+];
+
+#[derive(Clone)]
+pub struct VapidKeys {
+    secret_key: SecretKey,
+    subject: String,
+}
+
+impl VapidKeys {
+    pub fn new(secret_key: SecretKey, subject: impl Into<String>) -> Self {
+        Self {
+            secret_key,
+            subject: subject.into(),
+        }
+    }
+
+    pub fn generate(subject: impl Into<String>) -> Self {
+        Self::new(random_secret_key(), subject)
+    }
+
+    pub fn from_env() -> Self {
+        let srs_profile = env::var("SRS_PROFILE").unwrap_or_default();
+        let app_env = env::var("APP_ENV").unwrap_or_default();
+        let require_vapid_key = srs_profile == "prod" || app_env == "production";
+        let subject = env::var("VAPID_SUBJECT")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .unwrap_or_else(|| "mailto:admin@localhost".to_owned());
+
+        match env::var("VAPID_PRIVATE_KEY") {
+            Ok(value) if !value.trim().is_empty() => {
+                let secret_key = URL_SAFE_NO_PAD
+                    .decode(value.trim())
+                    .ok()
+                    .and_then(|bytes| SecretKey::from_slice(&bytes).ok())
+                    .expect("VAPID_PRIVATE_KEY must be a base64url-encoded P-256 private key");
+                Self::new(secret_key, subject)
+            }
+            _ if require_vapid_key => {
+                panic!("VAPID_PRIVATE_KEY must be set when SRS_PROFILE=prod or APP_ENV=production")
+            }
+            _ => {
+                warn!("VAPID_PRIVATE_KEY missing, generating ephemeral development key");
+                Self::generate(subject)
+            }
+        }
+    }
+
+    /// Uncompressed P-256 point, base64url-encoded, as expected by
+    /// `PushManager.subscribe({ applicationServerKey })`.
+    pub fn public_key_base64(&self) -> String {
+        URL_SAFE_NO_PAD.encode(self.secret_key.public_key().to_sec1_bytes())
+    }
+
+    fn authorization_header(&self, endpoint: &Url) -> Result<String, RepoError> {
+        let audience = endpoint.origin().ascii_serialization();
+        let claims = VapidClaims {
+            aud: audience,
+            exp: (Utc::now() + Duration::hours(12)).timestamp() as usize,
+            sub: self.subject.clone(),
+        };
+        let der = self
+            .secret_key
+            .to_pkcs8_der()
+            .map_err(|err| RepoError::Message(format!("invalid vapid key: {err}")))?;
+        let jwt = encode(
+            &Header::new(Algorithm::ES256),
+            &claims,
+            &EncodingKey::from_ec_der(der.as_bytes()),
+        )
+        .map_err(|err| RepoError::Message(format!("failed to sign vapid jwt: {err}")))?;
+        Ok(format!("vapid t={jwt}, k={}", self.public_key_base64()))
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct VapidClaims {
+    aud: String,
+    exp: usize,
+    sub: String,
+}
+
+#[derive(Debug, Serialize)]
+struct PushPayload<'a> {
+    title: &'a str,
+    body: &'a str,
+}
+
+/// Decodes the `p256dh` and `auth` keys a browser hands out with its push
+/// subscription, rejecting anything that could not be used for encryption.
+pub fn decode_subscription_keys(
+    p256dh: &str,
+    auth: &str,
+) -> Result<(PublicKey, Vec<u8>), RepoError> {
+    let public_key = URL_SAFE_NO_PAD
+        .decode(p256dh.trim_end_matches('='))
+        .ok()
+        .and_then(|bytes| PublicKey::from_sec1_bytes(&bytes).ok())
+        .ok_or_else(|| RepoError::Message("invalid p256dh key".to_owned()))?;
+    let auth_secret = URL_SAFE_NO_PAD
+        .decode(auth.trim_end_matches('='))
+        .ok()
+        .filter(|bytes| bytes.len() == 16)
+        .ok_or_else(|| RepoError::Message("invalid auth secret".to_owned()))?;
+    Ok((public_key, auth_secret))
+}
+
+/// Whether `endpoint` is an https URL on a known push service's default port.
+pub fn is_push_service_endpoint(endpoint: &Url) -> bool {
+    let Some(Host::Domain(host)) = endpoint.host() else {
+        return false;
+    };
+    endpoint.scheme() == "https"
+        && endpoint.port().is_none()
+        && endpoint.username().is_empty()
+        && endpoint.password().is_none()
+        && PUSH_SERVICE_DOMAINS.iter().any(|domain| {
+            host.strip_suffix(domain)
+                .is_some_and(|prefix| prefix.is_empty() || prefix.ends_with('.'))
+        })
+}
+
+/// The push endpoints devices may subscribe with and digests are posted to:
+/// known push services plus extra origins. Clones share the extra origins,
+/// so the subscribe check and the sender always agree.
+#[derive(Clone, Default)]
+pub struct PushEndpoints {
+    extra_origins: Arc<RwLock<Vec<Origin>>>,
+}
+
+impl PushEndpoints {
+    /// `WEB_PUSH_EXTRA_ORIGINS` lists further push service origins, comma
+    /// separated, e.g. a self-hosted one.
+    pub fn from_env() -> Self {
+        let endpoints = Self::default();
+        let raw = env::var("WEB_PUSH_EXTRA_ORIGINS").unwrap_or_default();
+        for value in raw
+            .split(',')
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+        {
+            match Url::parse(value) {
+                Ok(origin) => endpoints.allow_origin(&origin),
+                Err(_) => warn!(
+                    variable = "WEB_PUSH_EXTRA_ORIGINS",
+                    value, "push_config_invalid"
+                ),
+            }
+        }
+        endpoints
+    }
+
+    /// Also accepts endpoints on `origin`'s scheme, host and port.
+    pub fn allow_origin(&self, origin: &Url) {
+        self.extra_origins
+            .write()
+            .expect("push endpoints poisoned")
+            .push(origin.origin());
+    }
+
+    pub fn allows(&self, endpoint: &Url) -> bool {
+        is_push_service_endpoint(endpoint)
+            || self
+                .extra_origins
+                .read()
+                .expect("push endpoints poisoned")
+                .contains(&endpoint.origin())
+    }
+}
+
+/// Encrypts a push message body with the `aes128gcm` content coding from
+/// RFC 8188, keyed as described in RFC 8291.
+pub fn encrypt_payload(
+    ua_public: &PublicKey,
+    auth_secret: &[u8],
+    plaintext: &[u8],
+) -> Result<Vec<u8>, RepoError> {
+    let as_secret = random_secret_key();
+    let as_public = as_secret.public_key().to_sec1_bytes();
+    let ua_public_bytes = ua_public.to_sec1_bytes();
+    let shared_secret = diffie_hellman(as_secret.to_nonzero_scalar(), ua_public.as_affine());
+
+    let mut key_info = b"WebPush: info\0".to_vec();
+    key_info.extend_from_slice(&ua_public_bytes);
+    key_info.extend_from_slice(&as_public);
+    let mut ikm = [0_u8; 32];
+    Hkdf::<Sha256>::new(Some(auth_secret), shared_secret.raw_secret_bytes())
+        .expand(&key_info, &mut ikm)
+        .map_err(|_| RepoError::Message("web push key derivation failed".to_owned()))?;
+
+    let salt: [u8; 16] = rand::random();
+    let hkdf = Hkdf::<Sha256>::new(Some(&salt), &ikm);
+    let mut content_key = [0_u8; 16];
+    let mut nonce = [0_u8; 12];
+    hkdf.expand(b"Content-Encoding: aes128gcm\0", &mut content_key)
+        .and_then(|_| hkdf.expand(b"Content-Encoding: nonce\0", &mut nonce))
+        .map_err(|_| RepoError::Message("web push key derivation failed".to_owned()))?;
+
+    let mut record = plaintext.to_vec();
+    record.push(0x02);
+    let ciphertext = Aes128Gcm::new(&content_key.into())
+        .encrypt(&nonce.into(), record.as_slice())
+        .map_err(|_| RepoError::Message("web push encryption failed".to_owned()))?;
+
+    let mut body = Vec::with_capacity(16 + 4 + 1 + as_public.len() + ciphertext.len());
+    body.extend_from_slice(&salt);
+    body.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+    body.push(as_public.len() as u8);
+    body.extend_from_slice(&as_public);
+    body.extend_from_slice(&ciphertext);
+    Ok(body)
+}
+
+fn random_secret_key() -> SecretKey {
+    loop {
+        let bytes: [u8; 32] = rand::random();
+        if let Ok(secret_key) = SecretKey::from_slice(&bytes) {
+            return secret_key;
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct WebPushNotificationSender {
+    repo: Arc<dyn PushSubscriptionRepository>,
+    vapid: VapidKeys,
+    endpoints: PushEndpoints,
+    client: reqwest::Client,
+    ttl_secs: i64,
+}
+
+impl WebPushNotificationSender {
+    pub fn new(
+        repo: Arc<dyn PushSubscriptionRepository>,
+        vapid: VapidKeys,
+        endpoints: PushEndpoints,
+    ) -> Self {
+        let ttl_secs = env::var("WEB_PUSH_TTL_SECS")
+            .ok()
+            .and_then(|value| value.parse::<i64>().ok())
+            .filter(|value| *value >= 0)
+            .unwrap_or(60 * 60 * 24);
+        Self {
+            repo,
+            vapid,
+            endpoints,
+            // Push services answer directly; following a redirect would let
+            // one send the digest worker anywhere.
+            client: reqwest::Client::builder()
+                .timeout(StdDuration::from_secs(10))
+                .redirect(redirect::Policy::none())
+                .build()
+                .expect("build web push http client"),
+            ttl_secs,
+        }
+    }
+
+    async fn push(&self, subscription: &PushSubscription, payload: &[u8]) -> Result<(), RepoError> {
+        let endpoint = Url::parse(&subscription.endpoint)
+            .map_err(|err| RepoError::Message(format!("invalid push endpoint: {err}")))?;
+        // Rows may predate the allowlist or an origin since removed from it.
+        if !self.endpoints.allows(&endpoint) {
+            return Err(RepoError::Message(
+                "push endpoint is not a known push service".to_owned(),
+            ));
+        }
+        let (ua_public, auth_secret) =
+            decode_subscription_keys(&subscription.p256dh, &subscription.auth)?;
+        let body = encrypt_payload(&ua_public, &auth_secret, payload)?;
+        let response = self
+            .client
+            .post(endpoint.clone())
+            .header("Authorization", self.vapid.authorization_header(&endpoint)?)
+            .header("Content-Encoding", "aes128gcm")
+            .header("Content-Type", "application/octet-stream")
+            .header("TTL", self.ttl_secs.to_string())
+            .header("Urgency", "normal")
+            .body(body)
+            .send()
+            .await
+            .map_err(|err| RepoError::Message(format!("web push request failed: {err}")))?;
+
+        match response.status() {
+            status if status.is_success() => {
+                self.repo.touch_push_subscription(subscription.id).await
+            }
+            StatusCode::NOT_FOUND | StatusCode::GONE => {
+                info!(
+                    user_id = subscription.user_id,
+                    subscription_id = subscription.id,
+                    "web_push_subscription_expired"
+                );
+                self.repo
+                    .delete_push_subscription_by_endpoint(&subscription.endpoint)
+                    .await
+            }
+            status => Err(RepoError::Message(format!(
+                "push service rejected message with status {status}"
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl NotificationSender for WebPushNotificationSender {
+    async fn send_digest(&self, user_id: i64, subject: &str, body: &str) -> Result<(), RepoError> {
+        let subscriptions = self.repo.list_push_subscriptions(user_id).await?;
+        let payload = serde_json::to_vec(&PushPayload {
+            title: subject,
+            body,
+        })
+        .map_err(|err| RepoError::Message(format!("failed to encode push payload: {err}")))?;
+        for subscription in subscriptions {
+            match self.push(&subscription, &payload).await {
+                Ok(()) => info!(
+                    user_id,
+                    subscription_id = subscription.id,
+                    "web_push_delivered"
+                ),
+                Err(err) => warn!(
+                    user_id,
+                    subscription_id = subscription.id,
+                    error = %err,
+                    "web_push_failed"
+                ),
+            }
+        }
+        Ok(())
+    }
+
+    /// Push messages show up on every registered device, often on a lock
+    /// screen, so deletion tokens are never pushed.
+    async fn send_account_deletion(
+        &self,
+        _user_id: i64,
+        _subject: &str,
+        _body: &str,
+    ) -> Result<(), RepoError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aes_gcm::{Aes128Gcm, KeyInit, aead::Aead};
+    use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+    use hkdf::Hkdf;
+    use p256::{PublicKey, SecretKey, ecdh::diffie_hellman};
+    use sha2::Sha256;
+
+    use reqwest::Url;
+
+    use super::{
+        PushEndpoints, VapidKeys, decode_subscription_keys, encrypt_payload,
+        is_push_service_endpoint, random_secret_key,
+    };
+
+    fn decrypt(ua_secret: &SecretKey, auth_secret: &[u8], body: &[u8]) -> Vec<u8> {
+        let (salt, rest) = body.split_at(16);
+        let id_len = rest[4] as usize;
+        let as_public = PublicKey::from_sec1_bytes(&rest[5..5 + id_len]).expect("sender key");
+        let ciphertext = &rest[5 + id_len..];
+
+        let shared_secret = diffie_hellman(ua_secret.to_nonzero_scalar(), as_public.as_affine());
+        let mut key_info = b"WebPush: info\0".to_vec();
+        key_info.extend_from_slice(&ua_secret.public_key().to_sec1_bytes());
+        key_info.extend_from_slice(&as_public.to_sec1_bytes());
+        let mut ikm = [0_u8; 32];
+        Hkdf::<Sha256>::new(Some(auth_secret), shared_secret.raw_secret_bytes())
+            .expand(&key_info, &mut ikm)
+            .expect("ikm");
+        let hkdf = Hkdf::<Sha256>::new(Some(salt), &ikm);
+        let mut content_key = [0_u8; 16];
+        let mut nonce = [0_u8; 12];
+        hkdf.expand(b"Content-Encoding: aes128gcm\0", &mut content_key)
+            .expect("cek");
+        hkdf.expand(b"Content-Encoding: nonce\0", &mut nonce)
+            .expect("nonce");
+        let mut record = Aes128Gcm::new(&content_key.into())
+            .decrypt(&nonce.into(), ciphertext)
+            .expect("decrypt record");
+        assert_eq!(record.pop(), Some(0x02));
+        record
+    }
+
+    #[test]
+    fn encrypted_payload_round_trips_for_subscriber() {
+        let ua_secret = random_secret_key();
+        let auth_secret = [7_u8; 16];
+        let p256dh = URL_SAFE_NO_PAD.encode(ua_secret.public_key().to_sec1_bytes());
+        let auth = URL_SAFE_NO_PAD.encode(auth_secret);
+
+        let (ua_public, decoded_auth) = decode_subscription_keys(&p256dh, &auth).expect("keys");
+        let body = encrypt_payload(&ua_public, &decoded_auth, b"{\"title\":\"due\"}")
+            .expect("encrypt payload");
+
+        assert_eq!(
+            decrypt(&ua_secret, &auth_secret, &body),
+            b"{\"title\":\"due\"}"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_subscription_keys() {
+        let p256dh = URL_SAFE_NO_PAD.encode(random_secret_key().public_key().to_sec1_bytes());
+        assert!(decode_subscription_keys("not-a-key", "AAAAAAAAAAAAAAAAAAAAAA").is_err());
+        assert!(decode_subscription_keys(&p256dh, "c2hvcnQ").is_err());
+    }
+
+    #[test]
+    fn only_known_push_services_are_endpoints() {
+        let allowed = |endpoint: &str| is_push_service_endpoint(&Url::parse(endpoint).unwrap());
+        assert!(allowed("https://fcm.googleapis.com/fcm/send/abc"));
+        assert!(allowed(
+            "https://updates.push.services.mozilla.com/wpush/v2/abc"
+        ));
+        assert!(allowed("https://web.push.apple.com/abc"));
+        assert!(allowed(
+            "https://wns2-par02p.notify.windows.com/w/?token=abc"
+        ));
+        assert!(!allowed("http://fcm.googleapis.com/fcm/send/abc"));
+        assert!(!allowed("https://fcm.googleapis.com:8443/fcm/send/abc"));
+        assert!(!allowed("https://user@fcm.googleapis.com/fcm/send/abc"));
+        assert!(!allowed("https://evilfcm.googleapis.com/abc"));
+        assert!(!allowed("https://fcm.googleapis.com.evil.example/abc"));
+        assert!(!allowed("https://127.0.0.1/abc"));
+        assert!(!allowed("https://[::1]/abc"));
+        assert!(!allowed("https://169.254.169.254/latest/meta-data"));
+        assert!(!allowed("https://localhost/abc"));
+
+        let endpoints = PushEndpoints::default();
+        endpoints
+            .clone()
+            .allow_origin(&Url::parse("http://127.0.0.1:8080").unwrap());
+        assert!(endpoints.allows(&Url::parse("http://127.0.0.1:8080/push/abc").unwrap()));
+        assert!(!endpoints.allows(&Url::parse("http://127.0.0.1:8081/push/abc").unwrap()));
+        assert!(endpoints.allows(&Url::parse("https://fcm.googleapis.com/fcm/send/abc").unwrap()));
+    }
+
+    #[test]
+    fn vapid_public_key_is_uncompressed_point() {
+        let keys = VapidKeys::generate("mailto:test@example.com");
+        let decoded = URL_SAFE_NO_PAD
+            .decode(keys.public_key_base64())
+            .expect("base64url key");
+        assert_eq!(decoded.len(), 65);
+        assert_eq!(decoded[0], 0x04);
+    }
+}
@@ -0,0 +1,467 @@
+use std::env;
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use jsonwebtoken::{Algorithm, DecodingKey, crypto};
+use p256::ecdsa::{Signature, VerifyingKey, signature::Verifier};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::models::WebauthnCeremony;
+
+/// COSE algorithm identifiers, offered during registration in this order.
+pub const COSE_ES256: i64 = -7;
+pub const COSE_EDDSA: i64 = -8;
+pub const COSE_RS256: i64 = -257;
+pub const SUPPORTED_ALGORITHMS: [i64; 3] = [COSE_ES256, COSE_EDDSA, COSE_RS256];
+
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_ATTESTED_CREDENTIAL: u8 = 0x40;
+/// rpIdHash (32) + flags (1) + signCount (4).
+const AUTH_DATA_MIN_LEN: usize = 37;
+/// Attestation objects and COSE keys are shallow; anything deeper is hostile.
+const MAX_CBOR_DEPTH: usize = 8;
+
+#[derive(Debug, Error)]
+pub enum WebauthnError {
+    #[error("malformed {0}")]
+    Malformed(&'static str),
+    #[error("client data rejected: {0}")]
+    ClientData(String),
+    #[error("authenticator data rejected: {0}")]
+    AuthenticatorData(String),
+    #[error("unsupported credential public key: {0}")]
+    UnsupportedKey(String),
+    #[error("signature verification failed")]
+    BadSignature,
+    #[error("signature counter went backwards, the credential may be cloned")]
+    CounterRegression,
+}
+
+/// Relying party settings. `origins` are the exact frontend origins allowed to
+/// run ceremonies; `rp_id` must be their registrable domain.
+#[derive(Debug, Clone)]
+pub struct WebauthnConfig {
+    pub rp_id: String,
+    pub rp_name: String,
+    pub origins: Vec<String>,
+}
+
+impl WebauthnConfig {
+    pub fn from_env() -> Self {
+        let origins = env::var("WEBAUTHN_ORIGINS")
+            .unwrap_or_else(|_| "http://localhost:5173".to_owned())
+            .split(',')
+            .map(|origin| origin.trim().trim_end_matches('/').to_owned())
+            .filter(|origin| !origin.is_empty())
+            .collect();
+        Self {
+            rp_id: env::var("WEBAUTHN_RP_ID").unwrap_or_else(|_| "localhost".to_owned()),
+            rp_name: env::var("WEBAUTHN_RP_NAME").unwrap_or_else(|_| "SRS Anything".to_owned()),
+            origins,
+        }
+    }
+
+    /// Parses `clientDataJSON` and checks its ceremony type and origin. The
+    /// caller still has to match `challenge` against a pending challenge.
+    pub fn parse_client_data(
+        &self,
+        client_data_json: &[u8],
+        ceremony: WebauthnCeremony,
+    ) -> Result<ClientData, WebauthnError> {
+        let client_data: ClientData = serde_json::from_slice(client_data_json)
+            .map_err(|_| WebauthnError::Malformed("clientDataJSON"))?;
+        let expected_type = match ceremony {
+            WebauthnCeremony::Registration => "webauthn.create",
+            WebauthnCeremony::Authentication => "webauthn.get",
+        };
+        if client_data.kind != expected_type {
+            return Err(WebauthnError::ClientData(format!(
+                "expected type {expected_type}, got {}",
+                client_data.kind
+            )));
+        }
+        if client_data.cross_origin {
+            return Err(WebauthnError::ClientData(
+                "cross-origin ceremonies are not allowed".to_owned(),
+            ));
+        }
+        if !self
+            .origins
+            .iter()
+            .any(|origin| origin == client_data.origin.trim_end_matches('/'))
+        {
+            return Err(WebauthnError::ClientData(format!(
+                "origin {} is not allowed",
+                client_data.origin
+            )));
+        }
+        Ok(client_data)
+    }
+
+    /// Validates a `navigator.credentials.create()` response and extracts the
+    /// new credential. Attestation statements are not checked: options ask
+    /// for `attestation: "none"`, so only the authenticator data is trusted.
+    pub fn verify_registration(
+        &self,
+        attestation_object: &[u8],
+    ) -> Result<RegisteredCredential, WebauthnError> {
+        let (attestation, _) = decode_cbor(attestation_object)?;
+        let auth_data = attestation
+            .map_get_text("authData")
+            .and_then(Cbor::as_bytes)
+            .ok_or(WebauthnError::Malformed("attestationObject"))?;
+        let auth_data = AuthenticatorData::parse(auth_data)?;
+        self.check_authenticator_data(&auth_data)?;
+        let (credential_id, public_key) = auth_data
+            .attested_credential
+            .ok_or_else(|| WebauthnError::AuthenticatorData("no attested credential".to_owned()))?;
+        let algorithm = CosePublicKey::parse(&public_key)?.algorithm;
+        Ok(RegisteredCredential {
+            credential_id,
+            public_key,
+            algorithm,
+            sign_count: auth_data.sign_count,
+        })
+    }
+
+    /// Validates a `navigator.credentials.get()` response against the stored
+    /// public key and returns the authenticator's new signature counter.
+    pub fn verify_assertion(
+        &self,
+        client_data_json: &[u8],
+        authenticator_data: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+        stored_sign_count: u32,
+    ) -> Result<u32, WebauthnError> {
+        let auth_data = AuthenticatorData::parse(authenticator_data)?;
+        self.check_authenticator_data(&auth_data)?;
+        let mut signed = authenticator_data.to_vec();
+        signed.extend_from_slice(&Sha256::digest(client_data_json));
+        CosePublicKey::parse(public_key)?.verify(&signed, signature)?;
+
+        // Authenticators without a counter always report zero.
+        if (auth_data.sign_count != 0 || stored_sign_count != 0)
+            && auth_data.sign_count <= stored_sign_count
+        {
+            return Err(WebauthnError::CounterRegression);
+        }
+        Ok(auth_data.sign_count)
+    }
+
+    fn check_authenticator_data(&self, auth_data: &AuthenticatorData) -> Result<(), WebauthnError> {
+        if auth_data.rp_id_hash != Sha256::digest(self.rp_id.as_bytes()).as_slice() {
+            return Err(WebauthnError::AuthenticatorData(
+                "rpIdHash does not match".to_owned(),
+            ));
+        }
+        if auth_data.flags & FLAG_USER_PRESENT == 0 {
+            return Err(WebauthnError::AuthenticatorData(
+                "user presence flag not set".to_owned(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClientData {
+    #[serde(rename = "type")]
+    kind: String,
+    /// Base64url challenge echoed back by the browser.
+    pub challenge: String,
+    origin: String,
+    #[serde(rename = "crossOrigin", default)]
+    cross_origin: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct RegisteredCredential {
+    pub credential_id: Vec<u8>,
+    /// COSE_Key bytes, stored as-is and re-parsed on every assertion.
+    pub public_key: Vec<u8>,
+    pub algorithm: i64,
+    pub sign_count: u32,
+}
+
+struct AuthenticatorData {
+    rp_id_hash: Vec<u8>,
+    flags: u8,
+    sign_count: u32,
+    attested_credential: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+impl AuthenticatorData {
+    fn parse(bytes: &[u8]) -> Result<Self, WebauthnError> {
+        if bytes.len() < AUTH_DATA_MIN_LEN {
+            return Err(WebauthnError::Malformed("authenticatorData"));
+        }
+        let flags = bytes[32];
+        let sign_count = u32::from_be_bytes([bytes[33], bytes[34], bytes[35], bytes[36]]);
+        let attested_credential = if flags & FLAG_ATTESTED_CREDENTIAL != 0 {
+            // aaguid (16) + credentialIdLength (2) + credentialId + COSE key.
+            let rest = bytes
+                .get(AUTH_DATA_MIN_LEN + 16..)
+                .filter(|rest| rest.len() >= 2)
+                .ok_or(WebauthnError::Malformed("attested credential data"))?;
+            let id_len = usize::from(u16::from_be_bytes([rest[0], rest[1]]));
+            let credential_id = rest
+                .get(2..2 + id_len)
+                .ok_or(WebauthnError::Malformed("credential id"))?;
+            let key_bytes = &rest[2 + id_len..];
+            let (_, key_len) = decode_cbor(key_bytes)?;
+            Some((credential_id.to_vec(), key_bytes[..key_len].to_vec()))
+        } else {
+            None
+        };
+        Ok(Self {
+            rp_id_hash: bytes[..32].to_vec(),
+            flags,
+            sign_count,
+            attested_credential,
+        })
+    }
+}
+
+enum CoseKeyMaterial {
+    Es256 { x: Vec<u8>, y: Vec<u8> },
+    EdDsa { x: Vec<u8> },
+    Rs256 { n: Vec<u8>, e: Vec<u8> },
+}
+
+struct CosePublicKey {
+    algorithm: i64,
+    material: CoseKeyMaterial,
+}
+
+impl CosePublicKey {
+    fn parse(bytes: &[u8]) -> Result<Self, WebauthnError> {
+        let (key, _) = decode_cbor(bytes)?;
+        let int = |label: i64| key.map_get_int(label).and_then(Cbor::as_int);
+        let bytes_at = |label: i64| {
+            key.map_get_int(label)
+                .and_then(Cbor::as_bytes)
+                .map(<[u8]>::to_vec)
+                .ok_or(WebauthnError::Malformed("COSE key"))
+        };
+        let algorithm = int(3).ok_or(WebauthnError::Malformed("COSE key"))?;
+        let material = match (int(1), algorithm, int(-1)) {
+            (Some(2), COSE_ES256, Some(1)) => CoseKeyMaterial::Es256 {
+                x: bytes_at(-2)?,
+                y: bytes_at(-3)?,
+            },
+            (Some(1), COSE_EDDSA, Some(6)) => CoseKeyMaterial::EdDsa { x: bytes_at(-2)? },
+            (Some(3), COSE_RS256, _) => CoseKeyMaterial::Rs256 {
+                n: bytes_at(-1)?,
+                e: bytes_at(-2)?,
+            },
+            (kty, alg, _) => {
+                return Err(WebauthnError::UnsupportedKey(format!(
+                    "kty {kty:?} with alg {alg}"
+                )));
+            }
+        };
+        Ok(Self {
+            algorithm,
+            material,
+        })
+    }
+
+    fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), WebauthnError> {
+        let valid = match &self.material {
+            // WebAuthn ES256 signatures are DER-encoded, unlike JWS.
+            CoseKeyMaterial::Es256 { x, y } => {
+                let mut sec1 = vec![0x04];
+                sec1.extend_from_slice(x);
+                sec1.extend_from_slice(y);
+                let key = VerifyingKey::from_sec1_bytes(&sec1)
+                    .map_err(|err| WebauthnError::UnsupportedKey(err.to_string()))?;
+                let signature =
+                    Signature::from_der(signature).map_err(|_| WebauthnError::BadSignature)?;
+                key.verify(message, &signature).is_ok()
+            }
+            CoseKeyMaterial::EdDsa { x } => {
+                let key = DecodingKey::from_ed_components(&URL_SAFE_NO_PAD.encode(x))
+                    .map_err(|err| WebauthnError::UnsupportedKey(err.to_string()))?;
+                verify_with_jwt_crypto(message, signature, &key, Algorithm::EdDSA)
+            }
+            CoseKeyMaterial::Rs256 { n, e } => {
+                let key = DecodingKey::from_rsa_raw_components(n, e);
+                verify_with_jwt_crypto(message, signature, &key, Algorithm::RS256)
+            }
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(WebauthnError::BadSignature)
+        }
+    }
+}
+
+fn verify_with_jwt_crypto(
+    message: &[u8],
+    signature: &[u8],
+    key: &DecodingKey,
+    algorithm: Algorithm,
+) -> bool {
+    crypto::verify(&URL_SAFE_NO_PAD.encode(signature), message, key, algorithm).unwrap_or(false)
+}
+
+/// The subset of CBOR that attestation objects and COSE keys use.
+#[derive(Debug, Clone, PartialEq)]
+enum Cbor {
+    Int(i128),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<Cbor>),
+    Map(Vec<(Cbor, Cbor)>),
+    Simple(u8),
+}
+
+impl Cbor {
+    fn as_int(&self) -> Option<i64> {
+        match self {
+            Self::Int(value) => i64::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+
+    fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    fn map_get(&self, matches: impl Fn(&Cbor) -> bool) -> Option<&Cbor> {
+        match self {
+            Self::Map(entries) => entries
+                .iter()
+                .find(|(key, _)| matches(key))
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    fn map_get_int(&self, label: i64) -> Option<&Cbor> {
+        self.map_get(|key| *key == Self::Int(i128::from(label)))
+    }
+
+    fn map_get_text(&self, label: &str) -> Option<&Cbor> {
+        self.map_get(|key| matches!(key, Self::Text(text) if text == label))
+    }
+}
+
+/// Decodes one CBOR item and returns it with the number of bytes consumed.
+fn decode_cbor(input: &[u8]) -> Result<(Cbor, usize), WebauthnError> {
+    let mut reader = CborReader { input, pos: 0 };
+    let value = reader.item(0)?;
+    Ok((value, reader.pos))
+}
+
+struct CborReader<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl CborReader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], WebauthnError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.input.len())
+            .ok_or(WebauthnError::Malformed("CBOR"))?;
+        let bytes = &self.input[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn argument(&mut self, info: u8) -> Result<u64, WebauthnError> {
+        let width = match info {
+            0..=23 => return Ok(u64::from(info)),
+            24 => 1,
+            25 => 2,
+            26 => 4,
+            27 => 8,
+            // Indefinite lengths and reserved values never appear in WebAuthn.
+            _ => return Err(WebauthnError::Malformed("CBOR")),
+        };
+        Ok(self
+            .take(width)?
+            .iter()
+            .fold(0, |acc, byte| (acc << 8) | u64::from(*byte)))
+    }
+
+    /// Lengths and counts are bounded by the remaining input so a forged
+    /// header cannot trigger a huge allocation.
+    fn length(&mut self, info: u8) -> Result<usize, WebauthnError> {
+        usize::try_from(self.argument(info)?)
+            .ok()
+            .filter(|len| *len <= self.input.len() - self.pos)
+            .ok_or(WebauthnError::Malformed("CBOR"))
+    }
+
+    fn item(&mut self, depth: usize) -> Result<Cbor, WebauthnError> {
+        if depth > MAX_CBOR_DEPTH {
+            return Err(WebauthnError::Malformed("CBOR"));
+        }
+        let initial = self.take(1)?[0];
+        let (major, info) = (initial >> 5, initial & 0x1f);
+        Ok(match major {
+            0 => Cbor::Int(i128::from(self.argument(info)?)),
+            1 => Cbor::Int(-1 - i128::from(self.argument(info)?)),
+            2 => {
+                let len = self.length(info)?;
+                Cbor::Bytes(self.take(len)?.to_vec())
+            }
+            3 => {
+                let len = self.length(info)?;
+                let text = std::str::from_utf8(self.take(len)?)
+                    .map_err(|_| WebauthnError::Malformed("CBOR"))?;
+                Cbor::Text(text.to_owned())
+            }
+            4 => {
+                let len = self.length(info)?;
+                let items = (0..len)
+                    .map(|_| self.item(depth + 1))
+                    .collect::<Result<_, _>>()?;
+                Cbor::Array(items)
+            }
+            5 => {
+                let len = self.length(info)?;
+                let entries = (0..len)
+                    .map(|_| Ok((self.item(depth + 1)?, self.item(depth + 1)?)))
+                    .collect::<Result<_, WebauthnError>>()?;
+                Cbor::Map(entries)
+            }
+            // Tags carry no meaning here; decode the tagged item.
+            6 => {
+                self.argument(info)?;
+                self.item(depth + 1)?
+            }
+            _ if info < 24 => Cbor::Simple(info),
+            _ => return Err(WebauthnError::Malformed("CBOR")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cbor, decode_cbor};
+
+    #[test]
+    fn decodes_cose_key_map_and_reports_consumed_length() {
+        // {1: 2, 3: -7, -2: h'0102'} followed by trailing extension bytes.
+        let bytes = [0xa3, 0x01, 0x02, 0x03, 0x26, 0x21, 0x42, 0x01, 0x02, 0xff];
+        let (value, consumed) = decode_cbor(&bytes).expect("valid cbor");
+        assert_eq!(consumed, 9);
+        assert_eq!(value.map_get_int(3).and_then(Cbor::as_int), Some(-7));
+        assert_eq!(
+            value.map_get_int(-2).and_then(Cbor::as_bytes),
+            Some(&[1u8, 2][..])
+        );
+        // A byte string claiming more bytes than remain is rejected.
+        assert!(decode_cbor(&[0x5a, 0xff, 0xff, 0xff, 0xff]).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+//! Reads and writes the zip archives Anki packages are made of, on top of
+//! the `zip` crate.
+
+use std::io::{Cursor, Read, Write};
+
+use thiserror::Error;
+use zip::{
+    CompressionMethod, ZipArchive, ZipWriter, result::ZipError as ZipCrateError,
+    write::SimpleFileOptions,
+};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ZipError {
+    #[error("not a zip archive: {0}")]
+    Malformed(String),
+    #[error("entry {0} is larger than {1} bytes")]
+    TooLarge(String, usize),
+    #[error("entry {0} is corrupt: {1}")]
+    Corrupt(String, String),
+    #[error("cannot write archive: {0}")]
+    Write(String),
+}
+
+pub struct ZipReader<'a> {
+    archive: ZipArchive<Cursor<&'a [u8]>>,
+}
+
+impl<'a> ZipReader<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self, ZipError> {
+        let archive = ZipArchive::new(Cursor::new(data))
+            .map_err(|err| ZipError::Malformed(err.to_string()))?;
+        Ok(Self { archive })
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.archive.file_names()
+    }
+
+    /// Returns the uncompressed contents of `name`, or `None` when the archive
+    /// has no such entry. Reading stops as soon as more than `max_size` bytes
+    /// come out, whatever size the entry declares.
+    pub fn read(&mut self, name: &str, max_size: usize) -> Result<Option<Vec<u8>>, ZipError> {
+        let mut entry = match self.archive.by_name(name) {
+            Ok(entry) => entry,
+            Err(ZipCrateError::FileNotFound) => return Ok(None),
+            Err(err) => return Err(ZipError::Corrupt(name.to_owned(), err.to_string())),
+        };
+        if entry.size() > max_size as u64 {
+            return Err(ZipError::TooLarge(name.to_owned(), max_size));
+        }
+        let mut contents = Vec::new();
+        (&mut entry)
+            .take(max_size as u64 + 1)
+            .read_to_end(&mut contents)
+            .map_err(|err| ZipError::Corrupt(name.to_owned(), err.to_string()))?;
+        if contents.len() > max_size {
+            return Err(ZipError::TooLarge(name.to_owned(), max_size));
+        }
+        Ok(Some(contents))
+    }
+}
+
+/// Builds an archive holding `entries`, deflated.
+pub fn write_archive(entries: &[(&str, &[u8])]) -> Result<Vec<u8>, ZipError> {
+    let write_error = |err: ZipCrateError| ZipError::Write(err.to_string());
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, data) in entries {
+        writer.start_file(*name, options).map_err(write_error)?;
+        writer
+            .write_all(data)
+            .map_err(|err| write_error(err.into()))?;
+    }
+    Ok(writer.finish().map_err(write_error)?.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+    use super::{ZipError, ZipReader, write_archive};
+
+    #[test]
+    fn archive_round_trips() {
+        let archive =
+            write_archive(&[("media", b"{}"), ("collection.anki2", b"sqlite bytes")]).unwrap();
+        let mut reader = ZipReader::new(&archive).unwrap();
+        assert_eq!(
+            reader.names().collect::<Vec<_>>(),
+            ["media", "collection.anki2"]
+        );
+        assert_eq!(
+            reader.read("collection.anki2", 1024).unwrap().unwrap(),
+            b"sqlite bytes"
+        );
+        assert_eq!(reader.read("missing", 1024).unwrap(), None);
+        assert!(matches!(
+            reader.read("collection.anki2", 4),
+            Err(ZipError::TooLarge(..))
+        ));
+        assert!(matches!(
+            ZipReader::new(b"not a zip"),
+            Err(ZipError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn inflated_size_is_capped_while_reading() {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        writer.start_file("bomb", options).unwrap();
+        writer.write_all(&vec![0; 1 << 20]).unwrap();
+        let archive = writer.finish().unwrap().into_inner();
+        assert!(archive.len() < 4096);
+
+        let mut reader = ZipReader::new(&archive).unwrap();
+        assert_eq!(
+            reader.read("bomb", 1 << 20).unwrap().unwrap().len(),
+            1 << 20
+        );
+        assert!(matches!(
+            reader.read("bomb", 1024),
+            Err(ZipError::TooLarge(..))
+        ));
+    }
+}
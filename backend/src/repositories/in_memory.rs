@@ -1,19 +1,24 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use tokio::sync::Mutex;
 
 use crate::{
     models::{
-        EmailDeliveryLog, IngestProblemInput, IntegrationToken, NotificationPreference,
-        ProblemCard, ProblemEvent, ReviewEvent, User,
+        ArchiveImport, ArchiveImportSummary, CatalogProblemInput, CatalogSeedSummary, UncataloguedProblem, CalendarFeedToken, CardContent, ClientInfo, DailyActivity, EmailDeliveryLog, Goal, GoalMetric, GoalPeriod,
+        IdempotencyClaim, IngestProblemInput, IngestedEvent, IntegrationToken, GradedReview, LeechPolicy, NotificationPreference, OidcAuthRequest,
+        PasskeyCredential, Problem, ProblemCard, ProblemEvent, PushSubscription, ReviewEvent, ReviewStats, Session,
+        StoredResponse, User, UserIdentity, WebauthnCeremony, WebauthnChallenge,
     },
     repositories::{
         error::RepoError,
         traits::{
-            AuthRepository, EventRepository, IntegrationTokenRepository, NewIntegrationToken,
-            ReviewRepository, SettingsRepository,
+            ActivityRepository, ArchiveRepository, AuthRepository, CalendarFeedRepository, CardRepository, EventRepository,
+            IdempotencyRepository, IntegrationTokenRepository, IntegrationTokenRotation, LeechRepository, NewIntegrationToken,
+            NewOidcAuthRequest, NewPasskey, NewPushSubscription, NewWebauthnChallenge,
+            ProblemRepository, PushSubscriptionRepository, ReviewRepository, SettingsRepository,
+            StatsRepository,
         },
     },
     srs::{Grade, SrsSchedule},
@@ -50,6 +55,189 @@ impl AuthRepository for InMemoryRepository {
     async fn get_user_by_email(&self, email: &str) -> Result<Option<User>, RepoError> {
         Ok(self.inner.lock().await.get_user_by_email(email))
     }
+
+    async fn create_session(
+        &self,
+        user_id: i64,
+        session_token_hash: &str,
+        expires_at: DateTime<Utc>,
+        client: ClientInfo,
+    ) -> Result<Session, RepoError> {
+        Ok(self.inner.lock().await.create_session(
+            user_id,
+            session_token_hash.to_owned(),
+            expires_at,
+            client.ip,
+            client.user_agent,
+        ))
+    }
+
+    async fn user_from_session(
+        &self,
+        session_token_hash: &str,
+    ) -> Result<Option<(User, Session)>, RepoError> {
+        Ok(self
+            .inner
+            .lock()
+            .await
+            .user_from_session(session_token_hash))
+    }
+
+    async fn touch_session(&self, session_id: i64) -> Result<(), RepoError> {
+        self.inner.lock().await.touch_session(session_id);
+        Ok(())
+    }
+
+    async fn list_active_sessions(&self, user_id: i64) -> Result<Vec<Session>, RepoError> {
+        Ok(self.inner.lock().await.list_active_sessions(user_id))
+    }
+
+    async fn revoke_session(&self, user_id: i64, session_id: i64) -> Result<bool, RepoError> {
+        Ok(self.inner.lock().await.revoke_session(user_id, session_id))
+    }
+
+    async fn revoke_other_sessions(
+        &self,
+        user_id: i64,
+        keep_session_id: i64,
+    ) -> Result<u64, RepoError> {
+        Ok(self
+            .inner
+            .lock()
+            .await
+            .revoke_other_sessions(user_id, keep_session_id))
+    }
+
+    async fn create_oidc_auth_request(&self, request: NewOidcAuthRequest) -> Result<(), RepoError> {
+        self.inner.lock().await.create_oidc_auth_request(
+            request.state_hash,
+            request.nonce,
+            request.pkce_verifier,
+            request.expires_at,
+        );
+        Ok(())
+    }
+
+    async fn consume_oidc_auth_request(
+        &self,
+        state_hash: &str,
+    ) -> Result<Option<OidcAuthRequest>, RepoError> {
+        Ok(self
+            .inner
+            .lock()
+            .await
+            .consume_oidc_auth_request(state_hash))
+    }
+
+    async fn user_from_identity(
+        &self,
+        issuer: &str,
+        subject: &str,
+    ) -> Result<Option<User>, RepoError> {
+        Ok(self.inner.lock().await.user_from_identity(issuer, subject))
+    }
+
+    async fn link_identity(
+        &self,
+        user_id: i64,
+        issuer: &str,
+        subject: &str,
+        email: Option<&str>,
+    ) -> Result<UserIdentity, RepoError> {
+        Ok(self
+            .inner
+            .lock()
+            .await
+            .link_identity(user_id, issuer, subject, email))
+    }
+
+    async fn create_webauthn_challenge(
+        &self,
+        challenge: NewWebauthnChallenge,
+    ) -> Result<(), RepoError> {
+        self.inner.lock().await.create_webauthn_challenge(
+            challenge.challenge_hash,
+            challenge.ceremony,
+            challenge.user_id,
+            challenge.expires_at,
+        );
+        Ok(())
+    }
+
+    async fn consume_webauthn_challenge(
+        &self,
+        challenge_hash: &str,
+        ceremony: WebauthnCeremony,
+    ) -> Result<Option<WebauthnChallenge>, RepoError> {
+        Ok(self
+            .inner
+            .lock()
+            .await
+            .consume_webauthn_challenge(challenge_hash, ceremony))
+    }
+
+    async fn create_passkey(
+        &self,
+        user_id: i64,
+        passkey: NewPasskey,
+    ) -> Result<PasskeyCredential, RepoError> {
+        self.inner
+            .lock()
+            .await
+            .create_passkey(
+                user_id,
+                passkey.credential_id,
+                passkey.public_key,
+                passkey.algorithm,
+                passkey.sign_count,
+                passkey.label,
+            )
+            .ok_or_else(|| RepoError::Message("credential id already registered".to_owned()))
+    }
+
+    async fn list_passkeys(&self, user_id: i64) -> Result<Vec<PasskeyCredential>, RepoError> {
+        Ok(self.inner.lock().await.list_passkeys(user_id))
+    }
+
+    async fn passkey_by_credential_id(
+        &self,
+        credential_id: &str,
+    ) -> Result<Option<(User, PasskeyCredential)>, RepoError> {
+        Ok(self
+            .inner
+            .lock()
+            .await
+            .passkey_by_credential_id(credential_id))
+    }
+
+    async fn record_passkey_use(&self, passkey_id: i64, sign_count: i64) -> Result<(), RepoError> {
+        self.inner
+            .lock()
+            .await
+            .record_passkey_use(passkey_id, sign_count);
+        Ok(())
+    }
+
+    async fn delete_passkey(&self, user_id: i64, passkey_id: i64) -> Result<bool, RepoError> {
+        Ok(self.inner.lock().await.delete_passkey(user_id, passkey_id))
+    }
+
+    async fn create_account_deletion_request(
+        &self,
+        user_id: i64,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), RepoError> {
+        self.inner
+            .lock()
+            .await
+            .create_account_deletion_request(user_id, token_hash, expires_at);
+        Ok(())
+    }
+
+    async fn delete_account(&self, user_id: i64, token_hash: &str) -> Result<bool, RepoError> {
+        Ok(self.inner.lock().await.delete_account(user_id, token_hash))
+    }
 }
 
 #[async_trait]
@@ -91,7 +279,7 @@ impl IntegrationTokenRepository for InMemoryRepository {
     async fn user_from_integration_token(
         &self,
         token_hash: &str,
-    ) -> Result<Option<User>, RepoError> {
+    ) -> Result<Option<(User, IntegrationToken)>, RepoError> {
         Ok(self
             .inner
             .lock()
@@ -99,11 +287,105 @@ impl IntegrationTokenRepository for InMemoryRepository {
             .user_from_integration_token(token_hash))
     }
 
-    async fn touch_integration_token_usage(&self, token_hash: &str) -> Result<(), RepoError> {
+    async fn rotate_integration_token(
+        &self,
+        user_id: i64,
+        token_id: i64,
+        rotation: IntegrationTokenRotation,
+    ) -> Result<Option<IntegrationToken>, RepoError> {
+        Ok(self.inner.lock().await.rotate_integration_token(
+            user_id,
+            token_id,
+            rotation.token_hash,
+            rotation.previous_valid_until,
+            rotation.expires_at,
+        ))
+    }
+
+    async fn touch_integration_token_usage(
+        &self,
+        token_id: i64,
+        client: ClientInfo,
+    ) -> Result<(), RepoError> {
+        self.inner.lock().await.touch_integration_token_usage(
+            token_id,
+            client.ip,
+            client.user_agent,
+        );
+        Ok(())
+    }
+
+    async fn list_integration_tokens_expiring_between(
+        &self,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<IntegrationToken>, RepoError> {
+        Ok(self
+            .inner
+            .lock()
+            .await
+            .list_integration_tokens_expiring_between(from, until))
+    }
+
+    async fn mark_integration_token_expiry_notified(&self, token_id: i64) -> Result<(), RepoError> {
+        self.inner
+            .lock()
+            .await
+            .mark_integration_token_expiry_notified(token_id);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CalendarFeedRepository for InMemoryRepository {
+    async fn create_calendar_feed_token(
+        &self,
+        user_id: i64,
+        token_hash: &str,
+        label: &str,
+    ) -> Result<CalendarFeedToken, RepoError> {
+        Ok(self.inner.lock().await.create_calendar_feed_token(
+            user_id,
+            token_hash.to_owned(),
+            label.to_owned(),
+        ))
+    }
+
+    async fn list_calendar_feed_tokens(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<CalendarFeedToken>, RepoError> {
+        Ok(self.inner.lock().await.list_calendar_feed_tokens(user_id))
+    }
+
+    async fn revoke_calendar_feed_token(
+        &self,
+        user_id: i64,
+        token_id: i64,
+    ) -> Result<bool, RepoError> {
+        Ok(self
+            .inner
+            .lock()
+            .await
+            .revoke_calendar_feed_token(user_id, token_id))
+    }
+
+    async fn user_from_calendar_feed_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<User>, RepoError> {
+        Ok(self
+            .inner
+            .lock()
+            .await
+            .user_from_calendar_feed_token(token_hash))
+    }
+
+    async fn touch_calendar_feed_token_usage(&self, token_hash: &str) -> Result<(), RepoError> {
         self.inner
             .lock()
             .await
-            .touch_integration_token_usage(token_hash);
+            .touch_calendar_feed_token_usage(token_hash);
         Ok(())
     }
 }
@@ -114,9 +396,20 @@ impl EventRepository for InMemoryRepository {
         Ok(self.inner.lock().await.ingest_event(payload))
     }
 
+    async fn ingest_events(
+        &self,
+        payloads: Vec<IngestProblemInput>,
+    ) -> Result<Vec<IngestedEvent>, RepoError> {
+        Ok(self.inner.lock().await.ingest_events(payloads))
+    }
+
     async fn latest_event_for_user(&self, user_id: i64) -> Result<Option<ProblemEvent>, RepoError> {
         Ok(self.inner.lock().await.latest_event_for_user(user_id))
     }
+
+    async fn list_events(&self, user_id: i64) -> Result<Vec<ProblemEvent>, RepoError> {
+        Ok(self.inner.lock().await.list_events(user_id))
+    }
 }
 
 #[async_trait]
@@ -133,13 +426,22 @@ impl ReviewRepository for InMemoryRepository {
         Ok(self.inner.lock().await.upcoming_cards(user_id))
     }
 
+    async fn list_cards(&self, user_id: i64) -> Result<Vec<ProblemCard>, RepoError> {
+        Ok(self.inner.lock().await.list_cards(user_id))
+    }
+
     async fn grade_card(
         &self,
         user_id: i64,
         card_id: i64,
         grade: Grade,
-    ) -> Result<Option<ReviewEvent>, RepoError> {
-        Ok(self.inner.lock().await.grade_card(user_id, card_id, grade))
+        leech: LeechPolicy,
+    ) -> Result<Option<GradedReview>, RepoError> {
+        Ok(self
+            .inner
+            .lock()
+            .await
+            .grade_card(user_id, card_id, grade, leech))
     }
 
     async fn user_history(&self, user_id: i64) -> Result<Vec<ReviewEvent>, RepoError> {
@@ -147,6 +449,173 @@ impl ReviewRepository for InMemoryRepository {
     }
 }
 
+#[async_trait]
+impl StatsRepository for InMemoryRepository {
+    async fn review_stats(
+        &self,
+        user_id: i64,
+        since: DateTime<Utc>,
+        mature_after: Duration,
+    ) -> Result<ReviewStats, RepoError> {
+        Ok(self
+            .inner
+            .lock()
+            .await
+            .review_stats(user_id, since, mature_after))
+    }
+}
+
+#[async_trait]
+impl ActivityRepository for InMemoryRepository {
+    async fn daily_activity(
+        &self,
+        user_id: i64,
+        timezone: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<DailyActivity>, RepoError> {
+        Ok(self
+            .inner
+            .lock()
+            .await
+            .daily_activity(user_id, timezone, from, to))
+    }
+
+    async fn active_days(
+        &self,
+        user_id: i64,
+        timezone: &str,
+    ) -> Result<Vec<NaiveDate>, RepoError> {
+        Ok(self.inner.lock().await.active_days(user_id, timezone))
+    }
+
+    async fn list_goals(&self, user_id: i64) -> Result<Vec<Goal>, RepoError> {
+        Ok(self.inner.lock().await.list_goals(user_id))
+    }
+
+    async fn upsert_goal(
+        &self,
+        user_id: i64,
+        metric: GoalMetric,
+        period: GoalPeriod,
+        target: i64,
+    ) -> Result<Goal, RepoError> {
+        Ok(self
+            .inner
+            .lock()
+            .await
+            .upsert_goal(user_id, metric, period, target))
+    }
+
+    async fn delete_goal(&self, user_id: i64, goal_id: i64) -> Result<bool, RepoError> {
+        Ok(self.inner.lock().await.delete_goal(user_id, goal_id))
+    }
+}
+
+#[async_trait]
+impl LeechRepository for InMemoryRepository {
+    async fn list_leeches(&self, user_id: i64) -> Result<Vec<ProblemCard>, RepoError> {
+        Ok(self.inner.lock().await.list_leeches(user_id))
+    }
+
+    async fn clear_leech(
+        &self,
+        user_id: i64,
+        card_id: i64,
+    ) -> Result<Option<ProblemCard>, RepoError> {
+        Ok(self.inner.lock().await.clear_leech(user_id, card_id))
+    }
+}
+
+#[async_trait]
+impl ProblemRepository for InMemoryRepository {
+    async fn find_problem(&self, source: &str, slug: &str) -> Result<Option<Problem>, RepoError> {
+        Ok(self.inner.lock().await.find_problem(source, slug))
+    }
+
+    async fn count_problem_learners(&self, problem_id: i64) -> Result<i64, RepoError> {
+        Ok(self.inner.lock().await.count_problem_learners(problem_id))
+    }
+
+    async fn seed_problems(
+        &self,
+        version: i64,
+        problems: Vec<CatalogProblemInput>,
+    ) -> Result<CatalogSeedSummary, RepoError> {
+        Ok(self.inner.lock().await.seed_problems(version, problems))
+    }
+
+    async fn uncatalogued_problems(&self) -> Result<Vec<UncataloguedProblem>, RepoError> {
+        Ok(self.inner.lock().await.uncatalogued_problems())
+    }
+}
+
+#[async_trait]
+impl CardRepository for InMemoryRepository {
+    async fn create_card(
+        &self,
+        user_id: i64,
+        key: &str,
+        content: CardContent,
+        next_due_at: DateTime<Utc>,
+    ) -> Result<Option<ProblemCard>, RepoError> {
+        Ok(self
+            .inner
+            .lock()
+            .await
+            .create_card(user_id, key, content, next_due_at))
+    }
+
+    async fn get_card(&self, user_id: i64, card_id: i64) -> Result<Option<ProblemCard>, RepoError> {
+        Ok(self.inner.lock().await.get_card(user_id, card_id))
+    }
+
+    async fn update_card(
+        &self,
+        user_id: i64,
+        card_id: i64,
+        content: CardContent,
+    ) -> Result<Option<ProblemCard>, RepoError> {
+        Ok(self.inner.lock().await.update_card(user_id, card_id, content))
+    }
+
+    async fn delete_card(&self, user_id: i64, card_id: i64) -> Result<bool, RepoError> {
+        Ok(self.inner.lock().await.delete_card(user_id, card_id))
+    }
+
+    async fn cards_by_keys(
+        &self,
+        user_id: i64,
+        keys: &[String],
+    ) -> Result<Vec<ProblemCard>, RepoError> {
+        Ok(self.inner.lock().await.cards_by_keys(user_id, keys))
+    }
+
+    async fn upsert_cards(
+        &self,
+        user_id: i64,
+        cards: Vec<(String, CardContent)>,
+        next_due_at: DateTime<Utc>,
+    ) -> Result<Vec<ProblemCard>, RepoError> {
+        Ok(self
+            .inner
+            .lock()
+            .await
+            .upsert_cards(user_id, cards, next_due_at))
+    }
+}
+
+#[async_trait]
+impl ArchiveRepository for InMemoryRepository {
+    async fn import_archive(
+        &self,
+        user_id: i64,
+        import: ArchiveImport,
+    ) -> Result<ArchiveImportSummary, RepoError> {
+        Ok(self.inner.lock().await.import_archive(user_id, import))
+    }
+}
+
 #[async_trait]
 impl SettingsRepository for InMemoryRepository {
     async fn get_notification_preference(
@@ -161,11 +630,13 @@ impl SettingsRepository for InMemoryRepository {
         user_id: i64,
         email_enabled: bool,
         digest_hour_utc: u8,
+        timezone: Option<&str>,
     ) -> Result<Option<NotificationPreference>, RepoError> {
         Ok(self.inner.lock().await.set_notification_preference(
             user_id,
             email_enabled,
             digest_hour_utc,
+            timezone,
         ))
     }
 
@@ -190,3 +661,99 @@ impl SettingsRepository for InMemoryRepository {
         Ok(self.inner.lock().await.log_email(user_id, subject, body))
     }
 }
+
+#[async_trait]
+impl PushSubscriptionRepository for InMemoryRepository {
+    async fn upsert_push_subscription(
+        &self,
+        user_id: i64,
+        new_subscription: NewPushSubscription,
+    ) -> Result<PushSubscription, RepoError> {
+        Ok(self.inner.lock().await.upsert_push_subscription(
+            user_id,
+            new_subscription.endpoint,
+            new_subscription.p256dh,
+            new_subscription.auth,
+            new_subscription.device_label,
+        ))
+    }
+
+    async fn list_push_subscriptions(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<PushSubscription>, RepoError> {
+        Ok(self.inner.lock().await.list_push_subscriptions(user_id))
+    }
+
+    async fn delete_push_subscription(
+        &self,
+        user_id: i64,
+        subscription_id: i64,
+    ) -> Result<bool, RepoError> {
+        Ok(self
+            .inner
+            .lock()
+            .await
+            .delete_push_subscription(user_id, subscription_id))
+    }
+
+    async fn delete_push_subscription_by_endpoint(&self, endpoint: &str) -> Result<(), RepoError> {
+        self.inner
+            .lock()
+            .await
+            .delete_push_subscription_by_endpoint(endpoint);
+        Ok(())
+    }
+
+    async fn touch_push_subscription(&self, subscription_id: i64) -> Result<(), RepoError> {
+        self.inner
+            .lock()
+            .await
+            .touch_push_subscription(subscription_id);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl IdempotencyRepository for InMemoryRepository {
+    async fn claim_idempotency_key(
+        &self,
+        user_id: i64,
+        key: &str,
+        request_hash: &str,
+        now: DateTime<Utc>,
+        stale_before: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<IdempotencyClaim, RepoError> {
+        Ok(self.inner.lock().await.claim_idempotency_key(
+            user_id,
+            key,
+            request_hash,
+            now,
+            stale_before,
+            expires_at,
+        ))
+    }
+
+    async fn complete_idempotency_key(
+        &self,
+        user_id: i64,
+        key: &str,
+        response: StoredResponse,
+    ) -> Result<(), RepoError> {
+        self.inner
+            .lock()
+            .await
+            .complete_idempotency_key(user_id, key, response);
+        Ok(())
+    }
+
+    async fn release_idempotency_key(&self, user_id: i64, key: &str) -> Result<(), RepoError> {
+        self.inner.lock().await.release_idempotency_key(user_id, key);
+        Ok(())
+    }
+
+    async fn purge_expired_idempotency_keys(&self, now: DateTime<Utc>) -> Result<u64, RepoError> {
+        Ok(self.inner.lock().await.purge_expired_idempotency_keys(now))
+    }
+}
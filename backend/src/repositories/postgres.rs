@@ -1,24 +1,109 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
 use async_trait::async_trait;
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use rand::{Rng, distr::Alphanumeric};
-use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, Statement, TransactionTrait, Value};
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, DbBackend, QueryResult, Statement, TransactionTrait, Value,
+};
 
 use crate::{
     models::{
-        EmailDeliveryLog, IngestProblemInput, IntegrationToken, NotificationPreference,
-        ProblemCard, ProblemEvent, ProblemStatus, ReviewEvent, User, hash_token,
-        make_event_dedup_key,
+        ArchiveImport, ArchiveImportSummary, CUSTOM_CARD_SOURCE, CalendarFeedToken, CardContent,
+        CardKind, CatalogProblemInput, CatalogSeedSummary, ClientInfo, DailyActivity,
+        EmailDeliveryLog, Goal, GoalMetric, GoalPeriod, GradedReview, IdempotencyClaim,
+        IdempotencyRecord, ImportMode, IngestProblemInput, IngestedEvent, IntegrationToken,
+        LEECH_TAG, LeechAction, LeechPolicy, NotificationPreference, OidcAuthRequest,
+        PasskeyCredential, Problem, ProblemCard, ProblemEvent, ProblemStatus, PushSubscription,
+        RetentionCounts, ReviewEvent, ReviewStats, Session, StoredResponse, UncataloguedProblem,
+        User, UserIdentity, WebauthnCeremony, WebauthnChallenge, hash_token, make_event_dedup_key,
     },
     repositories::{
         error::RepoError,
         traits::{
-            AuthRepository, EventRepository, IntegrationTokenRepository, NewIntegrationToken,
-            ReviewRepository, SettingsRepository,
+            ActivityRepository, ArchiveRepository, AuthRepository, CalendarFeedRepository,
+            CardRepository, EventRepository, IdempotencyRepository, IntegrationTokenRepository,
+            IntegrationTokenRotation, LeechRepository, NewIntegrationToken, NewOidcAuthRequest,
+            NewPasskey, NewPushSubscription, NewWebauthnChallenge, ProblemRepository,
+            PushSubscriptionRepository, ReviewRepository, SettingsRepository, StatsRepository,
         },
     },
     srs::{Grade, SrsSchedule, next_interval_index},
 };
 
+/// Every table holding a `user_id`, children before parents. Account
+/// deletion clears these before removing the `users` row.
+const USER_OWNED_TABLES: [&str; 16] = [
+    "review_events",
+    "problem_cards",
+    "problem_events",
+    "notification_preferences",
+    "email_delivery_logs",
+    "integration_tokens",
+    "calendar_feed_tokens",
+    "push_subscriptions",
+    "sessions",
+    "magic_link_tokens",
+    "user_identities",
+    "passkey_credentials",
+    "webauthn_challenges",
+    "account_deletion_requests",
+    "idempotency_keys",
+    "goals",
+];
+
+/// Card columns for the queries that list cards. Problem cards show their
+/// catalog entry's title, URL, tags and difficulty, plus the card's own
+/// leech tag.
+const CARD_SELECT: &str = r#"
+    SELECT c.id, c.user_id, c.kind, c.source, c.problem_slug,
+           COALESCE(p.title, c.title) AS title, COALESCE(p.url, c.url) AS url,
+           c.front, c.back,
+           CASE WHEN p.tags IS NULL OR p.tags = '{}' THEN c.tags
+                WHEN 'leech' = ANY(c.tags) THEN array_append(p.tags, 'leech')
+                ELSE p.tags
+           END AS tags,
+           c.problem_id, p.difficulty, c.interval_index, c.next_due_at, c.created_at,
+           c.lapses, c.leech_at, c.suspended_at
+    FROM problem_cards c
+    LEFT JOIN problems p ON p.id = c.problem_id
+"#;
+
+/// One row per review from `$2` on, with what the stats group by. The window
+/// runs over each card's full history so maturity and lapses can look at
+/// reviews before `$2`. `$3` is the maturity threshold in seconds.
+const STATS_REVIEWS: &str = r#"
+    WITH history AS (
+        SELECT r.card_id, r.grade, r.reviewed_at,
+               LAG(r.grade) OVER card_reviews AS prior_grade,
+               LAG(r.next_due_at - r.reviewed_at) OVER card_reviews AS prior_interval,
+               r.reviewed_at - LAG(r.reviewed_at) OVER card_reviews AS gap
+        FROM review_events r
+        WHERE r.user_id = $1
+        WINDOW card_reviews AS (PARTITION BY r.card_id ORDER BY r.reviewed_at, r.id)
+    ),
+    reviews AS (
+        SELECT h.grade,
+               date_trunc('week', h.reviewed_at AT TIME ZONE 'UTC')::DATE AS week_start,
+               c.source,
+               COALESCE(NULLIF(p.tags, '{}'), c.tags) AS tags,
+               COALESCE(EXTRACT(EPOCH FROM h.prior_interval) >= $3, FALSE) AS mature,
+               COALESCE(h.grade = 'again' AND h.prior_grade <> 'again', FALSE) AS lapse,
+               EXTRACT(EPOCH FROM h.gap)::FLOAT8 AS gap_seconds
+        FROM history h
+        JOIN problem_cards c ON c.id = h.card_id
+        LEFT JOIN problems p ON p.id = c.problem_id
+        WHERE h.reviewed_at >= $2
+    )
+"#;
+
+const STATS_COUNTS: &str = r#"
+    COUNT(*) AS reviews,
+    COUNT(*) FILTER (WHERE mature) AS mature_reviews,
+    COUNT(*) FILTER (WHERE mature AND grade <> 'again') AS mature_passed,
+    COUNT(*) FILTER (WHERE lapse) AS lapses
+"#;
+
 #[derive(Clone)]
 pub struct PostgresRepository {
     db: DatabaseConnection,
@@ -67,6 +152,153 @@ impl PostgresRepository {
     fn duration_at_index(&self, index: usize) -> Duration {
         self.schedule.duration_for_index(index)
     }
+
+    /// Rewrites slugs the catalog lists as an alias to the problem's own slug
+    /// and URL, so renamed problems keep one card.
+    async fn resolve_aliases<C: ConnectionTrait>(
+        conn: &C,
+        payloads: &mut [IngestProblemInput],
+    ) -> Result<(), RepoError> {
+        let slugs = payloads
+            .iter()
+            .map(|payload| payload.problem_slug.clone())
+            .collect::<Vec<_>>();
+        let rows = conn
+            .query_all(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                SELECT p.source, alias, p.slug, p.url
+                FROM problems p, unnest(p.aliases) AS alias
+                WHERE p.aliases && $1 AND alias = ANY($1)
+                "#,
+                vec![Value::from(slugs)],
+            ))
+            .await?;
+        let mut canonical = HashMap::new();
+        for row in &rows {
+            canonical.insert(
+                (
+                    row.try_get::<String>("", "source")?,
+                    row.try_get::<String>("", "alias")?,
+                ),
+                (
+                    row.try_get::<String>("", "slug")?,
+                    row.try_get::<String>("", "url")?,
+                ),
+            );
+        }
+        for payload in payloads {
+            let key = (payload.source.clone(), payload.problem_slug.clone());
+            if let Some((slug, url)) = canonical.get(&key) {
+                payload.problem_slug = slug.clone();
+                payload.url = url.clone();
+            }
+        }
+        Ok(())
+    }
+
+    /// Flags the card as a leech once it has `policy.threshold` lapses,
+    /// tagging or suspending it as the policy says. Returns the card only
+    /// when this call flagged it, so each leech is acted on once.
+    async fn flag_leech<C: ConnectionTrait>(
+        conn: &C,
+        user_id: i64,
+        card_id: i64,
+        policy: LeechPolicy,
+        now: DateTime<Utc>,
+    ) -> Result<Option<ProblemCard>, RepoError> {
+        let flagged = conn
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                UPDATE problem_cards
+                SET leech_at = $4,
+                    tags = CASE WHEN $5 THEN array_append(array_remove(tags, $7), $7) ELSE tags END,
+                    suspended_at = CASE WHEN $6 THEN $4 ELSE suspended_at END
+                WHERE id = $1 AND user_id = $2 AND leech_at IS NULL AND lapses >= $3
+                "#,
+                vec![
+                    Value::from(card_id),
+                    Value::from(user_id),
+                    Value::from(i64::from(policy.threshold)),
+                    Value::from(now),
+                    Value::from(policy.action == LeechAction::Tag),
+                    Value::from(policy.action == LeechAction::Suspend),
+                    Value::from(LEECH_TAG),
+                ],
+            ))
+            .await?
+            .rows_affected();
+        if flagged == 0 {
+            return Ok(None);
+        }
+        let row = conn
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                format!(
+                    r#"
+                    {CARD_SELECT}
+                    WHERE c.id = $1
+                    "#
+                ),
+                vec![Value::from(card_id)],
+            ))
+            .await?;
+        row.as_ref().map(card_from_row).transpose()
+    }
+
+    /// Adds catalog entries for `(source, slug, url)` problems that have none.
+    /// `url` must be the source registry's canonical URL. Only the identity is
+    /// stored; title and metadata are left for the seeded dataset, and existing
+    /// entries are not touched.
+    async fn catalog_problems<C: ConnectionTrait>(
+        conn: &C,
+        problems: &BTreeSet<(String, String, String)>,
+    ) -> Result<(), RepoError> {
+        if problems.is_empty() {
+            return Ok(());
+        }
+        let (mut sources, mut slugs, mut urls) = (Vec::new(), Vec::new(), Vec::new());
+        for (source, slug, url) in problems {
+            sources.push(source.clone());
+            slugs.push(slug.clone());
+            urls.push(url.clone());
+        }
+        conn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"
+            INSERT INTO problems (source, slug, url)
+            SELECT * FROM unnest($1::TEXT[], $2::TEXT[], $3::TEXT[])
+            ON CONFLICT (source, slug) DO NOTHING
+            "#,
+            vec![Value::from(sources), Value::from(slugs), Value::from(urls)],
+        ))
+        .await?;
+        Ok(())
+    }
+
+    /// Links the user's unlinked problem cards to their catalog entries.
+    async fn link_cards_to_catalog<C: ConnectionTrait>(
+        conn: &C,
+        user_id: i64,
+    ) -> Result<(), RepoError> {
+        conn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"
+            UPDATE problem_cards c
+            SET problem_id = p.id
+            FROM problems p
+            WHERE c.user_id = $1
+              AND c.problem_id IS NULL
+              AND c.kind = 'problem'
+              AND p.source = c.source
+              AND p.slug = c.problem_slug
+            "#,
+            vec![Value::from(user_id)],
+        ))
+        .await?;
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -218,452 +450,2409 @@ impl AuthRepository for PostgresRepository {
         tx.commit().await?;
         Ok(Some(user))
     }
-}
 
-#[async_trait]
-impl IntegrationTokenRepository for PostgresRepository {
-    async fn create_integration_token(
+    async fn create_session(
         &self,
         user_id: i64,
-        token_hash: &str,
-        new_token: NewIntegrationToken,
-    ) -> Result<IntegrationToken, RepoError> {
+        session_token_hash: &str,
+        expires_at: DateTime<Utc>,
+        client: ClientInfo,
+    ) -> Result<Session, RepoError> {
         let row = self
             .db
             .query_one(Statement::from_sql_and_values(
                 DbBackend::Postgres,
                 r#"
-                INSERT INTO integration_tokens
-                    (user_id, token_hash, label, scopes, expires_at)
+                INSERT INTO sessions (user_id, session_token_hash, expires_at, user_agent, ip)
                 VALUES ($1, $2, $3, $4, $5)
-                RETURNING id, user_id, token_hash, label, scopes, created_at, expires_at, revoked_at, last_used_at
+                RETURNING id, user_id, session_token_hash, expires_at, created_at, last_seen_at,
+                          revoked_at, user_agent, ip
                 "#,
                 vec![
                     Value::from(user_id),
-                    Value::from(token_hash.to_owned()),
-                    Value::from(new_token.label),
-                    Value::from(new_token.scopes),
-                    Value::from(new_token.expires_at),
+                    Value::from(session_token_hash.to_owned()),
+                    Value::from(expires_at),
+                    Value::from(client.user_agent),
+                    Value::from(client.ip),
                 ],
             ))
             .await?
-            .ok_or_else(|| RepoError::Message("failed to insert integration token".to_owned()))?;
+            .ok_or_else(|| RepoError::Message("failed to insert session".to_owned()))?;
 
-        Ok(IntegrationToken {
-            id: row.try_get("", "id")?,
-            user_id: row.try_get("", "user_id")?,
-            token_hash: row.try_get("", "token_hash")?,
-            label: row.try_get("", "label")?,
-            scopes: row.try_get("", "scopes")?,
-            created_at: row.try_get("", "created_at")?,
-            expires_at: row.try_get("", "expires_at")?,
-            revoked_at: row.try_get("", "revoked_at")?,
-            last_used_at: row.try_get("", "last_used_at")?,
-        })
+        session_from_row(&row)
     }
 
-    async fn list_integration_tokens(
+    async fn user_from_session(
         &self,
-        user_id: i64,
-    ) -> Result<Vec<IntegrationToken>, RepoError> {
+        session_token_hash: &str,
+    ) -> Result<Option<(User, Session)>, RepoError> {
+        let row = self
+            .db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                SELECT u.id AS u_id, u.email AS u_email, u.created_at AS u_created_at,
+                       s.id, s.user_id, s.session_token_hash, s.expires_at, s.created_at,
+                       s.last_seen_at, s.revoked_at, s.user_agent, s.ip
+                FROM sessions s
+                INNER JOIN users u ON u.id = s.user_id
+                WHERE s.session_token_hash = $1
+                  AND s.revoked_at IS NULL
+                  AND s.expires_at > $2
+                LIMIT 1
+                "#,
+                vec![
+                    Value::from(session_token_hash.to_owned()),
+                    Value::from(Utc::now()),
+                ],
+            ))
+            .await?;
+
+        row.map(|row| {
+            let user = User {
+                id: row.try_get("", "u_id")?,
+                email: row.try_get("", "u_email")?,
+                created_at: row.try_get("", "u_created_at")?,
+            };
+            Ok((user, session_from_row(&row)?))
+        })
+        .transpose()
+    }
+
+    async fn touch_session(&self, session_id: i64) -> Result<(), RepoError> {
+        self.db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "UPDATE sessions SET last_seen_at = NOW() WHERE id = $1",
+                vec![Value::from(session_id)],
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn list_active_sessions(&self, user_id: i64) -> Result<Vec<Session>, RepoError> {
         let rows = self
             .db
             .query_all(Statement::from_sql_and_values(
                 DbBackend::Postgres,
                 r#"
-                SELECT id, user_id, token_hash, label, scopes, created_at, expires_at, revoked_at, last_used_at
-                FROM integration_tokens
+                SELECT id, user_id, session_token_hash, expires_at, created_at, last_seen_at,
+                       revoked_at, user_agent, ip
+                FROM sessions
                 WHERE user_id = $1
-                ORDER BY created_at DESC
+                  AND revoked_at IS NULL
+                  AND expires_at > $2
+                ORDER BY last_seen_at DESC
                 "#,
-                vec![Value::from(user_id)],
+                vec![Value::from(user_id), Value::from(Utc::now())],
             ))
             .await?;
 
-        rows.into_iter()
-            .map(|row| {
-                Ok(IntegrationToken {
-                    id: row.try_get("", "id")?,
-                    user_id: row.try_get("", "user_id")?,
-                    token_hash: row.try_get("", "token_hash")?,
-                    label: row.try_get("", "label")?,
-                    scopes: row.try_get("", "scopes")?,
-                    created_at: row.try_get("", "created_at")?,
-                    expires_at: row.try_get("", "expires_at")?,
-                    revoked_at: row.try_get("", "revoked_at")?,
-                    last_used_at: row.try_get("", "last_used_at")?,
-                })
-            })
-            .collect()
+        rows.iter().map(session_from_row).collect()
     }
 
-    async fn revoke_integration_token(
-        &self,
-        user_id: i64,
-        token_id: i64,
-    ) -> Result<bool, RepoError> {
+    async fn revoke_session(&self, user_id: i64, session_id: i64) -> Result<bool, RepoError> {
         let result = self
             .db
             .execute(Statement::from_sql_and_values(
                 DbBackend::Postgres,
                 r#"
-                UPDATE integration_tokens
+                UPDATE sessions
                 SET revoked_at = NOW()
                 WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
                 "#,
-                vec![Value::from(token_id), Value::from(user_id)],
+                vec![Value::from(session_id), Value::from(user_id)],
             ))
             .await?;
         Ok(result.rows_affected() > 0)
     }
 
-    async fn user_from_integration_token(
+    async fn revoke_other_sessions(
         &self,
-        token_hash: &str,
+        user_id: i64,
+        keep_session_id: i64,
+    ) -> Result<u64, RepoError> {
+        let result = self
+            .db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                UPDATE sessions
+                SET revoked_at = NOW()
+                WHERE user_id = $1 AND id <> $2 AND revoked_at IS NULL
+                "#,
+                vec![Value::from(user_id), Value::from(keep_session_id)],
+            ))
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn create_oidc_auth_request(&self, request: NewOidcAuthRequest) -> Result<(), RepoError> {
+        let txn = self.db.begin().await?;
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "DELETE FROM oidc_auth_requests WHERE expires_at <= $1",
+            vec![Value::from(Utc::now())],
+        ))
+        .await?;
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"
+            INSERT INTO oidc_auth_requests (state_hash, nonce, pkce_verifier, expires_at)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            vec![
+                Value::from(request.state_hash),
+                Value::from(request.nonce),
+                Value::from(request.pkce_verifier),
+                Value::from(request.expires_at),
+            ],
+        ))
+        .await?;
+        txn.commit().await?;
+        Ok(())
+    }
+
+    async fn consume_oidc_auth_request(
+        &self,
+        state_hash: &str,
+    ) -> Result<Option<OidcAuthRequest>, RepoError> {
+        let row = self
+            .db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                DELETE FROM oidc_auth_requests
+                WHERE state_hash = $1 AND expires_at > $2
+                RETURNING id, state_hash, nonce, pkce_verifier, created_at, expires_at
+                "#,
+                vec![Value::from(state_hash.to_owned()), Value::from(Utc::now())],
+            ))
+            .await?;
+
+        row.map(|row| {
+            Ok(OidcAuthRequest {
+                id: row.try_get("", "id")?,
+                state_hash: row.try_get("", "state_hash")?,
+                nonce: row.try_get("", "nonce")?,
+                pkce_verifier: row.try_get("", "pkce_verifier")?,
+                created_at: row.try_get("", "created_at")?,
+                expires_at: row.try_get("", "expires_at")?,
+            })
+        })
+        .transpose()
+    }
+
+    async fn user_from_identity(
+        &self,
+        issuer: &str,
+        subject: &str,
     ) -> Result<Option<User>, RepoError> {
-        let now = Utc::now();
         let row = self
             .db
             .query_one(Statement::from_sql_and_values(
                 DbBackend::Postgres,
                 r#"
                 SELECT u.id, u.email, u.created_at
-                FROM integration_tokens t
-                INNER JOIN users u ON u.id = t.user_id
-                WHERE t.token_hash = $1
-                  AND t.revoked_at IS NULL
-                  AND (t.expires_at IS NULL OR t.expires_at > $2)
-                LIMIT 1
+                FROM user_identities i
+                JOIN users u ON u.id = i.user_id
+                WHERE i.issuer = $1 AND i.subject = $2
                 "#,
-                vec![Value::from(token_hash.to_owned()), Value::from(now)],
+                vec![
+                    Value::from(issuer.to_owned()),
+                    Value::from(subject.to_owned()),
+                ],
             ))
             .await?;
 
-        row.map(|user_row| {
+        row.map(|row| {
             Ok(User {
-                id: user_row.try_get("", "id")?,
-                email: user_row.try_get("", "email")?,
-                created_at: user_row.try_get("", "created_at")?,
+                id: row.try_get("", "id")?,
+                email: row.try_get("", "email")?,
+                created_at: row.try_get("", "created_at")?,
             })
         })
         .transpose()
     }
 
-    async fn touch_integration_token_usage(&self, token_hash: &str) -> Result<(), RepoError> {
-        self.db
-            .execute(Statement::from_sql_and_values(
+    async fn link_identity(
+        &self,
+        user_id: i64,
+        issuer: &str,
+        subject: &str,
+        email: Option<&str>,
+    ) -> Result<UserIdentity, RepoError> {
+        let row = self
+            .db
+            .query_one(Statement::from_sql_and_values(
                 DbBackend::Postgres,
-                "UPDATE integration_tokens SET last_used_at = NOW() WHERE token_hash = $1",
-                vec![Value::from(token_hash.to_owned())],
+                r#"
+                INSERT INTO user_identities (user_id, issuer, subject, email)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (issuer, subject)
+                DO UPDATE SET email = EXCLUDED.email, last_login_at = NOW()
+                RETURNING id, user_id, issuer, subject, email, created_at, last_login_at
+                "#,
+                vec![
+                    Value::from(user_id),
+                    Value::from(issuer.to_owned()),
+                    Value::from(subject.to_owned()),
+                    Value::from(email.map(ToOwned::to_owned)),
+                ],
             ))
-            .await?;
-        Ok(())
+            .await?
+            .ok_or_else(|| RepoError::Message("identity upsert returned no row".to_owned()))?;
+
+        Ok(UserIdentity {
+            id: row.try_get("", "id")?,
+            user_id: row.try_get("", "user_id")?,
+            issuer: row.try_get("", "issuer")?,
+            subject: row.try_get("", "subject")?,
+            email: row.try_get("", "email")?,
+            created_at: row.try_get("", "created_at")?,
+            last_login_at: row.try_get("", "last_login_at")?,
+        })
     }
-}
 
-#[async_trait]
-impl EventRepository for PostgresRepository {
-    async fn ingest_event(&self, payload: IngestProblemInput) -> Result<ProblemEvent, RepoError> {
-        let status_for_dedup = payload.status.clone();
-        let status_for_event = payload.status.clone();
-        let dedup_key = make_event_dedup_key(
-            payload.user_id,
-            &payload.source,
-            &payload.problem_slug,
-            &status_for_dedup,
-            payload.occurred_at,
-        );
+    async fn create_webauthn_challenge(
+        &self,
+        challenge: NewWebauthnChallenge,
+    ) -> Result<(), RepoError> {
+        let txn = self.db.begin().await?;
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "DELETE FROM webauthn_challenges WHERE expires_at <= $1",
+            vec![Value::from(Utc::now())],
+        ))
+        .await?;
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"
+            INSERT INTO webauthn_challenges (challenge_hash, ceremony, user_id, expires_at)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            vec![
+                Value::from(challenge.challenge_hash),
+                Value::from(challenge.ceremony.as_str()),
+                Value::from(challenge.user_id),
+                Value::from(challenge.expires_at),
+            ],
+        ))
+        .await?;
+        txn.commit().await?;
+        Ok(())
+    }
 
-        let inserted = self
+    async fn consume_webauthn_challenge(
+        &self,
+        challenge_hash: &str,
+        ceremony: WebauthnCeremony,
+    ) -> Result<Option<WebauthnChallenge>, RepoError> {
+        let row = self
             .db
             .query_one(Statement::from_sql_and_values(
                 DbBackend::Postgres,
                 r#"
-                INSERT INTO problem_events
-                  (user_id, source, problem_slug, title, url, status, occurred_at, dedup_key)
-                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-                ON CONFLICT (dedup_key) DO NOTHING
-                RETURNING id, user_id, source, problem_slug, title, url, status, occurred_at, dedup_key
+                DELETE FROM webauthn_challenges
+                WHERE challenge_hash = $1 AND ceremony = $2 AND expires_at > $3
+                RETURNING id, challenge_hash, ceremony, user_id, created_at, expires_at
                 "#,
                 vec![
-                    Value::from(payload.user_id),
-                    Value::from(payload.source.clone()),
-                    Value::from(payload.problem_slug.clone()),
-                    Value::from(payload.title.clone()),
-                    Value::from(payload.url.clone()),
-                    Value::from(Self::status_to_db(status_for_event)),
-                    Value::from(payload.occurred_at),
-                    Value::from(dedup_key.clone()),
+                    Value::from(challenge_hash.to_owned()),
+                    Value::from(ceremony.as_str()),
+                    Value::from(Utc::now()),
                 ],
             ))
             .await?;
 
-        let event_row = match inserted {
-            Some(row) => row,
-            None => self
-                .db
-                .query_one(Statement::from_sql_and_values(
-                    DbBackend::Postgres,
-                    r#"
-                    SELECT id, user_id, source, problem_slug, title, url, status, occurred_at, dedup_key
+        row.map(|row| {
+            Ok(WebauthnChallenge {
+                id: row.try_get("", "id")?,
+                challenge_hash: row.try_get("", "challenge_hash")?,
+                ceremony,
+                user_id: row.try_get("", "user_id")?,
+                created_at: row.try_get("", "created_at")?,
+                expires_at: row.try_get("", "expires_at")?,
+            })
+        })
+        .transpose()
+    }
+
+    async fn create_passkey(
+        &self,
+        user_id: i64,
+        passkey: NewPasskey,
+    ) -> Result<PasskeyCredential, RepoError> {
+        let row = self
+            .db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                INSERT INTO passkey_credentials
+                  (user_id, credential_id, public_key, algorithm, sign_count, label)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                RETURNING id, user_id, credential_id, public_key, algorithm, sign_count, label,
+                          created_at, last_used_at
+                "#,
+                vec![
+                    Value::from(user_id),
+                    Value::from(passkey.credential_id),
+                    Value::from(passkey.public_key),
+                    Value::from(passkey.algorithm),
+                    Value::from(passkey.sign_count),
+                    Value::from(passkey.label),
+                ],
+            ))
+            .await?
+            .ok_or_else(|| RepoError::Message("passkey insert returned no row".to_owned()))?;
+        passkey_from_row(&row)
+    }
+
+    async fn list_passkeys(&self, user_id: i64) -> Result<Vec<PasskeyCredential>, RepoError> {
+        let rows = self
+            .db
+            .query_all(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                SELECT id, user_id, credential_id, public_key, algorithm, sign_count, label,
+                       created_at, last_used_at
+                FROM passkey_credentials
+                WHERE user_id = $1
+                ORDER BY created_at
+                "#,
+                vec![Value::from(user_id)],
+            ))
+            .await?;
+
+        rows.iter().map(passkey_from_row).collect()
+    }
+
+    async fn passkey_by_credential_id(
+        &self,
+        credential_id: &str,
+    ) -> Result<Option<(User, PasskeyCredential)>, RepoError> {
+        let row = self
+            .db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                SELECT p.id, p.user_id, p.credential_id, p.public_key, p.algorithm, p.sign_count,
+                       p.label, p.created_at, p.last_used_at,
+                       u.email AS u_email, u.created_at AS u_created_at
+                FROM passkey_credentials p
+                JOIN users u ON u.id = p.user_id
+                WHERE p.credential_id = $1
+                "#,
+                vec![Value::from(credential_id.to_owned())],
+            ))
+            .await?;
+
+        row.map(|row| {
+            let passkey = passkey_from_row(&row)?;
+            let user = User {
+                id: passkey.user_id,
+                email: row.try_get("", "u_email")?,
+                created_at: row.try_get("", "u_created_at")?,
+            };
+            Ok((user, passkey))
+        })
+        .transpose()
+    }
+
+    async fn record_passkey_use(&self, passkey_id: i64, sign_count: i64) -> Result<(), RepoError> {
+        self.db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                UPDATE passkey_credentials
+                SET sign_count = $2, last_used_at = NOW()
+                WHERE id = $1
+                "#,
+                vec![Value::from(passkey_id), Value::from(sign_count)],
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_passkey(&self, user_id: i64, passkey_id: i64) -> Result<bool, RepoError> {
+        let result = self
+            .db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "DELETE FROM passkey_credentials WHERE id = $1 AND user_id = $2",
+                vec![Value::from(passkey_id), Value::from(user_id)],
+            ))
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn create_account_deletion_request(
+        &self,
+        user_id: i64,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), RepoError> {
+        self.db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                INSERT INTO account_deletion_requests (user_id, token_hash, expires_at)
+                VALUES ($1, $2, $3)
+                "#,
+                vec![
+                    Value::from(user_id),
+                    Value::from(token_hash),
+                    Value::from(expires_at),
+                ],
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_account(&self, user_id: i64, token_hash: &str) -> Result<bool, RepoError> {
+        let tx = self.db.begin().await?;
+        let confirmed = tx
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                DELETE FROM account_deletion_requests
+                WHERE user_id = $1 AND token_hash = $2 AND expires_at > $3
+                "#,
+                vec![
+                    Value::from(user_id),
+                    Value::from(token_hash),
+                    Value::from(Utc::now()),
+                ],
+            ))
+            .await?
+            .rows_affected()
+            > 0;
+        if !confirmed {
+            tx.rollback().await?;
+            return Ok(false);
+        }
+        // Foreign keys cascade too; deleting explicitly keeps a table that
+        // forgot `ON DELETE CASCADE` from silently keeping user data.
+        for table in USER_OWNED_TABLES {
+            tx.execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                format!("DELETE FROM {table} WHERE user_id = $1"),
+                vec![Value::from(user_id)],
+            ))
+            .await?;
+        }
+        tx.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "DELETE FROM users WHERE id = $1",
+            vec![Value::from(user_id)],
+        ))
+        .await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl IntegrationTokenRepository for PostgresRepository {
+    async fn create_integration_token(
+        &self,
+        user_id: i64,
+        token_hash: &str,
+        new_token: NewIntegrationToken,
+    ) -> Result<IntegrationToken, RepoError> {
+        let row = self
+            .db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                INSERT INTO integration_tokens
+                    (user_id, token_hash, label, scopes, expires_at)
+                VALUES ($1, $2, $3, $4, $5)
+                RETURNING id, user_id, token_hash, label, scopes, created_at, expires_at, revoked_at,
+                          last_used_at, last_used_ip, last_used_user_agent, previous_token_hash,
+                          previous_token_expires_at, expiry_notified_at
+                "#,
+                vec![
+                    Value::from(user_id),
+                    Value::from(token_hash.to_owned()),
+                    Value::from(new_token.label),
+                    Value::from(new_token.scopes),
+                    Value::from(new_token.expires_at),
+                ],
+            ))
+            .await?
+            .ok_or_else(|| RepoError::Message("failed to insert integration token".to_owned()))?;
+
+        integration_token_from_row(&row)
+    }
+
+    async fn list_integration_tokens(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<IntegrationToken>, RepoError> {
+        let rows = self
+            .db
+            .query_all(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                SELECT id, user_id, token_hash, label, scopes, created_at, expires_at, revoked_at,
+                       last_used_at, last_used_ip, last_used_user_agent, previous_token_hash,
+                       previous_token_expires_at, expiry_notified_at
+                FROM integration_tokens
+                WHERE user_id = $1
+                ORDER BY created_at DESC
+                "#,
+                vec![Value::from(user_id)],
+            ))
+            .await?;
+
+        rows.iter().map(integration_token_from_row).collect()
+    }
+
+    async fn revoke_integration_token(
+        &self,
+        user_id: i64,
+        token_id: i64,
+    ) -> Result<bool, RepoError> {
+        let result = self
+            .db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                UPDATE integration_tokens
+                SET revoked_at = NOW()
+                WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+                "#,
+                vec![Value::from(token_id), Value::from(user_id)],
+            ))
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn user_from_integration_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<(User, IntegrationToken)>, RepoError> {
+        let now = Utc::now();
+        let row = self
+            .db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                SELECT u.id AS u_id, u.email AS u_email, u.created_at AS u_created_at,
+                       t.id, t.user_id, t.token_hash, t.label, t.scopes, t.created_at,
+                       t.expires_at, t.revoked_at, t.last_used_at, t.last_used_ip,
+                       t.last_used_user_agent, t.previous_token_hash,
+                       t.previous_token_expires_at, t.expiry_notified_at
+                FROM integration_tokens t
+                INNER JOIN users u ON u.id = t.user_id
+                WHERE (
+                        t.token_hash = $1
+                        OR (t.previous_token_hash = $1 AND t.previous_token_expires_at > $2)
+                      )
+                  AND t.revoked_at IS NULL
+                  AND (t.expires_at IS NULL OR t.expires_at > $2)
+                LIMIT 1
+                "#,
+                vec![Value::from(token_hash.to_owned()), Value::from(now)],
+            ))
+            .await?;
+
+        row.map(|row| {
+            let user = User {
+                id: row.try_get("", "u_id")?,
+                email: row.try_get("", "u_email")?,
+                created_at: row.try_get("", "u_created_at")?,
+            };
+            Ok((user, integration_token_from_row(&row)?))
+        })
+        .transpose()
+    }
+
+    async fn rotate_integration_token(
+        &self,
+        user_id: i64,
+        token_id: i64,
+        rotation: IntegrationTokenRotation,
+    ) -> Result<Option<IntegrationToken>, RepoError> {
+        let replace_expiry = rotation.expires_at.is_some();
+        let row = self
+            .db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                UPDATE integration_tokens
+                SET previous_token_hash = CASE WHEN $4::timestamptz IS NULL THEN NULL ELSE token_hash END,
+                    previous_token_expires_at = $4,
+                    token_hash = $3,
+                    expires_at = CASE WHEN $5 THEN $6 ELSE expires_at END,
+                    expiry_notified_at = CASE WHEN $5 THEN NULL ELSE expiry_notified_at END
+                WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+                RETURNING id, user_id, token_hash, label, scopes, created_at, expires_at, revoked_at,
+                          last_used_at, last_used_ip, last_used_user_agent, previous_token_hash,
+                          previous_token_expires_at, expiry_notified_at
+                "#,
+                vec![
+                    Value::from(token_id),
+                    Value::from(user_id),
+                    Value::from(rotation.token_hash),
+                    Value::from(rotation.previous_valid_until),
+                    Value::from(replace_expiry),
+                    Value::from(rotation.expires_at.flatten()),
+                ],
+            ))
+            .await?;
+
+        row.as_ref().map(integration_token_from_row).transpose()
+    }
+
+    async fn touch_integration_token_usage(
+        &self,
+        token_id: i64,
+        client: ClientInfo,
+    ) -> Result<(), RepoError> {
+        self.db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                UPDATE integration_tokens
+                SET last_used_at = NOW(), last_used_ip = $2, last_used_user_agent = $3
+                WHERE id = $1
+                "#,
+                vec![
+                    Value::from(token_id),
+                    Value::from(client.ip),
+                    Value::from(client.user_agent),
+                ],
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn list_integration_tokens_expiring_between(
+        &self,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<IntegrationToken>, RepoError> {
+        let rows = self
+            .db
+            .query_all(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                SELECT id, user_id, token_hash, label, scopes, created_at, expires_at, revoked_at,
+                       last_used_at, last_used_ip, last_used_user_agent, previous_token_hash,
+                       previous_token_expires_at, expiry_notified_at
+                FROM integration_tokens
+                WHERE revoked_at IS NULL
+                  AND expiry_notified_at IS NULL
+                  AND expires_at > $1
+                  AND expires_at <= $2
+                ORDER BY expires_at ASC
+                "#,
+                vec![Value::from(from), Value::from(until)],
+            ))
+            .await?;
+
+        rows.iter().map(integration_token_from_row).collect()
+    }
+
+    async fn mark_integration_token_expiry_notified(&self, token_id: i64) -> Result<(), RepoError> {
+        self.db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "UPDATE integration_tokens SET expiry_notified_at = NOW() WHERE id = $1",
+                vec![Value::from(token_id)],
+            ))
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CalendarFeedRepository for PostgresRepository {
+    async fn create_calendar_feed_token(
+        &self,
+        user_id: i64,
+        token_hash: &str,
+        label: &str,
+    ) -> Result<CalendarFeedToken, RepoError> {
+        let row = self
+            .db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                INSERT INTO calendar_feed_tokens (user_id, token_hash, label)
+                VALUES ($1, $2, $3)
+                RETURNING id, user_id, token_hash, label, created_at, revoked_at, last_used_at
+                "#,
+                vec![
+                    Value::from(user_id),
+                    Value::from(token_hash.to_owned()),
+                    Value::from(label.to_owned()),
+                ],
+            ))
+            .await?
+            .ok_or_else(|| RepoError::Message("failed to insert calendar feed token".to_owned()))?;
+
+        Ok(CalendarFeedToken {
+            id: row.try_get("", "id")?,
+            user_id: row.try_get("", "user_id")?,
+            token_hash: row.try_get("", "token_hash")?,
+            label: row.try_get("", "label")?,
+            created_at: row.try_get("", "created_at")?,
+            revoked_at: row.try_get("", "revoked_at")?,
+            last_used_at: row.try_get("", "last_used_at")?,
+        })
+    }
+
+    async fn list_calendar_feed_tokens(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<CalendarFeedToken>, RepoError> {
+        let rows = self
+            .db
+            .query_all(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                SELECT id, user_id, token_hash, label, created_at, revoked_at, last_used_at
+                FROM calendar_feed_tokens
+                WHERE user_id = $1
+                ORDER BY created_at DESC
+                "#,
+                vec![Value::from(user_id)],
+            ))
+            .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(CalendarFeedToken {
+                    id: row.try_get("", "id")?,
+                    user_id: row.try_get("", "user_id")?,
+                    token_hash: row.try_get("", "token_hash")?,
+                    label: row.try_get("", "label")?,
+                    created_at: row.try_get("", "created_at")?,
+                    revoked_at: row.try_get("", "revoked_at")?,
+                    last_used_at: row.try_get("", "last_used_at")?,
+                })
+            })
+            .collect()
+    }
+
+    async fn revoke_calendar_feed_token(
+        &self,
+        user_id: i64,
+        token_id: i64,
+    ) -> Result<bool, RepoError> {
+        let result = self
+            .db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                UPDATE calendar_feed_tokens
+                SET revoked_at = NOW()
+                WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+                "#,
+                vec![Value::from(token_id), Value::from(user_id)],
+            ))
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn user_from_calendar_feed_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<User>, RepoError> {
+        let row = self
+            .db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                SELECT u.id, u.email, u.created_at
+                FROM calendar_feed_tokens t
+                INNER JOIN users u ON u.id = t.user_id
+                WHERE t.token_hash = $1
+                  AND t.revoked_at IS NULL
+                LIMIT 1
+                "#,
+                vec![Value::from(token_hash.to_owned())],
+            ))
+            .await?;
+
+        row.map(|user_row| {
+            Ok(User {
+                id: user_row.try_get("", "id")?,
+                email: user_row.try_get("", "email")?,
+                created_at: user_row.try_get("", "created_at")?,
+            })
+        })
+        .transpose()
+    }
+
+    async fn touch_calendar_feed_token_usage(&self, token_hash: &str) -> Result<(), RepoError> {
+        self.db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "UPDATE calendar_feed_tokens SET last_used_at = NOW() WHERE token_hash = $1",
+                vec![Value::from(token_hash.to_owned())],
+            ))
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl EventRepository for PostgresRepository {
+    async fn ingest_event(
+        &self,
+        mut payload: IngestProblemInput,
+    ) -> Result<ProblemEvent, RepoError> {
+        Self::resolve_aliases(&self.db, std::slice::from_mut(&mut payload)).await?;
+        let user_id = payload.user_id;
+        let problem = BTreeSet::from([(
+            payload.source.clone(),
+            payload.problem_slug.clone(),
+            payload.url.clone(),
+        )]);
+        let status_for_dedup = payload.status.clone();
+        let status_for_event = payload.status.clone();
+        let dedup_key = make_event_dedup_key(
+            payload.user_id,
+            &payload.source,
+            &payload.problem_slug,
+            &status_for_dedup,
+            payload.occurred_at,
+        );
+
+        let inserted = self
+            .db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                INSERT INTO problem_events
+                  (user_id, source, problem_slug, title, url, status, occurred_at, dedup_key)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                ON CONFLICT (dedup_key) DO NOTHING
+                RETURNING id, user_id, source, problem_slug, title, url, status, occurred_at, dedup_key
+                "#,
+                vec![
+                    Value::from(payload.user_id),
+                    Value::from(payload.source.clone()),
+                    Value::from(payload.problem_slug.clone()),
+                    Value::from(payload.title.clone()),
+                    Value::from(payload.url.clone()),
+                    Value::from(Self::status_to_db(status_for_event)),
+                    Value::from(payload.occurred_at),
+                    Value::from(dedup_key.clone()),
+                ],
+            ))
+            .await?;
+
+        let event_row = match inserted {
+            Some(row) => row,
+            None => self
+                .db
+                .query_one(Statement::from_sql_and_values(
+                    DbBackend::Postgres,
+                    r#"
+                    SELECT id, user_id, source, problem_slug, title, url, status, occurred_at, dedup_key
                     FROM problem_events
                     WHERE dedup_key = $1
                     "#,
-                    vec![Value::from(dedup_key.clone())],
+                    vec![Value::from(dedup_key.clone())],
+                ))
+                .await?
+                .ok_or_else(|| RepoError::Message("deduplicated event not found".to_owned()))?,
+        };
+
+        let interval_index: i32 = 0;
+        let next_due_at = payload.occurred_at + self.duration_at_index(interval_index as usize);
+        self.db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                INSERT INTO problem_cards
+                  (user_id, source, problem_slug, title, url, interval_index, next_due_at, created_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                ON CONFLICT (user_id, source, problem_slug)
+                DO UPDATE
+                  SET title = EXCLUDED.title,
+                      url = EXCLUDED.url,
+                      interval_index = EXCLUDED.interval_index,
+                      next_due_at = EXCLUDED.next_due_at,
+                      created_at = LEAST(problem_cards.created_at, EXCLUDED.created_at)
+                "#,
+                vec![
+                    Value::from(payload.user_id),
+                    Value::from(payload.source),
+                    Value::from(payload.problem_slug),
+                    Value::from(payload.title),
+                    Value::from(payload.url),
+                    Value::from(interval_index),
+                    Value::from(next_due_at),
+                    Value::from(payload.occurred_at),
+                ],
+            ))
+            .await?;
+        Self::catalog_problems(&self.db, &problem).await?;
+        Self::link_cards_to_catalog(&self.db, user_id).await?;
+
+        Ok(ProblemEvent {
+            id: event_row.try_get("", "id")?,
+            user_id: event_row.try_get("", "user_id")?,
+            source: event_row.try_get("", "source")?,
+            problem_slug: event_row.try_get("", "problem_slug")?,
+            title: event_row.try_get("", "title")?,
+            url: event_row.try_get("", "url")?,
+            status: Self::status_from_db(event_row.try_get::<String>("", "status")?)?,
+            occurred_at: event_row.try_get("", "occurred_at")?,
+            dedup_key: event_row.try_get("", "dedup_key")?,
+        })
+    }
+
+    async fn ingest_events(
+        &self,
+        mut payloads: Vec<IngestProblemInput>,
+    ) -> Result<Vec<IngestedEvent>, RepoError> {
+        if payloads.is_empty() {
+            return Ok(Vec::new());
+        }
+        Self::resolve_aliases(&self.db, &mut payloads).await?;
+        let dedup_keys = payloads
+            .iter()
+            .map(|payload| {
+                make_event_dedup_key(
+                    payload.user_id,
+                    &payload.source,
+                    &payload.problem_slug,
+                    &payload.status,
+                    payload.occurred_at,
+                )
+            })
+            .collect::<Vec<_>>();
+        // Repeats inside the batch are written once and reported as
+        // deduplicated, like a replay of the same request would be.
+        let mut first_for_key = HashMap::new();
+        for (index, key) in dedup_keys.iter().enumerate() {
+            first_for_key.entry(key.as_str()).or_insert(index);
+        }
+        let unique = payloads
+            .iter()
+            .zip(&dedup_keys)
+            .enumerate()
+            .filter(|(index, (_, key))| first_for_key[key.as_str()] == *index)
+            .map(|(_, item)| item)
+            .collect::<Vec<_>>();
+
+        let tx = self.db.begin().await?;
+        let mut values = Vec::with_capacity(unique.len() * 8);
+        let mut rows = Vec::with_capacity(unique.len());
+        for (payload, dedup_key) in &unique {
+            let base = values.len();
+            rows.push(format!(
+                "(${}, ${}, ${}, ${}, ${}, ${}, ${}, ${})",
+                base + 1,
+                base + 2,
+                base + 3,
+                base + 4,
+                base + 5,
+                base + 6,
+                base + 7,
+                base + 8
+            ));
+            values.extend([
+                Value::from(payload.user_id),
+                Value::from(payload.source.clone()),
+                Value::from(payload.problem_slug.clone()),
+                Value::from(payload.title.clone()),
+                Value::from(payload.url.clone()),
+                Value::from(Self::status_to_db(payload.status.clone())),
+                Value::from(payload.occurred_at),
+                Value::from((*dedup_key).clone()),
+            ]);
+        }
+        let created = tx
+            .query_all(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                format!(
+                    r#"
+                    INSERT INTO problem_events
+                      (user_id, source, problem_slug, title, url, status, occurred_at, dedup_key)
+                    VALUES {}
+                    ON CONFLICT (dedup_key) DO NOTHING
+                    RETURNING id, user_id, source, problem_slug, title, url, status, occurred_at, dedup_key
+                    "#,
+                    rows.join(", ")
+                ),
+                values,
+            ))
+            .await?
+            .iter()
+            .map(event_from_row)
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut events = created
+            .into_iter()
+            .map(|event| (event.dedup_key.clone(), (event, true)))
+            .collect::<HashMap<_, _>>();
+
+        let existing_keys = unique
+            .iter()
+            .map(|(_, key)| (*key).clone())
+            .filter(|key| !events.contains_key(key))
+            .collect::<Vec<_>>();
+        if !existing_keys.is_empty() {
+            let placeholders = (1..=existing_keys.len())
+                .map(|index| format!("${index}"))
+                .collect::<Vec<_>>();
+            let rows = tx
+                .query_all(Statement::from_sql_and_values(
+                    DbBackend::Postgres,
+                    format!(
+                        r#"
+                        SELECT id, user_id, source, problem_slug, title, url, status, occurred_at, dedup_key
+                        FROM problem_events
+                        WHERE dedup_key IN ({})
+                        "#,
+                        placeholders.join(", ")
+                    ),
+                    existing_keys.into_iter().map(Value::from).collect::<Vec<_>>(),
                 ))
-                .await?
-                .ok_or_else(|| RepoError::Message("deduplicated event not found".to_owned()))?,
-        };
+                .await?;
+            for row in &rows {
+                let event = event_from_row(row)?;
+                events.insert(event.dedup_key.clone(), (event, false));
+            }
+        }
 
+        // A card can only be touched once per upsert statement, so keep the
+        // last payload for each problem, which is what sequential ingests
+        // would have left behind. Sorted so concurrent batches lock cards in
+        // the same order.
+        let mut cards = BTreeMap::new();
+        for payload in &payloads {
+            cards.insert(
+                (
+                    payload.user_id,
+                    payload.source.as_str(),
+                    payload.problem_slug.as_str(),
+                ),
+                payload,
+            );
+        }
         let interval_index: i32 = 0;
-        let next_due_at = payload.occurred_at + self.duration_at_index(interval_index as usize);
-        self.db
-            .execute(Statement::from_sql_and_values(
+        let mut values = Vec::with_capacity(cards.len() * 8);
+        let mut rows = Vec::with_capacity(cards.len());
+        for payload in cards.into_values() {
+            let base = values.len();
+            rows.push(format!(
+                "(${}, ${}, ${}, ${}, ${}, ${}, ${}, ${})",
+                base + 1,
+                base + 2,
+                base + 3,
+                base + 4,
+                base + 5,
+                base + 6,
+                base + 7,
+                base + 8
+            ));
+            values.extend([
+                Value::from(payload.user_id),
+                Value::from(payload.source.clone()),
+                Value::from(payload.problem_slug.clone()),
+                Value::from(payload.title.clone()),
+                Value::from(payload.url.clone()),
+                Value::from(interval_index),
+                Value::from(payload.occurred_at + self.duration_at_index(interval_index as usize)),
+                Value::from(payload.occurred_at),
+            ]);
+        }
+        tx.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            format!(
+                r#"
+                INSERT INTO problem_cards
+                  (user_id, source, problem_slug, title, url, interval_index, next_due_at, created_at)
+                VALUES {}
+                ON CONFLICT (user_id, source, problem_slug)
+                DO UPDATE
+                  SET title = EXCLUDED.title,
+                      url = EXCLUDED.url,
+                      interval_index = EXCLUDED.interval_index,
+                      next_due_at = EXCLUDED.next_due_at,
+                      created_at = LEAST(problem_cards.created_at, EXCLUDED.created_at)
+                "#,
+                rows.join(", ")
+            ),
+            values,
+        ))
+        .await?;
+        let problems = payloads
+            .iter()
+            .map(|payload| {
+                (
+                    payload.source.clone(),
+                    payload.problem_slug.clone(),
+                    payload.url.clone(),
+                )
+            })
+            .collect();
+        Self::catalog_problems(&tx, &problems).await?;
+        let user_ids = payloads
+            .iter()
+            .map(|payload| payload.user_id)
+            .collect::<BTreeSet<_>>();
+        for user_id in user_ids {
+            Self::link_cards_to_catalog(&tx, user_id).await?;
+        }
+        tx.commit().await?;
+
+        dedup_keys
+            .iter()
+            .enumerate()
+            .map(|(index, key)| {
+                let (event, created) = events.get(key).ok_or_else(|| {
+                    RepoError::Message("batch event missing after insert".to_owned())
+                })?;
+                Ok(IngestedEvent {
+                    event: event.clone(),
+                    created: *created && first_for_key[key.as_str()] == index,
+                })
+            })
+            .collect()
+    }
+
+    async fn latest_event_for_user(&self, user_id: i64) -> Result<Option<ProblemEvent>, RepoError> {
+        let row = self
+            .db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                SELECT id, user_id, source, problem_slug, title, url, status, occurred_at, dedup_key
+                FROM problem_events
+                WHERE user_id = $1
+                ORDER BY occurred_at DESC
+                LIMIT 1
+                "#,
+                vec![Value::from(user_id)],
+            ))
+            .await?;
+
+        row.as_ref().map(event_from_row).transpose()
+    }
+
+    async fn list_events(&self, user_id: i64) -> Result<Vec<ProblemEvent>, RepoError> {
+        let rows = self
+            .db
+            .query_all(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                SELECT id, user_id, source, problem_slug, title, url, status, occurred_at, dedup_key
+                FROM problem_events
+                WHERE user_id = $1
+                ORDER BY occurred_at ASC, id ASC
+                "#,
+                vec![Value::from(user_id)],
+            ))
+            .await?;
+        rows.iter().map(event_from_row).collect()
+    }
+}
+
+#[async_trait]
+impl ReviewRepository for PostgresRepository {
+    async fn due_cards(
+        &self,
+        user_id: i64,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<ProblemCard>, RepoError> {
+        let rows = self
+            .db
+            .query_all(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                format!(
+                    r#"
+                    {CARD_SELECT}
+                    WHERE c.user_id = $1 AND c.next_due_at <= $2 AND c.suspended_at IS NULL
+                    ORDER BY c.next_due_at ASC
+                    "#
+                ),
+                vec![Value::from(user_id), Value::from(now)],
+            ))
+            .await?;
+
+        rows.iter().map(card_from_row).collect()
+    }
+
+    async fn upcoming_cards(&self, user_id: i64) -> Result<Vec<ProblemCard>, RepoError> {
+        let rows = self
+            .db
+            .query_all(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                format!(
+                    r#"
+                    {CARD_SELECT}
+                    WHERE c.user_id = $1 AND c.suspended_at IS NULL
+                    ORDER BY c.next_due_at ASC
+                    LIMIT 10
+                    "#
+                ),
+                vec![Value::from(user_id)],
+            ))
+            .await?;
+
+        rows.iter().map(card_from_row).collect()
+    }
+
+    async fn list_cards(&self, user_id: i64) -> Result<Vec<ProblemCard>, RepoError> {
+        let rows = self
+            .db
+            .query_all(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                format!(
+                    r#"
+                    {CARD_SELECT}
+                    WHERE c.user_id = $1
+                    ORDER BY c.next_due_at ASC
+                    "#
+                ),
+                vec![Value::from(user_id)],
+            ))
+            .await?;
+
+        rows.iter().map(card_from_row).collect()
+    }
+
+    async fn grade_card(
+        &self,
+        user_id: i64,
+        card_id: i64,
+        grade: Grade,
+        leech: LeechPolicy,
+    ) -> Result<Option<GradedReview>, RepoError> {
+        let tx = self.db.begin().await?;
+
+        let card_row = tx
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                SELECT id, interval_index
+                FROM problem_cards
+                WHERE id = $1 AND user_id = $2
+                FOR UPDATE
+                "#,
+                vec![Value::from(card_id), Value::from(user_id)],
+            ))
+            .await?;
+
+        let Some(card_row) = card_row else {
+            tx.rollback().await?;
+            return Ok(None);
+        };
+
+        let current_index: i32 = card_row.try_get("", "interval_index")?;
+        let next_index = next_interval_index(
+            current_index.max(0) as usize,
+            grade,
+            self.schedule.max_index(),
+        ) as i32;
+        let reviewed_at = Utc::now();
+        let next_due_at = reviewed_at + self.duration_at_index(next_index as usize);
+
+        tx.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"
+            UPDATE problem_cards
+            SET interval_index = $1, next_due_at = $2, lapses = lapses + $4
+            WHERE id = $3
+            "#,
+            vec![
+                Value::from(next_index),
+                Value::from(next_due_at),
+                Value::from(card_id),
+                Value::from(i32::from(grade == Grade::Again)),
+            ],
+        ))
+        .await?;
+
+        let review_row = tx
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                INSERT INTO review_events (card_id, user_id, grade, reviewed_at, next_due_at)
+                VALUES ($1, $2, $3, $4, $5)
+                RETURNING id, card_id, user_id, grade, reviewed_at, next_due_at
+                "#,
+                vec![
+                    Value::from(card_id),
+                    Value::from(user_id),
+                    Value::from(Self::grade_to_db(grade)),
+                    Value::from(reviewed_at),
+                    Value::from(next_due_at),
+                ],
+            ))
+            .await?
+            .ok_or_else(|| RepoError::Message("failed to insert review".to_owned()))?;
+
+        let leech = if grade == Grade::Again {
+            Self::flag_leech(&tx, user_id, card_id, leech, reviewed_at).await?
+        } else {
+            None
+        };
+
+        tx.commit().await?;
+
+        Ok(Some(GradedReview {
+            review: ReviewEvent {
+                id: review_row.try_get("", "id")?,
+                card_id: review_row.try_get("", "card_id")?,
+                user_id: review_row.try_get("", "user_id")?,
+                grade: Self::grade_from_db(review_row.try_get::<String>("", "grade")?)?,
+                reviewed_at: review_row.try_get("", "reviewed_at")?,
+                next_due_at: review_row.try_get("", "next_due_at")?,
+            },
+            leech,
+        }))
+    }
+
+    async fn user_history(&self, user_id: i64) -> Result<Vec<ReviewEvent>, RepoError> {
+        let rows = self
+            .db
+            .query_all(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                SELECT id, card_id, user_id, grade, reviewed_at, next_due_at
+                FROM review_events
+                WHERE user_id = $1
+                ORDER BY reviewed_at DESC
+                "#,
+                vec![Value::from(user_id)],
+            ))
+            .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(ReviewEvent {
+                    id: row.try_get("", "id")?,
+                    card_id: row.try_get("", "card_id")?,
+                    user_id: row.try_get("", "user_id")?,
+                    grade: Self::grade_from_db(row.try_get::<String>("", "grade")?)?,
+                    reviewed_at: row.try_get("", "reviewed_at")?,
+                    next_due_at: row.try_get("", "next_due_at")?,
+                })
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl StatsRepository for PostgresRepository {
+    async fn review_stats(
+        &self,
+        user_id: i64,
+        since: DateTime<Utc>,
+        mature_after: Duration,
+    ) -> Result<ReviewStats, RepoError> {
+        let values = || {
+            vec![
+                Value::from(user_id),
+                Value::from(since),
+                Value::from(mature_after.num_seconds()),
+            ]
+        };
+        let mut stats = ReviewStats::default();
+        let grouped = self
+            .db
+            .query_all(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                format!(
+                    r#"
+                    {STATS_REVIEWS}
+                    SELECT GROUPING(week_start) = 0 AS by_week,
+                           GROUPING(source) = 0 AS by_source,
+                           GROUPING(grade) = 0 AS by_grade,
+                           week_start, source, grade, {STATS_COUNTS},
+                           AVG(gap_seconds) AS average_gap_seconds
+                    FROM reviews
+                    GROUP BY GROUPING SETS ((), (week_start), (source), (grade))
+                    ORDER BY week_start, reviews DESC, source
+                    "#
+                ),
+                values(),
+            ))
+            .await?;
+        for row in &grouped {
+            let counts = retention_counts_from_row(row)?;
+            if row.try_get("", "by_week")? {
+                stats.weeks.push((row.try_get("", "week_start")?, counts));
+            } else if row.try_get("", "by_source")? {
+                stats.sources.push((row.try_get("", "source")?, counts));
+            } else if row.try_get("", "by_grade")? {
+                stats.grades.push((
+                    Self::grade_from_db(row.try_get("", "grade")?)?,
+                    counts.reviews,
+                ));
+            } else {
+                stats.totals = counts;
+                stats.average_review_gap_seconds = row.try_get("", "average_gap_seconds")?;
+            }
+        }
+        stats.grades.sort_by_key(|(grade, _)| *grade as u8);
+
+        let tags = self
+            .db
+            .query_all(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                format!(
+                    r#"
+                    {STATS_REVIEWS}
+                    SELECT tag, {STATS_COUNTS}
+                    FROM reviews
+                    CROSS JOIN LATERAL unnest(tags) AS tag
+                    GROUP BY tag
+                    ORDER BY reviews DESC, tag
+                    "#
+                ),
+                values(),
+            ))
+            .await?;
+        for row in &tags {
+            stats
+                .tags
+                .push((row.try_get("", "tag")?, retention_counts_from_row(row)?));
+        }
+
+        let intervals = self
+            .db
+            .query_all(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                SELECT interval_index, COUNT(*) AS cards
+                FROM problem_cards
+                WHERE user_id = $1
+                GROUP BY interval_index
+                ORDER BY interval_index
+                "#,
+                vec![Value::from(user_id)],
+            ))
+            .await?;
+        for row in &intervals {
+            let interval_index: i32 = row.try_get("", "interval_index")?;
+            stats
+                .interval_indexes
+                .push((interval_index as usize, row.try_get("", "cards")?));
+        }
+        Ok(stats)
+    }
+}
+
+#[async_trait]
+impl ActivityRepository for PostgresRepository {
+    async fn daily_activity(
+        &self,
+        user_id: i64,
+        timezone: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<DailyActivity>, RepoError> {
+        let rows = self
+            .db
+            .query_all(Statement::from_sql_and_values(
                 DbBackend::Postgres,
                 r#"
-                INSERT INTO problem_cards
-                  (user_id, source, problem_slug, title, url, interval_index, next_due_at)
-                VALUES ($1, $2, $3, $4, $5, $6, $7)
-                ON CONFLICT (user_id, source, problem_slug)
-                DO UPDATE
-                  SET title = EXCLUDED.title,
-                      url = EXCLUDED.url,
-                      interval_index = EXCLUDED.interval_index,
-                      next_due_at = EXCLUDED.next_due_at
+                WITH bounds AS (
+                    SELECT $3::DATE::TIMESTAMP AT TIME ZONE $2 AS start_at,
+                           ($4::DATE + 1)::TIMESTAMP AT TIME ZONE $2 AS end_at
+                )
+                SELECT day, SUM(reviews)::BIGINT AS reviews, SUM(solved)::BIGINT AS solved,
+                       SUM(new_cards)::BIGINT AS new_cards
+                FROM (
+                    SELECT (r.reviewed_at AT TIME ZONE $2)::DATE AS day,
+                           COUNT(*) AS reviews, 0 AS solved, 0 AS new_cards
+                    FROM review_events r, bounds b
+                    WHERE r.user_id = $1 AND r.reviewed_at >= b.start_at AND r.reviewed_at < b.end_at
+                    GROUP BY 1
+                    UNION ALL
+                    SELECT (e.occurred_at AT TIME ZONE $2)::DATE,
+                           0, COUNT(DISTINCT e.source || '/' || e.problem_slug), 0
+                    FROM problem_events e, bounds b
+                    WHERE e.user_id = $1 AND e.status = 'solved'
+                      AND e.occurred_at >= b.start_at AND e.occurred_at < b.end_at
+                    GROUP BY 1
+                    UNION ALL
+                    SELECT (c.created_at AT TIME ZONE $2)::DATE, 0, 0, COUNT(*)
+                    FROM problem_cards c, bounds b
+                    WHERE c.user_id = $1 AND c.created_at >= b.start_at AND c.created_at < b.end_at
+                    GROUP BY 1
+                ) activity
+                GROUP BY day
+                ORDER BY day
                 "#,
                 vec![
-                    Value::from(payload.user_id),
-                    Value::from(payload.source),
-                    Value::from(payload.problem_slug),
-                    Value::from(payload.title),
-                    Value::from(payload.url),
-                    Value::from(interval_index),
-                    Value::from(next_due_at),
+                    Value::from(user_id),
+                    Value::from(timezone),
+                    Value::from(from),
+                    Value::from(to),
                 ],
             ))
             .await?;
-
-        Ok(ProblemEvent {
-            id: event_row.try_get("", "id")?,
-            user_id: event_row.try_get("", "user_id")?,
-            source: event_row.try_get("", "source")?,
-            problem_slug: event_row.try_get("", "problem_slug")?,
-            title: event_row.try_get("", "title")?,
-            url: event_row.try_get("", "url")?,
-            status: Self::status_from_db(event_row.try_get::<String>("", "status")?)?,
-            occurred_at: event_row.try_get("", "occurred_at")?,
-            dedup_key: event_row.try_get("", "dedup_key")?,
-        })
+        rows.iter()
+            .map(|row| {
+                Ok(DailyActivity {
+                    date: row.try_get("", "day")?,
+                    reviews: row.try_get("", "reviews")?,
+                    solved: row.try_get("", "solved")?,
+                    new_cards: row.try_get("", "new_cards")?,
+                })
+            })
+            .collect()
     }
 
-    async fn latest_event_for_user(&self, user_id: i64) -> Result<Option<ProblemEvent>, RepoError> {
-        let row = self
+    async fn active_days(&self, user_id: i64, timezone: &str) -> Result<Vec<NaiveDate>, RepoError> {
+        let rows = self
             .db
-            .query_one(Statement::from_sql_and_values(
+            .query_all(Statement::from_sql_and_values(
                 DbBackend::Postgres,
                 r#"
-                SELECT id, user_id, source, problem_slug, title, url, status, occurred_at, dedup_key
+                SELECT (reviewed_at AT TIME ZONE $2)::DATE AS day
+                FROM review_events
+                WHERE user_id = $1
+                UNION
+                SELECT (occurred_at AT TIME ZONE $2)::DATE
                 FROM problem_events
+                WHERE user_id = $1 AND status = 'solved'
+                ORDER BY day
+                "#,
+                vec![Value::from(user_id), Value::from(timezone)],
+            ))
+            .await?;
+        rows.iter().map(|row| Ok(row.try_get("", "day")?)).collect()
+    }
+
+    async fn list_goals(&self, user_id: i64) -> Result<Vec<Goal>, RepoError> {
+        let rows = self
+            .db
+            .query_all(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                SELECT id, user_id, metric, period, target, created_at
+                FROM goals
                 WHERE user_id = $1
-                ORDER BY occurred_at DESC
-                LIMIT 1
+                ORDER BY id
                 "#,
                 vec![Value::from(user_id)],
             ))
             .await?;
+        rows.iter().map(goal_from_row).collect()
+    }
 
-        row.map(|event_row| {
-            Ok(ProblemEvent {
-                id: event_row.try_get("", "id")?,
-                user_id: event_row.try_get("", "user_id")?,
-                source: event_row.try_get("", "source")?,
-                problem_slug: event_row.try_get("", "problem_slug")?,
-                title: event_row.try_get("", "title")?,
-                url: event_row.try_get("", "url")?,
-                status: Self::status_from_db(event_row.try_get::<String>("", "status")?)?,
-                occurred_at: event_row.try_get("", "occurred_at")?,
-                dedup_key: event_row.try_get("", "dedup_key")?,
-            })
-        })
-        .transpose()
+    async fn upsert_goal(
+        &self,
+        user_id: i64,
+        metric: GoalMetric,
+        period: GoalPeriod,
+        target: i64,
+    ) -> Result<Goal, RepoError> {
+        let row = self
+            .db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                INSERT INTO goals (user_id, metric, period, target)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (user_id, metric, period) DO UPDATE SET target = EXCLUDED.target
+                RETURNING id, user_id, metric, period, target, created_at
+                "#,
+                vec![
+                    Value::from(user_id),
+                    Value::from(metric.as_str()),
+                    Value::from(period.as_str()),
+                    Value::from(target),
+                ],
+            ))
+            .await?
+            .ok_or_else(|| RepoError::Message("goal upsert returned no row".to_owned()))?;
+        goal_from_row(&row)
+    }
+
+    async fn delete_goal(&self, user_id: i64, goal_id: i64) -> Result<bool, RepoError> {
+        let result = self
+            .db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "DELETE FROM goals WHERE id = $1 AND user_id = $2",
+                vec![Value::from(goal_id), Value::from(user_id)],
+            ))
+            .await?;
+        Ok(result.rows_affected() > 0)
     }
 }
 
 #[async_trait]
-impl ReviewRepository for PostgresRepository {
-    async fn due_cards(
-        &self,
-        user_id: i64,
-        now: DateTime<Utc>,
-    ) -> Result<Vec<ProblemCard>, RepoError> {
+impl LeechRepository for PostgresRepository {
+    async fn list_leeches(&self, user_id: i64) -> Result<Vec<ProblemCard>, RepoError> {
         let rows = self
             .db
             .query_all(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                format!(
+                    r#"
+                    {CARD_SELECT}
+                    WHERE c.user_id = $1 AND c.leech_at IS NOT NULL
+                    ORDER BY c.lapses DESC, c.leech_at ASC, c.id ASC
+                    "#
+                ),
+                vec![Value::from(user_id)],
+            ))
+            .await?;
+        rows.iter().map(card_from_row).collect()
+    }
+
+    async fn clear_leech(
+        &self,
+        user_id: i64,
+        card_id: i64,
+    ) -> Result<Option<ProblemCard>, RepoError> {
+        let cleared = self
+            .db
+            .execute(Statement::from_sql_and_values(
                 DbBackend::Postgres,
                 r#"
-                SELECT id, user_id, source, problem_slug, title, url, interval_index, next_due_at
-                FROM problem_cards
-                WHERE user_id = $1 AND next_due_at <= $2
-                ORDER BY next_due_at ASC
+                UPDATE problem_cards
+                SET lapses = 0, leech_at = NULL, suspended_at = NULL,
+                    tags = array_remove(tags, $3)
+                WHERE id = $1 AND user_id = $2
                 "#,
-                vec![Value::from(user_id), Value::from(now)],
+                vec![
+                    Value::from(card_id),
+                    Value::from(user_id),
+                    Value::from(LEECH_TAG),
+                ],
+            ))
+            .await?
+            .rows_affected();
+        if cleared == 0 {
+            return Ok(None);
+        }
+        self.get_card(user_id, card_id).await
+    }
+}
+
+#[async_trait]
+impl ProblemRepository for PostgresRepository {
+    async fn find_problem(&self, source: &str, slug: &str) -> Result<Option<Problem>, RepoError> {
+        let row = self
+            .db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                SELECT id, source, slug, title, url, difficulty, tags, aliases, catalog_version,
+                       created_at, updated_at
+                FROM problems
+                WHERE source = $1 AND (slug = $2 OR $2 = ANY(aliases))
+                ORDER BY slug = $2 DESC
+                LIMIT 1
+                "#,
+                vec![Value::from(source), Value::from(slug)],
             ))
             .await?;
+        row.as_ref().map(problem_from_row).transpose()
+    }
 
-        rows.into_iter()
+    async fn count_problem_learners(&self, problem_id: i64) -> Result<i64, RepoError> {
+        let row = self
+            .db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "SELECT COUNT(DISTINCT user_id) AS learners FROM problem_cards WHERE problem_id = $1",
+                vec![Value::from(problem_id)],
+            ))
+            .await?
+            .ok_or_else(|| RepoError::Message("learner count returned no row".to_owned()))?;
+        Ok(row.try_get("", "learners")?)
+    }
+
+    async fn seed_problems(
+        &self,
+        version: i64,
+        problems: Vec<CatalogProblemInput>,
+    ) -> Result<CatalogSeedSummary, RepoError> {
+        let tx = self.db.begin().await?;
+        let mut summary = CatalogSeedSummary::default();
+        for problem in problems {
+            // No row comes back when the stored entry already matches.
+            let row = tx
+                .query_one(Statement::from_sql_and_values(
+                    DbBackend::Postgres,
+                    r#"
+                    INSERT INTO problems
+                      (source, slug, title, url, difficulty, tags, aliases, catalog_version)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                    ON CONFLICT (source, slug) DO UPDATE
+                    SET title = EXCLUDED.title,
+                        url = EXCLUDED.url,
+                        difficulty = EXCLUDED.difficulty,
+                        tags = EXCLUDED.tags,
+                        aliases = EXCLUDED.aliases,
+                        catalog_version = EXCLUDED.catalog_version,
+                        updated_at = NOW()
+                    WHERE (problems.title, problems.url, problems.difficulty, problems.tags,
+                           problems.aliases, problems.catalog_version)
+                      IS DISTINCT FROM (EXCLUDED.title, EXCLUDED.url, EXCLUDED.difficulty,
+                           EXCLUDED.tags, EXCLUDED.aliases, EXCLUDED.catalog_version)
+                    RETURNING (xmax = 0) AS inserted
+                    "#,
+                    vec![
+                        Value::from(problem.source),
+                        Value::from(problem.slug),
+                        Value::from(problem.title),
+                        Value::from(problem.url),
+                        Value::from(problem.difficulty),
+                        Value::from(problem.tags),
+                        Value::from(problem.aliases),
+                        Value::from(version),
+                    ],
+                ))
+                .await?;
+            match row {
+                Some(row) if row.try_get::<bool>("", "inserted")? => summary.inserted += 1,
+                Some(_) => summary.updated += 1,
+                None => summary.unchanged += 1,
+            }
+        }
+        tx.execute(Statement::from_string(
+            DbBackend::Postgres,
+            r#"
+            UPDATE problem_cards c
+            SET problem_id = p.id
+            FROM problems p
+            WHERE c.problem_id IS NULL
+              AND c.kind = 'problem'
+              AND p.source = c.source
+              AND p.slug = c.problem_slug
+            "#
+            .to_owned(),
+        ))
+        .await?;
+        tx.commit().await?;
+        Ok(summary)
+    }
+
+    async fn uncatalogued_problems(&self) -> Result<Vec<UncataloguedProblem>, RepoError> {
+        let rows = self
+            .db
+            .query_all(Statement::from_string(
+                DbBackend::Postgres,
+                r#"
+                SELECT p.source, p.slug, MIN(c.title) AS title,
+                       COUNT(DISTINCT c.user_id) AS learners
+                FROM problems p
+                JOIN problem_cards c ON c.problem_id = p.id
+                WHERE p.catalog_version IS NULL
+                GROUP BY p.id
+                ORDER BY learners DESC, p.source, p.slug
+                "#
+                .to_owned(),
+            ))
+            .await?;
+        rows.iter()
             .map(|row| {
-                let index: i32 = row.try_get("", "interval_index")?;
-                Ok(ProblemCard {
-                    id: row.try_get("", "id")?,
-                    user_id: row.try_get("", "user_id")?,
+                Ok(UncataloguedProblem {
                     source: row.try_get("", "source")?,
-                    problem_slug: row.try_get("", "problem_slug")?,
+                    slug: row.try_get("", "slug")?,
                     title: row.try_get("", "title")?,
-                    url: row.try_get("", "url")?,
-                    interval_index: index.max(0) as usize,
-                    next_due_at: row.try_get("", "next_due_at")?,
+                    learners: row.try_get("", "learners")?,
                 })
             })
             .collect()
     }
+}
 
-    async fn upcoming_cards(&self, user_id: i64) -> Result<Vec<ProblemCard>, RepoError> {
+#[async_trait]
+impl CardRepository for PostgresRepository {
+    async fn create_card(
+        &self,
+        user_id: i64,
+        key: &str,
+        content: CardContent,
+        next_due_at: DateTime<Utc>,
+    ) -> Result<Option<ProblemCard>, RepoError> {
+        let row = self
+            .db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                INSERT INTO problem_cards
+                    (user_id, kind, source, problem_slug, title, url, front, back, tags,
+                     interval_index, next_due_at)
+                VALUES ($1, $2, $3, $4, $5, '', $6, $7, $8, 0, $9)
+                ON CONFLICT (user_id, source, problem_slug) DO NOTHING
+                RETURNING id, user_id, kind, source, problem_slug, title, url, front, back, tags,
+                          problem_id, NULL::TEXT AS difficulty, interval_index, next_due_at, created_at,
+                          lapses, leech_at, suspended_at
+                "#,
+                vec![
+                    Value::from(user_id),
+                    Value::from(content.kind.as_str()),
+                    Value::from(CUSTOM_CARD_SOURCE),
+                    Value::from(key),
+                    Value::from(content.title),
+                    Value::from(content.front),
+                    Value::from(content.back),
+                    Value::from(content.tags),
+                    Value::from(next_due_at),
+                ],
+            ))
+            .await?;
+        row.as_ref().map(card_from_row).transpose()
+    }
+
+    async fn get_card(&self, user_id: i64, card_id: i64) -> Result<Option<ProblemCard>, RepoError> {
+        let row = self
+            .db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                format!(
+                    r#"
+                    {CARD_SELECT}
+                    WHERE c.id = $1 AND c.user_id = $2
+                    "#
+                ),
+                vec![Value::from(card_id), Value::from(user_id)],
+            ))
+            .await?;
+        row.as_ref().map(card_from_row).transpose()
+    }
+
+    async fn update_card(
+        &self,
+        user_id: i64,
+        card_id: i64,
+        content: CardContent,
+    ) -> Result<Option<ProblemCard>, RepoError> {
+        let row = self
+            .db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                UPDATE problem_cards
+                SET kind = $3, title = $4, front = $5, back = $6, tags = $7
+                WHERE id = $1 AND user_id = $2 AND kind <> 'problem'
+                RETURNING id, user_id, kind, source, problem_slug, title, url, front, back, tags,
+                          problem_id, NULL::TEXT AS difficulty, interval_index, next_due_at, created_at,
+                          lapses, leech_at, suspended_at
+                "#,
+                vec![
+                    Value::from(card_id),
+                    Value::from(user_id),
+                    Value::from(content.kind.as_str()),
+                    Value::from(content.title),
+                    Value::from(content.front),
+                    Value::from(content.back),
+                    Value::from(content.tags),
+                ],
+            ))
+            .await?;
+        row.as_ref().map(card_from_row).transpose()
+    }
+
+    async fn delete_card(&self, user_id: i64, card_id: i64) -> Result<bool, RepoError> {
+        let result = self
+            .db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "DELETE FROM problem_cards WHERE id = $1 AND user_id = $2",
+                vec![Value::from(card_id), Value::from(user_id)],
+            ))
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn cards_by_keys(
+        &self,
+        user_id: i64,
+        keys: &[String],
+    ) -> Result<Vec<ProblemCard>, RepoError> {
         let rows = self
             .db
             .query_all(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                format!(
+                    r#"
+                    {CARD_SELECT}
+                    WHERE c.user_id = $1 AND c.source = $2 AND c.problem_slug = ANY($3)
+                    "#
+                ),
+                vec![
+                    Value::from(user_id),
+                    Value::from(CUSTOM_CARD_SOURCE),
+                    Value::from(keys.to_vec()),
+                ],
+            ))
+            .await?;
+        rows.iter().map(card_from_row).collect()
+    }
+
+    async fn upsert_cards(
+        &self,
+        user_id: i64,
+        cards: Vec<(String, CardContent)>,
+        next_due_at: DateTime<Utc>,
+    ) -> Result<Vec<ProblemCard>, RepoError> {
+        let tx = self.db.begin().await?;
+        let mut saved = Vec::with_capacity(cards.len());
+        for (key, content) in cards {
+            let row = tx
+                .query_one(Statement::from_sql_and_values(
+                    DbBackend::Postgres,
+                    r#"
+                    INSERT INTO problem_cards
+                        (user_id, kind, source, problem_slug, title, url, front, back, tags,
+                         interval_index, next_due_at)
+                    VALUES ($1, $2, $3, $4, $5, '', $6, $7, $8, 0, $9)
+                    ON CONFLICT (user_id, source, problem_slug) DO UPDATE
+                    SET kind = EXCLUDED.kind, title = EXCLUDED.title, front = EXCLUDED.front,
+                        back = EXCLUDED.back, tags = EXCLUDED.tags
+                    RETURNING id, user_id, kind, source, problem_slug, title, url, front, back, tags,
+                              problem_id, NULL::TEXT AS difficulty, interval_index, next_due_at, created_at,
+                          lapses, leech_at, suspended_at
+                    "#,
+                    vec![
+                        Value::from(user_id),
+                        Value::from(content.kind.as_str()),
+                        Value::from(CUSTOM_CARD_SOURCE),
+                        Value::from(key),
+                        Value::from(content.title),
+                        Value::from(content.front),
+                        Value::from(content.back),
+                        Value::from(content.tags),
+                        Value::from(next_due_at),
+                    ],
+                ))
+                .await?
+                .ok_or_else(|| RepoError::Message("card upsert returned no row".to_owned()))?;
+            saved.push(card_from_row(&row)?);
+        }
+        tx.commit().await?;
+        Ok(saved)
+    }
+}
+
+#[async_trait]
+impl IdempotencyRepository for PostgresRepository {
+    async fn claim_idempotency_key(
+        &self,
+        user_id: i64,
+        key: &str,
+        request_hash: &str,
+        now: DateTime<Utc>,
+        stale_before: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<IdempotencyClaim, RepoError> {
+        // An expired or stale record is taken over in place; a live one makes
+        // the upsert a no-op and is read back below.
+        let claimed = self
+            .db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                INSERT INTO idempotency_keys (user_id, idempotency_key, request_hash, created_at, expires_at)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (user_id, idempotency_key)
+                DO UPDATE SET request_hash = EXCLUDED.request_hash,
+                              response_status = NULL,
+                              response_content_type = NULL,
+                              response_body = NULL,
+                              created_at = EXCLUDED.created_at,
+                              expires_at = EXCLUDED.expires_at
+                  WHERE idempotency_keys.expires_at <= EXCLUDED.created_at
+                     OR (idempotency_keys.response_status IS NULL
+                         AND idempotency_keys.request_hash = EXCLUDED.request_hash
+                         AND idempotency_keys.created_at <= $6)
+                RETURNING user_id
+                "#,
+                vec![
+                    Value::from(user_id),
+                    Value::from(key.to_owned()),
+                    Value::from(request_hash.to_owned()),
+                    Value::from(now),
+                    Value::from(expires_at),
+                    Value::from(stale_before),
+                ],
+            ))
+            .await?;
+        if claimed.is_some() {
+            return Ok(IdempotencyClaim::Claimed);
+        }
+
+        let row = self
+            .db
+            .query_one(Statement::from_sql_and_values(
                 DbBackend::Postgres,
                 r#"
-                SELECT id, user_id, source, problem_slug, title, url, interval_index, next_due_at
-                FROM problem_cards
-                WHERE user_id = $1
-                ORDER BY next_due_at ASC
-                LIMIT 10
+                SELECT user_id, idempotency_key, request_hash, response_status,
+                       response_content_type, response_body, created_at, expires_at
+                FROM idempotency_keys
+                WHERE user_id = $1 AND idempotency_key = $2
                 "#,
-                vec![Value::from(user_id)],
+                vec![Value::from(user_id), Value::from(key.to_owned())],
             ))
             .await?;
-
-        rows.into_iter()
-            .map(|row| {
-                let index: i32 = row.try_get("", "interval_index")?;
-                Ok(ProblemCard {
-                    id: row.try_get("", "id")?,
-                    user_id: row.try_get("", "user_id")?,
-                    source: row.try_get("", "source")?,
-                    problem_slug: row.try_get("", "problem_slug")?,
-                    title: row.try_get("", "title")?,
-                    url: row.try_get("", "url")?,
-                    interval_index: index.max(0) as usize,
-                    next_due_at: row.try_get("", "next_due_at")?,
-                })
-            })
-            .collect()
+        // Released or purged since the upsert above.
+        let Some(row) = row else {
+            return Ok(IdempotencyClaim::Contended);
+        };
+        let response = match row.try_get::<Option<i32>>("", "response_status")? {
+            Some(status) => Some(StoredResponse {
+                status: u16::try_from(status)
+                    .map_err(|_| RepoError::Message(format!("invalid stored status {status}")))?,
+                content_type: row.try_get("", "response_content_type")?,
+                body: row
+                    .try_get::<Option<Vec<u8>>>("", "response_body")?
+                    .unwrap_or_default(),
+            }),
+            None => None,
+        };
+        Ok(IdempotencyClaim::Existing(IdempotencyRecord {
+            user_id: row.try_get("", "user_id")?,
+            key: row.try_get("", "idempotency_key")?,
+            request_hash: row.try_get("", "request_hash")?,
+            response,
+            claimed_at: row.try_get("", "created_at")?,
+            expires_at: row.try_get("", "expires_at")?,
+        }))
     }
 
-    async fn grade_card(
+    async fn complete_idempotency_key(
         &self,
         user_id: i64,
-        card_id: i64,
-        grade: Grade,
-    ) -> Result<Option<ReviewEvent>, RepoError> {
-        let tx = self.db.begin().await?;
+        key: &str,
+        response: StoredResponse,
+    ) -> Result<(), RepoError> {
+        self.db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                UPDATE idempotency_keys
+                SET response_status = $3, response_content_type = $4, response_body = $5
+                WHERE user_id = $1 AND idempotency_key = $2
+                "#,
+                vec![
+                    Value::from(user_id),
+                    Value::from(key.to_owned()),
+                    Value::from(i32::from(response.status)),
+                    Value::from(response.content_type),
+                    Value::from(response.body),
+                ],
+            ))
+            .await?;
+        Ok(())
+    }
 
-        let card_row = tx
-            .query_one(Statement::from_sql_and_values(
+    async fn release_idempotency_key(&self, user_id: i64, key: &str) -> Result<(), RepoError> {
+        self.db
+            .execute(Statement::from_sql_and_values(
                 DbBackend::Postgres,
                 r#"
-                SELECT id, interval_index
-                FROM problem_cards
-                WHERE id = $1 AND user_id = $2
-                FOR UPDATE
+                DELETE FROM idempotency_keys
+                WHERE user_id = $1 AND idempotency_key = $2 AND response_status IS NULL
                 "#,
-                vec![Value::from(card_id), Value::from(user_id)],
+                vec![Value::from(user_id), Value::from(key.to_owned())],
             ))
             .await?;
+        Ok(())
+    }
 
-        let Some(card_row) = card_row else {
-            tx.rollback().await?;
-            return Ok(None);
-        };
+    async fn purge_expired_idempotency_keys(&self, now: DateTime<Utc>) -> Result<u64, RepoError> {
+        let result = self
+            .db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "DELETE FROM idempotency_keys WHERE expires_at <= $1",
+                vec![Value::from(now)],
+            ))
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
 
-        let current_index: i32 = card_row.try_get("", "interval_index")?;
-        let next_index = next_interval_index(
-            current_index.max(0) as usize,
-            grade,
-            self.schedule.max_index(),
-        ) as i32;
-        let reviewed_at = Utc::now();
-        let next_due_at = reviewed_at + self.duration_at_index(next_index as usize);
+#[async_trait]
+impl ArchiveRepository for PostgresRepository {
+    async fn import_archive(
+        &self,
+        user_id: i64,
+        import: ArchiveImport,
+    ) -> Result<ArchiveImportSummary, RepoError> {
+        let tx = self.db.begin().await?;
+        let mut summary = ArchiveImportSummary::default();
 
-        tx.execute(Statement::from_sql_and_values(
-            DbBackend::Postgres,
-            "UPDATE problem_cards SET interval_index = $1, next_due_at = $2 WHERE id = $3",
-            vec![
-                Value::from(next_index),
-                Value::from(next_due_at),
-                Value::from(card_id),
-            ],
-        ))
-        .await?;
+        if import.mode == ImportMode::Replace {
+            let mut tables = vec!["review_events", "problem_cards", "problem_events"];
+            if import.goals.is_some() {
+                tables.push("goals");
+            }
+            for table in tables {
+                tx.execute(Statement::from_sql_and_values(
+                    DbBackend::Postgres,
+                    format!("DELETE FROM {table} WHERE user_id = $1"),
+                    vec![Value::from(user_id)],
+                ))
+                .await?;
+            }
+        }
 
-        let review_row = tx
-            .query_one(Statement::from_sql_and_values(
+        if let Some(settings) = import.settings {
+            tx.execute(Statement::from_sql_and_values(
                 DbBackend::Postgres,
                 r#"
-                INSERT INTO review_events (card_id, user_id, grade, reviewed_at, next_due_at)
-                VALUES ($1, $2, $3, $4, $5)
-                RETURNING id, card_id, user_id, grade, reviewed_at, next_due_at
+                INSERT INTO notification_preferences
+                  (user_id, email_enabled, digest_hour_utc, timezone)
+                VALUES ($1, $2, $3, COALESCE($4, 'UTC'))
+                ON CONFLICT (user_id)
+                DO UPDATE SET email_enabled = EXCLUDED.email_enabled,
+                              digest_hour_utc = EXCLUDED.digest_hour_utc,
+                              timezone = COALESCE($4, notification_preferences.timezone)
                 "#,
                 vec![
-                    Value::from(card_id),
                     Value::from(user_id),
-                    Value::from(Self::grade_to_db(grade)),
-                    Value::from(reviewed_at),
-                    Value::from(next_due_at),
+                    Value::from(settings.email_enabled),
+                    Value::from(i32::from(settings.digest_hour_utc)),
+                    Value::from(settings.timezone),
                 ],
             ))
-            .await?
-            .ok_or_else(|| RepoError::Message("failed to insert review".to_owned()))?;
+            .await?;
+        }
 
-        tx.commit().await?;
+        for goal in import.goals.into_iter().flatten() {
+            let inserted = tx
+                .execute(Statement::from_sql_and_values(
+                    DbBackend::Postgres,
+                    r#"
+                    INSERT INTO goals (user_id, metric, period, target)
+                    VALUES ($1, $2, $3, $4)
+                    ON CONFLICT (user_id, metric, period) DO NOTHING
+                    "#,
+                    vec![
+                        Value::from(user_id),
+                        Value::from(goal.metric.as_str()),
+                        Value::from(goal.period.as_str()),
+                        Value::from(goal.target),
+                    ],
+                ))
+                .await?
+                .rows_affected();
+            summary.goals_imported += inserted as usize;
+        }
 
-        Ok(Some(ReviewEvent {
-            id: review_row.try_get("", "id")?,
-            card_id: review_row.try_get("", "card_id")?,
-            user_id: review_row.try_get("", "user_id")?,
-            grade: Self::grade_from_db(review_row.try_get::<String>("", "grade")?)?,
-            reviewed_at: review_row.try_get("", "reviewed_at")?,
-            next_due_at: review_row.try_get("", "next_due_at")?,
-        }))
-    }
+        let catalogued = import
+            .cards
+            .iter()
+            .filter_map(|card| {
+                let url = card.catalog_url.clone()?;
+                Some((card.source.clone(), card.problem_slug.clone(), url))
+            })
+            .collect();
+        let mut card_ids = HashMap::new();
+        // Cards whose lapses the archive carried; their reviews are not
+        // counted again.
+        let mut lapses_given = HashSet::new();
+        for card in import.cards {
+            // Schedules may be shorter on this instance than where the
+            // archive came from.
+            let interval_index = card.interval_index.min(self.schedule.max_index()) as i32;
+            let leech_state = card
+                .lapses
+                .map(|lapses| (lapses, card.leech_at, card.suspended_at));
+            let existing = tx
+                .query_one(Statement::from_sql_and_values(
+                    DbBackend::Postgres,
+                    r#"
+                    SELECT id
+                    FROM problem_cards
+                    WHERE user_id = $1 AND source = $2 AND problem_slug = $3
+                    FOR UPDATE
+                    "#,
+                    vec![
+                        Value::from(user_id),
+                        Value::from(card.source.clone()),
+                        Value::from(card.problem_slug.clone()),
+                    ],
+                ))
+                .await?;
+            let (card_id, archive_wins): (i64, bool) = match existing {
+                Some(row) => {
+                    let card_id: i64 = row.try_get("", "id")?;
+                    let last_reviewed_at: Option<DateTime<Utc>> = tx
+                        .query_one(Statement::from_sql_and_values(
+                            DbBackend::Postgres,
+                            "SELECT MAX(reviewed_at) AS last_reviewed_at FROM review_events WHERE card_id = $1",
+                            vec![Value::from(card_id)],
+                        ))
+                        .await?
+                        .map(|row| row.try_get("", "last_reviewed_at"))
+                        .transpose()?
+                        .flatten();
+                    let archive_wins = card.last_reviewed_at > last_reviewed_at;
+                    if archive_wins {
+                        tx.execute(Statement::from_sql_and_values(
+                            DbBackend::Postgres,
+                            r#"
+                            UPDATE problem_cards
+                            SET title = $1, url = $2, interval_index = $3, next_due_at = $4,
+                                kind = $6, front = $7, back = $8, tags = $9
+                            WHERE id = $5
+                            "#,
+                            vec![
+                                Value::from(card.title),
+                                Value::from(card.url),
+                                Value::from(interval_index),
+                                Value::from(card.next_due_at),
+                                Value::from(card_id),
+                                Value::from(card.kind.as_str()),
+                                Value::from(card.front),
+                                Value::from(card.back),
+                                Value::from(card.tags),
+                            ],
+                        ))
+                        .await?;
+                    }
+                    summary.cards_merged += 1;
+                    (card_id, archive_wins)
+                }
+                None => {
+                    let row = tx
+                        .query_one(Statement::from_sql_and_values(
+                            DbBackend::Postgres,
+                            r#"
+                            INSERT INTO problem_cards
+                              (user_id, source, problem_slug, title, url, interval_index, next_due_at,
+                               kind, front, back, tags, created_at)
+                            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, COALESCE($12, NOW()))
+                            RETURNING id
+                            "#,
+                            vec![
+                                Value::from(user_id),
+                                Value::from(card.source),
+                                Value::from(card.problem_slug),
+                                Value::from(card.title),
+                                Value::from(card.url),
+                                Value::from(interval_index),
+                                Value::from(card.next_due_at),
+                                Value::from(card.kind.as_str()),
+                                Value::from(card.front),
+                                Value::from(card.back),
+                                Value::from(card.tags),
+                                Value::from(card.created_at),
+                            ],
+                        ))
+                        .await?
+                        .ok_or_else(|| RepoError::Message("card insert returned no row".to_owned()))?;
+                    summary.cards_created += 1;
+                    (row.try_get("", "id")?, true)
+                }
+            };
+            if let Some((lapses, leech_at, suspended_at)) = leech_state {
+                lapses_given.insert(card_id);
+                if archive_wins {
+                    tx.execute(Statement::from_sql_and_values(
+                        DbBackend::Postgres,
+                        r#"
+                        UPDATE problem_cards
+                        SET lapses = $2, leech_at = $3, suspended_at = $4
+                        WHERE id = $1
+                        "#,
+                        vec![
+                            Value::from(card_id),
+                            Value::from(i64::from(lapses)),
+                            Value::from(leech_at),
+                            Value::from(suspended_at),
+                        ],
+                    ))
+                    .await?;
+                }
+            }
+            card_ids.insert(card.archive_id, card_id);
+        }
 
-    async fn user_history(&self, user_id: i64) -> Result<Vec<ReviewEvent>, RepoError> {
-        let rows = self
-            .db
-            .query_all(Statement::from_sql_and_values(
-                DbBackend::Postgres,
-                r#"
-                SELECT id, card_id, user_id, grade, reviewed_at, next_due_at
-                FROM review_events
-                WHERE user_id = $1
-                ORDER BY reviewed_at DESC
-                "#,
-                vec![Value::from(user_id)],
-            ))
-            .await?;
+        for event in import.events {
+            let dedup_key = make_event_dedup_key(
+                user_id,
+                &event.source,
+                &event.problem_slug,
+                &event.status,
+                event.occurred_at,
+            );
+            let inserted = tx
+                .execute(Statement::from_sql_and_values(
+                    DbBackend::Postgres,
+                    r#"
+                    INSERT INTO problem_events
+                      (user_id, source, problem_slug, title, url, status, occurred_at, dedup_key)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                    ON CONFLICT (dedup_key) DO NOTHING
+                    "#,
+                    vec![
+                        Value::from(user_id),
+                        Value::from(event.source),
+                        Value::from(event.problem_slug),
+                        Value::from(event.title),
+                        Value::from(event.url),
+                        Value::from(Self::status_to_db(event.status)),
+                        Value::from(event.occurred_at),
+                        Value::from(dedup_key),
+                    ],
+                ))
+                .await?
+                .rows_affected();
+            summary.events_imported += inserted as usize;
+        }
 
-        rows.into_iter()
-            .map(|row| {
-                Ok(ReviewEvent {
-                    id: row.try_get("", "id")?,
-                    card_id: row.try_get("", "card_id")?,
-                    user_id: row.try_get("", "user_id")?,
-                    grade: Self::grade_from_db(row.try_get::<String>("", "grade")?)?,
-                    reviewed_at: row.try_get("", "reviewed_at")?,
-                    next_due_at: row.try_get("", "next_due_at")?,
-                })
-            })
-            .collect()
+        for review in import.reviews {
+            let card_id = *card_ids.get(&review.archive_card_id).ok_or_else(|| {
+                RepoError::Message(format!(
+                    "review references unknown archive card {}",
+                    review.archive_card_id
+                ))
+            })?;
+            let grade = Self::grade_to_db(review.grade);
+            let inserted = tx
+                .execute(Statement::from_sql_and_values(
+                    DbBackend::Postgres,
+                    r#"
+                    INSERT INTO review_events (card_id, user_id, grade, reviewed_at, next_due_at)
+                    SELECT $1, $2, $3, $4, $5
+                    WHERE NOT EXISTS (
+                      SELECT 1 FROM review_events
+                      WHERE card_id = $1 AND grade = $3 AND reviewed_at = $4
+                    )
+                    "#,
+                    vec![
+                        Value::from(card_id),
+                        Value::from(user_id),
+                        Value::from(grade),
+                        Value::from(review.reviewed_at),
+                        Value::from(review.next_due_at),
+                    ],
+                ))
+                .await?
+                .rows_affected();
+            if inserted > 0 {
+                if review.grade == Grade::Again && !lapses_given.contains(&card_id) {
+                    tx.execute(Statement::from_sql_and_values(
+                        DbBackend::Postgres,
+                        "UPDATE problem_cards SET lapses = lapses + 1 WHERE id = $1",
+                        vec![Value::from(card_id)],
+                    ))
+                    .await?;
+                }
+                summary.reviews_imported += 1;
+            } else {
+                summary.reviews_skipped += 1;
+            }
+        }
+
+        Self::catalog_problems(&tx, &catalogued).await?;
+        Self::link_cards_to_catalog(&tx, user_id).await?;
+        tx.commit().await?;
+        Ok(summary)
     }
 }
 
@@ -678,7 +2867,7 @@ impl SettingsRepository for PostgresRepository {
             .query_one(Statement::from_sql_and_values(
                 DbBackend::Postgres,
                 r#"
-                SELECT user_id, email_enabled, digest_hour_utc
+                SELECT user_id, email_enabled, digest_hour_utc, timezone
                 FROM notification_preferences
                 WHERE user_id = $1
                 "#,
@@ -686,15 +2875,7 @@ impl SettingsRepository for PostgresRepository {
             ))
             .await?;
 
-        row.map(|pref_row| {
-            let digest_hour: i32 = pref_row.try_get("", "digest_hour_utc")?;
-            Ok(NotificationPreference {
-                user_id: pref_row.try_get("", "user_id")?,
-                email_enabled: pref_row.try_get("", "email_enabled")?,
-                digest_hour_utc: digest_hour.clamp(0, 23) as u8,
-            })
-        })
-        .transpose()
+        row.as_ref().map(preference_from_row).transpose()
     }
 
     async fn set_notification_preference(
@@ -702,6 +2883,7 @@ impl SettingsRepository for PostgresRepository {
         user_id: i64,
         email_enabled: bool,
         digest_hour_utc: u8,
+        timezone: Option<&str>,
     ) -> Result<Option<NotificationPreference>, RepoError> {
         let clamped_hour = digest_hour_utc.min(23) as i32;
         let row = self
@@ -710,27 +2892,21 @@ impl SettingsRepository for PostgresRepository {
                 DbBackend::Postgres,
                 r#"
                 UPDATE notification_preferences
-                SET email_enabled = $1, digest_hour_utc = $2
+                SET email_enabled = $1, digest_hour_utc = $2,
+                    timezone = COALESCE($4, timezone)
                 WHERE user_id = $3
-                RETURNING user_id, email_enabled, digest_hour_utc
+                RETURNING user_id, email_enabled, digest_hour_utc, timezone
                 "#,
                 vec![
                     Value::from(email_enabled),
                     Value::from(clamped_hour),
                     Value::from(user_id),
+                    Value::from(timezone.map(ToOwned::to_owned)),
                 ],
             ))
             .await?;
 
-        row.map(|pref_row| {
-            let digest_hour: i32 = pref_row.try_get("", "digest_hour_utc")?;
-            Ok(NotificationPreference {
-                user_id: pref_row.try_get("", "user_id")?,
-                email_enabled: pref_row.try_get("", "email_enabled")?,
-                digest_hour_utc: digest_hour.clamp(0, 23) as u8,
-            })
-        })
-        .transpose()
+        row.as_ref().map(preference_from_row).transpose()
     }
 
     async fn list_users(&self) -> Result<Vec<User>, RepoError> {
@@ -786,3 +2962,258 @@ impl SettingsRepository for PostgresRepository {
         })
     }
 }
+
+#[async_trait]
+impl PushSubscriptionRepository for PostgresRepository {
+    async fn upsert_push_subscription(
+        &self,
+        user_id: i64,
+        new_subscription: NewPushSubscription,
+    ) -> Result<PushSubscription, RepoError> {
+        let row = self
+            .db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                INSERT INTO push_subscriptions (user_id, endpoint, p256dh, auth, device_label)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (endpoint)
+                DO UPDATE
+                  SET user_id = EXCLUDED.user_id,
+                      p256dh = EXCLUDED.p256dh,
+                      auth = EXCLUDED.auth,
+                      device_label = EXCLUDED.device_label
+                RETURNING id, user_id, endpoint, p256dh, auth, device_label, created_at, last_used_at
+                "#,
+                vec![
+                    Value::from(user_id),
+                    Value::from(new_subscription.endpoint),
+                    Value::from(new_subscription.p256dh),
+                    Value::from(new_subscription.auth),
+                    Value::from(new_subscription.device_label),
+                ],
+            ))
+            .await?
+            .ok_or_else(|| RepoError::Message("failed to upsert push subscription".to_owned()))?;
+
+        Ok(PushSubscription {
+            id: row.try_get("", "id")?,
+            user_id: row.try_get("", "user_id")?,
+            endpoint: row.try_get("", "endpoint")?,
+            p256dh: row.try_get("", "p256dh")?,
+            auth: row.try_get("", "auth")?,
+            device_label: row.try_get("", "device_label")?,
+            created_at: row.try_get("", "created_at")?,
+            last_used_at: row.try_get("", "last_used_at")?,
+        })
+    }
+
+    async fn list_push_subscriptions(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<PushSubscription>, RepoError> {
+        let rows = self
+            .db
+            .query_all(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                SELECT id, user_id, endpoint, p256dh, auth, device_label, created_at, last_used_at
+                FROM push_subscriptions
+                WHERE user_id = $1
+                ORDER BY created_at DESC
+                "#,
+                vec![Value::from(user_id)],
+            ))
+            .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(PushSubscription {
+                    id: row.try_get("", "id")?,
+                    user_id: row.try_get("", "user_id")?,
+                    endpoint: row.try_get("", "endpoint")?,
+                    p256dh: row.try_get("", "p256dh")?,
+                    auth: row.try_get("", "auth")?,
+                    device_label: row.try_get("", "device_label")?,
+                    created_at: row.try_get("", "created_at")?,
+                    last_used_at: row.try_get("", "last_used_at")?,
+                })
+            })
+            .collect()
+    }
+
+    async fn delete_push_subscription(
+        &self,
+        user_id: i64,
+        subscription_id: i64,
+    ) -> Result<bool, RepoError> {
+        let result = self
+            .db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "DELETE FROM push_subscriptions WHERE id = $1 AND user_id = $2",
+                vec![Value::from(subscription_id), Value::from(user_id)],
+            ))
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete_push_subscription_by_endpoint(&self, endpoint: &str) -> Result<(), RepoError> {
+        self.db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "DELETE FROM push_subscriptions WHERE endpoint = $1",
+                vec![Value::from(endpoint.to_owned())],
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn touch_push_subscription(&self, subscription_id: i64) -> Result<(), RepoError> {
+        self.db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "UPDATE push_subscriptions SET last_used_at = NOW() WHERE id = $1",
+                vec![Value::from(subscription_id)],
+            ))
+            .await?;
+        Ok(())
+    }
+}
+
+fn integration_token_from_row(row: &QueryResult) -> Result<IntegrationToken, RepoError> {
+    Ok(IntegrationToken {
+        id: row.try_get("", "id")?,
+        user_id: row.try_get("", "user_id")?,
+        token_hash: row.try_get("", "token_hash")?,
+        label: row.try_get("", "label")?,
+        scopes: row.try_get("", "scopes")?,
+        created_at: row.try_get("", "created_at")?,
+        expires_at: row.try_get("", "expires_at")?,
+        revoked_at: row.try_get("", "revoked_at")?,
+        last_used_at: row.try_get("", "last_used_at")?,
+        last_used_ip: row.try_get("", "last_used_ip")?,
+        last_used_user_agent: row.try_get("", "last_used_user_agent")?,
+        previous_token_hash: row.try_get("", "previous_token_hash")?,
+        previous_token_expires_at: row.try_get("", "previous_token_expires_at")?,
+        expiry_notified_at: row.try_get("", "expiry_notified_at")?,
+    })
+}
+
+fn session_from_row(row: &QueryResult) -> Result<Session, RepoError> {
+    Ok(Session {
+        id: row.try_get("", "id")?,
+        user_id: row.try_get("", "user_id")?,
+        session_token_hash: row.try_get("", "session_token_hash")?,
+        expires_at: row.try_get("", "expires_at")?,
+        created_at: row.try_get("", "created_at")?,
+        last_seen_at: row.try_get("", "last_seen_at")?,
+        revoked_at: row.try_get("", "revoked_at")?,
+        user_agent: row.try_get("", "user_agent")?,
+        ip: row.try_get("", "ip")?,
+    })
+}
+
+fn card_from_row(row: &QueryResult) -> Result<ProblemCard, RepoError> {
+    let index: i32 = row.try_get("", "interval_index")?;
+    let kind: String = row.try_get("", "kind")?;
+    Ok(ProblemCard {
+        id: row.try_get("", "id")?,
+        user_id: row.try_get("", "user_id")?,
+        kind: CardKind::parse(&kind)
+            .ok_or_else(|| RepoError::Message(format!("unknown card kind {kind}")))?,
+        source: row.try_get("", "source")?,
+        problem_slug: row.try_get("", "problem_slug")?,
+        title: row.try_get("", "title")?,
+        url: row.try_get("", "url")?,
+        front: row.try_get("", "front")?,
+        back: row.try_get("", "back")?,
+        tags: row.try_get("", "tags")?,
+        problem_id: row.try_get("", "problem_id")?,
+        difficulty: row.try_get("", "difficulty")?,
+        interval_index: index.max(0) as usize,
+        next_due_at: row.try_get("", "next_due_at")?,
+        created_at: row.try_get("", "created_at")?,
+        lapses: row.try_get::<i32>("", "lapses")?.max(0) as u32,
+        leech_at: row.try_get("", "leech_at")?,
+        suspended_at: row.try_get("", "suspended_at")?,
+    })
+}
+
+fn goal_from_row(row: &QueryResult) -> Result<Goal, RepoError> {
+    let metric: String = row.try_get("", "metric")?;
+    let period: String = row.try_get("", "period")?;
+    Ok(Goal {
+        id: row.try_get("", "id")?,
+        user_id: row.try_get("", "user_id")?,
+        metric: GoalMetric::parse(&metric)
+            .ok_or_else(|| RepoError::Message(format!("unknown goal metric {metric}")))?,
+        period: GoalPeriod::parse(&period)
+            .ok_or_else(|| RepoError::Message(format!("unknown goal period {period}")))?,
+        target: row.try_get("", "target")?,
+        created_at: row.try_get("", "created_at")?,
+    })
+}
+
+fn preference_from_row(row: &QueryResult) -> Result<NotificationPreference, RepoError> {
+    let digest_hour: i32 = row.try_get("", "digest_hour_utc")?;
+    Ok(NotificationPreference {
+        user_id: row.try_get("", "user_id")?,
+        email_enabled: row.try_get("", "email_enabled")?,
+        digest_hour_utc: digest_hour.clamp(0, 23) as u8,
+        timezone: row.try_get("", "timezone")?,
+    })
+}
+
+fn retention_counts_from_row(row: &QueryResult) -> Result<RetentionCounts, RepoError> {
+    Ok(RetentionCounts {
+        reviews: row.try_get("", "reviews")?,
+        mature_reviews: row.try_get("", "mature_reviews")?,
+        mature_passed: row.try_get("", "mature_passed")?,
+        lapses: row.try_get("", "lapses")?,
+    })
+}
+
+fn problem_from_row(row: &QueryResult) -> Result<Problem, RepoError> {
+    Ok(Problem {
+        id: row.try_get("", "id")?,
+        source: row.try_get("", "source")?,
+        slug: row.try_get("", "slug")?,
+        title: row.try_get("", "title")?,
+        url: row.try_get("", "url")?,
+        difficulty: row.try_get("", "difficulty")?,
+        tags: row.try_get("", "tags")?,
+        aliases: row.try_get("", "aliases")?,
+        catalog_version: row.try_get("", "catalog_version")?,
+        created_at: row.try_get("", "created_at")?,
+        updated_at: row.try_get("", "updated_at")?,
+    })
+}
+
+fn event_from_row(row: &QueryResult) -> Result<ProblemEvent, RepoError> {
+    Ok(ProblemEvent {
+        id: row.try_get("", "id")?,
+        user_id: row.try_get("", "user_id")?,
+        source: row.try_get("", "source")?,
+        problem_slug: row.try_get("", "problem_slug")?,
+        title: row.try_get("", "title")?,
+        url: row.try_get("", "url")?,
+        status: PostgresRepository::status_from_db(row.try_get::<String>("", "status")?)?,
+        occurred_at: row.try_get("", "occurred_at")?,
+        dedup_key: row.try_get("", "dedup_key")?,
+    })
+}
+
+fn passkey_from_row(row: &QueryResult) -> Result<PasskeyCredential, RepoError> {
+    Ok(PasskeyCredential {
+        id: row.try_get("", "id")?,
+        user_id: row.try_get("", "user_id")?,
+        credential_id: row.try_get("", "credential_id")?,
+        public_key: row.try_get("", "public_key")?,
+        algorithm: row.try_get("", "algorithm")?,
+        sign_count: row.try_get("", "sign_count")?,
+        label: row.try_get("", "label")?,
+        created_at: row.try_get("", "created_at")?,
+        last_used_at: row.try_get("", "last_used_at")?,
+    })
+}
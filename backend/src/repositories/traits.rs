@@ -1,10 +1,15 @@
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 
 use crate::{
     models::{
-        EmailDeliveryLog, IngestProblemInput, IntegrationToken, NotificationPreference,
-        ProblemCard, ProblemEvent, ReviewEvent, User,
+        ArchiveImport, ArchiveImportSummary, CalendarFeedToken, CardContent, CatalogProblemInput,
+        CatalogSeedSummary, ClientInfo, DailyActivity, EmailDeliveryLog, Goal, GoalMetric,
+        GoalPeriod, GradedReview, IdempotencyClaim, IngestProblemInput, IngestedEvent,
+        IntegrationToken, LeechPolicy, NotificationPreference, OidcAuthRequest, PasskeyCredential,
+        Problem, ProblemCard, ProblemEvent, PushSubscription, ReviewEvent, ReviewStats, Session,
+        StoredResponse, UncataloguedProblem, User, UserIdentity, WebauthnCeremony,
+        WebauthnChallenge,
     },
     repositories::error::RepoError,
     srs::Grade,
@@ -16,6 +21,101 @@ pub trait AuthRepository: Send + Sync {
     async fn get_user_by_email(&self, email: &str) -> Result<Option<User>, RepoError>;
     async fn create_magic_link(&self, user_id: i64) -> Result<String, RepoError>;
     async fn verify_magic_link(&self, token: &str) -> Result<Option<User>, RepoError>;
+    async fn create_session(
+        &self,
+        user_id: i64,
+        session_token_hash: &str,
+        expires_at: DateTime<Utc>,
+        client: ClientInfo,
+    ) -> Result<Session, RepoError>;
+    async fn user_from_session(
+        &self,
+        session_token_hash: &str,
+    ) -> Result<Option<(User, Session)>, RepoError>;
+    async fn touch_session(&self, session_id: i64) -> Result<(), RepoError>;
+    async fn list_active_sessions(&self, user_id: i64) -> Result<Vec<Session>, RepoError>;
+    async fn revoke_session(&self, user_id: i64, session_id: i64) -> Result<bool, RepoError>;
+    async fn revoke_other_sessions(
+        &self,
+        user_id: i64,
+        keep_session_id: i64,
+    ) -> Result<u64, RepoError>;
+    async fn create_oidc_auth_request(&self, request: NewOidcAuthRequest) -> Result<(), RepoError>;
+    /// Removes and returns an unexpired request, so each `state` is single-use.
+    async fn consume_oidc_auth_request(
+        &self,
+        state_hash: &str,
+    ) -> Result<Option<OidcAuthRequest>, RepoError>;
+    async fn user_from_identity(
+        &self,
+        issuer: &str,
+        subject: &str,
+    ) -> Result<Option<User>, RepoError>;
+    async fn link_identity(
+        &self,
+        user_id: i64,
+        issuer: &str,
+        subject: &str,
+        email: Option<&str>,
+    ) -> Result<UserIdentity, RepoError>;
+    async fn create_webauthn_challenge(
+        &self,
+        challenge: NewWebauthnChallenge,
+    ) -> Result<(), RepoError>;
+    /// Removes and returns the challenge if it is unexpired and was issued
+    /// for `ceremony`.
+    async fn consume_webauthn_challenge(
+        &self,
+        challenge_hash: &str,
+        ceremony: WebauthnCeremony,
+    ) -> Result<Option<WebauthnChallenge>, RepoError>;
+    async fn create_passkey(
+        &self,
+        user_id: i64,
+        passkey: NewPasskey,
+    ) -> Result<PasskeyCredential, RepoError>;
+    async fn list_passkeys(&self, user_id: i64) -> Result<Vec<PasskeyCredential>, RepoError>;
+    async fn passkey_by_credential_id(
+        &self,
+        credential_id: &str,
+    ) -> Result<Option<(User, PasskeyCredential)>, RepoError>;
+    async fn record_passkey_use(&self, passkey_id: i64, sign_count: i64) -> Result<(), RepoError>;
+    async fn delete_passkey(&self, user_id: i64, passkey_id: i64) -> Result<bool, RepoError>;
+    async fn create_account_deletion_request(
+        &self,
+        user_id: i64,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), RepoError>;
+    /// Deletes the user and every row they own, but only if `token_hash`
+    /// matches an unexpired deletion request of theirs. Returns `false`
+    /// (and deletes nothing) otherwise.
+    async fn delete_account(&self, user_id: i64, token_hash: &str) -> Result<bool, RepoError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct NewWebauthnChallenge {
+    pub challenge_hash: String,
+    pub ceremony: WebauthnCeremony,
+    pub user_id: Option<i64>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewPasskey {
+    pub credential_id: String,
+    pub public_key: Vec<u8>,
+    pub algorithm: i64,
+    pub sign_count: i64,
+    pub label: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewOidcAuthRequest {
+    pub state_hash: String,
+    pub nonce: String,
+    pub pkce_verifier: String,
+    pub expires_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone)]
@@ -25,6 +125,15 @@ pub struct NewIntegrationToken {
     pub expires_at: Option<DateTime<Utc>>,
 }
 
+#[derive(Debug, Clone)]
+pub struct IntegrationTokenRotation {
+    pub token_hash: String,
+    /// When set, the replaced secret keeps authenticating until this instant.
+    pub previous_valid_until: Option<DateTime<Utc>>,
+    /// `Some` replaces the expiry (and re-arms the expiry notice), `None` keeps it.
+    pub expires_at: Option<Option<DateTime<Utc>>>,
+}
+
 #[async_trait]
 pub trait IntegrationTokenRepository: Send + Sync {
     async fn create_integration_token(
@@ -45,14 +154,88 @@ pub trait IntegrationTokenRepository: Send + Sync {
     async fn user_from_integration_token(
         &self,
         token_hash: &str,
+    ) -> Result<Option<(User, IntegrationToken)>, RepoError>;
+    async fn rotate_integration_token(
+        &self,
+        user_id: i64,
+        token_id: i64,
+        rotation: IntegrationTokenRotation,
+    ) -> Result<Option<IntegrationToken>, RepoError>;
+    async fn touch_integration_token_usage(
+        &self,
+        token_id: i64,
+        client: ClientInfo,
+    ) -> Result<(), RepoError>;
+    async fn list_integration_tokens_expiring_between(
+        &self,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<IntegrationToken>, RepoError>;
+    async fn mark_integration_token_expiry_notified(&self, token_id: i64) -> Result<(), RepoError>;
+}
+
+#[async_trait]
+pub trait CalendarFeedRepository: Send + Sync {
+    async fn create_calendar_feed_token(
+        &self,
+        user_id: i64,
+        token_hash: &str,
+        label: &str,
+    ) -> Result<CalendarFeedToken, RepoError>;
+    async fn list_calendar_feed_tokens(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<CalendarFeedToken>, RepoError>;
+    async fn revoke_calendar_feed_token(
+        &self,
+        user_id: i64,
+        token_id: i64,
+    ) -> Result<bool, RepoError>;
+    async fn user_from_calendar_feed_token(
+        &self,
+        token_hash: &str,
     ) -> Result<Option<User>, RepoError>;
-    async fn touch_integration_token_usage(&self, token_hash: &str) -> Result<(), RepoError>;
+    async fn touch_calendar_feed_token_usage(&self, token_hash: &str) -> Result<(), RepoError>;
 }
 
 #[async_trait]
 pub trait EventRepository: Send + Sync {
     async fn ingest_event(&self, payload: IngestProblemInput) -> Result<ProblemEvent, RepoError>;
+    /// Ingests several events atomically, returning one result per payload
+    /// in order. Cards are updated as if the payloads were sent one by one.
+    async fn ingest_events(
+        &self,
+        payloads: Vec<IngestProblemInput>,
+    ) -> Result<Vec<IngestedEvent>, RepoError>;
     async fn latest_event_for_user(&self, user_id: i64) -> Result<Option<ProblemEvent>, RepoError>;
+    async fn list_events(&self, user_id: i64) -> Result<Vec<ProblemEvent>, RepoError>;
+}
+
+#[async_trait]
+pub trait IdempotencyRepository: Send + Sync {
+    /// Records `key` as in flight unless a live record already exists, in
+    /// which case that record is returned untouched. Expired records are
+    /// replaced, and so are in-flight records for the same request claimed
+    /// before `stale_before`, whose request is taken to have died.
+    async fn claim_idempotency_key(
+        &self,
+        user_id: i64,
+        key: &str,
+        request_hash: &str,
+        now: DateTime<Utc>,
+        stale_before: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<IdempotencyClaim, RepoError>;
+    async fn complete_idempotency_key(
+        &self,
+        user_id: i64,
+        key: &str,
+        response: StoredResponse,
+    ) -> Result<(), RepoError>;
+    /// Forgets an in-flight key so the client can retry, e.g. after a
+    /// server error.
+    async fn release_idempotency_key(&self, user_id: i64, key: &str) -> Result<(), RepoError>;
+    async fn purge_expired_idempotency_keys(&self, now: DateTime<Utc>) -> Result<u64, RepoError>;
 }
 
 #[async_trait]
@@ -63,15 +246,141 @@ pub trait ReviewRepository: Send + Sync {
         now: DateTime<Utc>,
     ) -> Result<Vec<ProblemCard>, RepoError>;
     async fn upcoming_cards(&self, user_id: i64) -> Result<Vec<ProblemCard>, RepoError>;
+    async fn list_cards(&self, user_id: i64) -> Result<Vec<ProblemCard>, RepoError>;
+    /// Saves the grade. When an `again` takes the card to `leech.threshold`
+    /// lapses, the card is flagged and `leech.action` applied in the same
+    /// transaction.
     async fn grade_card(
         &self,
         user_id: i64,
         card_id: i64,
         grade: Grade,
-    ) -> Result<Option<ReviewEvent>, RepoError>;
+        leech: LeechPolicy,
+    ) -> Result<Option<GradedReview>, RepoError>;
     async fn user_history(&self, user_id: i64) -> Result<Vec<ReviewEvent>, RepoError>;
 }
 
+#[async_trait]
+pub trait StatsRepository: Send + Sync {
+    /// Aggregates the user's reviews from `since` on. Maturity and lapses
+    /// look at each card's full history, including reviews before `since`.
+    async fn review_stats(
+        &self,
+        user_id: i64,
+        since: DateTime<Utc>,
+        mature_after: Duration,
+    ) -> Result<ReviewStats, RepoError>;
+}
+
+/// Per-day activity and the goals measured against it. Days are calendar
+/// days in `timezone`, an IANA name.
+#[async_trait]
+pub trait ActivityRepository: Send + Sync {
+    /// Days from `from` through `to` with any activity, oldest first.
+    async fn daily_activity(
+        &self,
+        user_id: i64,
+        timezone: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<DailyActivity>, RepoError>;
+    /// Every day with a review or a solved problem, oldest first.
+    async fn active_days(&self, user_id: i64, timezone: &str) -> Result<Vec<NaiveDate>, RepoError>;
+    async fn list_goals(&self, user_id: i64) -> Result<Vec<Goal>, RepoError>;
+    /// Creates the goal, or retargets the user's goal with the same metric
+    /// and period.
+    async fn upsert_goal(
+        &self,
+        user_id: i64,
+        metric: GoalMetric,
+        period: GoalPeriod,
+        target: i64,
+    ) -> Result<Goal, RepoError>;
+    async fn delete_goal(&self, user_id: i64, goal_id: i64) -> Result<bool, RepoError>;
+}
+
+/// Cards the user keeps failing. Lapses are counted and leeches flagged
+/// by [`ReviewRepository::grade_card`]; archive imports count lapses too.
+#[async_trait]
+pub trait LeechRepository: Send + Sync {
+    /// Flagged cards, most lapses first.
+    async fn list_leeches(&self, user_id: i64) -> Result<Vec<ProblemCard>, RepoError>;
+    /// Resets the lapse count and removes the flag, the leech tag and any
+    /// suspension.
+    async fn clear_leech(
+        &self,
+        user_id: i64,
+        card_id: i64,
+    ) -> Result<Option<ProblemCard>, RepoError>;
+}
+
+/// The problem catalog shared by all users. Ingestion and imports add to it
+/// through [`EventRepository`] and [`ArchiveRepository`].
+#[async_trait]
+pub trait ProblemRepository: Send + Sync {
+    /// Looks a problem up by its slug or one of its aliases.
+    async fn find_problem(&self, source: &str, slug: &str) -> Result<Option<Problem>, RepoError>;
+    /// Number of users with a card for the problem.
+    async fn count_problem_learners(&self, problem_id: i64) -> Result<i64, RepoError>;
+    /// Inserts or overwrites dataset entries in one transaction. Entries
+    /// already stored as given are left alone.
+    async fn seed_problems(
+        &self,
+        version: i64,
+        problems: Vec<CatalogProblemInput>,
+    ) -> Result<CatalogSeedSummary, RepoError>;
+    /// Problems with cards that no dataset version has described, most
+    /// learned first.
+    async fn uncatalogued_problems(&self) -> Result<Vec<UncataloguedProblem>, RepoError>;
+}
+
+/// Authored (non-problem) cards. Scheduling and reviews go through
+/// [`ReviewRepository`] like every other card.
+#[async_trait]
+pub trait CardRepository: Send + Sync {
+    /// Returns `None` when the user already has a card with this key.
+    async fn create_card(
+        &self,
+        user_id: i64,
+        key: &str,
+        content: CardContent,
+        next_due_at: DateTime<Utc>,
+    ) -> Result<Option<ProblemCard>, RepoError>;
+    async fn get_card(&self, user_id: i64, card_id: i64) -> Result<Option<ProblemCard>, RepoError>;
+    /// Only authored cards can be edited; problem cards yield `None`.
+    async fn update_card(
+        &self,
+        user_id: i64,
+        card_id: i64,
+        content: CardContent,
+    ) -> Result<Option<ProblemCard>, RepoError>;
+    async fn delete_card(&self, user_id: i64, card_id: i64) -> Result<bool, RepoError>;
+    async fn cards_by_keys(
+        &self,
+        user_id: i64,
+        keys: &[String],
+    ) -> Result<Vec<ProblemCard>, RepoError>;
+    /// Creates or rewrites authored cards by key in one transaction. New cards
+    /// are due at `next_due_at`; existing ones keep their schedule.
+    async fn upsert_cards(
+        &self,
+        user_id: i64,
+        cards: Vec<(String, CardContent)>,
+        next_due_at: DateTime<Utc>,
+    ) -> Result<Vec<ProblemCard>, RepoError>;
+}
+
+#[async_trait]
+pub trait ArchiveRepository: Send + Sync {
+    /// Restores an archive into the user's account in one transaction, so a
+    /// failure part-way leaves the account untouched.
+    async fn import_archive(
+        &self,
+        user_id: i64,
+        import: ArchiveImport,
+    ) -> Result<ArchiveImportSummary, RepoError>;
+}
+
 #[async_trait]
 pub trait SettingsRepository: Send + Sync {
     async fn get_notification_preference(
@@ -83,6 +392,7 @@ pub trait SettingsRepository: Send + Sync {
         user_id: i64,
         email_enabled: bool,
         digest_hour_utc: u8,
+        timezone: Option<&str>,
     ) -> Result<Option<NotificationPreference>, RepoError>;
     async fn list_users(&self) -> Result<Vec<User>, RepoError>;
     async fn log_email(
@@ -92,3 +402,31 @@ pub trait SettingsRepository: Send + Sync {
         body: &str,
     ) -> Result<EmailDeliveryLog, RepoError>;
 }
+
+#[derive(Debug, Clone)]
+pub struct NewPushSubscription {
+    pub endpoint: String,
+    pub p256dh: String,
+    pub auth: String,
+    pub device_label: String,
+}
+
+#[async_trait]
+pub trait PushSubscriptionRepository: Send + Sync {
+    async fn upsert_push_subscription(
+        &self,
+        user_id: i64,
+        new_subscription: NewPushSubscription,
+    ) -> Result<PushSubscription, RepoError>;
+    async fn list_push_subscriptions(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<PushSubscription>, RepoError>;
+    async fn delete_push_subscription(
+        &self,
+        user_id: i64,
+        subscription_id: i64,
+    ) -> Result<bool, RepoError>;
+    async fn delete_push_subscription_by_endpoint(&self, endpoint: &str) -> Result<(), RepoError>;
+    async fn touch_push_subscription(&self, subscription_id: i64) -> Result<(), RepoError>;
+}
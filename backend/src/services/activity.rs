@@ -0,0 +1,265 @@
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use tracing::{info, warn};
+
+use crate::{
+    activity::{self, Streak},
+    dto::activity::{
+        ActivitySummary, ActivityTotals, GoalProgress, HeatmapQuery, HeatmapResponse,
+        SaveGoalRequest,
+    },
+    errors::AppError,
+    models::{DailyActivity, Goal, GoalPeriod},
+    repositories::traits::ActivityRepository,
+};
+
+use super::settings::SettingsService;
+
+const HEATMAP_DAYS: i64 = 365;
+pub(crate) const MAX_GOAL_TARGET: i64 = 10_000;
+
+#[derive(Clone)]
+pub struct ActivityService {
+    repo: Arc<dyn ActivityRepository>,
+    settings_service: SettingsService,
+}
+
+impl ActivityService {
+    pub fn new(repo: Arc<dyn ActivityRepository>, settings_service: SettingsService) -> Self {
+        Self {
+            repo,
+            settings_service,
+        }
+    }
+
+    pub async fn heatmap(
+        &self,
+        user_id: i64,
+        query: HeatmapQuery,
+        now: DateTime<Utc>,
+    ) -> Result<HeatmapResponse, AppError> {
+        let timezone = self.timezone(user_id).await?;
+        let today = activity::local_date(now, timezone);
+        let (from, to) = match query.year {
+            Some(year) => {
+                let bounds = (year <= today.year())
+                    .then(|| {
+                        NaiveDate::from_ymd_opt(year, 1, 1)
+                            .zip(NaiveDate::from_ymd_opt(year, 12, 31))
+                    })
+                    .flatten();
+                bounds.ok_or_else(|| {
+                    AppError::InvalidInput(format!("year must be {} or earlier", today.year()))
+                })?
+            }
+            None => (today - Duration::days(HEATMAP_DAYS - 1), today),
+        };
+        let active = self.daily_activity(user_id, timezone, from, to).await?;
+        let mut totals = ActivityTotals::default();
+        for day in &active {
+            totals.reviews += day.reviews;
+            totals.solved += day.solved;
+            totals.new_cards += day.new_cards;
+            totals.active_days += usize::from(day.is_active());
+        }
+        let by_date: HashMap<NaiveDate, DailyActivity> =
+            active.iter().map(|day| (day.date, *day)).collect();
+        let days = from
+            .iter_days()
+            .take_while(|date| *date <= to)
+            .map(|date| {
+                by_date
+                    .get(&date)
+                    .copied()
+                    .unwrap_or_else(|| DailyActivity::empty(date))
+            })
+            .collect();
+        let streak = self.streak(user_id, timezone, today).await?;
+        info!(
+            user_id,
+            %from,
+            %to,
+            active_days = totals.active_days,
+            current_streak = streak.current,
+            "activity_heatmap_built"
+        );
+        Ok(HeatmapResponse {
+            timezone: timezone.name().to_owned(),
+            from,
+            to,
+            totals,
+            streak,
+            days,
+        })
+    }
+
+    /// Current streak and goal progress for the dashboard and digests.
+    pub async fn summary(
+        &self,
+        user_id: i64,
+        now: DateTime<Utc>,
+    ) -> Result<ActivitySummary, AppError> {
+        let timezone = self.timezone(user_id).await?;
+        let today = activity::local_date(now, timezone);
+        Ok(ActivitySummary {
+            streak: self.streak(user_id, timezone, today).await?,
+            goals: self.goal_progress(user_id, timezone, today).await?,
+        })
+    }
+
+    pub async fn goals(
+        &self,
+        user_id: i64,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<GoalProgress>, AppError> {
+        let timezone = self.timezone(user_id).await?;
+        self.goal_progress(user_id, timezone, activity::local_date(now, timezone))
+            .await
+    }
+
+    pub async fn list_goals(&self, user_id: i64) -> Result<Vec<Goal>, AppError> {
+        self.repo
+            .list_goals(user_id)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))
+    }
+
+    pub async fn save_goal(
+        &self,
+        user_id: i64,
+        request: SaveGoalRequest,
+        now: DateTime<Utc>,
+    ) -> Result<GoalProgress, AppError> {
+        if !(1..=MAX_GOAL_TARGET).contains(&request.target) {
+            return Err(AppError::InvalidInput(format!(
+                "target must be between 1 and {MAX_GOAL_TARGET}"
+            )));
+        }
+        let goal = self
+            .repo
+            .upsert_goal(user_id, request.metric, request.period, request.target)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        info!(
+            user_id,
+            goal_id = goal.id,
+            metric = goal.metric.as_str(),
+            period = goal.period.as_str(),
+            target = goal.target,
+            "goal_saved"
+        );
+        let timezone = self.timezone(user_id).await?;
+        let today = activity::local_date(now, timezone);
+        let activity = self
+            .daily_activity(
+                user_id,
+                timezone,
+                activity::period_start(goal.period, today),
+                today,
+            )
+            .await?;
+        Ok(progress_for(goal, &activity, today))
+    }
+
+    pub async fn delete_goal(&self, user_id: i64, goal_id: i64) -> Result<(), AppError> {
+        let deleted = self
+            .repo
+            .delete_goal(user_id, goal_id)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        if !deleted {
+            warn!(user_id, goal_id, "goal_not_found");
+            return Err(AppError::GoalNotFound);
+        }
+        info!(user_id, goal_id, "goal_deleted");
+        Ok(())
+    }
+
+    async fn goal_progress(
+        &self,
+        user_id: i64,
+        timezone: Tz,
+        today: NaiveDate,
+    ) -> Result<Vec<GoalProgress>, AppError> {
+        let goals = self
+            .repo
+            .list_goals(user_id)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        let Some(from) = goals
+            .iter()
+            .map(|goal| activity::period_start(goal.period, today))
+            .min()
+        else {
+            return Ok(Vec::new());
+        };
+        let activity = self.daily_activity(user_id, timezone, from, today).await?;
+        Ok(goals
+            .into_iter()
+            .map(|goal| progress_for(goal, &activity, today))
+            .collect())
+    }
+
+    async fn streak(
+        &self,
+        user_id: i64,
+        timezone: Tz,
+        today: NaiveDate,
+    ) -> Result<Streak, AppError> {
+        let days = self
+            .repo
+            .active_days(user_id, timezone.name())
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        Ok(activity::streaks(&days, today))
+    }
+
+    async fn daily_activity(
+        &self,
+        user_id: i64,
+        timezone: Tz,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<DailyActivity>, AppError> {
+        self.repo
+            .daily_activity(user_id, timezone.name(), from, to)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))
+    }
+
+    /// Users without settings, or with a zone this build does not know,
+    /// count days in UTC.
+    pub async fn timezone(&self, user_id: i64) -> Result<Tz, AppError> {
+        let name = match self.settings_service.get(user_id).await {
+            Ok(pref) => pref.timezone,
+            Err(AppError::SettingsNotFound) => return Ok(chrono_tz::UTC),
+            Err(err) => return Err(err),
+        };
+        Ok(activity::parse_timezone(&name).unwrap_or_else(|| {
+            warn!(user_id, timezone = %name, "activity_timezone_unknown");
+            chrono_tz::UTC
+        }))
+    }
+}
+
+fn progress_for(goal: Goal, activity: &[DailyActivity], today: NaiveDate) -> GoalProgress {
+    let period_start = activity::period_start(goal.period, today);
+    let period_end = match goal.period {
+        GoalPeriod::Day => period_start,
+        GoalPeriod::Week => period_start + Duration::days(6),
+    };
+    let progress = activity
+        .iter()
+        .filter(|day| day.date >= period_start && day.date <= today)
+        .map(|day| day.count(goal.metric))
+        .sum::<i64>();
+    GoalProgress {
+        met: progress >= goal.target,
+        period_start,
+        period_end,
+        progress,
+        goal,
+    }
+}
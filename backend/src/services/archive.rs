@@ -0,0 +1,997 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use chrono::{DateTime, Duration, DurationRound, TimeZone, Utc};
+use futures_util::{Stream, StreamExt, stream};
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::{
+    dto::archive::{
+        ARCHIVE_FORMAT, ARCHIVE_VERSION, ExportArchive, ExportCard, ExportGoal, ExportProblemEvent,
+        ExportReview, ExportSettings, ExportUser,
+    },
+    errors::AppError,
+    models::{
+        ArchiveImport, ArchiveImportSummary, CardKind, Goal, ImportMode, ImportedCard,
+        ImportedEvent, ImportedGoal, ImportedReview, ImportedSettings, NotificationPreference,
+        ProblemCard, ProblemEvent, ProblemStatus, ReviewEvent, User,
+    },
+    ports::anki::{
+        self, AnkiCard, AnkiCollection, AnkiError, AnkiNote, AnkiReview, CARD_TYPE_NEW,
+        CARD_TYPE_REVIEW, QUEUE_INTRADAY_LEARNING, SRS_NOTE_FIELDS, SRS_NOTE_TYPE,
+    },
+    ports::leetcode_history::{self, LeetCodeSubmission},
+    repositories::traits::ArchiveRepository,
+    srs::{Grade, SrsSchedule},
+};
+
+use super::{
+    activity::{ActivityService, MAX_GOAL_TARGET},
+    event::EventService,
+    integrations::IntegrationsService,
+    review::ReviewService,
+    settings::SettingsService,
+};
+
+#[derive(Debug, Clone, Copy)]
+enum ExportSection {
+    Header,
+    Settings,
+    Goals,
+    IntegrationTokens,
+    Cards,
+    ProblemEvents,
+    Reviews,
+}
+
+/// Order matches the fields of [`crate::dto::archive::ExportArchive`].
+const EXPORT_SECTIONS: [ExportSection; 7] = [
+    ExportSection::Header,
+    ExportSection::Settings,
+    ExportSection::Goals,
+    ExportSection::IntegrationTokens,
+    ExportSection::Cards,
+    ExportSection::ProblemEvents,
+    ExportSection::Reviews,
+];
+
+#[derive(Clone)]
+pub struct ArchiveService {
+    repo: Arc<dyn ArchiveRepository>,
+    review_service: ReviewService,
+    event_service: EventService,
+    settings_service: SettingsService,
+    activity_service: ActivityService,
+    integrations_service: IntegrationsService,
+    schedule: SrsSchedule,
+}
+
+impl ArchiveService {
+    pub fn new(
+        repo: Arc<dyn ArchiveRepository>,
+        review_service: ReviewService,
+        event_service: EventService,
+        settings_service: SettingsService,
+        activity_service: ActivityService,
+        integrations_service: IntegrationsService,
+        schedule: SrsSchedule,
+    ) -> Self {
+        Self {
+            repo,
+            review_service,
+            event_service,
+            settings_service,
+            activity_service,
+            integrations_service,
+            schedule,
+        }
+    }
+
+    /// Streams the user's archive as JSON text chunks, loading one section at
+    /// a time rather than building the whole document up front.
+    pub fn export(
+        &self,
+        user: User,
+        now: DateTime<Utc>,
+    ) -> impl Stream<Item = Result<String, AppError>> + Send + 'static {
+        let service = self.clone();
+        stream::iter(EXPORT_SECTIONS)
+            .then(move |section| {
+                let service = service.clone();
+                let user = user.clone();
+                async move { service.export_section(section, &user, now).await }
+            })
+            .chain(stream::once(async { Ok("}\n".to_owned()) }))
+    }
+
+    /// Restores cards, problem events, review history, settings and goals from an
+    /// archive produced by [`Self::export`]. Token metadata is ignored since
+    /// secrets cannot be carried over.
+    pub async fn import(
+        &self,
+        user_id: i64,
+        archive: ExportArchive,
+        mode: ImportMode,
+    ) -> Result<ArchiveImportSummary, AppError> {
+        let mut import = validate_archive(archive, mode).inspect_err(|err| {
+            warn!(user_id, error = %err, "archive_import_rejected");
+        })?;
+        self.catalog_cards(&mut import);
+        let summary = self
+            .repo
+            .import_archive(user_id, import)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        info!(
+            user_id,
+            format = "json",
+            mode = ?mode,
+            cards_created = summary.cards_created,
+            cards_merged = summary.cards_merged,
+            events_imported = summary.events_imported,
+            reviews_imported = summary.reviews_imported,
+            reviews_skipped = summary.reviews_skipped,
+            goals_imported = summary.goals_imported,
+            "archive_imported"
+        );
+        Ok(summary)
+    }
+
+    /// Imports an Anki `.apkg`: each note becomes a card and its review log
+    /// is replayed as review history. Notification settings are left alone.
+    pub async fn import_anki(
+        &self,
+        user_id: i64,
+        package: &[u8],
+        mode: ImportMode,
+        now: DateTime<Utc>,
+    ) -> Result<ArchiveImportSummary, AppError> {
+        let collection = anki::read_package(package)
+            .await
+            .map_err(anki_error)
+            .inspect_err(|err| warn!(user_id, error = %err, "anki_import_rejected"))?;
+        let mut import = anki_import(collection, mode, &self.schedule, now);
+        self.catalog_cards(&mut import);
+        let summary = self
+            .repo
+            .import_archive(user_id, import)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        info!(
+            user_id,
+            format = "anki",
+            mode = ?mode,
+            cards_created = summary.cards_created,
+            cards_merged = summary.cards_merged,
+            reviews_imported = summary.reviews_imported,
+            reviews_skipped = summary.reviews_skipped,
+            "archive_imported"
+        );
+        Ok(summary)
+    }
+
+    /// Imports a LeetCode submission history dump (JSON or CSV). Every
+    /// submission becomes a problem event at its original time, and each
+    /// problem gets a card scheduled from its accepted submissions. Always
+    /// merges: events are deduplicated and existing cards keep their schedule,
+    /// so importing the same dump twice changes nothing.
+    pub async fn import_leetcode_history(
+        &self,
+        user_id: i64,
+        body: &str,
+        now: DateTime<Utc>,
+    ) -> Result<ArchiveImportSummary, AppError> {
+        let submissions = leetcode_history::parse_submissions(body)
+            .map_err(|err| AppError::InvalidInput(err.to_string()))
+            .inspect_err(|err| warn!(user_id, error = %err, "leetcode_history_rejected"))?;
+        let submission_count = submissions.len();
+        let mut import = leetcode_history_import(submissions, &self.schedule, now);
+        self.catalog_cards(&mut import);
+        let summary = self
+            .repo
+            .import_archive(user_id, import)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        info!(
+            user_id,
+            format = "leetcode",
+            submissions = submission_count,
+            cards_created = summary.cards_created,
+            cards_merged = summary.cards_merged,
+            events_imported = summary.events_imported,
+            "archive_imported"
+        );
+        Ok(summary)
+    }
+
+    /// Sets `catalog_url` on problem cards whose source and slug the source
+    /// registry accepts as they are, so the import catalogs those problems.
+    fn catalog_cards(&self, import: &mut ArchiveImport) {
+        let sources = self.event_service.sources();
+        for card in import
+            .cards
+            .iter_mut()
+            .filter(|card| card.kind == CardKind::Problem)
+        {
+            card.catalog_url = sources
+                .normalize(&card.source, &card.problem_slug, "")
+                .ok()
+                .filter(|problem| {
+                    problem.source == card.source && problem.problem_slug == card.problem_slug
+                })
+                .map(|problem| problem.url);
+        }
+    }
+
+    /// Builds an Anki `.apkg` deck holding every card with its schedule and
+    /// review history.
+    pub async fn export_anki(&self, user: &User, now: DateTime<Utc>) -> Result<Vec<u8>, AppError> {
+        let cards = self.review_service.list_cards(user.id).await?;
+        let reviews = self.review_service.history(user.id).await?;
+        let collection = anki_collection(user, cards, reviews, &self.schedule, now);
+        let package = anki::write_package("SRS Anything", &collection, now.timestamp_millis())
+            .await
+            .map_err(|err| AppError::Internal(format!("failed to build anki package: {err}")))?;
+        info!(
+            user_id = user.id,
+            cards = collection.cards.len(),
+            reviews = collection.revlog.len(),
+            bytes = package.len(),
+            "anki_package_exported"
+        );
+        Ok(package)
+    }
+
+    async fn export_section(
+        &self,
+        section: ExportSection,
+        user: &User,
+        now: DateTime<Utc>,
+    ) -> Result<String, AppError> {
+        let chunk = match section {
+            ExportSection::Header => format!(
+                "{{\"format\":{},\"version\":{ARCHIVE_VERSION},\"exported_at\":{},\"user\":{}",
+                to_json(&ARCHIVE_FORMAT)?,
+                to_json(&now)?,
+                to_json(&ExportUser::from(user.clone()))?
+            ),
+            ExportSection::Settings => {
+                let settings = self.settings_service.get(user.id).await?;
+                field("settings", &ExportSettings::from(settings))?
+            }
+            ExportSection::Goals => {
+                let goals = self.activity_service.list_goals(user.id).await?;
+                let goals = goals.into_iter().map(ExportGoal::from).collect::<Vec<_>>();
+                field("goals", &goals)?
+            }
+            ExportSection::IntegrationTokens => {
+                let tokens = self.integrations_service.list_tokens(user.id).await?;
+                field("integration_tokens", &tokens)?
+            }
+            ExportSection::Cards => {
+                let cards = self.review_service.list_cards(user.id).await?;
+                let cards = cards.into_iter().map(ExportCard::from).collect::<Vec<_>>();
+                field("cards", &cards)?
+            }
+            ExportSection::ProblemEvents => {
+                let events = self.event_service.list_for_user(user.id).await?;
+                let events = events
+                    .into_iter()
+                    .map(ExportProblemEvent::from)
+                    .collect::<Vec<_>>();
+                field("problem_events", &events)?
+            }
+            ExportSection::Reviews => {
+                let reviews = self.review_service.history(user.id).await?;
+                let reviews = reviews
+                    .into_iter()
+                    .map(ExportReview::from)
+                    .collect::<Vec<_>>();
+                field("reviews", &reviews)?
+            }
+        };
+        info!(user_id = user.id, section = ?section, bytes = chunk.len(), "archive_section_exported");
+        Ok(chunk)
+    }
+}
+
+/// Checks the archive is one we understand and that reviews only point at
+/// cards it contains, before anything is written.
+fn validate_archive(archive: ExportArchive, mode: ImportMode) -> Result<ArchiveImport, AppError> {
+    if archive.format != ARCHIVE_FORMAT {
+        return Err(AppError::InvalidInput(format!(
+            "expected a {ARCHIVE_FORMAT} archive"
+        )));
+    }
+    if archive.version == 0 || archive.version > ARCHIVE_VERSION {
+        return Err(AppError::InvalidInput(format!(
+            "unsupported archive version {}",
+            archive.version
+        )));
+    }
+    if archive.settings.digest_hour_utc > 23 {
+        return Err(AppError::InvalidInput(
+            "digest_hour_utc must be between 0 and 23".to_owned(),
+        ));
+    }
+    if let Some(timezone) = &archive.settings.timezone
+        && crate::activity::parse_timezone(timezone).is_none()
+    {
+        return Err(AppError::InvalidInput(format!(
+            "unknown timezone {timezone}"
+        )));
+    }
+
+    if let Some(goal) = archive
+        .goals
+        .iter()
+        .flatten()
+        .find(|goal| !(1..=MAX_GOAL_TARGET).contains(&goal.target))
+    {
+        return Err(AppError::InvalidInput(format!(
+            "goal target {} must be between 1 and {MAX_GOAL_TARGET}",
+            goal.target
+        )));
+    }
+
+    let mut card_ids = HashSet::new();
+    for card in &archive.cards {
+        if card.source.trim().is_empty() || card.problem_slug.trim().is_empty() {
+            return Err(AppError::InvalidInput(format!(
+                "card {} needs a source and problem_slug",
+                card.id
+            )));
+        }
+        if !card_ids.insert(card.id) {
+            return Err(AppError::InvalidInput(format!(
+                "card id {} appears more than once",
+                card.id
+            )));
+        }
+    }
+    let mut last_reviewed_at = HashMap::<i64, DateTime<Utc>>::new();
+    for review in &archive.reviews {
+        if !card_ids.contains(&review.card_id) {
+            return Err(AppError::InvalidInput(format!(
+                "review references unknown card {}",
+                review.card_id
+            )));
+        }
+        last_reviewed_at
+            .entry(review.card_id)
+            .and_modify(|latest| *latest = (*latest).max(review.reviewed_at))
+            .or_insert(review.reviewed_at);
+    }
+
+    Ok(ArchiveImport {
+        mode,
+        settings: Some(ImportedSettings {
+            email_enabled: archive.settings.email_enabled,
+            digest_hour_utc: archive.settings.digest_hour_utc,
+            timezone: archive.settings.timezone,
+        }),
+        goals: archive.goals.map(|goals| {
+            goals
+                .into_iter()
+                .map(|goal| ImportedGoal {
+                    metric: goal.metric,
+                    period: goal.period,
+                    target: goal.target,
+                })
+                .collect()
+        }),
+        cards: archive
+            .cards
+            .into_iter()
+            .map(|card| ImportedCard {
+                archive_id: card.id,
+                last_reviewed_at: last_reviewed_at.get(&card.id).copied(),
+                kind: card.kind,
+                source: card.source,
+                problem_slug: card.problem_slug,
+                title: card.title,
+                url: card.url,
+                catalog_url: None,
+                front: card.front,
+                back: card.back,
+                tags: card.tags,
+                interval_index: card.interval_index,
+                next_due_at: card.next_due_at,
+                created_at: card.created_at,
+                lapses: card.lapses,
+                leech_at: card.leech_at,
+                suspended_at: card.suspended_at,
+            })
+            .collect(),
+        events: archive
+            .problem_events
+            .into_iter()
+            .map(|event| ImportedEvent {
+                source: event.source,
+                problem_slug: event.problem_slug,
+                title: event.title,
+                url: event.url,
+                status: event.status,
+                occurred_at: event.occurred_at,
+            })
+            .collect(),
+        reviews: archive
+            .reviews
+            .into_iter()
+            .map(|review| ImportedReview {
+                archive_card_id: review.card_id,
+                grade: review.grade,
+                reviewed_at: review.reviewed_at,
+                next_due_at: review.next_due_at,
+            })
+            .collect(),
+    })
+}
+
+fn anki_error(err: AnkiError) -> AppError {
+    match err {
+        AnkiError::Io(err) => AppError::Internal(format!("failed to unpack anki package: {err}")),
+        err => AppError::InvalidInput(err.to_string()),
+    }
+}
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Maps an Anki collection onto our cards. All cards of a note share one
+/// problem card, scheduled from the note's first card.
+fn anki_import(
+    collection: AnkiCollection,
+    mode: ImportMode,
+    schedule: &SrsSchedule,
+    now: DateTime<Utc>,
+) -> ArchiveImport {
+    let notes = collection
+        .notes
+        .iter()
+        .map(|note| (note.id, note))
+        .collect::<HashMap<_, _>>();
+    let note_for_card = collection
+        .cards
+        .iter()
+        .map(|card| (card.id, card.note_id))
+        .collect::<HashMap<_, _>>();
+
+    let mut reviews = Vec::new();
+    let mut last_reviewed_at = HashMap::<i64, DateTime<Utc>>::new();
+    for entry in &collection.revlog {
+        let (Some(note_id), Some(grade)) = (
+            note_for_card.get(&entry.card_id),
+            grade_for_ease(entry.ease),
+        ) else {
+            continue;
+        };
+        let Some(reviewed_at) = DateTime::from_timestamp_millis(entry.id) else {
+            continue;
+        };
+        last_reviewed_at
+            .entry(*note_id)
+            .and_modify(|latest| *latest = (*latest).max(reviewed_at))
+            .or_insert(reviewed_at);
+        reviews.push(ImportedReview {
+            archive_card_id: *note_id,
+            grade,
+            reviewed_at,
+            next_due_at: reviewed_at + anki_interval(entry.interval),
+        });
+    }
+
+    let mut cards = Vec::new();
+    let mut seen_notes = HashSet::new();
+    let mut skipped_notes = 0;
+    for card in &collection.cards {
+        if !seen_notes.insert(card.note_id) {
+            continue;
+        }
+        let Some((source, problem_slug, title, url)) = notes
+            .get(&card.note_id)
+            .and_then(|note| problem_identity(note))
+        else {
+            skipped_notes += 1;
+            continue;
+        };
+        cards.push(ImportedCard {
+            archive_id: card.note_id,
+            kind: CardKind::Problem,
+            source,
+            problem_slug,
+            title,
+            url,
+            catalog_url: None,
+            front: None,
+            back: None,
+            tags: Vec::new(),
+            interval_index: interval_index_for_days(schedule, card.interval),
+            next_due_at: anki_due_at(card, collection.created_at).unwrap_or(now),
+            // Anki note ids are their creation time in milliseconds.
+            created_at: DateTime::from_timestamp_millis(card.note_id),
+            last_reviewed_at: last_reviewed_at.get(&card.note_id).copied(),
+            lapses: None,
+            leech_at: None,
+            suspended_at: None,
+        });
+    }
+    if skipped_notes > 0 {
+        warn!(skipped_notes, "anki_notes_without_title_skipped");
+    }
+    // Reviews of notes that were skipped have nothing to attach to.
+    let imported_notes = cards
+        .iter()
+        .map(|card| card.archive_id)
+        .collect::<HashSet<_>>();
+    reviews.retain(|review| imported_notes.contains(&review.archive_card_id));
+
+    ArchiveImport {
+        mode,
+        settings: None,
+        goals: None,
+        cards,
+        events: Vec::new(),
+        reviews,
+    }
+}
+
+/// Works out `(source, problem_slug, title, url)` for a note. Notes we
+/// exported carry these as fields; anything else is matched against a
+/// LeetCode problem link, falling back to an `anki` card named after the
+/// first field.
+fn problem_identity(note: &AnkiNote) -> Option<(String, String, String, String)> {
+    let field = |name| note.field(name).map(str::trim).unwrap_or_default();
+    if !field("Source").is_empty() && !field("Slug").is_empty() {
+        let title = strip_html(field("Title"));
+        let title = if title.is_empty() {
+            field("Slug").to_owned()
+        } else {
+            title
+        };
+        return Some((
+            field("Source").to_owned(),
+            field("Slug").to_owned(),
+            title,
+            field("URL").to_owned(),
+        ));
+    }
+
+    let title = strip_html(note.fields.first().map(|(_, value)| value.as_str())?);
+    if let Some(slug) = note
+        .fields
+        .iter()
+        .find_map(|(_, value)| leetcode_slug(value))
+    {
+        let title = if title.is_empty() {
+            slug.clone()
+        } else {
+            title
+        };
+        let url = format!("https://leetcode.com/problems/{slug}/");
+        return Some(("leetcode".to_owned(), slug, title, url));
+    }
+    let slug = slugify(&title);
+    if slug.is_empty() {
+        return None;
+    }
+    let url = note
+        .fields
+        .iter()
+        .find_map(|(_, value)| first_url(value))
+        .unwrap_or_default();
+    Some(("anki".to_owned(), slug, title, url))
+}
+
+fn leetcode_slug(value: &str) -> Option<String> {
+    let (_, rest) = value.split_once("leetcode.com/problems/")?;
+    let slug = rest
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .collect::<String>();
+    (!slug.is_empty()).then_some(slug)
+}
+
+fn first_url(value: &str) -> Option<String> {
+    let start = value.find("https://").or_else(|| value.find("http://"))?;
+    let url = value[start..]
+        .chars()
+        .take_while(|c| !c.is_whitespace() && !matches!(c, '"' | '\'' | '<' | '>'))
+        .collect::<String>();
+    Some(url)
+}
+
+/// Drops tags and decodes the handful of entities Anki's editor produces.
+fn strip_html(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut in_tag = false;
+    for c in value.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn slugify(title: &str) -> String {
+    let mut slug = String::new();
+    for c in title.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_owned()
+}
+
+fn grade_for_ease(ease: i64) -> Option<Grade> {
+    match ease {
+        1 => Some(Grade::Again),
+        2 => Some(Grade::Hard),
+        3 => Some(Grade::Good),
+        4 => Some(Grade::Easy),
+        // 0 marks a manual reschedule rather than an answer.
+        _ => None,
+    }
+}
+
+fn ease_for_grade(grade: Grade) -> i64 {
+    match grade {
+        Grade::Again => 1,
+        Grade::Hard => 2,
+        Grade::Good => 3,
+        Grade::Easy => 4,
+    }
+}
+
+/// Anki stores review intervals in days, or as negative seconds while a
+/// card is still in learning.
+fn anki_interval(interval: i64) -> Duration {
+    if interval >= 0 {
+        Duration::days(interval)
+    } else {
+        Duration::seconds(-interval)
+    }
+}
+
+fn to_anki_interval(interval: Duration) -> i64 {
+    if interval >= Duration::days(1) {
+        interval.num_days()
+    } else {
+        -interval.num_seconds().max(1)
+    }
+}
+
+/// Largest schedule step that does not exceed the Anki interval.
+fn interval_index_for_days(schedule: &SrsSchedule, interval_days: i64) -> usize {
+    let interval = Duration::days(interval_days.max(0));
+    (0..=schedule.max_index())
+        .take_while(|index| schedule.duration_for_index(*index) <= interval)
+        .last()
+        .unwrap_or(0)
+}
+
+fn anki_due_at(card: &AnkiCard, created_at: i64) -> Option<DateTime<Utc>> {
+    if card.card_type == CARD_TYPE_NEW {
+        return None;
+    }
+    let seconds = if card.queue == QUEUE_INTRADAY_LEARNING {
+        card.due
+    } else {
+        created_at.checked_add(card.due.checked_mul(SECONDS_PER_DAY)?)?
+    };
+    Utc.timestamp_opt(seconds, 0).single()
+}
+
+/// Maps our cards and review history onto a single-deck Anki collection.
+/// Cards never reviewed go out as new cards.
+fn anki_collection(
+    user: &User,
+    cards: Vec<ProblemCard>,
+    mut reviews: Vec<ReviewEvent>,
+    schedule: &SrsSchedule,
+    now: DateTime<Utc>,
+) -> AnkiCollection {
+    reviews.sort_by_key(|review| (review.reviewed_at, review.id));
+    let earliest = cards
+        .iter()
+        .map(|card| card.next_due_at)
+        .chain(reviews.iter().map(|review| review.reviewed_at))
+        .fold(user.created_at.min(now), DateTime::min);
+    let created_at = earliest
+        .duration_trunc(Duration::days(1))
+        .unwrap_or(earliest)
+        .timestamp();
+
+    // (reps, lapses) per card.
+    let mut review_counts = HashMap::<i64, (i64, i64)>::new();
+    for review in &reviews {
+        let counts = review_counts.entry(review.card_id).or_default();
+        counts.0 += 1;
+        counts.1 += i64::from(review.grade == Grade::Again);
+    }
+
+    let base_id = now.timestamp_millis();
+    let mut anki_card_ids = HashMap::new();
+    let mut collection = AnkiCollection {
+        created_at,
+        ..AnkiCollection::default()
+    };
+    for (position, card) in cards.into_iter().enumerate() {
+        let id = base_id + position as i64;
+        anki_card_ids.insert(card.id, id);
+        let (reps, lapses) = review_counts.get(&card.id).copied().unwrap_or_default();
+        let reviewed = reps > 0;
+        let interval_days = schedule
+            .duration_for_index(card.interval_index)
+            .num_days()
+            .max(1);
+        collection.cards.push(AnkiCard {
+            id,
+            note_id: id,
+            card_type: if reviewed {
+                CARD_TYPE_REVIEW
+            } else {
+                CARD_TYPE_NEW
+            },
+            queue: if reviewed {
+                CARD_TYPE_REVIEW
+            } else {
+                CARD_TYPE_NEW
+            },
+            due: if reviewed {
+                (card.next_due_at.timestamp() - created_at).div_euclid(SECONDS_PER_DAY)
+            } else {
+                position as i64 + 1
+            },
+            interval: if reviewed { interval_days } else { 0 },
+            factor: if reviewed { 2500 } else { 0 },
+            reps,
+            lapses,
+        });
+        collection.notes.push(AnkiNote {
+            id,
+            guid: format!("srs:{}:{}", card.source, card.problem_slug),
+            note_type: SRS_NOTE_TYPE.to_owned(),
+            fields: SRS_NOTE_FIELDS
+                .iter()
+                .map(|name| name.to_string())
+                .zip([card.title, card.url, card.source, card.problem_slug])
+                .collect(),
+        });
+    }
+
+    // Revlog ids are millisecond timestamps and must be unique.
+    let mut last_id = i64::MIN;
+    let mut last_interval = HashMap::new();
+    for review in reviews {
+        let Some(card_id) = anki_card_ids.get(&review.card_id).copied() else {
+            continue;
+        };
+        let id = review.reviewed_at.timestamp_millis().max(last_id + 1);
+        last_id = id;
+        let interval = to_anki_interval(review.next_due_at - review.reviewed_at);
+        let previous = last_interval.insert(card_id, interval);
+        collection.revlog.push(AnkiReview {
+            id,
+            card_id,
+            ease: ease_for_grade(review.grade),
+            interval,
+            last_interval: previous.unwrap_or_default(),
+            factor: 2500,
+            time_ms: 0,
+            // Learning for a card's first answer, review afterwards.
+            review_type: i64::from(previous.is_some()),
+        });
+    }
+    collection
+}
+
+/// Turns submissions into events and one card per problem. The more days a
+/// problem was solved on, the further along the schedule its card starts;
+/// a card then drops a step for every interval-doubling gap since the last
+/// solve, so long-untouched problems come back early.
+fn leetcode_history_import(
+    submissions: Vec<LeetCodeSubmission>,
+    schedule: &SrsSchedule,
+    now: DateTime<Utc>,
+) -> ArchiveImport {
+    struct Problem {
+        title: String,
+        latest_at: DateTime<Utc>,
+        first_at: DateTime<Utc>,
+        solve_days: HashSet<chrono::NaiveDate>,
+        last_solved_at: Option<DateTime<Utc>>,
+    }
+
+    // Problems in the order they first appear, indexed by slug.
+    let mut problems = Vec::<(String, Problem)>::new();
+    let mut positions = HashMap::<String, usize>::new();
+    let mut events = Vec::with_capacity(submissions.len());
+    for submission in submissions {
+        let url = format!("https://leetcode.com/problems/{}/", submission.problem_slug);
+        let position = *positions
+            .entry(submission.problem_slug.clone())
+            .or_insert_with(|| {
+                problems.push((
+                    submission.problem_slug.clone(),
+                    Problem {
+                        title: submission.title.clone(),
+                        latest_at: submission.submitted_at,
+                        first_at: submission.submitted_at,
+                        solve_days: HashSet::new(),
+                        last_solved_at: None,
+                    },
+                ));
+                problems.len() - 1
+            });
+        let problem = &mut problems[position].1;
+        problem.first_at = problem.first_at.min(submission.submitted_at);
+        if submission.submitted_at >= problem.latest_at {
+            problem.latest_at = submission.submitted_at;
+            problem.title = submission.title.clone();
+        }
+        if submission.accepted {
+            problem
+                .solve_days
+                .insert(submission.submitted_at.date_naive());
+            problem.last_solved_at = problem.last_solved_at.max(Some(submission.submitted_at));
+        }
+        events.push(ImportedEvent {
+            source: "leetcode".to_owned(),
+            problem_slug: submission.problem_slug,
+            title: submission.title,
+            url,
+            status: if submission.accepted {
+                ProblemStatus::Solved
+            } else {
+                ProblemStatus::Unsolved
+            },
+            occurred_at: submission.submitted_at,
+        });
+    }
+
+    let cards = problems
+        .into_iter()
+        .enumerate()
+        .map(|(archive_id, (problem_slug, problem))| {
+            let (interval_index, next_due_at) = match problem.last_solved_at {
+                Some(last_solved_at) => {
+                    let mut index = (problem.solve_days.len() - 1).min(schedule.max_index());
+                    while index > 0 && now - last_solved_at > schedule.duration_for_index(index) * 2
+                    {
+                        index -= 1;
+                    }
+                    let due = last_solved_at + schedule.duration_for_index(index);
+                    (index, due.max(now))
+                }
+                None => (0, now),
+            };
+            ImportedCard {
+                archive_id: archive_id as i64,
+                kind: CardKind::Problem,
+                url: format!("https://leetcode.com/problems/{problem_slug}/"),
+                catalog_url: None,
+                front: None,
+                back: None,
+                tags: Vec::new(),
+                source: "leetcode".to_owned(),
+                problem_slug,
+                title: problem.title,
+                interval_index,
+                next_due_at,
+                created_at: Some(problem.first_at),
+                last_reviewed_at: None,
+                lapses: None,
+                leech_at: None,
+                suspended_at: None,
+            }
+        })
+        .collect();
+
+    ArchiveImport {
+        mode: ImportMode::Merge,
+        settings: None,
+        goals: None,
+        cards,
+        events,
+        reviews: Vec::new(),
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, AppError> {
+    serde_json::to_string(value)
+        .map_err(|err| AppError::Internal(format!("failed to serialize export: {err}")))
+}
+
+fn field<T: Serialize>(name: &str, value: &T) -> Result<String, AppError> {
+    Ok(format!(",\"{name}\":{}", to_json(value)?))
+}
+
+impl From<User> for ExportUser {
+    fn from(user: User) -> Self {
+        Self {
+            email: user.email,
+            created_at: user.created_at,
+        }
+    }
+}
+
+impl From<NotificationPreference> for ExportSettings {
+    fn from(preference: NotificationPreference) -> Self {
+        Self {
+            email_enabled: preference.email_enabled,
+            digest_hour_utc: preference.digest_hour_utc,
+            timezone: Some(preference.timezone),
+        }
+    }
+}
+
+impl From<ProblemCard> for ExportCard {
+    fn from(card: ProblemCard) -> Self {
+        Self {
+            id: card.id,
+            kind: card.kind,
+            source: card.source,
+            problem_slug: card.problem_slug,
+            title: card.title,
+            url: card.url,
+            front: card.front,
+            back: card.back,
+            tags: card.tags,
+            interval_index: card.interval_index,
+            next_due_at: card.next_due_at,
+            created_at: Some(card.created_at),
+            lapses: Some(card.lapses),
+            leech_at: card.leech_at,
+            suspended_at: card.suspended_at,
+        }
+    }
+}
+
+impl From<Goal> for ExportGoal {
+    fn from(goal: Goal) -> Self {
+        Self {
+            metric: goal.metric,
+            period: goal.period,
+            target: goal.target,
+        }
+    }
+}
+
+impl From<ProblemEvent> for ExportProblemEvent {
+    fn from(event: ProblemEvent) -> Self {
+        Self {
+            source: event.source,
+            problem_slug: event.problem_slug,
+            title: event.title,
+            url: event.url,
+            status: event.status,
+            occurred_at: event.occurred_at,
+        }
+    }
+}
+
+impl From<ReviewEvent> for ExportReview {
+    fn from(review: ReviewEvent) -> Self {
+        Self {
+            card_id: review.card_id,
+            grade: review.grade,
+            reviewed_at: review.reviewed_at,
+            next_due_at: review.next_due_at,
+        }
+    }
+}
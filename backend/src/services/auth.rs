@@ -1,39 +1,89 @@
-use std::{env, sync::Arc};
+use std::{env, path::PathBuf, sync::Arc, time::Duration as StdDuration};
 
-use chrono::{Duration, Utc};
-use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{Validation, decode, decode_header, encode, jwk::JwkSet};
 use serde::{Deserialize, Serialize};
 use tracing::{info, warn};
 
-use crate::{errors::AppError, models::User, repositories::traits::AuthRepository};
+use rand::{Rng, distr::Alphanumeric};
+
+use crate::{
+    dto::auth::{
+        PasskeyAssertionCredential, PasskeyAttestationCredential, PasskeyAuthenticatorSelection,
+        PasskeyCredentialDescriptor, PasskeyCredentialParameters, PasskeyLoginOptions,
+        PasskeyRegistrationOptions, PasskeyRelyingParty, PasskeySummary, PasskeyUserEntity,
+        SessionSummary,
+    },
+    errors::AppError,
+    models::{ClientInfo, PasskeyCredential, Session, User, WebauthnCeremony, hash_token},
+    ports::{
+        notification_sender::NotificationSender,
+        oidc::{OidcConfig, OidcProvider, pkce_challenge},
+        webauthn::{SUPPORTED_ALGORITHMS, WebauthnConfig, WebauthnError},
+    },
+    repositories::traits::{AuthRepository, NewOidcAuthRequest, NewPasskey, NewWebauthnChallenge},
+    services::jwt_keys::JwtKeyring,
+};
+
+/// `last_seen_at` is refreshed at most this often to avoid a write per request.
+const SESSION_TOUCH_INTERVAL_SECS: i64 = 300;
+/// How long a user has to finish logging in at the identity provider.
+const OIDC_REQUEST_TTL_MINUTES: i64 = 10;
+/// How long a passkey prompt stays valid; also sent to the browser as `timeout`.
+const WEBAUTHN_CHALLENGE_TTL_SECS: i64 = 300;
+const MAX_PASSKEY_LABEL_LEN: usize = 64;
+/// How long an account deletion confirmation token stays valid.
+const ACCOUNT_DELETION_TTL_MINUTES: i64 = 30;
 
 #[derive(Debug, Clone)]
 pub struct AuthConfig {
-    pub jwt_secret: String,
+    /// HS256 secret. With `jwt_keys_dir` set it only verifies tokens issued
+    /// before the switch to asymmetric keys.
+    pub jwt_secret: Option<String>,
+    /// Directory of Ed25519/RSA PEM keys that sign session tokens.
+    pub jwt_keys_dir: Option<PathBuf>,
+    /// How often the key directory is re-read to pick up rotated keys.
+    pub jwt_keys_reload_secs: u64,
     pub jwt_issuer: String,
     pub jwt_audience: String,
     pub jwt_expiration_secs: i64,
+    pub webauthn: WebauthnConfig,
+    /// Whether responses carry tokens that are otherwise only sent to the
+    /// user. Off when SRS_PROFILE=prod or APP_ENV=production.
+    pub dev_tokens: bool,
 }
 
 impl AuthConfig {
     pub fn from_env() -> Self {
         let srs_profile = env::var("SRS_PROFILE").unwrap_or_default();
         let app_env = env::var("APP_ENV").unwrap_or_default();
-        let require_jwt_secret = srs_profile == "prod" || app_env == "production";
+        let require_jwt_key = srs_profile == "prod" || app_env == "production";
 
+        let jwt_keys_dir = env::var("JWT_KEYS_DIR")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .map(PathBuf::from);
         let jwt_secret = match env::var("JWT_SECRET") {
-            Ok(value) if !value.trim().is_empty() => value,
-            _ if require_jwt_secret => {
-                panic!("JWT_SECRET must be set when SRS_PROFILE=prod or APP_ENV=production")
-            }
+            Ok(value) if !value.trim().is_empty() => Some(value),
+            _ if jwt_keys_dir.is_some() => None,
+            _ if require_jwt_key => panic!(
+                "JWT_KEYS_DIR or JWT_SECRET must be set when SRS_PROFILE=prod or APP_ENV=production"
+            ),
             _ => {
                 warn!("JWT_SECRET missing, using insecure development fallback");
-                "dev-insecure-secret-change-me".to_owned()
+                Some("dev-insecure-secret-change-me".to_owned())
             }
         };
 
         Self {
             jwt_secret,
+            jwt_keys_dir,
+            jwt_keys_reload_secs: env::var("JWT_KEYS_RELOAD_SECS")
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .filter(|value| *value > 0)
+                .unwrap_or(300),
             jwt_issuer: env::var("JWT_ISSUER").unwrap_or_else(|_| "srs-anything".to_owned()),
             jwt_audience: env::var("JWT_AUDIENCE")
                 .unwrap_or_else(|_| "srs-anything-web".to_owned()),
@@ -42,6 +92,8 @@ impl AuthConfig {
                 .and_then(|value| value.parse::<i64>().ok())
                 .filter(|value| *value > 0)
                 .unwrap_or(60 * 60 * 24 * 30),
+            webauthn: WebauthnConfig::from_env(),
+            dev_tokens: !require_jwt_key,
         }
     }
 }
@@ -53,27 +105,181 @@ struct Claims {
     aud: String,
     exp: usize,
     iat: usize,
+    jti: String,
 }
 
 #[derive(Clone)]
 pub struct AuthService {
     repo: Arc<dyn AuthRepository>,
-    encoding_key: EncodingKey,
-    decoding_key: DecodingKey,
+    keyring: JwtKeyring,
     config: AuthConfig,
+    oidc: Option<OidcProvider>,
+    sender: Arc<dyn NotificationSender>,
+}
+
+/// Where to send the browser to start an OIDC login, plus the `state` it must
+/// bring back (also pinned to the browser in a cookie).
+pub struct OidcLoginStart {
+    pub authorization_url: String,
+    pub state: String,
+}
+
+/// A pending account deletion whose confirmation token has been sent.
+pub struct AccountDeletionRequested {
+    /// The token itself, only outside production.
+    pub dev_token: Option<String>,
+    pub expires_at: DateTime<Utc>,
 }
 
 impl AuthService {
-    pub fn new(repo: Arc<dyn AuthRepository>, config: AuthConfig) -> Self {
-        let secret_bytes = config.jwt_secret.as_bytes();
+    pub fn new(
+        repo: Arc<dyn AuthRepository>,
+        config: AuthConfig,
+        sender: Arc<dyn NotificationSender>,
+    ) -> Self {
+        let keyring = match (&config.jwt_keys_dir, &config.jwt_secret) {
+            (Some(dir), secret) => JwtKeyring::from_dir(dir, secret.as_deref())
+                .unwrap_or_else(|err| panic!("failed to load JWT keys: {err}")),
+            (None, Some(secret)) => JwtKeyring::hmac(secret),
+            (None, None) => panic!("either JWT_KEYS_DIR or JWT_SECRET is required"),
+        };
         Self {
             repo,
-            encoding_key: EncodingKey::from_secret(secret_bytes),
-            decoding_key: DecodingKey::from_secret(secret_bytes),
+            keyring,
             config,
+            oidc: None,
+            sender,
         }
     }
 
+    /// Public session-token keys for `/.well-known/jwks.json`.
+    pub fn jwks(&self) -> JwkSet {
+        self.keyring.jwks()
+    }
+
+    /// `None` when tokens are signed with the HS256 secret and there is
+    /// nothing to reload.
+    pub fn jwt_keys_reload_interval(&self) -> Option<StdDuration> {
+        self.keyring
+            .uses_key_dir()
+            .then(|| StdDuration::from_secs(self.config.jwt_keys_reload_secs))
+    }
+
+    pub fn reload_jwt_keys(&self) -> Result<(), AppError> {
+        self.keyring
+            .reload()
+            .map_err(|err| AppError::Internal(err.to_string()))
+    }
+
+    /// Enables OpenID Connect login alongside magic links.
+    pub fn with_oidc(mut self, config: OidcConfig) -> Self {
+        info!(issuer = %config.issuer_url, "auth_oidc_enabled");
+        self.oidc = Some(OidcProvider::new(config));
+        self
+    }
+
+    pub fn oidc_post_login_redirect(&self) -> Option<&str> {
+        self.oidc.as_ref().map(OidcProvider::post_login_redirect)
+    }
+
+    pub async fn begin_oidc_login(&self) -> Result<OidcLoginStart, AppError> {
+        let oidc = self.oidc.as_ref().ok_or(AppError::OidcNotConfigured)?;
+        let state = random_token(32);
+        let nonce = random_token(32);
+        let pkce_verifier = random_token(64);
+        let authorization_url = oidc
+            .authorization_url(&state, &nonce, &pkce_challenge(&pkce_verifier))
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        self.repo
+            .create_oidc_auth_request(NewOidcAuthRequest {
+                state_hash: hash_token(&state),
+                nonce,
+                pkce_verifier,
+                expires_at: Utc::now() + Duration::minutes(OIDC_REQUEST_TTL_MINUTES),
+            })
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        info!("auth_oidc_login_started");
+        Ok(OidcLoginStart {
+            authorization_url,
+            state,
+        })
+    }
+
+    /// Finishes the authorization-code flow: the returned `state` must match the
+    /// browser's state cookie and a pending request, the ID token must carry
+    /// that request's nonce. Known identities log straight in; new ones are
+    /// linked to the user with the same verified email.
+    pub async fn complete_oidc_login(
+        &self,
+        code: &str,
+        state: &str,
+        state_cookie: Option<&str>,
+        client: ClientInfo,
+    ) -> Result<(User, String), AppError> {
+        let oidc = self.oidc.as_ref().ok_or(AppError::OidcNotConfigured)?;
+        if state_cookie != Some(state) {
+            warn!("auth_oidc_state_mismatch");
+            return Err(AppError::OidcLoginFailed("state mismatch".to_owned()));
+        }
+        let request = self
+            .repo
+            .consume_oidc_auth_request(&hash_token(state))
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?
+            .ok_or_else(|| {
+                warn!("auth_oidc_request_unknown_or_expired");
+                AppError::OidcLoginFailed("login request expired".to_owned())
+            })?;
+        let claims = async {
+            let id_token = oidc.exchange_code(code, &request.pkce_verifier).await?;
+            oidc.verify_id_token(&id_token, &request.nonce).await
+        }
+        .await
+        .map_err(|err| {
+            warn!(error = %err, "auth_oidc_token_rejected");
+            AppError::OidcLoginFailed(err.to_string())
+        })?;
+
+        let existing = self
+            .repo
+            .user_from_identity(&claims.iss, &claims.sub)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        let user = match existing {
+            Some(user) => user,
+            None => {
+                let email = claims
+                    .email
+                    .as_deref()
+                    .filter(|email| email.contains('@') && claims.email_verified())
+                    .ok_or_else(|| {
+                        warn!("auth_oidc_email_unverified");
+                        AppError::OidcLoginFailed(
+                            "provider did not return a verified email".to_owned(),
+                        )
+                    })?;
+                self.repo
+                    .get_or_create_user(&email.to_lowercase())
+                    .await
+                    .map_err(|err| AppError::Internal(err.to_string()))?
+            }
+        };
+        let identity = self
+            .repo
+            .link_identity(user.id, &claims.iss, &claims.sub, claims.email.as_deref())
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        let jwt = self.start_session(&user, client).await?;
+        info!(
+            user_id = user.id,
+            identity_id = identity.id,
+            "auth_oidc_login_completed"
+        );
+        Ok((user, jwt))
+    }
+
     pub async fn request_magic_link(&self, email: &str) -> Result<String, AppError> {
         if !email.contains('@') {
             warn!("auth_invalid_email");
@@ -93,7 +299,11 @@ impl AuthService {
         Ok(token)
     }
 
-    pub async fn verify_magic_link(&self, token: &str) -> Result<(User, String), AppError> {
+    pub async fn verify_magic_link(
+        &self,
+        token: &str,
+        client: ClientInfo,
+    ) -> Result<(User, String), AppError> {
         let user = self
             .repo
             .verify_magic_link(token)
@@ -103,52 +313,497 @@ impl AuthService {
                 warn!("auth_magic_link_invalid_or_expired");
                 AppError::InvalidOrExpiredMagicLink
             })?;
-        let jwt = self.issue_jwt(&user.email)?;
+        let jwt = self.start_session(&user, client).await?;
         info!(user_id = user.id, "auth_magic_link_verified");
         Ok((user, jwt))
     }
 
-    pub async fn user_from_jwt_cookie(&self, token: Option<&str>) -> Result<User, AppError> {
+    /// Resolves the cookie JWT to its user and backing session row. Tokens
+    /// whose session was revoked or has expired are rejected.
+    pub async fn user_from_jwt_cookie(
+        &self,
+        token: Option<&str>,
+    ) -> Result<(User, Session), AppError> {
         let token = token.ok_or(AppError::MissingAuthCookie)?;
         let claims = self.decode_jwt(token)?;
-        let user = self
+        let (user, session) = self
             .repo
-            .get_user_by_email(&claims.sub)
+            .user_from_session(&hash_token(&claims.jti))
             .await
             .map_err(|err| AppError::Internal(err.to_string()))?
+            .filter(|(user, _)| user.email == claims.sub)
             .ok_or_else(|| {
                 warn!("auth_invalid_session");
                 AppError::InvalidSession
             })?;
-        info!(user_id = user.id, "auth_jwt_resolved");
-        Ok(user)
+        if Utc::now() - session.last_seen_at > Duration::seconds(SESSION_TOUCH_INTERVAL_SECS) {
+            self.repo
+                .touch_session(session.id)
+                .await
+                .map_err(|err| AppError::Internal(err.to_string()))?;
+        }
+        info!(
+            user_id = user.id,
+            session_id = session.id,
+            "auth_jwt_resolved"
+        );
+        Ok((user, session))
+    }
+
+    pub async fn list_sessions(
+        &self,
+        user_id: i64,
+        current_session_id: i64,
+    ) -> Result<Vec<SessionSummary>, AppError> {
+        let sessions = self
+            .repo
+            .list_active_sessions(user_id)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        info!(
+            user_id,
+            session_count = sessions.len(),
+            "auth_sessions_listed"
+        );
+        Ok(sessions
+            .into_iter()
+            .map(|session| SessionSummary {
+                current: session.id == current_session_id,
+                id: session.id,
+                created_at: session.created_at,
+                last_seen_at: session.last_seen_at,
+                expires_at: session.expires_at,
+                user_agent: session.user_agent,
+                ip: session.ip,
+            })
+            .collect())
+    }
+
+    pub async fn revoke_session(&self, user_id: i64, session_id: i64) -> Result<(), AppError> {
+        let revoked = self
+            .repo
+            .revoke_session(user_id, session_id)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        if !revoked {
+            warn!(user_id, session_id, "auth_session_not_found");
+            return Err(AppError::SessionNotFound);
+        }
+        info!(user_id, session_id, "auth_session_revoked");
+        Ok(())
+    }
+
+    pub async fn revoke_other_sessions(
+        &self,
+        user_id: i64,
+        current_session_id: i64,
+    ) -> Result<u64, AppError> {
+        let revoked_count = self
+            .repo
+            .revoke_other_sessions(user_id, current_session_id)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        info!(user_id, revoked_count, "auth_other_sessions_revoked");
+        Ok(revoked_count)
+    }
+
+    pub async fn begin_passkey_registration(
+        &self,
+        user: &User,
+    ) -> Result<PasskeyRegistrationOptions, AppError> {
+        let existing = self
+            .repo
+            .list_passkeys(user.id)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        let challenge = self
+            .issue_webauthn_challenge(WebauthnCeremony::Registration, Some(user.id))
+            .await?;
+        let rp = &self.config.webauthn;
+        info!(user_id = user.id, "auth_passkey_registration_started");
+        Ok(PasskeyRegistrationOptions {
+            challenge,
+            rp: PasskeyRelyingParty {
+                id: rp.rp_id.clone(),
+                name: rp.rp_name.clone(),
+            },
+            user: PasskeyUserEntity {
+                id: user_handle(user.id),
+                name: user.email.clone(),
+                display_name: user.email.clone(),
+            },
+            pub_key_cred_params: SUPPORTED_ALGORITHMS
+                .into_iter()
+                .map(|alg| PasskeyCredentialParameters {
+                    kind: "public-key",
+                    alg,
+                })
+                .collect(),
+            timeout: WEBAUTHN_CHALLENGE_TTL_SECS as u64 * 1000,
+            attestation: "none",
+            exclude_credentials: existing
+                .into_iter()
+                .map(|passkey| PasskeyCredentialDescriptor {
+                    kind: "public-key",
+                    id: passkey.credential_id,
+                })
+                .collect(),
+            // Discoverable credentials let the login prompt skip the email step.
+            authenticator_selection: PasskeyAuthenticatorSelection {
+                resident_key: "required",
+                require_resident_key: true,
+                user_verification: "preferred",
+            },
+        })
+    }
+
+    pub async fn finish_passkey_registration(
+        &self,
+        user: &User,
+        label: Option<String>,
+        credential: PasskeyAttestationCredential,
+    ) -> Result<PasskeySummary, AppError> {
+        let label = label
+            .map(|label| label.trim().to_owned())
+            .filter(|label| !label.is_empty())
+            .unwrap_or_else(|| "Passkey".to_owned());
+        if label.chars().count() > MAX_PASSKEY_LABEL_LEN {
+            return Err(AppError::InvalidInput(format!(
+                "label must be at most {MAX_PASSKEY_LABEL_LEN} characters"
+            )));
+        }
+        let client_data_json =
+            decode_base64url("clientDataJSON", &credential.response.client_data_json)?;
+        let attestation_object =
+            decode_base64url("attestationObject", &credential.response.attestation_object)?;
+        let webauthn = &self.config.webauthn;
+        let client_data = webauthn
+            .parse_client_data(&client_data_json, WebauthnCeremony::Registration)
+            .map_err(passkey_rejected)?;
+        let challenge = self
+            .repo
+            .consume_webauthn_challenge(
+                &hash_token(&client_data.challenge),
+                WebauthnCeremony::Registration,
+            )
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        if challenge.and_then(|challenge| challenge.user_id) != Some(user.id) {
+            warn!(
+                user_id = user.id,
+                "auth_passkey_challenge_unknown_or_expired"
+            );
+            return Err(AppError::PasskeyRejected(
+                "challenge is unknown or expired".to_owned(),
+            ));
+        }
+        let registered = webauthn
+            .verify_registration(&attestation_object)
+            .map_err(passkey_rejected)?;
+        let credential_id = URL_SAFE_NO_PAD.encode(&registered.credential_id);
+        if credential.id != credential_id {
+            return Err(AppError::PasskeyRejected(
+                "credential id does not match authenticator data".to_owned(),
+            ));
+        }
+        let already_registered = self
+            .repo
+            .passkey_by_credential_id(&credential_id)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?
+            .is_some();
+        if already_registered {
+            return Err(AppError::InvalidInput(
+                "passkey is already registered".to_owned(),
+            ));
+        }
+        let passkey = self
+            .repo
+            .create_passkey(
+                user.id,
+                NewPasskey {
+                    credential_id,
+                    public_key: registered.public_key,
+                    algorithm: registered.algorithm,
+                    sign_count: i64::from(registered.sign_count),
+                    label,
+                },
+            )
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        info!(
+            user_id = user.id,
+            passkey_id = passkey.id,
+            algorithm = passkey.algorithm,
+            "auth_passkey_registered"
+        );
+        Ok(passkey.into())
+    }
+
+    pub async fn begin_passkey_login(&self) -> Result<PasskeyLoginOptions, AppError> {
+        let challenge = self
+            .issue_webauthn_challenge(WebauthnCeremony::Authentication, None)
+            .await?;
+        info!("auth_passkey_login_started");
+        Ok(PasskeyLoginOptions {
+            challenge,
+            rp_id: self.config.webauthn.rp_id.clone(),
+            timeout: WEBAUTHN_CHALLENGE_TTL_SECS as u64 * 1000,
+            user_verification: "preferred",
+        })
+    }
+
+    /// Verifies a passkey assertion and starts the same cookie session a
+    /// magic link would.
+    pub async fn finish_passkey_login(
+        &self,
+        credential: PasskeyAssertionCredential,
+        client: ClientInfo,
+    ) -> Result<(User, String), AppError> {
+        let response = &credential.response;
+        let client_data_json = decode_base64url("clientDataJSON", &response.client_data_json)?;
+        let authenticator_data =
+            decode_base64url("authenticatorData", &response.authenticator_data)?;
+        let signature = decode_base64url("signature", &response.signature)?;
+        let webauthn = &self.config.webauthn;
+        let client_data = webauthn
+            .parse_client_data(&client_data_json, WebauthnCeremony::Authentication)
+            .map_err(passkey_rejected)?;
+        self.repo
+            .consume_webauthn_challenge(
+                &hash_token(&client_data.challenge),
+                WebauthnCeremony::Authentication,
+            )
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?
+            .ok_or_else(|| {
+                warn!("auth_passkey_challenge_unknown_or_expired");
+                AppError::PasskeyRejected("challenge is unknown or expired".to_owned())
+            })?;
+        let (user, passkey) = self
+            .repo
+            .passkey_by_credential_id(&credential.id)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?
+            .ok_or_else(|| {
+                warn!("auth_passkey_unknown_credential");
+                AppError::PasskeyRejected("unknown credential".to_owned())
+            })?;
+        if response
+            .user_handle
+            .as_deref()
+            .is_some_and(|handle| !handle.is_empty() && handle != user_handle(user.id))
+        {
+            warn!(user_id = user.id, "auth_passkey_user_handle_mismatch");
+            return Err(AppError::PasskeyRejected(
+                "user handle does not match credential".to_owned(),
+            ));
+        }
+        let sign_count = webauthn
+            .verify_assertion(
+                &client_data_json,
+                &authenticator_data,
+                &signature,
+                &passkey.public_key,
+                u32::try_from(passkey.sign_count).unwrap_or(u32::MAX),
+            )
+            .map_err(|err| {
+                warn!(user_id = user.id, passkey_id = passkey.id, error = %err, "auth_passkey_assertion_rejected");
+                passkey_rejected(err)
+            })?;
+        self.repo
+            .record_passkey_use(passkey.id, i64::from(sign_count))
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        let jwt = self.start_session(&user, client).await?;
+        info!(
+            user_id = user.id,
+            passkey_id = passkey.id,
+            "auth_passkey_login_completed"
+        );
+        Ok((user, jwt))
+    }
+
+    pub async fn list_passkeys(&self, user_id: i64) -> Result<Vec<PasskeySummary>, AppError> {
+        let passkeys = self
+            .repo
+            .list_passkeys(user_id)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        info!(
+            user_id,
+            passkey_count = passkeys.len(),
+            "auth_passkeys_listed"
+        );
+        Ok(passkeys.into_iter().map(Into::into).collect())
+    }
+
+    pub async fn delete_passkey(&self, user_id: i64, passkey_id: i64) -> Result<(), AppError> {
+        let deleted = self
+            .repo
+            .delete_passkey(user_id, passkey_id)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        if !deleted {
+            warn!(user_id, passkey_id, "auth_passkey_not_found");
+            return Err(AppError::PasskeyNotFound);
+        }
+        info!(user_id, passkey_id, "auth_passkey_deleted");
+        Ok(())
+    }
+
+    /// Sends the user the token that `delete_account` must be called with,
+    /// the same way a magic link proves control of the mailbox.
+    pub async fn request_account_deletion(
+        &self,
+        user_id: i64,
+    ) -> Result<AccountDeletionRequested, AppError> {
+        let token = random_token(48);
+        let expires_at = Utc::now() + Duration::minutes(ACCOUNT_DELETION_TTL_MINUTES);
+        self.repo
+            .create_account_deletion_request(user_id, &hash_token(&token), expires_at)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        let body = format!(
+            "Someone asked to delete your SRS Anything account and all of its data. \
+             To confirm, use this token within {ACCOUNT_DELETION_TTL_MINUTES} minutes:\n\n\
+             {token}\n\nIf this wasn't you, ignore this message and your account stays as it is."
+        );
+        self.sender
+            .send_account_deletion(user_id, "Confirm deleting your account", &body)
+            .await
+            .map_err(|err| {
+                warn!(user_id, error = %err, "auth_account_deletion_send_failed");
+                AppError::Internal(err.to_string())
+            })?;
+        info!(user_id, "auth_account_deletion_requested");
+        Ok(AccountDeletionRequested {
+            dev_token: self.config.dev_tokens.then_some(token),
+            expires_at,
+        })
+    }
+
+    pub async fn delete_account(&self, user_id: i64, token: &str) -> Result<(), AppError> {
+        let deleted = self
+            .repo
+            .delete_account(user_id, &hash_token(token.trim()))
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        if !deleted {
+            warn!(user_id, "auth_account_deletion_unconfirmed");
+            return Err(AppError::InvalidOrExpiredDeletionToken);
+        }
+        info!(user_id, "auth_account_deleted");
+        Ok(())
     }
 
     pub fn session_max_age_secs(&self) -> i64 {
         self.config.jwt_expiration_secs
     }
 
-    fn issue_jwt(&self, email: &str) -> Result<String, AppError> {
-        let iat = Utc::now().timestamp();
-        let exp = (Utc::now() + Duration::seconds(self.config.jwt_expiration_secs)).timestamp();
+    async fn start_session(&self, user: &User, client: ClientInfo) -> Result<String, AppError> {
+        let jti = random_token(32);
+        let expires_at = Utc::now() + Duration::seconds(self.config.jwt_expiration_secs);
+        let session = self
+            .repo
+            .create_session(user.id, &hash_token(&jti), expires_at, client)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        info!(
+            user_id = user.id,
+            session_id = session.id,
+            "auth_session_created"
+        );
+        self.issue_jwt(&user.email, jti, expires_at)
+    }
+
+    async fn issue_webauthn_challenge(
+        &self,
+        ceremony: WebauthnCeremony,
+        user_id: Option<i64>,
+    ) -> Result<String, AppError> {
+        let challenge = URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>());
+        self.repo
+            .create_webauthn_challenge(NewWebauthnChallenge {
+                challenge_hash: hash_token(&challenge),
+                ceremony,
+                user_id,
+                expires_at: Utc::now() + Duration::seconds(WEBAUTHN_CHALLENGE_TTL_SECS),
+            })
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        Ok(challenge)
+    }
+
+    fn issue_jwt(
+        &self,
+        email: &str,
+        jti: String,
+        expires_at: DateTime<Utc>,
+    ) -> Result<String, AppError> {
         let claims = Claims {
             sub: email.to_owned(),
             iss: self.config.jwt_issuer.clone(),
             aud: self.config.jwt_audience.clone(),
-            iat: iat as usize,
-            exp: exp as usize,
+            iat: Utc::now().timestamp() as usize,
+            exp: expires_at.timestamp() as usize,
+            jti,
         };
-        encode(&Header::new(Algorithm::HS256), &claims, &self.encoding_key)
+        let (header, encoding_key) = self
+            .keyring
+            .signing_key(Utc::now())
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        encode(&header, &claims, &encoding_key)
             .map_err(|err| AppError::Internal(format!("failed to issue jwt: {err}")))
     }
 
     fn decode_jwt(&self, token: &str) -> Result<Claims, AppError> {
-        let mut validation = Validation::new(Algorithm::HS256);
+        let header = decode_header(token).map_err(|_| AppError::InvalidSession)?;
+        let (algorithm, decoding_key) =
+            self.keyring.verification_key(&header).ok_or_else(|| {
+                warn!(kid = ?header.kid, "auth_jwt_unknown_key");
+                AppError::InvalidSession
+            })?;
+        let mut validation = Validation::new(algorithm);
         validation.set_issuer(std::slice::from_ref(&self.config.jwt_issuer));
         validation.set_audience(std::slice::from_ref(&self.config.jwt_audience));
         validation.set_required_spec_claims(&["exp", "iat", "iss", "aud", "sub"]);
-        decode::<Claims>(token, &self.decoding_key, &validation)
+        decode::<Claims>(token, &decoding_key, &validation)
             .map(|token_data| token_data.claims)
             .map_err(|_| AppError::InvalidSession)
     }
 }
+
+fn random_token(len: usize) -> String {
+    rand::rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+/// Opaque WebAuthn user handle; avoids putting the email on authenticators.
+fn user_handle(user_id: i64) -> String {
+    URL_SAFE_NO_PAD.encode(user_id.to_be_bytes())
+}
+
+fn decode_base64url(field: &str, value: &str) -> Result<Vec<u8>, AppError> {
+    URL_SAFE_NO_PAD
+        .decode(value.trim_end_matches('='))
+        .map_err(|_| AppError::InvalidInput(format!("{field} must be base64url")))
+}
+
+fn passkey_rejected(err: WebauthnError) -> AppError {
+    AppError::PasskeyRejected(err.to_string())
+}
+
+impl From<PasskeyCredential> for PasskeySummary {
+    fn from(passkey: PasskeyCredential) -> Self {
+        Self {
+            id: passkey.id,
+            label: passkey.label,
+            created_at: passkey.created_at,
+            last_used_at: passkey.last_used_at,
+        }
+    }
+}
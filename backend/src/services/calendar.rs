@@ -0,0 +1,303 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use chrono_tz::Tz;
+use rand::{Rng, distr::Alphanumeric};
+use tracing::{info, warn};
+
+use crate::{
+    activity,
+    dto::calendar::{CalendarFeedSummary, CreateCalendarFeedResponse},
+    errors::AppError,
+    models::{CalendarFeedToken, ProblemCard, User, hash_token},
+    repositories::traits::CalendarFeedRepository,
+};
+
+use super::{activity::ActivityService, review::ReviewService};
+
+pub const CALENDAR_FEED_PATH: &str = "/calendar/reviews.ics";
+
+#[derive(Clone)]
+pub struct CalendarService {
+    review_service: ReviewService,
+    activity_service: ActivityService,
+    repo: Arc<dyn CalendarFeedRepository>,
+}
+
+impl CalendarService {
+    pub fn new(
+        review_service: ReviewService,
+        activity_service: ActivityService,
+        repo: Arc<dyn CalendarFeedRepository>,
+    ) -> Self {
+        Self {
+            review_service,
+            activity_service,
+            repo,
+        }
+    }
+
+    pub async fn list_feeds(&self, user_id: i64) -> Result<Vec<CalendarFeedSummary>, AppError> {
+        let tokens = self
+            .repo
+            .list_calendar_feed_tokens(user_id)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        info!(user_id, feed_count = tokens.len(), "calendar_feeds_listed");
+        Ok(tokens.into_iter().map(CalendarFeedSummary::from).collect())
+    }
+
+    pub async fn create_feed(
+        &self,
+        user_id: i64,
+        label: &str,
+    ) -> Result<CreateCalendarFeedResponse, AppError> {
+        if label.trim().is_empty() {
+            return Err(AppError::InvalidInput(
+                "calendar feed label cannot be empty".to_owned(),
+            ));
+        }
+        let raw_secret: String = rand::rng()
+            .sample_iter(&Alphanumeric)
+            .take(48)
+            .map(char::from)
+            .collect();
+        let token = format!("srs_cal_{raw_secret}");
+        let created = self
+            .repo
+            .create_calendar_feed_token(user_id, &hash_token(&token), label.trim())
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        info!(user_id, feed_id = created.id, "calendar_feed_created");
+        Ok(CreateCalendarFeedResponse {
+            feed_path: format!("{CALENDAR_FEED_PATH}?token={token}"),
+            token,
+            feed_summary: created.into(),
+        })
+    }
+
+    pub async fn revoke_feed(&self, user_id: i64, feed_id: i64) -> Result<(), AppError> {
+        let revoked = self
+            .repo
+            .revoke_calendar_feed_token(user_id, feed_id)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        if !revoked {
+            warn!(user_id, feed_id, "calendar_feed_not_found");
+            return Err(AppError::CalendarFeedNotFound);
+        }
+        info!(user_id, feed_id, "calendar_feed_revoked");
+        Ok(())
+    }
+
+    pub async fn user_from_feed_token(&self, token: Option<&str>) -> Result<User, AppError> {
+        let raw = token.ok_or(AppError::MissingApiToken)?;
+        let hash = hash_token(raw.trim());
+        let user = self
+            .repo
+            .user_from_calendar_feed_token(&hash)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?
+            .ok_or(AppError::InvalidApiToken)?;
+        self.repo
+            .touch_calendar_feed_token_usage(&hash)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        Ok(user)
+    }
+
+    pub async fn render_feed(&self, user_id: i64, now: DateTime<Utc>) -> Result<String, AppError> {
+        let cards = self.review_service.upcoming_cards(user_id).await?;
+        let timezone = self.activity_service.timezone(user_id).await?;
+        let calendar = render_review_calendar(user_id, &cards, now, timezone);
+        info!(user_id, card_count = cards.len(), "calendar_feed_rendered");
+        Ok(calendar)
+    }
+}
+
+impl From<CalendarFeedToken> for CalendarFeedSummary {
+    fn from(token: CalendarFeedToken) -> Self {
+        Self {
+            id: token.id,
+            label: token.label,
+            created_at: token.created_at,
+            revoked_at: token.revoked_at,
+            last_used_at: token.last_used_at,
+        }
+    }
+}
+
+/// Renders one all-day event per day in the user's timezone that has reviews
+/// due. Overdue cards are folded into today so the calendar never shows
+/// reviews in the past.
+pub fn render_review_calendar(
+    user_id: i64,
+    cards: &[ProblemCard],
+    now: DateTime<Utc>,
+    timezone: Tz,
+) -> String {
+    let today = activity::local_date(now, timezone);
+    let mut days: BTreeMap<NaiveDate, Vec<&ProblemCard>> = BTreeMap::new();
+    for card in cards {
+        let day = activity::local_date(card.next_due_at, timezone).max(today);
+        days.entry(day).or_default().push(card);
+    }
+
+    let stamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_owned(),
+        "VERSION:2.0".to_owned(),
+        "PRODID:-//srs-anything//Review calendar//EN".to_owned(),
+        "CALSCALE:GREGORIAN".to_owned(),
+        "METHOD:PUBLISH".to_owned(),
+        "X-WR-CALNAME:SRS reviews".to_owned(),
+    ];
+    for (day, day_cards) in days {
+        let summary = match day_cards.len() {
+            1 => "SRS: 1 review due".to_owned(),
+            count => format!("SRS: {count} reviews due"),
+        };
+        let description = day_cards
+            .iter()
+            .map(|card| match card.url.as_str() {
+                "" => card.title.clone(),
+                url => format!("{} - {url}", card.title),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let next_day = day.checked_add_days(Days::new(1)).unwrap_or(day);
+        lines.extend([
+            "BEGIN:VEVENT".to_owned(),
+            format!("UID:{user_id}-{}@srs-anything", day.format("%Y%m%d")),
+            format!("DTSTAMP:{stamp}"),
+            format!("DTSTART;VALUE=DATE:{}", day.format("%Y%m%d")),
+            format!("DTEND;VALUE=DATE:{}", next_day.format("%Y%m%d")),
+            format!("SUMMARY:{}", escape_text(&summary)),
+            format!("DESCRIPTION:{}", escape_text(&description)),
+            "TRANSP:TRANSPARENT".to_owned(),
+            "END:VEVENT".to_owned(),
+        ]);
+    }
+    lines.push("END:VCALENDAR".to_owned());
+
+    lines
+        .iter()
+        .map(|line| fold_line(line))
+        .collect::<Vec<_>>()
+        .join("")
+}
+
+/// Escapes TEXT values per RFC 5545. CRLF and bare CR become `\n` too, since
+/// a raw CR would end the content line early.
+fn escape_text(value: &str) -> String {
+    value
+        .replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Splits content lines at 75 octets as required by RFC 5545, never
+/// breaking inside a UTF-8 sequence.
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 8);
+    let mut octets = 0;
+    for ch in line.chars() {
+        if octets + ch.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+        folded.push(ch);
+        octets += ch.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use crate::models::{CardKind, ProblemCard};
+
+    use super::{escape_text, fold_line, render_review_calendar};
+
+    fn card(id: i64, slug: &str, next_due_at: chrono::DateTime<Utc>) -> ProblemCard {
+        ProblemCard {
+            id,
+            user_id: 7,
+            kind: CardKind::Problem,
+            source: "leetcode".to_owned(),
+            problem_slug: slug.to_owned(),
+            title: format!("Problem {slug}"),
+            url: format!("https://leetcode.com/problems/{slug}"),
+            front: None,
+            back: None,
+            tags: Vec::new(),
+            problem_id: None,
+            difficulty: None,
+            interval_index: 0,
+            next_due_at,
+            created_at: next_due_at - Duration::days(1),
+            lapses: 0,
+            leech_at: None,
+            suspended_at: None,
+        }
+    }
+
+    #[test]
+    fn groups_cards_into_one_event_per_day() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 10, 9, 0, 0).unwrap();
+        let cards = vec![
+            card(1, "two-sum", now - Duration::days(2)),
+            card(2, "three-sum", now + Duration::hours(3)),
+            card(3, "lru-cache", now + Duration::days(1)),
+        ];
+
+        let calendar = render_review_calendar(7, &cards, now, chrono_tz::UTC);
+
+        assert_eq!(calendar.matches("BEGIN:VEVENT").count(), 2);
+        assert!(calendar.contains("DTSTART;VALUE=DATE:20260310\r\n"));
+        assert!(calendar.contains("SUMMARY:SRS: 2 reviews due\r\n"));
+        assert!(calendar.contains("DTSTART;VALUE=DATE:20260311\r\n"));
+        assert!(calendar.contains("SUMMARY:SRS: 1 review due\r\n"));
+        assert!(calendar.contains("UID:7-20260311@srs-anything"));
+        assert!(calendar.ends_with("END:VCALENDAR\r\n"));
+    }
+
+    #[test]
+    fn groups_cards_by_the_users_local_day() {
+        // 20:00 UTC on March 10 is already March 11 in Tokyo.
+        let now = Utc.with_ymd_and_hms(2026, 3, 10, 20, 0, 0).unwrap();
+        let cards = vec![
+            card(1, "two-sum", now + Duration::hours(1)),
+            card(2, "three-sum", now + Duration::hours(20)),
+        ];
+
+        let tokyo = render_review_calendar(7, &cards, now, chrono_tz::Asia::Tokyo);
+        assert!(tokyo.contains("DTSTART;VALUE=DATE:20260311\r\n"));
+        assert!(tokyo.contains("DTSTART;VALUE=DATE:20260312\r\n"));
+        assert!(!tokyo.contains("DTSTART;VALUE=DATE:20260310\r\n"));
+
+        let utc = render_review_calendar(7, &cards, now, chrono_tz::UTC);
+        assert!(utc.contains("DTSTART;VALUE=DATE:20260310\r\n"));
+        assert!(utc.contains("DTSTART;VALUE=DATE:20260311\r\n"));
+    }
+
+    #[test]
+    fn escapes_carriage_returns_in_text() {
+        assert_eq!(escape_text("a\rb\r\nc\nd"), "a\\nb\\nc\\nd");
+        assert!(!escape_text("title\r").contains('\r'));
+    }
+
+    #[test]
+    fn folds_long_lines_at_75_octets() {
+        let folded = fold_line(&"x".repeat(160));
+        let lines: Vec<_> = folded.trim_end_matches("\r\n").split("\r\n").collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|line| line.len() <= 75));
+        assert!(lines[1].starts_with(' '));
+    }
+}
@@ -0,0 +1,471 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use chrono::{DateTime, Utc};
+use rand::{Rng, distr::Alphanumeric};
+use tracing::{info, warn};
+
+use crate::{
+    cloze,
+    dto::cards::{CardImportResponse, CardImportResult, CreateCardRequest, UpdateCardRequest},
+    errors::AppError,
+    models::{CardContent, CardKind, ProblemCard},
+    ports::card_notes::{self, CsvColumns, NoteCard},
+    repositories::traits::CardRepository,
+};
+
+const MAX_KEY_CHARS: usize = 128;
+const MAX_TEXT_CHARS: usize = 10_000;
+const MAX_TITLE_CHARS: usize = 120;
+const MAX_TAGS: usize = 32;
+const MAX_TAG_CHARS: usize = 64;
+
+#[derive(Clone)]
+pub struct CardService {
+    repo: Arc<dyn CardRepository>,
+}
+
+impl CardService {
+    pub fn new(repo: Arc<dyn CardRepository>) -> Self {
+        Self { repo }
+    }
+
+    /// Authored cards are due straight away and then follow the same schedule
+    /// as problem cards.
+    pub async fn create(
+        &self,
+        user_id: i64,
+        payload: CreateCardRequest,
+        now: DateTime<Utc>,
+    ) -> Result<ProblemCard, AppError> {
+        let content = card_content(payload.kind, payload.front, payload.back, payload.tags)?;
+        let key = match payload.key {
+            Some(key) => normalize_key(&key)?,
+            None => random_key(),
+        };
+        let created = self
+            .repo
+            .create_card(user_id, &key, content, now)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        let card = created.ok_or_else(|| {
+            warn!(user_id, "card_key_taken");
+            AppError::CardKeyTaken(key)
+        })?;
+        info!(
+            user_id,
+            card_id = card.id,
+            kind = card.kind.as_str(),
+            "card_created"
+        );
+        Ok(card)
+    }
+
+    pub async fn get(&self, user_id: i64, card_id: i64) -> Result<ProblemCard, AppError> {
+        self.repo
+            .get_card(user_id, card_id)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?
+            .ok_or(AppError::CardNotFound)
+    }
+
+    pub async fn update(
+        &self,
+        user_id: i64,
+        card_id: i64,
+        payload: UpdateCardRequest,
+    ) -> Result<ProblemCard, AppError> {
+        let current = self.get(user_id, card_id).await?;
+        if current.kind == CardKind::Problem {
+            return Err(AppError::InvalidInput(
+                "problem cards follow their source and cannot be edited".to_owned(),
+            ));
+        }
+        let content = card_content(
+            payload.kind.unwrap_or(current.kind),
+            payload.front.or(current.front).unwrap_or_default(),
+            payload.back.or(current.back),
+            payload.tags.unwrap_or(current.tags),
+        )?;
+        let card = self
+            .repo
+            .update_card(user_id, card_id, content)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?
+            .ok_or(AppError::CardNotFound)?;
+        info!(user_id, card_id, kind = card.kind.as_str(), "card_updated");
+        Ok(card)
+    }
+
+    /// Deletes any card, including problem cards, together with its reviews.
+    pub async fn delete(&self, user_id: i64, card_id: i64) -> Result<(), AppError> {
+        let deleted = self
+            .repo
+            .delete_card(user_id, card_id)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        if !deleted {
+            warn!(user_id, card_id, "card_delete_not_found");
+            return Err(AppError::CardNotFound);
+        }
+        info!(user_id, card_id, "card_deleted");
+        Ok(())
+    }
+
+    pub async fn import_markdown(
+        &self,
+        user_id: i64,
+        text: &str,
+        dry_run: bool,
+        now: DateTime<Utc>,
+    ) -> Result<CardImportResponse, AppError> {
+        let notes = card_notes::parse_markdown(text)
+            .map_err(|err| AppError::InvalidInput(err.to_string()))
+            .inspect_err(|err| warn!(user_id, error = %err, "card_import_rejected"))?;
+        self.import_notes(user_id, "markdown", notes, dry_run, now)
+            .await
+    }
+
+    pub async fn import_csv(
+        &self,
+        user_id: i64,
+        text: &str,
+        columns: &CsvColumns,
+        dry_run: bool,
+        now: DateTime<Utc>,
+    ) -> Result<CardImportResponse, AppError> {
+        let notes = card_notes::parse_csv(text, columns)
+            .map_err(|err| AppError::InvalidInput(err.to_string()))
+            .inspect_err(|err| warn!(user_id, error = %err, "card_import_rejected"))?;
+        self.import_notes(user_id, "csv", notes, dry_run, now).await
+    }
+
+    /// Matches notes to existing cards by key: new keys are created, changed
+    /// cards are rewritten with their schedule kept, and invalid, repeated or
+    /// unchanged notes are skipped. A dry run stops after the comparison.
+    async fn import_notes(
+        &self,
+        user_id: i64,
+        format: &'static str,
+        notes: Vec<NoteCard>,
+        dry_run: bool,
+        now: DateTime<Utc>,
+    ) -> Result<CardImportResponse, AppError> {
+        let mut seen = HashSet::new();
+        let mut planned = Vec::with_capacity(notes.len());
+        for note in notes {
+            let line = note.line;
+            let checked = normalize_key(&note.key).and_then(|key| {
+                if !seen.insert(key.clone()) {
+                    return Err(AppError::InvalidInput(format!(
+                        "key {key} appears earlier in this file"
+                    )));
+                }
+                let content = card_content(note.kind, note.front, note.back, note.tags)?;
+                Ok((key, content))
+            });
+            planned.push((line, note.key, checked));
+        }
+
+        let keys = planned
+            .iter()
+            .filter_map(|(_, _, checked)| checked.as_ref().ok().map(|(key, _)| key.clone()))
+            .collect::<Vec<_>>();
+        let existing = self
+            .repo
+            .cards_by_keys(user_id, &keys)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?
+            .into_iter()
+            .map(|card| (card.problem_slug.clone(), card))
+            .collect::<HashMap<_, _>>();
+
+        let mut writes = Vec::new();
+        let mut results = Vec::with_capacity(planned.len());
+        for (line, raw_key, checked) in planned {
+            let (key, content) = match checked {
+                Ok(checked) => checked,
+                Err(err) => {
+                    results.push(CardImportResult::Skipped {
+                        line,
+                        key: raw_key,
+                        reason: rejection_reason(err),
+                    });
+                    continue;
+                }
+            };
+            let title = content.title.clone();
+            results.push(match existing.get(&key) {
+                Some(card) if has_content(card, &content) => CardImportResult::Skipped {
+                    line,
+                    key,
+                    reason: "unchanged".to_owned(),
+                },
+                Some(card) => {
+                    writes.push((key.clone(), content));
+                    CardImportResult::Updated {
+                        line,
+                        key,
+                        title,
+                        card_id: card.id,
+                    }
+                }
+                None => {
+                    writes.push((key.clone(), content));
+                    CardImportResult::Created {
+                        line,
+                        key,
+                        title,
+                        card_id: None,
+                    }
+                }
+            });
+        }
+
+        if !dry_run && !writes.is_empty() {
+            let saved = self
+                .repo
+                .upsert_cards(user_id, writes, now)
+                .await
+                .map_err(|err| AppError::Internal(err.to_string()))?
+                .into_iter()
+                .map(|card| (card.problem_slug, card.id))
+                .collect::<HashMap<_, _>>();
+            for result in &mut results {
+                if let CardImportResult::Created { key, card_id, .. } = result {
+                    *card_id = saved.get(key).copied();
+                }
+            }
+        }
+
+        let response = CardImportResponse::new(dry_run, results);
+        info!(
+            user_id,
+            format,
+            dry_run,
+            created = response.created,
+            updated = response.updated,
+            skipped = response.skipped,
+            "cards_imported"
+        );
+        Ok(response)
+    }
+}
+
+fn has_content(card: &ProblemCard, content: &CardContent) -> bool {
+    card.kind == content.kind
+        && card.front.as_deref() == Some(content.front.as_str())
+        && card.back == content.back
+        && card.tags == content.tags
+}
+
+fn rejection_reason(err: AppError) -> String {
+    match err {
+        AppError::InvalidInput(reason) => reason,
+        other => other.to_string(),
+    }
+}
+
+/// Validates authored content and derives the title shown in queues, digests
+/// and calendars.
+pub fn card_content(
+    kind: CardKind,
+    front: String,
+    back: Option<String>,
+    tags: Vec<String>,
+) -> Result<CardContent, AppError> {
+    let front = front.trim().to_owned();
+    let back = back
+        .map(|back| back.trim().to_owned())
+        .filter(|back| !back.is_empty());
+    if front.is_empty() {
+        return Err(AppError::InvalidInput(
+            "card front cannot be empty".to_owned(),
+        ));
+    }
+    if front.chars().count() > MAX_TEXT_CHARS
+        || back
+            .as_ref()
+            .is_some_and(|back| back.chars().count() > MAX_TEXT_CHARS)
+    {
+        return Err(AppError::InvalidInput(format!(
+            "card text is limited to {MAX_TEXT_CHARS} characters per side"
+        )));
+    }
+    match kind {
+        CardKind::Problem => {
+            return Err(AppError::InvalidInput(
+                "problem cards are created from solve events".to_owned(),
+            ));
+        }
+        CardKind::Basic if back.is_none() => {
+            return Err(AppError::InvalidInput("basic cards need a back".to_owned()));
+        }
+        CardKind::Cloze => {
+            let deletions = cloze::deletions(&front);
+            if deletions.is_empty() {
+                return Err(AppError::InvalidInput(
+                    "cloze cards need at least one {{c1::...}} deletion".to_owned(),
+                ));
+            }
+            if let Some(deletion) = deletions
+                .iter()
+                .find(|deletion| deletion.answer.trim().is_empty())
+            {
+                return Err(AppError::InvalidInput(format!(
+                    "cloze deletion c{} has no answer",
+                    deletion.number
+                )));
+            }
+        }
+        CardKind::Basic => {}
+    }
+    Ok(CardContent {
+        kind,
+        title: card_title(kind, &front),
+        front,
+        back,
+        tags: normalize_tags(tags)?,
+    })
+}
+
+/// Lowercases tags, drops a leading `#` and repeats, and joins words with `-`.
+fn normalize_tags(tags: Vec<String>) -> Result<Vec<String>, AppError> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag
+            .trim()
+            .trim_start_matches('#')
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join("-")
+            .to_lowercase();
+        if tag.is_empty() || normalized.contains(&tag) {
+            continue;
+        }
+        if tag.chars().count() > MAX_TAG_CHARS {
+            return Err(AppError::InvalidInput(format!(
+                "tags are limited to {MAX_TAG_CHARS} characters"
+            )));
+        }
+        normalized.push(tag);
+    }
+    if normalized.len() > MAX_TAGS {
+        return Err(AppError::InvalidInput(format!(
+            "a card can have at most {MAX_TAGS} tags"
+        )));
+    }
+    Ok(normalized)
+}
+
+fn card_title(kind: CardKind, front: &str) -> String {
+    let shown = match kind {
+        CardKind::Cloze => cloze::render_prompt(front),
+        _ => front.to_owned(),
+    };
+    let line = shown
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default();
+    if line.chars().count() <= MAX_TITLE_CHARS {
+        return line.to_owned();
+    }
+    let mut title: String = line.chars().take(MAX_TITLE_CHARS - 1).collect();
+    title.push('…');
+    title
+}
+
+pub fn normalize_key(key: &str) -> Result<String, AppError> {
+    let key = key.trim();
+    if key.is_empty()
+        || key.chars().count() > MAX_KEY_CHARS
+        || key.chars().any(|ch| ch.is_whitespace() || ch.is_control())
+    {
+        return Err(AppError::InvalidInput(format!(
+            "card key must be 1-{MAX_KEY_CHARS} characters without spaces"
+        )));
+    }
+    Ok(key.to_owned())
+}
+
+fn random_key() -> String {
+    let suffix: String = rand::rng()
+        .sample_iter(&Alphanumeric)
+        .take(16)
+        .map(char::from)
+        .collect();
+    format!("card-{}", suffix.to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{card_content, normalize_key};
+    use crate::models::CardKind;
+
+    #[test]
+    fn content_is_validated_per_kind_and_titled_from_the_front() {
+        let basic = card_content(
+            CardKind::Basic,
+            "  What is a monad?\nIn one line.".to_owned(),
+            Some(" A monoid in the category of endofunctors ".to_owned()),
+            Vec::new(),
+        )
+        .expect("valid basic card");
+        assert_eq!(basic.title, "What is a monad?");
+        assert_eq!(
+            basic.back.as_deref(),
+            Some("A monoid in the category of endofunctors")
+        );
+
+        let cloze = card_content(
+            CardKind::Cloze,
+            "Rust's {{c1::borrow checker}} enforces aliasing rules".to_owned(),
+            None,
+            Vec::new(),
+        )
+        .expect("valid cloze card");
+        assert_eq!(cloze.title, "Rust's [...] enforces aliasing rules");
+
+        let tagged = card_content(
+            CardKind::Basic,
+            "Q".to_owned(),
+            Some("A".to_owned()),
+            vec![
+                "#Rust".to_owned(),
+                "rust".to_owned(),
+                " error handling ".to_owned(),
+            ],
+        )
+        .expect("valid tags");
+        assert_eq!(tagged.tags, ["rust", "error-handling"]);
+
+        let invalid = [
+            (CardKind::Basic, "Q", Some("  ")),
+            (CardKind::Cloze, "no deletion", None),
+            (CardKind::Cloze, "empty {{c1::}} and {{c2:: ::hint}}", None),
+            (CardKind::Problem, "Q", Some("A")),
+            (CardKind::Basic, " ", Some("A")),
+        ];
+        for (kind, front, back) in invalid {
+            assert!(
+                card_content(
+                    kind,
+                    front.to_owned(),
+                    back.map(ToOwned::to_owned),
+                    Vec::new()
+                )
+                .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn keys_reject_whitespace() {
+        assert_eq!(normalize_key(" notes/rust-1 ").unwrap(), "notes/rust-1");
+        assert!(normalize_key("two words").is_err());
+        assert!(normalize_key("").is_err());
+    }
+}
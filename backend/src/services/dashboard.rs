@@ -1,49 +1,62 @@
-use crate::{dto::dashboard::DashboardResponse, errors::AppError};
+use crate::{
+    dto::dashboard::{DashboardResponse, SourceCount},
+    errors::AppError,
+};
 use tracing::info;
 
-use super::{event::EventService, review::ReviewService};
+use super::{activity::ActivityService, event::EventService, review::ReviewService};
 
 #[derive(Clone)]
 pub struct DashboardService {
     review_service: ReviewService,
     event_service: EventService,
+    activity_service: ActivityService,
 }
 
 impl DashboardService {
-    pub fn new(review_service: ReviewService, event_service: EventService) -> Self {
+    pub fn new(
+        review_service: ReviewService,
+        event_service: EventService,
+        activity_service: ActivityService,
+    ) -> Self {
         Self {
             review_service,
             event_service,
+            activity_service,
         }
     }
 
     pub async fn dashboard_for_user(&self, user_id: i64) -> Result<DashboardResponse, AppError> {
-        let due = self
-            .review_service
-            .due_cards(user_id, chrono::Utc::now())
-            .await?;
+        let now = chrono::Utc::now();
+        let due = self.review_service.due_cards(user_id, now).await?;
         let upcoming = self.review_service.upcoming_cards(user_id).await?;
-        let leetcode_count = upcoming
+        let source_counts = self
+            .event_service
+            .sources()
+            .sources()
             .iter()
-            .filter(|card| card.source == "leetcode")
-            .count();
-        let neetcode_count = upcoming
-            .iter()
-            .filter(|card| card.source == "neetcode")
-            .count();
+            .map(|source| SourceCount {
+                source: source.id().to_owned(),
+                display_name: source.definition.display_name.clone(),
+                count: upcoming
+                    .iter()
+                    .filter(|card| card.source == source.id())
+                    .count(),
+            })
+            .collect();
         let response = DashboardResponse {
             due_count: due.len(),
             upcoming_count: upcoming.len(),
-            leetcode_count,
-            neetcode_count,
+            source_counts,
             latest_ingestion: self.event_service.latest_for_user(user_id).await?,
+            activity: self.activity_service.summary(user_id, now).await?,
         };
         info!(
             user_id,
             due_count = response.due_count,
             upcoming_count = response.upcoming_count,
-            leetcode_count = response.leetcode_count,
-            neetcode_count = response.neetcode_count,
+            source_count = response.source_counts.len(),
+            current_streak = response.activity.streak.current,
             "dashboard_built"
         );
         Ok(response)
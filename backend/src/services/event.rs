@@ -1,23 +1,36 @@
 use std::sync::Arc;
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::{
     errors::AppError,
-    models::{IngestProblemInput, ProblemEvent},
+    models::{BatchIngestOutcome, IngestProblemInput, ProblemEvent},
     repositories::traits::EventRepository,
+    sources::SourceRegistry,
 };
 
+/// Most events a single `/events/batch` request may carry.
+pub const MAX_BATCH_EVENTS: usize = 200;
+
 #[derive(Clone)]
 pub struct EventService {
     repo: Arc<dyn EventRepository>,
+    sources: Arc<SourceRegistry>,
 }
 
 impl EventService {
-    pub fn new(repo: Arc<dyn EventRepository>) -> Self {
-        Self { repo }
+    pub fn new(repo: Arc<dyn EventRepository>, sources: Arc<SourceRegistry>) -> Self {
+        Self { repo, sources }
+    }
+
+    pub fn sources(&self) -> &SourceRegistry {
+        &self.sources
     }
 
     pub async fn ingest(&self, payload: IngestProblemInput) -> Result<ProblemEvent, AppError> {
+        let payload = self.validate_event(payload).map_err(|reason| {
+            warn!(reason = %reason, "event_rejected");
+            AppError::InvalidInput(reason)
+        })?;
         let user_id = payload.user_id;
         let source = payload.source.clone();
         let problem_slug = payload.problem_slug.clone();
@@ -36,10 +49,90 @@ impl EventService {
         Ok(event)
     }
 
+    /// Ingests the valid items of a batch in one transaction. `items` holds
+    /// the payloads that parsed, or why they did not; the result has one
+    /// outcome per item in the same order.
+    pub async fn ingest_batch(
+        &self,
+        user_id: i64,
+        items: Vec<Result<IngestProblemInput, String>>,
+    ) -> Result<Vec<BatchIngestOutcome>, AppError> {
+        if items.len() > MAX_BATCH_EVENTS {
+            return Err(AppError::InvalidInput(format!(
+                "a batch holds at most {MAX_BATCH_EVENTS} events"
+            )));
+        }
+        let items = items
+            .into_iter()
+            .map(|item| item.and_then(|payload| self.validate_event(payload)))
+            .collect::<Vec<_>>();
+        let accepted = items
+            .iter()
+            .filter_map(|item| item.as_ref().ok().cloned())
+            .collect::<Vec<_>>();
+        let mut ingested = self
+            .repo
+            .ingest_events(accepted)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?
+            .into_iter();
+
+        let outcomes = items
+            .into_iter()
+            .map(|item| match item {
+                Ok(_) => match ingested.next() {
+                    Some(result) if result.created => BatchIngestOutcome::Created(result.event),
+                    Some(result) => BatchIngestOutcome::Deduplicated(result.event),
+                    None => BatchIngestOutcome::Rejected("not ingested".to_owned()),
+                },
+                Err(reason) => BatchIngestOutcome::Rejected(reason),
+            })
+            .collect::<Vec<_>>();
+        let count = |matches: fn(&BatchIngestOutcome) -> bool| {
+            outcomes.iter().filter(|outcome| matches(outcome)).count()
+        };
+        info!(
+            user_id,
+            created = count(|outcome| matches!(outcome, BatchIngestOutcome::Created(_))),
+            deduplicated = count(|outcome| matches!(outcome, BatchIngestOutcome::Deduplicated(_))),
+            rejected = count(|outcome| matches!(outcome, BatchIngestOutcome::Rejected(_))),
+            "event_batch_ingested"
+        );
+        Ok(outcomes)
+    }
+
     pub async fn latest_for_user(&self, user_id: i64) -> Result<Option<ProblemEvent>, AppError> {
         self.repo
             .latest_event_for_user(user_id)
             .await
             .map_err(|err| AppError::Internal(err.to_string()))
     }
+
+    pub async fn list_for_user(&self, user_id: i64) -> Result<Vec<ProblemEvent>, AppError> {
+        self.repo
+            .list_events(user_id)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))
+    }
+
+    /// Checks the event against the source registry and swaps in the
+    /// canonical source id, slug and URL.
+    fn validate_event(&self, payload: IngestProblemInput) -> Result<IngestProblemInput, String> {
+        if payload.source.trim().is_empty() {
+            return Err("source must not be empty".to_owned());
+        }
+        if payload.problem_slug.trim().is_empty() {
+            return Err("problem_slug must not be empty".to_owned());
+        }
+        let problem = self
+            .sources
+            .normalize(&payload.source, &payload.problem_slug, &payload.url)
+            .map_err(|err| err.to_string())?;
+        Ok(IngestProblemInput {
+            source: problem.source,
+            problem_slug: problem.problem_slug,
+            url: problem.url,
+            ..payload
+        })
+    }
 }
@@ -0,0 +1,110 @@
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use tracing::info;
+
+use crate::{
+    dto::forecast::{ForecastDay, ForecastQuery, ForecastResponse},
+    errors::AppError,
+    srs::SrsSchedule,
+};
+
+use super::review::ReviewService;
+
+const MAX_DAYS: u32 = 365;
+/// UTC-12:00 to UTC+14:00.
+const UTC_OFFSET_RANGE_MINUTES: std::ops::RangeInclusive<i32> = -720..=840;
+const DEFAULT_PASS_RATE: f64 = 0.9;
+
+#[derive(Clone)]
+pub struct ForecastService {
+    review_service: ReviewService,
+    schedule: SrsSchedule,
+}
+
+impl ForecastService {
+    pub fn new(review_service: ReviewService, schedule: SrsSchedule) -> Self {
+        Self {
+            review_service,
+            schedule,
+        }
+    }
+
+    pub async fn forecast(
+        &self,
+        user_id: i64,
+        query: ForecastQuery,
+        now: DateTime<Utc>,
+    ) -> Result<ForecastResponse, AppError> {
+        if !(1..=MAX_DAYS).contains(&query.days) {
+            return Err(AppError::InvalidInput(format!(
+                "days must be between 1 and {MAX_DAYS}"
+            )));
+        }
+        let offset = UTC_OFFSET_RANGE_MINUTES
+            .contains(&query.utc_offset_minutes)
+            .then(|| FixedOffset::east_opt(query.utc_offset_minutes * 60))
+            .flatten()
+            .ok_or_else(|| {
+                AppError::InvalidInput("utc_offset_minutes must be between -720 and 840".to_owned())
+            })?;
+        let pass_rate = match (query.simulate, query.pass_rate) {
+            (false, _) => None,
+            (true, Some(rate)) if (0.0..=1.0).contains(&rate) => Some(rate),
+            (true, Some(_)) => {
+                return Err(AppError::InvalidInput(
+                    "pass_rate must be between 0 and 1".to_owned(),
+                ));
+            }
+            (true, None) => Some(DEFAULT_PASS_RATE),
+        };
+
+        let cards = self.review_service.list_cards(user_id).await?;
+        let today = now.with_timezone(&offset).date_naive();
+        let days = query.days as usize;
+        let mut due = vec![0; days];
+        let mut scheduled = Vec::with_capacity(cards.len());
+        let mut overdue = 0;
+        for card in cards.iter().filter(|card| card.suspended_at.is_none()) {
+            if card.next_due_at < now {
+                overdue += 1;
+            }
+            let due_on = card
+                .next_due_at
+                .max(now)
+                .with_timezone(&offset)
+                .date_naive();
+            let day = (due_on - today).num_days() as usize;
+            if day < days {
+                due[day] += 1;
+                scheduled.push((day, card.interval_index));
+            }
+        }
+        let projected =
+            pass_rate.map(|rate| self.schedule.project_daily_reviews(&scheduled, days, rate));
+
+        let forecast = due
+            .into_iter()
+            .enumerate()
+            .map(|(day, due)| ForecastDay {
+                date: today + Duration::days(day as i64),
+                due,
+                projected: projected
+                    .as_ref()
+                    .map(|projected| (projected[day] * 100.0).round() / 100.0),
+            })
+            .collect();
+        info!(
+            user_id,
+            days = query.days,
+            simulated = pass_rate.is_some(),
+            overdue,
+            "forecast_built"
+        );
+        Ok(ForecastResponse {
+            days: query.days,
+            utc_offset_minutes: query.utc_offset_minutes,
+            overdue,
+            pass_rate,
+            forecast,
+        })
+    }
+}
@@ -0,0 +1,123 @@
+use std::{env, sync::Arc};
+
+use chrono::{DateTime, Duration, Utc};
+use tracing::{info, warn};
+
+use crate::{
+    errors::AppError,
+    models::{IdempotencyClaim, StoredResponse},
+    repositories::traits::IdempotencyRepository,
+};
+
+const DEFAULT_IDEMPOTENCY_TTL_SECS: i64 = 24 * 60 * 60;
+/// How long a claim without a response blocks retries. After that the
+/// request is assumed to have died and a retry may run it again.
+const IN_PROGRESS_LEASE_SECS: i64 = 60;
+
+/// Outcome of presenting an `Idempotency-Key` for a request.
+#[derive(Debug)]
+pub enum IdempotencyDecision {
+    /// First use of the key: run the handler and record its response.
+    Proceed,
+    /// The same request already completed; send its response again.
+    Replay(StoredResponse),
+}
+
+#[derive(Clone)]
+pub struct IdempotencyService {
+    repo: Arc<dyn IdempotencyRepository>,
+    ttl: Duration,
+}
+
+impl IdempotencyService {
+    pub fn new(repo: Arc<dyn IdempotencyRepository>) -> Self {
+        let ttl_secs = env::var("IDEMPOTENCY_TTL_SECS")
+            .ok()
+            .and_then(|value| value.parse::<i64>().ok())
+            .filter(|secs| *secs > 0)
+            .unwrap_or(DEFAULT_IDEMPOTENCY_TTL_SECS);
+        Self {
+            repo,
+            ttl: Duration::seconds(ttl_secs),
+        }
+    }
+
+    pub async fn begin(
+        &self,
+        user_id: i64,
+        key: &str,
+        request_hash: &str,
+        now: DateTime<Utc>,
+    ) -> Result<IdempotencyDecision, AppError> {
+        let claim = self
+            .repo
+            .claim_idempotency_key(
+                user_id,
+                key,
+                request_hash,
+                now,
+                now - Duration::seconds(IN_PROGRESS_LEASE_SECS),
+                now + self.ttl,
+            )
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        let record = match claim {
+            IdempotencyClaim::Claimed => return Ok(IdempotencyDecision::Proceed),
+            IdempotencyClaim::Existing(record) => record,
+            IdempotencyClaim::Contended => {
+                warn!(user_id, "idempotency_key_contended");
+                return Err(AppError::IdempotencyRequestInProgress);
+            }
+        };
+        if record.request_hash != request_hash {
+            warn!(user_id, "idempotency_key_reused");
+            return Err(AppError::IdempotencyKeyReused);
+        }
+        match record.response {
+            Some(response) => {
+                info!(
+                    user_id,
+                    status = response.status,
+                    "idempotent_response_replayed"
+                );
+                Ok(IdempotencyDecision::Replay(response))
+            }
+            None => {
+                warn!(user_id, "idempotent_request_in_progress");
+                Err(AppError::IdempotencyRequestInProgress)
+            }
+        }
+    }
+
+    pub async fn complete(
+        &self,
+        user_id: i64,
+        key: &str,
+        response: StoredResponse,
+    ) -> Result<(), AppError> {
+        self.repo
+            .complete_idempotency_key(user_id, key, response)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))
+    }
+
+    /// Frees a key whose request failed so the client can retry with it.
+    pub async fn release(&self, user_id: i64, key: &str) -> Result<(), AppError> {
+        self.repo
+            .release_idempotency_key(user_id, key)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))
+    }
+
+    pub async fn purge_expired(&self, now: DateTime<Utc>) -> Result<u64, AppError> {
+        let purged = self
+            .repo
+            .purge_expired_idempotency_keys(now)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        if purged > 0 {
+            info!(purged, "idempotency_keys_purged");
+        }
+        Ok(purged)
+    }
+}
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Duration, Utc};
 use rand::{Rng, distr::Alphanumeric};
 use tracing::{info, warn};
 
@@ -9,12 +9,19 @@ use crate::{
         CreateIntegrationTokenResponse, IntegrationTokenSummary, IntegrationsResponse,
     },
     errors::AppError,
-    models::{User, hash_token},
-    repositories::traits::{IntegrationTokenRepository, NewIntegrationToken},
+    models::{
+        ClientInfo, INTEGRATION_SCOPES, IntegrationToken, SCOPE_EVENTS_WRITE, User, hash_token,
+    },
+    repositories::traits::{
+        IntegrationTokenRepository, IntegrationTokenRotation, NewIntegrationToken,
+    },
 };
 
 use super::event::EventService;
 
+/// Longest window during which a rotated-out secret is still accepted.
+const MAX_ROTATION_GRACE_MINUTES: i64 = 7 * 24 * 60;
+
 #[derive(Clone)]
 pub struct IntegrationsService {
     event_service: EventService,
@@ -30,11 +37,7 @@ impl IntegrationsService {
     }
 
     pub async fn get(&self, user_id: i64) -> Result<IntegrationsResponse, AppError> {
-        let tokens = self
-            .repo
-            .list_integration_tokens(user_id)
-            .await
-            .map_err(|err| AppError::Internal(err.to_string()))?;
+        let tokens = self.list_tokens(user_id).await?;
         let response = IntegrationsResponse {
             api_token_setup: vec![
                 "Create an API token from this page (backend-generated, shown once).".to_owned(),
@@ -48,18 +51,7 @@ impl IntegrationsService {
                 "Login with your LeetCode account".to_owned(),
                 "Open or solve a problem and verify the event appears here".to_owned(),
             ],
-            tokens: tokens
-                .into_iter()
-                .map(|token| IntegrationTokenSummary {
-                    id: token.id,
-                    label: token.label,
-                    scopes: token.scopes,
-                    created_at: token.created_at,
-                    expires_at: token.expires_at,
-                    revoked_at: token.revoked_at,
-                    last_used_at: token.last_used_at,
-                })
-                .collect(),
+            tokens,
         };
         info!(
             user_id,
@@ -70,23 +62,35 @@ impl IntegrationsService {
         Ok(response)
     }
 
+    pub async fn list_tokens(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<IntegrationTokenSummary>, AppError> {
+        let tokens = self
+            .repo
+            .list_integration_tokens(user_id)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        Ok(tokens
+            .into_iter()
+            .map(IntegrationTokenSummary::from)
+            .collect())
+    }
+
     pub async fn create_token(
         &self,
         user_id: i64,
         label: &str,
         expires_in_days: Option<i64>,
+        scopes: Option<Vec<String>>,
     ) -> Result<CreateIntegrationTokenResponse, AppError> {
         if label.trim().is_empty() {
             return Err(AppError::InvalidInput(
                 "token label cannot be empty".to_owned(),
             ));
         }
-        let raw_secret: String = rand::rng()
-            .sample_iter(&Alphanumeric)
-            .take(48)
-            .map(char::from)
-            .collect();
-        let token = format!("srs_it_{raw_secret}");
+        let scopes = normalize_scopes(scopes)?;
+        let token = generate_token_secret();
         let token_hash = hash_token(&token);
         let expires_at = expires_in_days
             .filter(|days| *days > 0)
@@ -98,21 +102,13 @@ impl IntegrationsService {
                 &token_hash,
                 NewIntegrationToken {
                     label: label.trim().to_owned(),
-                    scopes: vec!["events:write".to_owned()],
+                    scopes,
                     expires_at,
                 },
             )
             .await
             .map_err(|err| AppError::Internal(err.to_string()))?;
-        let token_summary = IntegrationTokenSummary {
-            id: created.id,
-            label: created.label,
-            scopes: created.scopes,
-            created_at: created.created_at,
-            expires_at: created.expires_at,
-            revoked_at: created.revoked_at,
-            last_used_at: created.last_used_at,
-        };
+        let token_summary = IntegrationTokenSummary::from(created);
         info!(
             user_id,
             token_id = token_summary.id,
@@ -138,29 +134,197 @@ impl IntegrationsService {
         Ok(())
     }
 
-    pub async fn user_from_api_key(&self, token: Option<&str>) -> Result<User, AppError> {
+    /// Issues a new secret for an existing token. With a grace period the old
+    /// secret keeps working until it elapses; otherwise it stops immediately.
+    pub async fn rotate_token(
+        &self,
+        user_id: i64,
+        token_id: i64,
+        grace_period_minutes: Option<i64>,
+        expires_in_days: Option<i64>,
+    ) -> Result<CreateIntegrationTokenResponse, AppError> {
+        let grace_period_minutes = grace_period_minutes.unwrap_or(0);
+        if !(0..=MAX_ROTATION_GRACE_MINUTES).contains(&grace_period_minutes) {
+            return Err(AppError::InvalidInput(format!(
+                "grace_period_minutes must be between 0 and {MAX_ROTATION_GRACE_MINUTES}"
+            )));
+        }
+        let now = Utc::now();
+        let token = generate_token_secret();
+        let rotated = self
+            .repo
+            .rotate_integration_token(
+                user_id,
+                token_id,
+                IntegrationTokenRotation {
+                    token_hash: hash_token(&token),
+                    previous_valid_until: (grace_period_minutes > 0)
+                        .then(|| now + Duration::minutes(grace_period_minutes)),
+                    expires_at: expires_in_days
+                        .map(|days| (days > 0).then(|| now + Duration::days(days))),
+                },
+            )
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        let Some(rotated) = rotated else {
+            warn!(user_id, token_id, "integration_token_not_found");
+            return Err(AppError::IntegrationTokenNotFound);
+        };
+        info!(
+            user_id,
+            token_id, grace_period_minutes, "integration_token_rotated"
+        );
+        Ok(CreateIntegrationTokenResponse {
+            token,
+            token_summary: rotated.into(),
+        })
+    }
+
+    pub async fn tokens_expiring_within(
+        &self,
+        now: DateTime<Utc>,
+        window: Duration,
+    ) -> Result<Vec<IntegrationToken>, AppError> {
+        self.repo
+            .list_integration_tokens_expiring_between(now, now + window)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))
+    }
+
+    pub async fn mark_expiry_notified(&self, token_id: i64) -> Result<(), AppError> {
+        self.repo
+            .mark_integration_token_expiry_notified(token_id)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))
+    }
+
+    /// Resolves the owner of an API key without checking scopes or recording
+    /// usage; the handler's own extractor still authorizes the request.
+    pub async fn owner_of_api_key(&self, token: &str) -> Result<Option<User>, AppError> {
+        Ok(self
+            .repo
+            .user_from_integration_token(&hash_token(token.trim()))
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?
+            .map(|(user, _)| user))
+    }
+
+    pub async fn user_from_api_key(
+        &self,
+        token: Option<&str>,
+        required_scope: &str,
+        client: ClientInfo,
+    ) -> Result<User, AppError> {
         let raw = token.ok_or(AppError::MissingApiToken)?;
         let hash = hash_token(raw.trim());
-        let user = self
+        let (user, integration_token) = self
             .repo
             .user_from_integration_token(&hash)
             .await
             .map_err(|err| AppError::Internal(err.to_string()))?
             .ok_or(AppError::InvalidApiToken)?;
+        if !integration_token
+            .scopes
+            .iter()
+            .any(|scope| scope == required_scope)
+        {
+            warn!(
+                user_id = user.id,
+                token_id = integration_token.id,
+                required_scope,
+                "integration_token_scope_denied"
+            );
+            return Err(AppError::InsufficientScope(required_scope.to_owned()));
+        }
+        if integration_token.token_hash != hash {
+            warn!(
+                user_id = user.id,
+                token_id = integration_token.id,
+                "integration_token_previous_secret_used"
+            );
+        }
         self.repo
-            .touch_integration_token_usage(&hash)
+            .touch_integration_token_usage(integration_token.id, client)
             .await
             .map_err(|err| AppError::Internal(err.to_string()))?;
         Ok(user)
     }
 }
 
+impl From<IntegrationToken> for IntegrationTokenSummary {
+    fn from(token: IntegrationToken) -> Self {
+        Self {
+            id: token.id,
+            label: token.label,
+            scopes: token.scopes,
+            created_at: token.created_at,
+            expires_at: token.expires_at,
+            revoked_at: token.revoked_at,
+            last_used_at: token.last_used_at,
+            last_used_ip: token.last_used_ip,
+            last_used_user_agent: token.last_used_user_agent,
+            previous_secret_expires_at: token.previous_token_expires_at,
+        }
+    }
+}
+
+fn generate_token_secret() -> String {
+    let raw_secret: String = rand::rng()
+        .sample_iter(&Alphanumeric)
+        .take(48)
+        .map(char::from)
+        .collect();
+    format!("srs_it_{raw_secret}")
+}
+
+/// Validates requested scopes against [`INTEGRATION_SCOPES`], falling back to
+/// `events:write` so existing userscript setups keep working.
+fn normalize_scopes(requested: Option<Vec<String>>) -> Result<Vec<String>, AppError> {
+    let mut scopes = requested
+        .unwrap_or_default()
+        .into_iter()
+        .map(|scope| scope.trim().to_lowercase())
+        .collect::<Vec<_>>();
+    if let Some(unknown) = scopes
+        .iter()
+        .find(|scope| !INTEGRATION_SCOPES.contains(&scope.as_str()))
+    {
+        return Err(AppError::InvalidInput(format!("unknown scope: {unknown}")));
+    }
+    if scopes.is_empty() {
+        scopes.push(SCOPE_EVENTS_WRITE.to_owned());
+    }
+    scopes.sort();
+    scopes.dedup();
+    Ok(scopes)
+}
+
 #[cfg(test)]
 mod tests {
     use serde_json::Value;
 
     use crate::dto::integrations::IntegrationsResponse;
 
+    use super::normalize_scopes;
+
+    #[test]
+    fn scopes_default_to_events_write_and_reject_unknown_values() {
+        assert_eq!(
+            normalize_scopes(None).expect("default scopes"),
+            vec!["events:write".to_owned()]
+        );
+        assert_eq!(
+            normalize_scopes(Some(vec![
+                "reviews:read".to_owned(),
+                "Cards:Read".to_owned(),
+                "reviews:read".to_owned(),
+            ]))
+            .expect("valid scopes"),
+            vec!["cards:read".to_owned(), "reviews:read".to_owned()]
+        );
+        assert!(normalize_scopes(Some(vec!["admin".to_owned()])).is_err());
+    }
+
     #[test]
     fn integrations_response_uses_setup_steps_not_hint_token() {
         let response = IntegrationsResponse {
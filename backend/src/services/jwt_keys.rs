@@ -0,0 +1,382 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, NaiveDate, Utc};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey, Header,
+    jwk::{Jwk, JwkSet},
+};
+use rsa::{
+    RsaPrivateKey,
+    pkcs1::{DecodeRsaPrivateKey, EncodeRsaPrivateKey},
+    pkcs8::DecodePrivateKey,
+    traits::PublicKeyParts,
+};
+use serde_json::json;
+use thiserror::Error;
+use tracing::info;
+
+/// Smaller RSA moduli are refused outright.
+const MIN_RSA_BITS: usize = 2048;
+
+#[derive(Debug, Error)]
+pub enum JwtKeyError {
+    #[error("cannot read key directory {0}: {1}")]
+    Directory(String, String),
+    #[error("invalid key file {0}: {1}")]
+    InvalidKey(String, String),
+    #[error("no signing key is active yet")]
+    NoActiveKey,
+}
+
+struct JwtKey {
+    kid: String,
+    algorithm: Algorithm,
+    /// Keys sign new tokens from this moment; until then they are only
+    /// published so verifiers can pick them up ahead of the switch.
+    active_from: DateTime<Utc>,
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    jwk: Jwk,
+}
+
+/// Session-token keys. With a key directory every `*.pem` file is a key whose
+/// `kid` is the file stem; a `YYYY-MM-DD` prefix schedules when it starts
+/// signing. The newest active key signs, all loaded keys verify. A legacy
+/// HS256 secret, when present, keeps verifying tokens issued before the move
+/// to asymmetric keys (and signs when no directory is configured).
+#[derive(Clone)]
+pub struct JwtKeyring {
+    keys: Arc<RwLock<Vec<Arc<JwtKey>>>>,
+    dir: Option<PathBuf>,
+    legacy_secret: Option<(EncodingKey, DecodingKey)>,
+}
+
+impl JwtKeyring {
+    pub fn hmac(secret: &str) -> Self {
+        Self {
+            keys: Arc::new(RwLock::new(Vec::new())),
+            dir: None,
+            legacy_secret: Some((
+                EncodingKey::from_secret(secret.as_bytes()),
+                DecodingKey::from_secret(secret.as_bytes()),
+            )),
+        }
+    }
+
+    pub fn from_dir(
+        dir: impl Into<PathBuf>,
+        legacy_secret: Option<&str>,
+    ) -> Result<Self, JwtKeyError> {
+        let dir = dir.into();
+        let keys = load_dir(&dir)?;
+        if keys.is_empty() {
+            return Err(JwtKeyError::Directory(
+                dir.display().to_string(),
+                "no *.pem keys found".to_owned(),
+            ));
+        }
+        let keyring = Self {
+            keys: Arc::new(RwLock::new(keys)),
+            dir: Some(dir),
+            legacy_secret: legacy_secret.map(|secret| {
+                (
+                    EncodingKey::from_secret(secret.as_bytes()),
+                    DecodingKey::from_secret(secret.as_bytes()),
+                )
+            }),
+        };
+        keyring.log_loaded();
+        Ok(keyring)
+    }
+
+    pub fn uses_key_dir(&self) -> bool {
+        self.dir.is_some()
+    }
+
+    /// Re-reads the key directory so added or retired keys take effect
+    /// without a restart. A broken directory keeps the previous keys.
+    pub fn reload(&self) -> Result<(), JwtKeyError> {
+        let Some(dir) = &self.dir else {
+            return Ok(());
+        };
+        let keys = load_dir(dir)?;
+        if keys.is_empty() {
+            return Err(JwtKeyError::Directory(
+                dir.display().to_string(),
+                "no *.pem keys found".to_owned(),
+            ));
+        }
+        *self.keys.write().expect("jwt keyring poisoned") = keys;
+        self.log_loaded();
+        Ok(())
+    }
+
+    /// Header and key to sign a new token with at `now`.
+    pub fn signing_key(&self, now: DateTime<Utc>) -> Result<(Header, EncodingKey), JwtKeyError> {
+        if self.dir.is_none() {
+            let (encoding_key, _) = self
+                .legacy_secret
+                .as_ref()
+                .ok_or(JwtKeyError::NoActiveKey)?;
+            return Ok((Header::new(Algorithm::HS256), encoding_key.clone()));
+        }
+        let keys = self.keys.read().expect("jwt keyring poisoned");
+        let key = keys
+            .iter()
+            .filter(|key| key.active_from <= now)
+            .max_by(|a, b| (a.active_from, &a.kid).cmp(&(b.active_from, &b.kid)))
+            .ok_or(JwtKeyError::NoActiveKey)?;
+        let mut header = Header::new(key.algorithm);
+        header.kid = Some(key.kid.clone());
+        Ok((header, key.encoding_key.clone()))
+    }
+
+    /// Key and algorithm a token must verify against. Tokens with a `kid`
+    /// must match a loaded key and its algorithm; tokens without one are only
+    /// accepted as legacy HS256.
+    pub fn verification_key(&self, header: &Header) -> Option<(Algorithm, DecodingKey)> {
+        match &header.kid {
+            Some(kid) => self
+                .keys
+                .read()
+                .expect("jwt keyring poisoned")
+                .iter()
+                .find(|key| &key.kid == kid && key.algorithm == header.alg)
+                .map(|key| (key.algorithm, key.decoding_key.clone())),
+            None if header.alg == Algorithm::HS256 => self
+                .legacy_secret
+                .as_ref()
+                .map(|(_, decoding_key)| (Algorithm::HS256, decoding_key.clone())),
+            None => None,
+        }
+    }
+
+    /// Public halves of every loaded key. Empty when only HS256 is in use.
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: self
+                .keys
+                .read()
+                .expect("jwt keyring poisoned")
+                .iter()
+                .map(|key| key.jwk.clone())
+                .collect(),
+        }
+    }
+
+    fn log_loaded(&self) {
+        let keys = self.keys.read().expect("jwt keyring poisoned");
+        let kids = keys
+            .iter()
+            .map(|key| key.kid.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+        info!(key_count = keys.len(), kids = %kids, "jwt_keys_loaded");
+    }
+}
+
+fn load_dir(dir: &Path) -> Result<Vec<Arc<JwtKey>>, JwtKeyError> {
+    let entries = fs::read_dir(dir)
+        .map_err(|err| JwtKeyError::Directory(dir.display().to_string(), err.to_string()))?;
+    let mut keys = Vec::new();
+    for entry in entries {
+        let path = entry
+            .map_err(|err| JwtKeyError::Directory(dir.display().to_string(), err.to_string()))?
+            .path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("pem") {
+            continue;
+        }
+        keys.push(Arc::new(load_key(&path)?));
+    }
+    keys.sort_by(|a, b| a.kid.cmp(&b.kid));
+    Ok(keys)
+}
+
+fn load_key(path: &Path) -> Result<JwtKey, JwtKeyError> {
+    let invalid = |reason: String| JwtKeyError::InvalidKey(path.display().to_string(), reason);
+    let kid = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .filter(|stem| !stem.is_empty())
+        .ok_or_else(|| invalid("file name is not a usable key id".to_owned()))?
+        .to_owned();
+    let active_from = kid
+        .get(..10)
+        .and_then(|prefix| NaiveDate::parse_from_str(prefix, "%Y-%m-%d").ok())
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|datetime| datetime.and_utc())
+        .unwrap_or(DateTime::<Utc>::MIN_UTC);
+    let pem = fs::read_to_string(path).map_err(|err| invalid(err.to_string()))?;
+
+    if let Ok(signing_key) = ed25519_dalek::SigningKey::from_pkcs8_pem(&pem) {
+        let public_key = URL_SAFE_NO_PAD.encode(signing_key.verifying_key().as_bytes());
+        let der = ed25519_dalek::pkcs8::EncodePrivateKey::to_pkcs8_der(&signing_key)
+            .map_err(|err| invalid(err.to_string()))?;
+        return Ok(JwtKey {
+            algorithm: Algorithm::EdDSA,
+            active_from,
+            encoding_key: EncodingKey::from_ed_der(der.as_bytes()),
+            decoding_key: DecodingKey::from_ed_components(&public_key)
+                .map_err(|err| invalid(err.to_string()))?,
+            jwk: public_jwk(
+                &kid,
+                "EdDSA",
+                json!({ "kty": "OKP", "crv": "Ed25519", "x": public_key }),
+            )?,
+            kid,
+        });
+    }
+
+    let private_key = RsaPrivateKey::from_pkcs8_pem(&pem)
+        .or_else(|_| RsaPrivateKey::from_pkcs1_pem(&pem))
+        .map_err(|_| invalid("expected an Ed25519 or RSA private key in PEM".to_owned()))?;
+    if private_key.size() * 8 < MIN_RSA_BITS {
+        return Err(invalid(format!(
+            "RSA keys must be at least {MIN_RSA_BITS} bits"
+        )));
+    }
+    let der = private_key
+        .to_pkcs1_der()
+        .map_err(|err| invalid(err.to_string()))?;
+    let (n, e) = (private_key.n().to_bytes_be(), private_key.e().to_bytes_be());
+    Ok(JwtKey {
+        algorithm: Algorithm::RS256,
+        active_from,
+        encoding_key: EncodingKey::from_rsa_der(der.as_bytes()),
+        decoding_key: DecodingKey::from_rsa_raw_components(&n, &e),
+        jwk: public_jwk(
+            &kid,
+            "RS256",
+            json!({ "kty": "RSA", "n": URL_SAFE_NO_PAD.encode(&n), "e": URL_SAFE_NO_PAD.encode(&e) }),
+        )?,
+        kid,
+    })
+}
+
+fn public_jwk(kid: &str, alg: &str, mut params: serde_json::Value) -> Result<Jwk, JwtKeyError> {
+    params["kid"] = json!(kid);
+    params["alg"] = json!(alg);
+    params["use"] = json!("sig");
+    serde_json::from_value(params)
+        .map_err(|err| JwtKeyError::InvalidKey(kid.to_owned(), err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+    use chrono::{TimeZone, Utc};
+    use ed25519_dalek::{SigningKey, pkcs8::EncodePrivateKey};
+    use jsonwebtoken::Algorithm;
+    use rsa::{RsaPrivateKey, pkcs8::DecodePrivateKey, traits::PublicKeyParts};
+
+    use super::JwtKeyring;
+
+    /// Generated with `openssl genpkey -algorithm RSA -pkeyopt rsa_keygen_bits:2048`;
+    /// only ever used by these tests.
+    const RSA_KEY_PEM: &str = include_str!("../../tests/fixtures/jwt/rs256_test_key.pem");
+
+    fn write_ed25519_key(dir: &std::path::Path, kid: &str) {
+        let pem = SigningKey::from_bytes(&rand::random())
+            .to_pkcs8_pem(Default::default())
+            .expect("encode key");
+        fs::write(dir.join(format!("{kid}.pem")), pem.as_bytes()).expect("write key");
+    }
+
+    fn verify(keyring: &JwtKeyring, token: &str) -> serde_json::Value {
+        let header = jsonwebtoken::decode_header(token).expect("token header");
+        let (algorithm, decoding_key) = keyring
+            .verification_key(&header)
+            .expect("key still verifies");
+        jsonwebtoken::decode::<serde_json::Value>(
+            token,
+            &decoding_key,
+            &jsonwebtoken::Validation::new(algorithm),
+        )
+        .expect("valid signature")
+        .claims
+    }
+
+    #[test]
+    fn scheduled_key_takes_over_signing_while_old_key_still_verifies() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        for kid in ["2024-01-01-current", "2024-06-01-next"] {
+            write_ed25519_key(dir.path(), kid);
+        }
+        let keyring = JwtKeyring::from_dir(dir.path(), None).expect("load keys");
+
+        let (header, _) = keyring
+            .signing_key(Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap())
+            .expect("active key");
+        assert_eq!(header.alg, Algorithm::EdDSA);
+        assert_eq!(header.kid.as_deref(), Some("2024-01-01-current"));
+        let (header, _) = keyring
+            .signing_key(Utc.with_ymd_and_hms(2024, 6, 2, 0, 0, 0).unwrap())
+            .expect("active key");
+        assert_eq!(header.kid.as_deref(), Some("2024-06-01-next"));
+
+        let (old_header, old_key) = keyring
+            .signing_key(Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap())
+            .expect("active key");
+        let claims = serde_json::json!({ "sub": "1", "exp": 4_102_444_800_u64 });
+        let token = jsonwebtoken::encode(&old_header, &claims, &old_key).expect("sign token");
+        assert_eq!(verify(&keyring, &token), claims);
+        assert!(
+            keyring
+                .verification_key(&jsonwebtoken::Header::default())
+                .is_none()
+        );
+        assert_eq!(keyring.jwks().keys.len(), 2);
+        assert!(
+            keyring
+                .signing_key(Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn rsa_key_signs_as_rs256_and_publishes_its_modulus_and_exponent() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        write_ed25519_key(dir.path(), "2024-01-01-ed");
+        fs::write(dir.path().join("2024-06-01-rsa.pem"), RSA_KEY_PEM).expect("write key");
+        let keyring = JwtKeyring::from_dir(dir.path(), None).expect("load keys");
+
+        let (header, key) = keyring
+            .signing_key(Utc.with_ymd_and_hms(2024, 7, 1, 0, 0, 0).unwrap())
+            .expect("active key");
+        assert_eq!(header.alg, Algorithm::RS256);
+        assert_eq!(header.kid.as_deref(), Some("2024-06-01-rsa"));
+        let claims = serde_json::json!({ "sub": "1", "exp": 4_102_444_800_u64 });
+        let token = jsonwebtoken::encode(&header, &claims, &key).expect("sign token");
+        assert_eq!(verify(&keyring, &token), claims);
+
+        // A token claiming the RSA kid under another algorithm is refused.
+        let mut wrong_alg = header.clone();
+        wrong_alg.alg = Algorithm::EdDSA;
+        assert!(keyring.verification_key(&wrong_alg).is_none());
+
+        let private_key = RsaPrivateKey::from_pkcs8_pem(RSA_KEY_PEM).expect("fixture key");
+        let jwks = serde_json::to_value(keyring.jwks()).expect("serialize jwks");
+        let rsa_jwk = jwks["keys"]
+            .as_array()
+            .expect("key list")
+            .iter()
+            .find(|jwk| jwk["kid"] == "2024-06-01-rsa")
+            .expect("rsa key published");
+        assert_eq!(rsa_jwk["kty"], "RSA");
+        assert_eq!(rsa_jwk["alg"], "RS256");
+        assert_eq!(rsa_jwk["use"], "sig");
+        assert_eq!(
+            rsa_jwk["n"],
+            URL_SAFE_NO_PAD.encode(private_key.n().to_bytes_be())
+        );
+        assert_eq!(rsa_jwk["e"], "AQAB");
+        assert!(rsa_jwk.get("d").is_none());
+    }
+}
@@ -0,0 +1,105 @@
+use std::{env, sync::Arc};
+
+use tracing::{info, warn};
+
+use crate::{
+    errors::AppError,
+    models::{LeechAction, LeechPolicy, ProblemCard},
+    ports::notification_sender::NotificationSender,
+    repositories::traits::LeechRepository,
+};
+
+impl LeechPolicy {
+    /// Reads `LEECH_THRESHOLD` (a positive lapse count) and `LEECH_ACTION`
+    /// (`tag`, `suspend` or `notify`); invalid values fall back to the defaults.
+    pub fn from_env() -> Self {
+        let mut policy = Self::default();
+        if let Ok(raw) = env::var("LEECH_THRESHOLD") {
+            match raw.trim().parse::<u32>().ok().filter(|value| *value > 0) {
+                Some(threshold) => policy.threshold = threshold,
+                None => warn!(variable = "LEECH_THRESHOLD", value = %raw, "leech_config_invalid"),
+            }
+        }
+        if let Ok(raw) = env::var("LEECH_ACTION") {
+            match LeechAction::parse(&raw.trim().to_lowercase()) {
+                Some(action) => policy.action = action,
+                None => warn!(variable = "LEECH_ACTION", value = %raw, "leech_config_invalid"),
+            }
+        }
+        policy
+    }
+}
+
+#[derive(Clone)]
+pub struct LeechService {
+    repo: Arc<dyn LeechRepository>,
+    policy: LeechPolicy,
+    sender: Arc<dyn NotificationSender>,
+}
+
+impl LeechService {
+    pub fn new(
+        repo: Arc<dyn LeechRepository>,
+        policy: LeechPolicy,
+        sender: Arc<dyn NotificationSender>,
+    ) -> Self {
+        Self {
+            repo,
+            policy,
+            sender,
+        }
+    }
+
+    pub fn policy(&self) -> LeechPolicy {
+        self.policy
+    }
+
+    /// Acts on a card that a grade just flagged as a leech. The grade is
+    /// already saved, so a failed notification is only logged.
+    pub async fn flagged(&self, user_id: i64, card: &ProblemCard) {
+        info!(
+            user_id,
+            card_id = card.id,
+            lapses = card.lapses,
+            action = self.policy.action.as_str(),
+            "leech_flagged"
+        );
+        if self.policy.action != LeechAction::Notify {
+            return;
+        }
+        let subject = format!("\"{}\" keeps slipping", card.title);
+        let body = format!(
+            "You have missed \"{}\" {} times. It is listed under leeches so you can restudy it from scratch.",
+            card.title, card.lapses
+        );
+        if let Err(err) = self.sender.send_digest(user_id, &subject, &body).await {
+            warn!(user_id, card_id = card.id, error = %err, "leech_notify_failed");
+        }
+    }
+
+    pub async fn list(&self, user_id: i64) -> Result<Vec<ProblemCard>, AppError> {
+        let cards = self
+            .repo
+            .list_leeches(user_id)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        info!(user_id, leech_count = cards.len(), "leeches_listed");
+        Ok(cards)
+    }
+
+    /// Marks a leech as restudied: lapses start over and the card returns to
+    /// the review queue.
+    pub async fn clear(&self, user_id: i64, card_id: i64) -> Result<ProblemCard, AppError> {
+        let card = self
+            .repo
+            .clear_leech(user_id, card_id)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?
+            .ok_or_else(|| {
+                warn!(user_id, card_id, "leech_clear_card_not_found");
+                AppError::CardNotFound
+            })?;
+        info!(user_id, card_id, "leech_cleared");
+        Ok(card)
+    }
+}
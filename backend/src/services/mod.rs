@@ -1,7 +1,18 @@
+pub mod activity;
+pub mod archive;
 pub mod auth;
+pub mod calendar;
+pub mod cards;
 pub mod dashboard;
 pub mod event;
+pub mod forecast;
+pub mod idempotency;
 pub mod integrations;
+pub mod jwt_keys;
+pub mod leech;
 pub mod notification;
+pub mod problems;
+pub mod push;
 pub mod review;
 pub mod settings;
+pub mod stats;
@@ -1,29 +1,56 @@
-use std::sync::Arc;
+use std::{env, sync::Arc};
 
-use chrono::{DateTime, Timelike, Utc};
+use chrono::{DateTime, Duration, Timelike, Utc};
 use tracing::{info, warn};
 
-use crate::{errors::AppError, ports::notification_sender::NotificationSender};
+use crate::{
+    dto::activity::ActivitySummary,
+    errors::AppError,
+    models::{GoalMetric, GoalPeriod, ProblemCard},
+    ports::notification_sender::NotificationSender,
+};
 
-use super::{review::ReviewService, settings::SettingsService};
+use super::{
+    activity::ActivityService, integrations::IntegrationsService, leech::LeechService,
+    review::ReviewService, settings::SettingsService,
+};
+
+const DEFAULT_TOKEN_EXPIRY_WARNING_DAYS: i64 = 7;
+const DIGEST_LEECH_LIMIT: usize = 5;
 
 #[derive(Clone)]
 pub struct NotificationService {
     settings_service: SettingsService,
     review_service: ReviewService,
+    activity_service: ActivityService,
+    leech_service: LeechService,
+    integrations_service: IntegrationsService,
     sender: Arc<dyn NotificationSender>,
+    token_expiry_warning: Duration,
 }
 
 impl NotificationService {
     pub fn new(
         settings_service: SettingsService,
         review_service: ReviewService,
+        activity_service: ActivityService,
+        leech_service: LeechService,
+        integrations_service: IntegrationsService,
         sender: Arc<dyn NotificationSender>,
     ) -> Self {
+        let warning_days = env::var("INTEGRATION_TOKEN_EXPIRY_WARNING_DAYS")
+            .ok()
+            .and_then(|value| value.parse::<i64>().ok())
+            .filter(|days| *days > 0)
+            .unwrap_or(DEFAULT_TOKEN_EXPIRY_WARNING_DAYS);
         Self {
             settings_service,
             review_service,
+            activity_service,
+            leech_service,
+            integrations_service,
             sender,
+            token_expiry_warning: Duration::days(warning_days),
         }
     }
 
@@ -50,15 +77,24 @@ impl NotificationService {
                 continue;
             }
             let due = self.review_service.due_cards(user.id, now).await?;
-            if due.is_empty() {
+            let activity = self.activity_service.summary(user.id, now).await?;
+            let goals_open = activity.goals.iter().any(|goal| !goal.met);
+            if due.is_empty() && !goals_open {
                 info!(user_id = user.id, "digest_skipped_no_due_cards");
                 continue;
             }
-            let subject = format!("SRS reminder: {} reviews due", due.len());
-            let body = format!(
+            let subject = if due.is_empty() {
+                "SRS reminder: goals in progress".to_owned()
+            } else {
+                format!("SRS reminder: {} reviews due", due.len())
+            };
+            let mut body = format!(
                 "You have {} due reviews. Open your dashboard to continue.",
                 due.len()
             );
+            body.push_str(&activity_digest(&activity));
+            let leeches = self.leech_service.list(user.id).await?;
+            body.push_str(&leech_digest(&leeches));
             self.sender
                 .send_digest(user.id, &subject, &body)
                 .await
@@ -68,4 +104,87 @@ impl NotificationService {
         info!("digest_tick_finished");
         Ok(())
     }
+
+    /// Warns owners once per token when an integration token is about to
+    /// expire, so scripts can be rotated before ingestion starts failing.
+    pub async fn process_token_expiry_once(&self, now: DateTime<Utc>) -> Result<(), AppError> {
+        let tokens = self
+            .integrations_service
+            .tokens_expiring_within(now, self.token_expiry_warning)
+            .await?;
+        for token in tokens {
+            let Some(expires_at) = token.expires_at else {
+                continue;
+            };
+            let subject = format!("API token \"{}\" expires soon", token.label);
+            let body = format!(
+                "Your API token \"{}\" expires on {}. Rotate it from the integrations page to keep your scripts working.",
+                token.label,
+                expires_at.format("%Y-%m-%d %H:%M UTC")
+            );
+            self.sender
+                .send_digest(token.user_id, &subject, &body)
+                .await
+                .map_err(|err| AppError::Internal(err.to_string()))?;
+            self.integrations_service
+                .mark_expiry_notified(token.id)
+                .await?;
+            info!(
+                user_id = token.user_id,
+                token_id = token.id,
+                "integration_token_expiry_notified"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The worst leeches, appended to the digest body so they get restudied.
+fn leech_digest(leeches: &[ProblemCard]) -> String {
+    if leeches.is_empty() {
+        return String::new();
+    }
+    let mut text = String::from("\n\nLeeches to restudy:");
+    for card in leeches.iter().take(DIGEST_LEECH_LIMIT) {
+        text.push_str(&format!("\n- {} ({} lapses)", card.title, card.lapses));
+    }
+    if leeches.len() > DIGEST_LEECH_LIMIT {
+        text.push_str(&format!(
+            "\n- and {} more",
+            leeches.len() - DIGEST_LEECH_LIMIT
+        ));
+    }
+    text
+}
+
+/// Streak and goal lines appended to the digest body.
+fn activity_digest(activity: &ActivitySummary) -> String {
+    let mut text = String::new();
+    if activity.streak.current > 0 {
+        text.push_str(&format!(
+            "\n\nCurrent streak: {} days (longest {}).",
+            activity.streak.current, activity.streak.longest
+        ));
+    }
+    if !activity.goals.is_empty() {
+        text.push_str("\n\nGoals:");
+        for progress in &activity.goals {
+            let metric = match progress.goal.metric {
+                GoalMetric::Reviews => "reviews",
+                GoalMetric::Solved => "problems solved",
+                GoalMetric::NewCards => "new cards",
+            };
+            let period = match progress.goal.period {
+                GoalPeriod::Day => "today",
+                GoalPeriod::Week => "this week",
+            };
+            text.push_str(&format!(
+                "\n- {metric} {period}: {}/{}{}",
+                progress.progress,
+                progress.goal.target,
+                if progress.met { " (met)" } else { "" }
+            ));
+        }
+    }
+    text
 }
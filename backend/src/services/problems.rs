@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use tracing::{info, warn};
+
+use crate::{
+    catalog::{CatalogFile, CatalogSeedReport},
+    dto::problems::ProblemResponse,
+    errors::AppError,
+    repositories::traits::ProblemRepository,
+    sources::SourceRegistry,
+};
+
+#[derive(Clone)]
+pub struct ProblemService {
+    repo: Arc<dyn ProblemRepository>,
+    sources: Arc<SourceRegistry>,
+}
+
+impl ProblemService {
+    pub fn new(repo: Arc<dyn ProblemRepository>, sources: Arc<SourceRegistry>) -> Self {
+        Self { repo, sources }
+    }
+
+    /// Looks a catalog entry up by its canonical slug or an alias, together
+    /// with how many users are learning it.
+    pub async fn get(&self, source: &str, slug: &str) -> Result<ProblemResponse, AppError> {
+        let source = source.trim().to_ascii_lowercase();
+        let problem = self
+            .repo
+            .find_problem(&source, slug.trim())
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?
+            .ok_or(AppError::ProblemNotFound)?;
+        let learners = self
+            .repo
+            .count_problem_learners(problem.id)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        Ok(ProblemResponse { problem, learners })
+    }
+
+    /// Writes the dataset into the catalog. Running it again with the same
+    /// file changes nothing; invalid entries are skipped and reported.
+    pub async fn seed_catalog(&self, catalog: &CatalogFile) -> Result<CatalogSeedReport, AppError> {
+        let (problems, rejected) = catalog.validate(&self.sources);
+        for reason in &rejected {
+            warn!(version = catalog.version, reason = %reason, "problem_catalog_entry_rejected");
+        }
+        let summary = self
+            .repo
+            .seed_problems(catalog.version, problems)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        let uncatalogued = self
+            .repo
+            .uncatalogued_problems()
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        info!(
+            version = catalog.version,
+            inserted = summary.inserted,
+            updated = summary.updated,
+            unchanged = summary.unchanged,
+            rejected = rejected.len(),
+            uncatalogued = uncatalogued.len(),
+            "problem_catalog_seeded"
+        );
+        Ok(CatalogSeedReport {
+            version: catalog.version,
+            summary,
+            rejected,
+            uncatalogued,
+        })
+    }
+}
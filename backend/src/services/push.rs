@@ -0,0 +1,119 @@
+use std::sync::Arc;
+
+use reqwest::Url;
+use tracing::{info, warn};
+
+use crate::{
+    errors::AppError,
+    models::PushSubscription,
+    ports::web_push::{PushEndpoints, VapidKeys, decode_subscription_keys},
+    repositories::traits::{NewPushSubscription, PushSubscriptionRepository},
+};
+
+#[derive(Clone)]
+pub struct PushService {
+    repo: Arc<dyn PushSubscriptionRepository>,
+    vapid: VapidKeys,
+    endpoints: PushEndpoints,
+}
+
+impl PushService {
+    pub fn new(
+        repo: Arc<dyn PushSubscriptionRepository>,
+        vapid: VapidKeys,
+        endpoints: PushEndpoints,
+    ) -> Self {
+        Self {
+            repo,
+            vapid,
+            endpoints,
+        }
+    }
+
+    /// Also accepts endpoints on `origin`'s scheme, host and port, skipping
+    /// the known push service check. The digest sender sharing these
+    /// endpoints posts to them as well.
+    pub fn with_endpoint_origin(self, origin: &Url) -> Self {
+        self.endpoints.allow_origin(origin);
+        self
+    }
+
+    pub fn vapid_public_key(&self) -> String {
+        self.vapid.public_key_base64()
+    }
+
+    pub async fn subscribe(
+        &self,
+        user_id: i64,
+        endpoint: &str,
+        p256dh: &str,
+        auth: &str,
+        device_label: &str,
+    ) -> Result<PushSubscription, AppError> {
+        let endpoint_url = Url::parse(endpoint.trim())
+            .map_err(|_| AppError::InvalidInput("push endpoint must be a URL".to_owned()))?;
+        if !self.endpoints.allows(&endpoint_url) {
+            warn!(
+                user_id,
+                host = endpoint_url.host_str().unwrap_or_default(),
+                "push_endpoint_rejected"
+            );
+            return Err(AppError::InvalidInput(
+                "push endpoint must be an https URL of a known push service".to_owned(),
+            ));
+        }
+        decode_subscription_keys(p256dh, auth)
+            .map_err(|err| AppError::InvalidInput(err.to_string()))?;
+        let device_label = match device_label.trim() {
+            "" => "Unknown device".to_owned(),
+            label => label.chars().take(120).collect(),
+        };
+        let subscription = self
+            .repo
+            .upsert_push_subscription(
+                user_id,
+                NewPushSubscription {
+                    endpoint: endpoint_url.to_string(),
+                    p256dh: p256dh.to_owned(),
+                    auth: auth.to_owned(),
+                    device_label,
+                },
+            )
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        info!(
+            user_id,
+            subscription_id = subscription.id,
+            "push_subscription_saved"
+        );
+        Ok(subscription)
+    }
+
+    pub async fn list(&self, user_id: i64) -> Result<Vec<PushSubscription>, AppError> {
+        let subscriptions = self
+            .repo
+            .list_push_subscriptions(user_id)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        info!(
+            user_id,
+            subscription_count = subscriptions.len(),
+            "push_subscriptions_listed"
+        );
+        Ok(subscriptions)
+    }
+
+    pub async fn unsubscribe(&self, user_id: i64, subscription_id: i64) -> Result<(), AppError> {
+        let deleted = self
+            .repo
+            .delete_push_subscription(user_id, subscription_id)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        if !deleted {
+            warn!(user_id, subscription_id, "push_subscription_not_found");
+            return Err(AppError::PushSubscriptionNotFound);
+        }
+        info!(user_id, subscription_id, "push_subscription_deleted");
+        Ok(())
+    }
+}
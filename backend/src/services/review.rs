@@ -10,14 +10,20 @@ use crate::{
     srs::Grade,
 };
 
+use super::leech::LeechService;
+
 #[derive(Clone)]
 pub struct ReviewService {
     repo: Arc<dyn ReviewRepository>,
+    leech_service: LeechService,
 }
 
 impl ReviewService {
-    pub fn new(repo: Arc<dyn ReviewRepository>) -> Self {
-        Self { repo }
+    pub fn new(repo: Arc<dyn ReviewRepository>, leech_service: LeechService) -> Self {
+        Self {
+            repo,
+            leech_service,
+        }
     }
 
     pub async fn due_cards(
@@ -48,24 +54,42 @@ impl ReviewService {
         Ok(cards)
     }
 
+    pub async fn list_cards(&self, user_id: i64) -> Result<Vec<ProblemCard>, AppError> {
+        let cards = self
+            .repo
+            .list_cards(user_id)
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+        info!(user_id, card_count = cards.len(), "review_list_cards");
+        Ok(cards)
+    }
+
     pub async fn grade_card(
         &self,
         user_id: i64,
         card_id: i64,
         grade: Grade,
     ) -> Result<ReviewEvent, AppError> {
-        let maybe_review = self
+        let maybe_graded = self
             .repo
-            .grade_card(user_id, card_id, grade)
+            .grade_card(user_id, card_id, grade, self.leech_service.policy())
             .await
             .map_err(|err| AppError::Internal(err.to_string()))?;
 
-        let review = maybe_review.ok_or_else(|| {
+        let graded = maybe_graded.ok_or_else(|| {
             warn!(user_id, card_id, "review_grade_card_not_found");
             AppError::CardNotFound
         })?;
-        info!(user_id, card_id, review_id = review.id, "review_graded");
-        Ok(review)
+        info!(
+            user_id,
+            card_id,
+            review_id = graded.review.id,
+            "review_graded"
+        );
+        if let Some(card) = &graded.leech {
+            self.leech_service.flagged(user_id, card).await;
+        }
+        Ok(graded.review)
     }
 
     pub async fn history(&self, user_id: i64) -> Result<Vec<ReviewEvent>, AppError> {
@@ -2,7 +2,8 @@ use std::sync::Arc;
 use tracing::{info, warn};
 
 use crate::{
-    errors::AppError, models::NotificationPreference, repositories::traits::SettingsRepository,
+    activity::parse_timezone, errors::AppError, models::NotificationPreference,
+    repositories::traits::SettingsRepository,
 };
 
 #[derive(Clone)]
@@ -40,10 +41,18 @@ impl SettingsService {
         user_id: i64,
         email_enabled: bool,
         digest_hour_utc: u8,
+        timezone: Option<&str>,
     ) -> Result<NotificationPreference, AppError> {
+        let timezone = timezone
+            .map(|name| {
+                parse_timezone(name)
+                    .map(|timezone| timezone.name())
+                    .ok_or_else(|| AppError::InvalidInput(format!("unknown timezone {name}")))
+            })
+            .transpose()?;
         let maybe_pref = self
             .repo
-            .set_notification_preference(user_id, email_enabled, digest_hour_utc)
+            .set_notification_preference(user_id, email_enabled, digest_hour_utc, timezone)
             .await
             .map_err(|err| AppError::Internal(err.to_string()))?;
 
@@ -55,6 +64,7 @@ impl SettingsService {
             user_id,
             email_enabled = pref.email_enabled,
             digest_hour_utc = pref.digest_hour_utc,
+            timezone = %pref.timezone,
             "settings_saved"
         );
         Ok(pref)
@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use tracing::info;
+
+use crate::{
+    dto::stats::{
+        GradeCounts, IntervalBucket, SourceStats, StatsQuery, StatsResponse, TagStats, WeeklyStats,
+    },
+    errors::AppError,
+    repositories::traits::StatsRepository,
+    srs::Grade,
+};
+
+const MAX_WEEKS: u32 = 104;
+const MAX_MATURE_DAYS: u32 = 365;
+
+#[derive(Clone)]
+pub struct StatsService {
+    repo: Arc<dyn StatsRepository>,
+}
+
+impl StatsService {
+    pub fn new(repo: Arc<dyn StatsRepository>) -> Self {
+        Self { repo }
+    }
+
+    pub async fn stats(
+        &self,
+        user_id: i64,
+        query: StatsQuery,
+        now: DateTime<Utc>,
+    ) -> Result<StatsResponse, AppError> {
+        if !(1..=MAX_WEEKS).contains(&query.weeks) {
+            return Err(AppError::InvalidInput(format!(
+                "weeks must be between 1 and {MAX_WEEKS}"
+            )));
+        }
+        if !(1..=MAX_MATURE_DAYS).contains(&query.mature_days) {
+            return Err(AppError::InvalidInput(format!(
+                "mature_days must be between 1 and {MAX_MATURE_DAYS}"
+            )));
+        }
+        let first_week = week_start(now.date_naive()) - Duration::weeks(i64::from(query.weeks - 1));
+        let since = first_week.and_time(chrono::NaiveTime::MIN).and_utc();
+        let stats = self
+            .repo
+            .review_stats(user_id, since, Duration::days(query.mature_days.into()))
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))?;
+
+        let weekly = (0..query.weeks)
+            .map(|offset| {
+                let week_start = first_week + Duration::weeks(offset.into());
+                let counts = stats
+                    .weeks
+                    .iter()
+                    .find(|(week, _)| *week == week_start)
+                    .map(|(_, counts)| *counts)
+                    .unwrap_or_default();
+                WeeklyStats {
+                    week_start,
+                    stats: counts.into(),
+                }
+            })
+            .collect();
+        let mut grades = GradeCounts::default();
+        for (grade, count) in stats.grades {
+            let slot = match grade {
+                Grade::Again => &mut grades.again,
+                Grade::Hard => &mut grades.hard,
+                Grade::Good => &mut grades.good,
+                Grade::Easy => &mut grades.easy,
+            };
+            *slot += count;
+        }
+        info!(
+            user_id,
+            weeks = query.weeks,
+            reviews = stats.totals.reviews,
+            "stats_built"
+        );
+        Ok(StatsResponse {
+            since,
+            mature_days: query.mature_days,
+            totals: stats.totals.into(),
+            average_review_gap_seconds: stats.average_review_gap_seconds,
+            weekly,
+            grades,
+            interval_histogram: stats
+                .interval_indexes
+                .into_iter()
+                .map(|(interval_index, cards)| IntervalBucket {
+                    interval_index,
+                    cards,
+                })
+                .collect(),
+            sources: stats
+                .sources
+                .into_iter()
+                .map(|(source, counts)| SourceStats {
+                    source,
+                    stats: counts.into(),
+                })
+                .collect(),
+            tags: stats
+                .tags
+                .into_iter()
+                .map(|(tag, counts)| TagStats {
+                    tag,
+                    stats: counts.into(),
+                })
+                .collect(),
+        })
+    }
+}
+
+fn week_start(day: NaiveDate) -> NaiveDate {
+    day - Duration::days(day.weekday().num_days_from_monday().into())
+}
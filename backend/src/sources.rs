@@ -0,0 +1,547 @@
+use std::{collections::BTreeMap, fs};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::warn;
+use url::Url;
+
+use crate::models::{CUSTOM_CARD_SOURCE, ProblemStatus};
+
+pub const DEFAULT_SOURCES_PATH: &str = "config/sources.yaml";
+
+/// Compiled in so a missing or broken file still leaves the stock sources.
+const BUILTIN_SOURCES: &str = include_str!("../config/sources.yaml");
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SourceDefinition {
+    pub id: String,
+    pub display_name: String,
+    /// Hosts event URLs may point at; `www.` is accepted on top of these.
+    pub hosts: Vec<String>,
+    /// Regex a normalized slug must match in full.
+    pub slug_pattern: String,
+    /// Canonical problem URL. Its `{placeholders}` are the parts of the
+    /// problem's identity; the slug is those parts joined with `/`.
+    pub url_template: String,
+    /// Other URL paths that identify a problem, as regexes with one named
+    /// group per identity part.
+    #[serde(default)]
+    pub url_patterns: Vec<String>,
+    /// Platform verdicts, matched case-insensitively. Verdicts not listed
+    /// count as unsolved attempts.
+    #[serde(default)]
+    pub verdicts: BTreeMap<String, ProblemStatus>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SourcesFile {
+    pub sources: Vec<SourceDefinition>,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SourceError {
+    #[error("unknown source '{0}'")]
+    UnknownSource(String),
+    #[error("url is not a valid absolute URL")]
+    InvalidUrl,
+    #[error("url host '{host}' does not belong to source '{source_id}'")]
+    HostNotAllowed { source_id: String, host: String },
+    #[error("problem_slug '{slug}' is not a valid {source_id} slug")]
+    InvalidSlug { source_id: String, slug: String },
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SourcesConfigError {
+    #[error("cannot read {path}: {message}")]
+    Read { path: String, message: String },
+    #[error("invalid sources file {path}: {message}")]
+    Invalid { path: String, message: String },
+}
+
+/// An event's identity after it has been checked against the registry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizedProblem {
+    pub source: String,
+    pub problem_slug: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct RegisteredSource {
+    pub definition: SourceDefinition,
+    /// Identity parts in slug order, e.g. `["contest", "index"]`.
+    parts: Vec<String>,
+    slug: Regex,
+    /// The template's own path first, then `url_patterns`. Each matches a
+    /// path prefix, so trailing tabs like `/description/` fall away.
+    url_paths: Vec<Regex>,
+}
+
+impl RegisteredSource {
+    pub fn new(mut definition: SourceDefinition) -> Result<Self, String> {
+        let id = definition.id.clone();
+        if id.trim().is_empty() || id != id.to_lowercase() {
+            return Err(format!("source id '{id}' must be lowercase"));
+        }
+        if id == CUSTOM_CARD_SOURCE {
+            return Err(format!("source id '{id}' is reserved for authored cards"));
+        }
+        if definition.hosts.is_empty() {
+            return Err(format!("source '{id}' lists no hosts"));
+        }
+        let (origin, path) = split_template(&definition.url_template)
+            .ok_or_else(|| format!("source '{id}' url_template is not an absolute URL"))?;
+        if origin.contains('{') {
+            return Err(format!(
+                "source '{id}' url_template has placeholders in its host"
+            ));
+        }
+        let (template_path, parts) = template_path_pattern(path);
+        if parts.is_empty() {
+            return Err(format!("source '{id}' url_template has no placeholders"));
+        }
+        let slug = Regex::new(&format!("^(?:{})$", definition.slug_pattern))
+            .map_err(|err| format!("source '{id}' slug_pattern: {err}"))?;
+        let mut url_paths = Vec::with_capacity(definition.url_patterns.len() + 1);
+        for pattern in std::iter::once(&template_path).chain(&definition.url_patterns) {
+            let regex = Regex::new(&format!("^(?:{pattern})(?:/.*)?$"))
+                .map_err(|err| format!("source '{id}' url pattern: {err}"))?;
+            let names = regex.capture_names().flatten().collect::<Vec<_>>();
+            if let Some(missing) = parts.iter().find(|part| !names.contains(&part.as_str())) {
+                return Err(format!(
+                    "source '{id}' url pattern '{pattern}' does not capture '{missing}'"
+                ));
+            }
+            url_paths.push(regex);
+        }
+        definition.verdicts = definition
+            .verdicts
+            .into_iter()
+            .map(|(verdict, status)| (normalize_verdict(&verdict), status))
+            .collect();
+        Ok(Self {
+            definition,
+            parts,
+            slug,
+            url_paths,
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.definition.id
+    }
+
+    pub fn canonical_url(&self, slug: &str) -> String {
+        self.parts.iter().zip(slug.split('/')).fold(
+            self.definition.url_template.clone(),
+            |url, (part, value)| url.replace(&format!("{{{part}}}"), value),
+        )
+    }
+
+    /// Maps a platform verdict such as `WRONG_ANSWER` or `AC` to a status.
+    pub fn status_for_verdict(&self, verdict: &str) -> ProblemStatus {
+        self.definition
+            .verdicts
+            .get(&normalize_verdict(verdict))
+            .cloned()
+            .unwrap_or(ProblemStatus::Unsolved)
+    }
+
+    fn allows_host(&self, host: &str) -> bool {
+        let host = host.to_ascii_lowercase();
+        let host = host.strip_prefix("www.").unwrap_or(&host);
+        self.definition
+            .hosts
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(host))
+    }
+
+    fn is_valid_slug(&self, slug: &str) -> bool {
+        self.slug.is_match(slug) && slug.split('/').count() == self.parts.len()
+    }
+
+    fn slug_from_path(&self, path: &str) -> Option<String> {
+        let path = path.to_lowercase();
+        self.url_paths.iter().find_map(|pattern| {
+            let captures = pattern.captures(&path)?;
+            let slug = self
+                .parts
+                .iter()
+                .map(|part| captures.name(part).map(|value| value.as_str()))
+                .collect::<Option<Vec<_>>>()?
+                .join("/");
+            self.is_valid_slug(&slug).then_some(slug)
+        })
+    }
+
+    /// Trims, lowercases and drops trailing path segments, so
+    /// `Two-Sum/description/` becomes `two-sum`.
+    fn normalize_slug(&self, raw: &str) -> Option<String> {
+        let cleaned = raw.trim().trim_matches('/').to_lowercase();
+        let segments = cleaned.split('/').collect::<Vec<_>>();
+        (1..=segments.len())
+            .rev()
+            .map(|len| segments[..len].join("/"))
+            .find(|candidate| self.is_valid_slug(candidate))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SourceRegistry {
+    sources: Vec<RegisteredSource>,
+}
+
+impl SourceRegistry {
+    pub fn from_definitions(definitions: Vec<SourceDefinition>) -> Result<Self, String> {
+        let mut sources: Vec<RegisteredSource> = Vec::with_capacity(definitions.len());
+        for definition in definitions {
+            if sources.iter().any(|source| source.id() == definition.id) {
+                return Err(format!("source '{}' is defined twice", definition.id));
+            }
+            sources.push(RegisteredSource::new(definition)?);
+        }
+        if sources.is_empty() {
+            return Err("no sources defined".to_owned());
+        }
+        Ok(Self { sources })
+    }
+
+    /// The sources shipped in `config/sources.yaml`.
+    pub fn builtin() -> Self {
+        let file =
+            serde_yaml::from_str::<SourcesFile>(BUILTIN_SOURCES).expect("built-in sources parse");
+        Self::from_definitions(file.sources).expect("built-in sources are valid")
+    }
+
+    pub fn sources(&self) -> &[RegisteredSource] {
+        &self.sources
+    }
+
+    pub fn get(&self, id: &str) -> Option<&RegisteredSource> {
+        self.sources.iter().find(|source| source.id() == id)
+    }
+
+    pub fn status_for_verdict(
+        &self,
+        source: &str,
+        verdict: &str,
+    ) -> Result<ProblemStatus, SourceError> {
+        self.get(&source.trim().to_lowercase())
+            .map(|registered| registered.status_for_verdict(verdict))
+            .ok_or_else(|| SourceError::UnknownSource(source.trim().to_owned()))
+    }
+
+    /// Checks an incoming event against its source and returns the canonical
+    /// slug and URL. A slug found in the URL wins over `problem_slug`, since
+    /// page scrapers often send the last path segment (`description`).
+    pub fn normalize(
+        &self,
+        source: &str,
+        problem_slug: &str,
+        url: &str,
+    ) -> Result<NormalizedProblem, SourceError> {
+        let source_id = source.trim().to_lowercase();
+        let registered = self
+            .get(&source_id)
+            .ok_or_else(|| SourceError::UnknownSource(source.trim().to_owned()))?;
+
+        let mut slug_from_url = None;
+        if !url.trim().is_empty() {
+            let parsed = Url::parse(url.trim()).map_err(|_| SourceError::InvalidUrl)?;
+            let host = parsed.host_str().ok_or(SourceError::InvalidUrl)?;
+            if !registered.allows_host(host) {
+                return Err(SourceError::HostNotAllowed {
+                    source_id,
+                    host: host.to_owned(),
+                });
+            }
+            slug_from_url = registered.slug_from_path(parsed.path());
+        }
+        let problem_slug = slug_from_url
+            .or_else(|| registered.normalize_slug(problem_slug))
+            .ok_or_else(|| SourceError::InvalidSlug {
+                source_id: source_id.clone(),
+                slug: problem_slug.trim().to_owned(),
+            })?;
+        Ok(NormalizedProblem {
+            url: registered.canonical_url(&problem_slug),
+            source: source_id,
+            problem_slug,
+        })
+    }
+}
+
+impl Default for SourceRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+/// Loads the registry from `config_path`, which must be readable and valid.
+/// Without one, `config/sources.yaml` is used when it is there and valid, and
+/// the compiled-in copy otherwise.
+pub fn load_source_registry(
+    config_path: Option<&str>,
+) -> Result<SourceRegistry, SourcesConfigError> {
+    let Some(path) = config_path else {
+        return Ok(match read_sources_file(DEFAULT_SOURCES_PATH) {
+            Ok(registry) => registry,
+            Err(SourcesConfigError::Read { .. }) => SourceRegistry::builtin(),
+            Err(err) => {
+                warn!(path = DEFAULT_SOURCES_PATH, error = %err, "source_registry_invalid");
+                SourceRegistry::builtin()
+            }
+        });
+    };
+    read_sources_file(path)
+}
+
+fn read_sources_file(path: &str) -> Result<SourceRegistry, SourcesConfigError> {
+    let invalid = |message: String| SourcesConfigError::Invalid {
+        path: path.to_owned(),
+        message,
+    };
+    let contents = fs::read_to_string(path).map_err(|err| SourcesConfigError::Read {
+        path: path.to_owned(),
+        message: err.to_string(),
+    })?;
+    let file =
+        serde_yaml::from_str::<SourcesFile>(&contents).map_err(|err| invalid(err.to_string()))?;
+    SourceRegistry::from_definitions(file.sources).map_err(invalid)
+}
+
+/// Splits `https://host/path/{slug}` into `https://host` and `/path/{slug}`.
+fn split_template(template: &str) -> Option<(&str, &str)> {
+    let after_scheme = template.find("://")? + 3;
+    let path_start = template[after_scheme..]
+        .find('/')
+        .map_or(template.len(), |offset| after_scheme + offset);
+    Url::parse(&template[..path_start]).ok()?;
+    Some(template.split_at(path_start))
+}
+
+/// Turns a template path into a regex with one named group per placeholder
+/// and returns the placeholder names in order. A placeholder used twice (as
+/// in AtCoder's `{contest}_{index}` task ids) is captured only the first time.
+fn template_path_pattern(path: &str) -> (String, Vec<String>) {
+    let mut pattern = String::new();
+    let mut parts: Vec<String> = Vec::new();
+    let mut rest = path.trim_end_matches('/');
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}').map(|offset| open + offset) else {
+            break;
+        };
+        pattern.push_str(&regex::escape(&rest[..open]));
+        let name = &rest[open + 1..close];
+        if parts.iter().any(|part| part == name) {
+            pattern.push_str("[^/]+?");
+        } else {
+            pattern.push_str(&format!("(?P<{name}>[^/]+?)"));
+            parts.push(name.to_owned());
+        }
+        rest = &rest[close + 1..];
+    }
+    pattern.push_str(&regex::escape(rest));
+    (pattern, parts)
+}
+
+fn normalize_verdict(verdict: &str) -> String {
+    verdict.trim().to_lowercase().replace([' ', '-'], "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::models::{CUSTOM_CARD_SOURCE, ProblemStatus};
+
+    use super::{SourceError, SourceRegistry, SourcesConfigError, load_source_registry};
+
+    #[test]
+    fn normalizes_slug_and_url_for_registered_sources() {
+        let registry = SourceRegistry::builtin();
+        let problem = registry
+            .normalize(
+                "LeetCode",
+                "description",
+                "https://leetcode.com/problems/Two-Sum/description/?envType=study-plan",
+            )
+            .expect("valid event");
+        assert_eq!(problem.source, "leetcode");
+        assert_eq!(problem.problem_slug, "two-sum");
+        assert_eq!(problem.url, "https://leetcode.com/problems/two-sum/");
+
+        let problem = registry
+            .normalize("neetcode", " Two-Sum/description/ ", "")
+            .expect("slug only");
+        assert_eq!(problem.problem_slug, "two-sum");
+        assert_eq!(problem.url, "https://neetcode.io/problems/two-sum");
+    }
+
+    #[test]
+    fn builds_multi_part_identities_from_any_known_url_form() {
+        let registry = SourceRegistry::builtin();
+        let cases = [
+            (
+                "codeforces",
+                "",
+                "https://codeforces.com/contest/1520/problem/A",
+                "1520/a",
+                "https://codeforces.com/problemset/problem/1520/a",
+            ),
+            (
+                "codeforces",
+                "1520/A",
+                "",
+                "1520/a",
+                "https://codeforces.com/problemset/problem/1520/a",
+            ),
+            (
+                "atcoder",
+                "",
+                "https://atcoder.jp/contests/abc300/tasks/abc300_a?lang=en",
+                "abc300/a",
+                "https://atcoder.jp/contests/abc300/tasks/abc300_a",
+            ),
+            (
+                "hackerrank",
+                "",
+                "https://www.hackerrank.com/contests/week-1/challenges/solve-me-first",
+                "solve-me-first",
+                "https://www.hackerrank.com/challenges/solve-me-first/problem",
+            ),
+            (
+                "exercism",
+                "rust/two-fer",
+                "https://exercism.org/tracks/rust/exercises/two-fer/solutions",
+                "rust/two-fer",
+                "https://exercism.org/tracks/rust/exercises/two-fer",
+            ),
+        ];
+        for (source, slug, url, expected_slug, expected_url) in cases {
+            let problem = registry
+                .normalize(source, slug, url)
+                .unwrap_or_else(|err| panic!("{source} {url}: {err}"));
+            assert_eq!(problem.problem_slug, expected_slug);
+            assert_eq!(problem.url, expected_url);
+        }
+        assert!(matches!(
+            registry.normalize("codeforces", "two-sum", ""),
+            Err(SourceError::InvalidSlug { .. })
+        ));
+    }
+
+    #[test]
+    fn maps_platform_verdicts_per_source() {
+        let registry = SourceRegistry::builtin();
+        for (source, verdict, expected) in [
+            ("codeforces", "OK", ProblemStatus::Solved),
+            ("codeforces", "WRONG_ANSWER", ProblemStatus::Unsolved),
+            ("atcoder", "AC", ProblemStatus::Solved),
+            ("atcoder", "TLE", ProblemStatus::Unsolved),
+            ("exercism", "published", ProblemStatus::Solved),
+            ("exercism", "iterated", ProblemStatus::Unsolved),
+            ("leetcode", "Wrong Answer", ProblemStatus::Unsolved),
+        ] {
+            assert_eq!(
+                registry.status_for_verdict(source, verdict),
+                Ok(expected),
+                "{source} {verdict}"
+            );
+        }
+        assert!(registry.status_for_verdict("codewars", "ok").is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_sources_foreign_hosts_and_bad_slugs() {
+        let registry = SourceRegistry::builtin();
+        assert_eq!(
+            registry.normalize("codewars", "two-sum", ""),
+            Err(SourceError::UnknownSource("codewars".to_owned()))
+        );
+        assert!(matches!(
+            registry.normalize(
+                "leetcode",
+                "two-sum",
+                "https://evil.example/problems/two-sum"
+            ),
+            Err(SourceError::HostNotAllowed { .. })
+        ));
+        assert!(matches!(
+            registry.normalize("leetcode", "two sum!", ""),
+            Err(SourceError::InvalidSlug { .. })
+        ));
+        assert_eq!(
+            registry.normalize("leetcode", "two-sum", "not a url"),
+            Err(SourceError::InvalidUrl)
+        );
+    }
+
+    #[test]
+    fn loads_sources_file_and_rejects_invalid_ones() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("sources.yaml");
+        fs::write(
+            &path,
+            r#"
+sources:
+  - id: kattis
+    display_name: Kattis
+    hosts: [open.kattis.com]
+    slug_pattern: "[a-z0-9]+"
+    url_template: "https://open.kattis.com/problems/{slug}"
+"#,
+        )
+        .expect("write sources");
+        let registry = load_source_registry(path.to_str()).expect("kattis registry");
+        assert_eq!(registry.sources().len(), 1);
+        assert_eq!(
+            registry
+                .normalize("kattis", "", "https://open.kattis.com/problems/hello/")
+                .expect("kattis event")
+                .problem_slug,
+            "hello"
+        );
+
+        fs::write(&path, "sources: []").expect("write sources");
+        assert!(matches!(
+            load_source_registry(path.to_str()),
+            Err(SourcesConfigError::Invalid { .. })
+        ));
+        fs::write(&path, "sources: [").expect("write sources");
+        assert!(matches!(
+            load_source_registry(path.to_str()),
+            Err(SourcesConfigError::Invalid { .. })
+        ));
+        assert!(matches!(
+            load_source_registry(dir.path().join("missing.yaml").to_str()),
+            Err(SourcesConfigError::Read { .. })
+        ));
+        assert!(
+            load_source_registry(None)
+                .unwrap()
+                .get("leetcode")
+                .is_some()
+        );
+
+        fs::write(
+            &path,
+            format!(
+                r#"
+sources:
+  - id: {CUSTOM_CARD_SOURCE}
+    display_name: Notes
+    hosts: [notes.example.com]
+    slug_pattern: "[a-z]+"
+    url_template: "https://notes.example.com/{{slug}}"
+"#
+            ),
+        )
+        .expect("write sources");
+        assert!(matches!(
+            load_source_registry(path.to_str()),
+            Err(SourcesConfigError::Invalid { .. })
+        ));
+    }
+}
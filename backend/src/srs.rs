@@ -77,6 +77,47 @@ impl SrsSchedule {
             IntervalUnit::Minutes => Duration::minutes(value),
         }
     }
+
+    /// Expected reviews per day if every review happens on its due day and
+    /// passes (`Good`) with probability `pass_rate`, otherwise `Again`.
+    /// `cards` holds each card's due day (as an offset from the first day)
+    /// and interval index. Intervals under a day still push the next review
+    /// to the following day.
+    pub fn project_daily_reviews(
+        &self,
+        cards: &[(usize, usize)],
+        days: usize,
+        pass_rate: f64,
+    ) -> Vec<f64> {
+        let interval_days = |index: usize| {
+            let days = self.duration_for_index(index).num_seconds() as f64 / 86_400.0;
+            (days.round() as usize).max(1)
+        };
+        let mut scheduled = vec![vec![0.0_f64; self.max_index() + 1]; days];
+        for &(day, index) in cards {
+            if day < days {
+                scheduled[day][index.min(self.max_index())] += 1.0;
+            }
+        }
+        let mut projected = vec![0.0; days];
+        for day in 0..days {
+            for index in 0..=self.max_index() {
+                let reviews = scheduled[day][index];
+                if reviews == 0.0 {
+                    continue;
+                }
+                projected[day] += reviews;
+                for (grade, share) in [(Grade::Good, pass_rate), (Grade::Again, 1.0 - pass_rate)] {
+                    let next_index = next_interval_index(index, grade, self.max_index());
+                    let next_day = day + interval_days(next_index);
+                    if next_day < days {
+                        scheduled[next_day][next_index] += reviews * share;
+                    }
+                }
+            }
+        }
+        projected
+    }
 }
 
 impl Default for SrsSchedule {
@@ -151,6 +192,30 @@ mod tests {
         assert_eq!(schedule.duration_for_index(1).num_minutes(), 3);
     }
 
+    #[test]
+    fn projects_follow_up_reviews_under_the_schedule() {
+        let schedule = SrsSchedule::from_profile(ScheduleProfile {
+            unit: IntervalUnit::Days,
+            intervals: vec![1, 3],
+        })
+        .expect("valid schedule");
+        let always_pass = schedule.project_daily_reviews(&[(0, 0), (2, 1)], 6, 1.0);
+        assert_eq!(always_pass, vec![1.0, 0.0, 1.0, 1.0, 0.0, 1.0]);
+
+        let coin_flip = schedule.project_daily_reviews(&[(0, 0)], 3, 0.5);
+        assert_eq!(coin_flip, vec![1.0, 0.5, 0.25]);
+
+        let minutes = SrsSchedule::from_profile(ScheduleProfile {
+            unit: IntervalUnit::Minutes,
+            intervals: vec![1, 3],
+        })
+        .expect("valid schedule");
+        assert_eq!(
+            minutes.project_daily_reviews(&[(0, 1)], 3, 1.0),
+            vec![1.0, 1.0, 1.0]
+        );
+    }
+
     #[test]
     fn maps_profile_duration_seconds() {
         let schedule = SrsSchedule::from_profile(ScheduleProfile {
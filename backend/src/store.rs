@@ -1,11 +1,18 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
 use rand::{Rng, distr::Alphanumeric};
 
+use crate::activity::{local_date, parse_timezone};
 use crate::models::{
-    EmailDeliveryLog, IngestProblemInput, IntegrationToken, MagicLinkToken, NotificationPreference,
-    ProblemCard, ProblemEvent, ReviewEvent, Session, User, hash_token, make_event_dedup_key,
+    AccountDeletionRequest, ArchiveImport, ArchiveImportSummary, CUSTOM_CARD_SOURCE,
+    CalendarFeedToken, CardContent, CardKind, CatalogProblemInput, CatalogSeedSummary,
+    DailyActivity, EmailDeliveryLog, Goal, GoalMetric, GoalPeriod, GradedReview, IdempotencyClaim,
+    IdempotencyRecord, ImportMode, IngestProblemInput, IngestedEvent, IntegrationToken, LEECH_TAG,
+    LeechAction, LeechPolicy, MagicLinkToken, NotificationPreference, OidcAuthRequest,
+    PasskeyCredential, Problem, ProblemCard, ProblemEvent, ProblemStatus, PushSubscription,
+    RetentionCounts, ReviewEvent, ReviewStats, Session, StoredResponse, UncataloguedProblem, User,
+    UserIdentity, WebauthnCeremony, WebauthnChallenge, hash_token, make_event_dedup_key,
 };
 use crate::srs::{Grade, SrsSchedule, next_interval_index};
 
@@ -15,13 +22,23 @@ pub struct InMemoryStore {
     pub users_by_email: HashMap<String, i64>,
     pub magic_tokens: HashMap<i64, MagicLinkToken>,
     pub sessions: HashMap<i64, Session>,
+    pub user_identities: HashMap<(String, String), UserIdentity>,
+    pub oidc_auth_requests: HashMap<String, OidcAuthRequest>,
+    pub passkeys: HashMap<i64, PasskeyCredential>,
+    pub webauthn_challenges: HashMap<String, WebauthnChallenge>,
+    pub account_deletion_requests: HashMap<String, AccountDeletionRequest>,
     pub integration_tokens: HashMap<i64, IntegrationToken>,
+    pub calendar_feed_tokens: HashMap<i64, CalendarFeedToken>,
     pub events: HashMap<i64, ProblemEvent>,
     pub cards: HashMap<i64, ProblemCard>,
     pub card_index: HashMap<String, i64>,
     pub reviews: HashMap<i64, ReviewEvent>,
     pub notification_preferences: HashMap<i64, NotificationPreference>,
     pub email_logs: HashMap<i64, EmailDeliveryLog>,
+    pub push_subscriptions: HashMap<i64, PushSubscription>,
+    pub idempotency_keys: HashMap<(i64, String), IdempotencyRecord>,
+    pub problems: HashMap<i64, Problem>,
+    pub goals: HashMap<i64, Goal>,
     pub schedule: SrsSchedule,
     dedup: HashSet<String>,
     next_id: i64,
@@ -58,6 +75,7 @@ impl InMemoryStore {
                 user_id: user.id,
                 email_enabled: true,
                 digest_hour_utc: 12,
+                timezone: "UTC".to_owned(),
             },
         );
         self.users.insert(user.id, user.clone());
@@ -102,25 +120,313 @@ impl InMemoryStore {
         self.users.get(&user_id).cloned()
     }
 
-    pub fn user_from_session(&self, bearer_token: &str) -> Option<User> {
+    pub fn create_session(
+        &mut self,
+        user_id: i64,
+        session_token_hash: String,
+        expires_at: DateTime<Utc>,
+        ip: Option<String>,
+        user_agent: Option<String>,
+    ) -> Session {
+        let now = Utc::now();
+        let session = Session {
+            id: self.new_id(),
+            user_id,
+            session_token_hash,
+            expires_at,
+            created_at: now,
+            last_seen_at: now,
+            revoked_at: None,
+            user_agent,
+            ip,
+        };
+        self.sessions.insert(session.id, session.clone());
+        session
+    }
+
+    pub fn user_from_session(&self, session_token_hash: &str) -> Option<(User, Session)> {
         let now = Utc::now();
-        let token_hash = hash_token(bearer_token);
-        let user_id = self.sessions.values().find_map(|session| {
-            (session.session_token_hash == token_hash && session.expires_at > now)
-                .then_some(session.user_id)
+        let session = self.sessions.values().find(|session| {
+            session.session_token_hash == session_token_hash
+                && session.expires_at > now
+                && session.revoked_at.is_none()
         })?;
-        self.users.get(&user_id).cloned()
+        let user = self.users.get(&session.user_id).cloned()?;
+        Some((user, session.clone()))
+    }
+
+    pub fn touch_session(&mut self, session_id: i64) {
+        if let Some(session) = self.sessions.get_mut(&session_id) {
+            session.last_seen_at = Utc::now();
+        }
     }
 
-    pub fn revoke_session(&mut self, bearer_token: &str) {
-        let token_hash = hash_token(bearer_token);
-        let to_remove = self
+    pub fn list_active_sessions(&self, user_id: i64) -> Vec<Session> {
+        let now = Utc::now();
+        let mut sessions: Vec<_> = self
             .sessions
-            .iter()
-            .find_map(|(id, session)| (session.session_token_hash == token_hash).then_some(*id));
-        if let Some(id) = to_remove {
-            self.sessions.remove(&id);
+            .values()
+            .filter(|session| {
+                session.user_id == user_id
+                    && session.expires_at > now
+                    && session.revoked_at.is_none()
+            })
+            .cloned()
+            .collect();
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.last_seen_at));
+        sessions
+    }
+
+    pub fn revoke_session(&mut self, user_id: i64, session_id: i64) -> bool {
+        let Some(session) = self.sessions.get_mut(&session_id) else {
+            return false;
+        };
+        if session.user_id != user_id || session.revoked_at.is_some() {
+            return false;
+        }
+        session.revoked_at = Some(Utc::now());
+        true
+    }
+
+    pub fn revoke_other_sessions(&mut self, user_id: i64, keep_session_id: i64) -> u64 {
+        let now = Utc::now();
+        let mut revoked = 0;
+        for session in self.sessions.values_mut() {
+            if session.user_id == user_id
+                && session.id != keep_session_id
+                && session.revoked_at.is_none()
+            {
+                session.revoked_at = Some(now);
+                revoked += 1;
+            }
+        }
+        revoked
+    }
+
+    pub fn create_oidc_auth_request(
+        &mut self,
+        state_hash: String,
+        nonce: String,
+        pkce_verifier: String,
+        expires_at: DateTime<Utc>,
+    ) {
+        let now = Utc::now();
+        self.oidc_auth_requests
+            .retain(|_, request| request.expires_at > now);
+        let request = OidcAuthRequest {
+            id: self.new_id(),
+            state_hash: state_hash.clone(),
+            nonce,
+            pkce_verifier,
+            created_at: now,
+            expires_at,
+        };
+        self.oidc_auth_requests.insert(state_hash, request);
+    }
+
+    pub fn consume_oidc_auth_request(&mut self, state_hash: &str) -> Option<OidcAuthRequest> {
+        self.oidc_auth_requests
+            .remove(state_hash)
+            .filter(|request| request.expires_at > Utc::now())
+    }
+
+    pub fn user_from_identity(&self, issuer: &str, subject: &str) -> Option<User> {
+        let identity = self
+            .user_identities
+            .get(&(issuer.to_owned(), subject.to_owned()))?;
+        self.users.get(&identity.user_id).cloned()
+    }
+
+    pub fn link_identity(
+        &mut self,
+        user_id: i64,
+        issuer: &str,
+        subject: &str,
+        email: Option<&str>,
+    ) -> UserIdentity {
+        let now = Utc::now();
+        let key = (issuer.to_owned(), subject.to_owned());
+        if let Some(identity) = self.user_identities.get_mut(&key) {
+            identity.email = email.map(ToOwned::to_owned);
+            identity.last_login_at = now;
+            return identity.clone();
+        }
+        let identity = UserIdentity {
+            id: self.new_id(),
+            user_id,
+            issuer: issuer.to_owned(),
+            subject: subject.to_owned(),
+            email: email.map(ToOwned::to_owned),
+            created_at: now,
+            last_login_at: now,
+        };
+        self.user_identities.insert(key, identity.clone());
+        identity
+    }
+
+    pub fn create_webauthn_challenge(
+        &mut self,
+        challenge_hash: String,
+        ceremony: WebauthnCeremony,
+        user_id: Option<i64>,
+        expires_at: DateTime<Utc>,
+    ) {
+        let now = Utc::now();
+        self.webauthn_challenges
+            .retain(|_, challenge| challenge.expires_at > now);
+        let challenge = WebauthnChallenge {
+            id: self.new_id(),
+            challenge_hash: challenge_hash.clone(),
+            ceremony,
+            user_id,
+            created_at: now,
+            expires_at,
+        };
+        self.webauthn_challenges.insert(challenge_hash, challenge);
+    }
+
+    pub fn consume_webauthn_challenge(
+        &mut self,
+        challenge_hash: &str,
+        ceremony: WebauthnCeremony,
+    ) -> Option<WebauthnChallenge> {
+        self.webauthn_challenges
+            .remove(challenge_hash)
+            .filter(|challenge| challenge.ceremony == ceremony && challenge.expires_at > Utc::now())
+    }
+
+    pub fn create_passkey(
+        &mut self,
+        user_id: i64,
+        credential_id: String,
+        public_key: Vec<u8>,
+        algorithm: i64,
+        sign_count: i64,
+        label: String,
+    ) -> Option<PasskeyCredential> {
+        if self
+            .passkeys
+            .values()
+            .any(|passkey| passkey.credential_id == credential_id)
+        {
+            return None;
+        }
+        let passkey = PasskeyCredential {
+            id: self.new_id(),
+            user_id,
+            credential_id,
+            public_key,
+            algorithm,
+            sign_count,
+            label,
+            created_at: Utc::now(),
+            last_used_at: None,
+        };
+        self.passkeys.insert(passkey.id, passkey.clone());
+        Some(passkey)
+    }
+
+    pub fn list_passkeys(&self, user_id: i64) -> Vec<PasskeyCredential> {
+        let mut passkeys = self
+            .passkeys
+            .values()
+            .filter(|passkey| passkey.user_id == user_id)
+            .cloned()
+            .collect::<Vec<_>>();
+        passkeys.sort_by_key(|passkey| passkey.created_at);
+        passkeys
+    }
+
+    pub fn passkey_by_credential_id(
+        &self,
+        credential_id: &str,
+    ) -> Option<(User, PasskeyCredential)> {
+        let passkey = self
+            .passkeys
+            .values()
+            .find(|passkey| passkey.credential_id == credential_id)?;
+        let user = self.users.get(&passkey.user_id)?;
+        Some((user.clone(), passkey.clone()))
+    }
+
+    pub fn record_passkey_use(&mut self, passkey_id: i64, sign_count: i64) {
+        if let Some(passkey) = self.passkeys.get_mut(&passkey_id) {
+            passkey.sign_count = sign_count;
+            passkey.last_used_at = Some(Utc::now());
+        }
+    }
+
+    pub fn delete_passkey(&mut self, user_id: i64, passkey_id: i64) -> bool {
+        if self
+            .passkeys
+            .get(&passkey_id)
+            .is_some_and(|passkey| passkey.user_id == user_id)
+        {
+            self.passkeys.remove(&passkey_id);
+            return true;
+        }
+        false
+    }
+
+    pub fn create_account_deletion_request(
+        &mut self,
+        user_id: i64,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) {
+        let request = AccountDeletionRequest {
+            id: self.new_id(),
+            user_id,
+            token_hash: token_hash.to_owned(),
+            created_at: Utc::now(),
+            expires_at,
+        };
+        self.account_deletion_requests
+            .insert(request.token_hash.clone(), request);
+    }
+
+    pub fn delete_account(&mut self, user_id: i64, token_hash: &str) -> bool {
+        let confirmed = self
+            .account_deletion_requests
+            .get(token_hash)
+            .is_some_and(|request| request.user_id == user_id && request.expires_at > Utc::now());
+        if !confirmed {
+            return false;
+        }
+        if let Some(user) = self.users.remove(&user_id) {
+            self.users_by_email.remove(&user.email);
         }
+        self.magic_tokens
+            .retain(|_, token| token.user_id != user_id);
+        self.sessions
+            .retain(|_, session| session.user_id != user_id);
+        self.user_identities
+            .retain(|_, identity| identity.user_id != user_id);
+        self.passkeys
+            .retain(|_, passkey| passkey.user_id != user_id);
+        self.webauthn_challenges
+            .retain(|_, challenge| challenge.user_id != Some(user_id));
+        self.account_deletion_requests
+            .retain(|_, request| request.user_id != user_id);
+        self.integration_tokens
+            .retain(|_, token| token.user_id != user_id);
+        self.calendar_feed_tokens
+            .retain(|_, token| token.user_id != user_id);
+        self.events.retain(|_, event| event.user_id != user_id);
+        self.dedup
+            .retain(|key| !key.starts_with(&format!("{user_id}:")));
+        self.cards.retain(|_, card| card.user_id != user_id);
+        self.card_index
+            .retain(|key, _| !key.starts_with(&format!("{user_id}:")));
+        self.reviews.retain(|_, review| review.user_id != user_id);
+        self.notification_preferences.remove(&user_id);
+        self.email_logs.retain(|_, log| log.user_id != user_id);
+        self.push_subscriptions
+            .retain(|_, subscription| subscription.user_id != user_id);
+        self.idempotency_keys
+            .retain(|(owner, _), _| *owner != user_id);
+        self.goals.retain(|_, goal| goal.user_id != user_id);
+        true
     }
 
     pub fn create_integration_token(
@@ -141,6 +447,11 @@ impl InMemoryStore {
             expires_at,
             revoked_at: None,
             last_used_at: None,
+            last_used_ip: None,
+            last_used_user_agent: None,
+            previous_token_hash: None,
+            previous_token_expires_at: None,
+            expiry_notified_at: None,
         };
         self.integration_tokens.insert(token.id, token.clone());
         token
@@ -168,22 +479,143 @@ impl InMemoryStore {
         true
     }
 
-    pub fn user_from_integration_token(&self, token_hash: &str) -> Option<User> {
+    pub fn user_from_integration_token(
+        &self,
+        token_hash: &str,
+    ) -> Option<(User, IntegrationToken)> {
         let now = Utc::now();
-        let user_id = self.integration_tokens.values().find_map(|token| {
+        let token = self.integration_tokens.values().find(|token| {
             let not_expired = token
                 .expires_at
                 .map(|expires| expires > now)
                 .unwrap_or(true);
-            (token.token_hash == token_hash && token.revoked_at.is_none() && not_expired)
-                .then_some(token.user_id)
+            let previous_valid = token.previous_token_hash.as_deref() == Some(token_hash)
+                && token
+                    .previous_token_expires_at
+                    .map(|expires| expires > now)
+                    .unwrap_or(false);
+            (token.token_hash == token_hash || previous_valid)
+                && token.revoked_at.is_none()
+                && not_expired
+        })?;
+        let user = self.users.get(&token.user_id).cloned()?;
+        Some((user, token.clone()))
+    }
+
+    pub fn rotate_integration_token(
+        &mut self,
+        user_id: i64,
+        token_id: i64,
+        token_hash: String,
+        previous_valid_until: Option<DateTime<Utc>>,
+        expires_at: Option<Option<DateTime<Utc>>>,
+    ) -> Option<IntegrationToken> {
+        let token = self.integration_tokens.get_mut(&token_id)?;
+        if token.user_id != user_id || token.revoked_at.is_some() {
+            return None;
+        }
+        let previous_hash = std::mem::replace(&mut token.token_hash, token_hash);
+        token.previous_token_hash = previous_valid_until.map(|_| previous_hash);
+        token.previous_token_expires_at = previous_valid_until;
+        if let Some(expires_at) = expires_at {
+            token.expires_at = expires_at;
+            token.expiry_notified_at = None;
+        }
+        Some(token.clone())
+    }
+
+    pub fn touch_integration_token_usage(
+        &mut self,
+        token_id: i64,
+        ip: Option<String>,
+        user_agent: Option<String>,
+    ) {
+        if let Some(token) = self.integration_tokens.get_mut(&token_id) {
+            token.last_used_at = Some(Utc::now());
+            token.last_used_ip = ip;
+            token.last_used_user_agent = user_agent;
+        }
+    }
+
+    pub fn list_integration_tokens_expiring_between(
+        &self,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Vec<IntegrationToken> {
+        let mut tokens: Vec<_> = self
+            .integration_tokens
+            .values()
+            .filter(|token| {
+                token.revoked_at.is_none()
+                    && token.expiry_notified_at.is_none()
+                    && token
+                        .expires_at
+                        .map(|expires| expires > from && expires <= until)
+                        .unwrap_or(false)
+            })
+            .cloned()
+            .collect();
+        tokens.sort_by_key(|token| token.expires_at);
+        tokens
+    }
+
+    pub fn mark_integration_token_expiry_notified(&mut self, token_id: i64) {
+        if let Some(token) = self.integration_tokens.get_mut(&token_id) {
+            token.expiry_notified_at = Some(Utc::now());
+        }
+    }
+
+    pub fn create_calendar_feed_token(
+        &mut self,
+        user_id: i64,
+        token_hash: String,
+        label: String,
+    ) -> CalendarFeedToken {
+        let token = CalendarFeedToken {
+            id: self.new_id(),
+            user_id,
+            token_hash,
+            label,
+            created_at: Utc::now(),
+            revoked_at: None,
+            last_used_at: None,
+        };
+        self.calendar_feed_tokens.insert(token.id, token.clone());
+        token
+    }
+
+    pub fn list_calendar_feed_tokens(&self, user_id: i64) -> Vec<CalendarFeedToken> {
+        let mut tokens: Vec<_> = self
+            .calendar_feed_tokens
+            .values()
+            .filter(|token| token.user_id == user_id)
+            .cloned()
+            .collect();
+        tokens.sort_by_key(|token| std::cmp::Reverse(token.created_at));
+        tokens
+    }
+
+    pub fn revoke_calendar_feed_token(&mut self, user_id: i64, token_id: i64) -> bool {
+        let Some(token) = self.calendar_feed_tokens.get_mut(&token_id) else {
+            return false;
+        };
+        if token.user_id != user_id || token.revoked_at.is_some() {
+            return false;
+        }
+        token.revoked_at = Some(Utc::now());
+        true
+    }
+
+    pub fn user_from_calendar_feed_token(&self, token_hash: &str) -> Option<User> {
+        let user_id = self.calendar_feed_tokens.values().find_map(|token| {
+            (token.token_hash == token_hash && token.revoked_at.is_none()).then_some(token.user_id)
         })?;
         self.users.get(&user_id).cloned()
     }
 
-    pub fn touch_integration_token_usage(&mut self, token_hash: &str) {
+    pub fn touch_calendar_feed_token_usage(&mut self, token_hash: &str) {
         if let Some(token) = self
-            .integration_tokens
+            .calendar_feed_tokens
             .values_mut()
             .find(|token| token.token_hash == token_hash)
         {
@@ -191,7 +623,169 @@ impl InMemoryStore {
         }
     }
 
-    pub fn ingest_event(&mut self, payload: IngestProblemInput) -> ProblemEvent {
+    /// Looks a problem up by its slug or one of its aliases.
+    pub fn find_problem(&self, source: &str, slug: &str) -> Option<Problem> {
+        let mut matches = self
+            .problems
+            .values()
+            .filter(|problem| problem.source == source);
+        matches
+            .clone()
+            .find(|problem| problem.slug == slug)
+            .or_else(|| matches.find(|problem| problem.aliases.iter().any(|alias| alias == slug)))
+            .cloned()
+    }
+
+    pub fn count_problem_learners(&self, problem_id: i64) -> i64 {
+        self.cards
+            .values()
+            .filter(|card| card.problem_id == Some(problem_id))
+            .map(|card| card.user_id)
+            .collect::<HashSet<_>>()
+            .len() as i64
+    }
+
+    pub fn seed_problems(
+        &mut self,
+        version: i64,
+        problems: Vec<CatalogProblemInput>,
+    ) -> CatalogSeedSummary {
+        let mut summary = CatalogSeedSummary::default();
+        let now = Utc::now();
+        for input in problems {
+            let existing = self
+                .problems
+                .values_mut()
+                .find(|problem| problem.source == input.source && problem.slug == input.slug);
+            match existing {
+                Some(problem)
+                    if problem.title.as_deref() == Some(input.title.as_str())
+                        && problem.url == input.url
+                        && problem.difficulty == input.difficulty
+                        && problem.tags == input.tags
+                        && problem.aliases == input.aliases
+                        && problem.catalog_version == Some(version) =>
+                {
+                    summary.unchanged += 1;
+                }
+                Some(problem) => {
+                    problem.title = Some(input.title);
+                    problem.url = input.url;
+                    problem.difficulty = input.difficulty;
+                    problem.tags = input.tags;
+                    problem.aliases = input.aliases;
+                    problem.catalog_version = Some(version);
+                    problem.updated_at = now;
+                    summary.updated += 1;
+                }
+                None => {
+                    let id = self.new_id();
+                    self.problems.insert(
+                        id,
+                        Problem {
+                            id,
+                            source: input.source,
+                            slug: input.slug,
+                            title: Some(input.title),
+                            url: input.url,
+                            difficulty: input.difficulty,
+                            tags: input.tags,
+                            aliases: input.aliases,
+                            catalog_version: Some(version),
+                            created_at: now,
+                            updated_at: now,
+                        },
+                    );
+                    summary.inserted += 1;
+                }
+            }
+        }
+        let problems: Vec<_> = self.problems.values().cloned().collect();
+        for card in self
+            .cards
+            .values_mut()
+            .filter(|card| card.kind == CardKind::Problem)
+        {
+            if let Some(problem) = problems
+                .iter()
+                .find(|problem| problem.source == card.source && problem.slug == card.problem_slug)
+            {
+                card.problem_id = Some(problem.id);
+                if let Some(title) = &problem.title {
+                    card.title = title.clone();
+                    card.url = problem.url.clone();
+                    card.tags = problem.tags.clone();
+                    card.difficulty = problem.difficulty.clone();
+                }
+            }
+        }
+        summary
+    }
+
+    pub fn uncatalogued_problems(&self) -> Vec<UncataloguedProblem> {
+        let mut uncatalogued = self
+            .problems
+            .values()
+            .filter(|problem| problem.catalog_version.is_none())
+            .filter_map(|problem| {
+                let title = self
+                    .cards
+                    .values()
+                    .filter(|card| card.problem_id == Some(problem.id))
+                    .map(|card| card.title.clone())
+                    .min()?;
+                Some(UncataloguedProblem {
+                    source: problem.source.clone(),
+                    slug: problem.slug.clone(),
+                    title,
+                    learners: self.count_problem_learners(problem.id),
+                })
+            })
+            .collect::<Vec<_>>();
+        uncatalogued.sort_by(|a, b| {
+            b.learners
+                .cmp(&a.learners)
+                .then_with(|| (&a.source, &a.slug).cmp(&(&b.source, &b.slug)))
+        });
+        uncatalogued
+    }
+
+    fn catalog_entry(&self, source: &str, slug: &str) -> Option<Problem> {
+        self.problems
+            .values()
+            .find(|problem| problem.source == source && problem.slug == slug)
+            .cloned()
+    }
+
+    /// The catalog entry for a problem, added with only its identity when
+    /// there is none. `url` is the source registry's canonical URL.
+    fn catalog_problem(&mut self, source: &str, slug: &str, url: &str) -> Problem {
+        if let Some(problem) = self.catalog_entry(source, slug) {
+            return problem;
+        }
+        let now = Utc::now();
+        let problem = Problem {
+            id: self.new_id(),
+            source: source.to_owned(),
+            slug: slug.to_owned(),
+            title: None,
+            url: url.to_owned(),
+            difficulty: None,
+            tags: Vec::new(),
+            aliases: Vec::new(),
+            catalog_version: None,
+            created_at: now,
+            updated_at: now,
+        };
+        self.problems.insert(problem.id, problem.clone());
+        problem
+    }
+
+    pub fn ingest_event(&mut self, mut payload: IngestProblemInput) -> ProblemEvent {
+        if let Some(problem) = self.find_problem(&payload.source, &payload.problem_slug) {
+            payload.problem_slug = problem.slug;
+            payload.url = problem.url;
+        }
         let dedup_key = make_event_dedup_key(
             payload.user_id,
             &payload.source,
@@ -235,25 +829,61 @@ impl InMemoryStore {
 
         let interval_index = 0;
         let next_due_at = payload.occurred_at + self.schedule.duration_for_index(interval_index);
+        let problem = self.catalog_problem(&payload.source, &payload.problem_slug, &payload.url);
+        let existing = self.cards.get(&card_id);
         let card = ProblemCard {
             id: card_id,
             user_id: payload.user_id,
+            kind: CardKind::Problem,
             source: payload.source,
             problem_slug: payload.problem_slug,
-            title: payload.title,
-            url: payload.url,
+            title: problem.title.unwrap_or(payload.title),
+            url: problem.url,
+            front: None,
+            back: None,
+            tags: problem.tags,
+            problem_id: Some(problem.id),
+            difficulty: problem.difficulty,
             interval_index,
             next_due_at,
+            created_at: existing.map_or(payload.occurred_at, |card| {
+                card.created_at.min(payload.occurred_at)
+            }),
+            lapses: existing.map_or(0, |card| card.lapses),
+            leech_at: existing.and_then(|card| card.leech_at),
+            suspended_at: existing.and_then(|card| card.suspended_at),
         };
         self.cards.insert(card_id, card);
         event
     }
 
+    pub fn ingest_events(&mut self, payloads: Vec<IngestProblemInput>) -> Vec<IngestedEvent> {
+        payloads
+            .into_iter()
+            .map(|payload| {
+                let dedup_key = make_event_dedup_key(
+                    payload.user_id,
+                    &payload.source,
+                    &payload.problem_slug,
+                    &payload.status,
+                    payload.occurred_at,
+                );
+                let created = !self.dedup.contains(&dedup_key);
+                IngestedEvent {
+                    event: self.ingest_event(payload),
+                    created,
+                }
+            })
+            .collect()
+    }
+
     pub fn due_cards(&self, user_id: i64, now: DateTime<Utc>) -> Vec<ProblemCard> {
         let mut cards: Vec<_> = self
             .cards
             .values()
-            .filter(|card| card.user_id == user_id && card.next_due_at <= now)
+            .filter(|card| {
+                card.user_id == user_id && card.next_due_at <= now && card.suspended_at.is_none()
+            })
             .cloned()
             .collect();
         cards.sort_by_key(|card| card.next_due_at);
@@ -264,7 +894,7 @@ impl InMemoryStore {
         let mut cards: Vec<_> = self
             .cards
             .values()
-            .filter(|card| card.user_id == user_id)
+            .filter(|card| card.user_id == user_id && card.suspended_at.is_none())
             .cloned()
             .collect();
         cards.sort_by_key(|card| card.next_due_at);
@@ -272,7 +902,130 @@ impl InMemoryStore {
         cards
     }
 
-    pub fn grade_card(&mut self, user_id: i64, card_id: i64, grade: Grade) -> Option<ReviewEvent> {
+    pub fn list_cards(&self, user_id: i64) -> Vec<ProblemCard> {
+        let mut cards: Vec<_> = self
+            .cards
+            .values()
+            .filter(|card| card.user_id == user_id)
+            .cloned()
+            .collect();
+        cards.sort_by_key(|card| card.next_due_at);
+        cards
+    }
+
+    pub fn create_card(
+        &mut self,
+        user_id: i64,
+        key: &str,
+        content: CardContent,
+        next_due_at: DateTime<Utc>,
+    ) -> Option<ProblemCard> {
+        let card_key = format!("{user_id}:{CUSTOM_CARD_SOURCE}:{key}");
+        if self.card_index.contains_key(&card_key) {
+            return None;
+        }
+        let card = ProblemCard {
+            id: self.new_id(),
+            user_id,
+            kind: content.kind,
+            source: CUSTOM_CARD_SOURCE.to_owned(),
+            problem_slug: key.to_owned(),
+            title: content.title,
+            url: String::new(),
+            front: Some(content.front),
+            back: content.back,
+            tags: content.tags,
+            problem_id: None,
+            difficulty: None,
+            interval_index: 0,
+            next_due_at,
+            created_at: Utc::now(),
+            lapses: 0,
+            leech_at: None,
+            suspended_at: None,
+        };
+        self.card_index.insert(card_key, card.id);
+        self.cards.insert(card.id, card.clone());
+        Some(card)
+    }
+
+    pub fn get_card(&self, user_id: i64, card_id: i64) -> Option<ProblemCard> {
+        self.cards
+            .get(&card_id)
+            .filter(|card| card.user_id == user_id)
+            .cloned()
+    }
+
+    pub fn update_card(
+        &mut self,
+        user_id: i64,
+        card_id: i64,
+        content: CardContent,
+    ) -> Option<ProblemCard> {
+        let card = self
+            .cards
+            .get_mut(&card_id)
+            .filter(|card| card.user_id == user_id && card.kind != CardKind::Problem)?;
+        card.kind = content.kind;
+        card.title = content.title;
+        card.front = Some(content.front);
+        card.back = content.back;
+        card.tags = content.tags;
+        Some(card.clone())
+    }
+
+    pub fn delete_card(&mut self, user_id: i64, card_id: i64) -> bool {
+        let Some(card) = self.get_card(user_id, card_id) else {
+            return false;
+        };
+        self.cards.remove(&card_id);
+        self.card_index.remove(&format!(
+            "{}:{}:{}",
+            card.user_id, card.source, card.problem_slug
+        ));
+        self.reviews.retain(|_, review| review.card_id != card_id);
+        true
+    }
+
+    pub fn cards_by_keys(&self, user_id: i64, keys: &[String]) -> Vec<ProblemCard> {
+        keys.iter()
+            .filter_map(|key| {
+                self.card_index
+                    .get(&format!("{user_id}:{CUSTOM_CARD_SOURCE}:{key}"))
+            })
+            .filter_map(|card_id| self.cards.get(card_id))
+            .cloned()
+            .collect()
+    }
+
+    pub fn upsert_cards(
+        &mut self,
+        user_id: i64,
+        cards: Vec<(String, CardContent)>,
+        next_due_at: DateTime<Utc>,
+    ) -> Vec<ProblemCard> {
+        cards
+            .into_iter()
+            .filter_map(|(key, content)| {
+                let existing = self
+                    .card_index
+                    .get(&format!("{user_id}:{CUSTOM_CARD_SOURCE}:{key}"))
+                    .copied();
+                match existing {
+                    Some(card_id) => self.update_card(user_id, card_id, content),
+                    None => self.create_card(user_id, &key, content, next_due_at),
+                }
+            })
+            .collect()
+    }
+
+    pub fn grade_card(
+        &mut self,
+        user_id: i64,
+        card_id: i64,
+        grade: Grade,
+        leech: LeechPolicy,
+    ) -> Option<GradedReview> {
         let next_due_at = {
             let card = self.cards.get_mut(&card_id)?;
             if card.user_id != user_id {
@@ -281,6 +1034,9 @@ impl InMemoryStore {
             card.interval_index =
                 next_interval_index(card.interval_index, grade, self.schedule.max_index());
             card.next_due_at = Utc::now() + self.schedule.duration_for_index(card.interval_index);
+            if grade == Grade::Again {
+                card.lapses += 1;
+            }
             card.next_due_at
         };
         let review = ReviewEvent {
@@ -292,7 +1048,12 @@ impl InMemoryStore {
             next_due_at,
         };
         self.reviews.insert(review.id, review.clone());
-        Some(review)
+        let leech = if grade == Grade::Again {
+            self.flag_leech(card_id, leech, review.reviewed_at)
+        } else {
+            None
+        };
+        Some(GradedReview { review, leech })
     }
 
     pub fn user_history(&self, user_id: i64) -> Vec<ReviewEvent> {
@@ -306,19 +1067,285 @@ impl InMemoryStore {
         items
     }
 
-    pub fn get_notification_preference(&self, user_id: i64) -> Option<NotificationPreference> {
-        self.notification_preferences.get(&user_id).cloned()
-    }
-
-    pub fn set_notification_preference(
+    /// Mirrors the Postgres stats query; see [`ReviewStats`].
+    pub fn review_stats(
+        &self,
+        user_id: i64,
+        since: DateTime<Utc>,
+        mature_after: Duration,
+    ) -> ReviewStats {
+        fn add(counts: &mut RetentionCounts, mature: bool, passed: bool, lapse: bool) {
+            counts.reviews += 1;
+            counts.mature_reviews += i64::from(mature);
+            counts.mature_passed += i64::from(mature && passed);
+            counts.lapses += i64::from(lapse);
+        }
+        fn ranked(groups: HashMap<String, RetentionCounts>) -> Vec<(String, RetentionCounts)> {
+            let mut groups = groups.into_iter().collect::<Vec<_>>();
+            groups.sort_by(|(a, a_counts), (b, b_counts)| {
+                b_counts
+                    .reviews
+                    .cmp(&a_counts.reviews)
+                    .then_with(|| a.cmp(b))
+            });
+            groups
+        }
+
+        let mut history = self
+            .reviews
+            .values()
+            .filter(|review| review.user_id == user_id)
+            .collect::<Vec<_>>();
+        history.sort_by_key(|review| (review.card_id, review.reviewed_at, review.id));
+
+        let mut totals = RetentionCounts::default();
+        let mut weeks: BTreeMap<NaiveDate, RetentionCounts> = BTreeMap::new();
+        let mut grades = [0_i64; 4];
+        let mut sources: HashMap<String, RetentionCounts> = HashMap::new();
+        let mut tags: HashMap<String, RetentionCounts> = HashMap::new();
+        let mut gaps = Vec::new();
+        for (index, review) in history.iter().enumerate() {
+            if review.reviewed_at < since {
+                continue;
+            }
+            let prior = index
+                .checked_sub(1)
+                .map(|prior| history[prior])
+                .filter(|prior| prior.card_id == review.card_id);
+            let mature =
+                prior.is_some_and(|prior| prior.next_due_at - prior.reviewed_at >= mature_after);
+            let passed = review.grade != Grade::Again;
+            let lapse = !passed && prior.is_some_and(|prior| prior.grade != Grade::Again);
+            if let Some(prior) = prior {
+                gaps.push(
+                    (review.reviewed_at - prior.reviewed_at).num_milliseconds() as f64 / 1000.0,
+                );
+            }
+            let day = review.reviewed_at.date_naive();
+            let week_start = day - Duration::days(day.weekday().num_days_from_monday().into());
+
+            add(&mut totals, mature, passed, lapse);
+            add(weeks.entry(week_start).or_default(), mature, passed, lapse);
+            grades[review.grade as usize] += 1;
+            let card = self.cards.get(&review.card_id);
+            let problem = card
+                .and_then(|card| card.problem_id)
+                .and_then(|id| self.problems.get(&id));
+            if let Some(card) = card {
+                add(
+                    sources.entry(card.source.clone()).or_default(),
+                    mature,
+                    passed,
+                    lapse,
+                );
+            }
+            let card_tags = problem
+                .map(|problem| &problem.tags)
+                .filter(|tags| !tags.is_empty())
+                .or(card.map(|card| &card.tags));
+            for tag in card_tags.into_iter().flatten() {
+                add(tags.entry(tag.clone()).or_default(), mature, passed, lapse);
+            }
+        }
+
+        let mut interval_indexes: BTreeMap<usize, i64> = BTreeMap::new();
+        for card in self.cards.values().filter(|card| card.user_id == user_id) {
+            *interval_indexes.entry(card.interval_index).or_default() += 1;
+        }
+        ReviewStats {
+            totals,
+            average_review_gap_seconds: (!gaps.is_empty())
+                .then(|| gaps.iter().sum::<f64>() / gaps.len() as f64),
+            weeks: weeks.into_iter().collect(),
+            grades: [Grade::Again, Grade::Hard, Grade::Good, Grade::Easy]
+                .into_iter()
+                .zip(grades)
+                .filter(|(_, count)| *count > 0)
+                .collect(),
+            sources: ranked(sources),
+            tags: ranked(tags),
+            interval_indexes: interval_indexes.into_iter().collect(),
+        }
+    }
+
+    pub fn daily_activity(
+        &self,
+        user_id: i64,
+        timezone: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Vec<DailyActivity> {
+        let timezone = parse_timezone(timezone).unwrap_or(chrono_tz::UTC);
+        let mut days = BTreeMap::<NaiveDate, DailyActivity>::new();
+        let mut tally = |at: DateTime<Utc>, add: fn(&mut DailyActivity)| {
+            let date = local_date(at, timezone);
+            if (from..=to).contains(&date) {
+                add(days
+                    .entry(date)
+                    .or_insert_with(|| DailyActivity::empty(date)));
+            }
+        };
+        for review in self
+            .reviews
+            .values()
+            .filter(|review| review.user_id == user_id)
+        {
+            tally(review.reviewed_at, |activity| activity.reviews += 1);
+        }
+        let mut solved = HashSet::new();
+        for event in self
+            .events
+            .values()
+            .filter(|event| event.user_id == user_id && event.status == ProblemStatus::Solved)
+        {
+            let date = local_date(event.occurred_at, timezone);
+            if solved.insert((date, &event.source, &event.problem_slug)) {
+                tally(event.occurred_at, |activity| activity.solved += 1);
+            }
+        }
+        for card in self.cards.values().filter(|card| card.user_id == user_id) {
+            tally(card.created_at, |activity| activity.new_cards += 1);
+        }
+        days.into_values().collect()
+    }
+
+    pub fn active_days(&self, user_id: i64, timezone: &str) -> Vec<NaiveDate> {
+        let timezone = parse_timezone(timezone).unwrap_or(chrono_tz::UTC);
+        let reviewed = self
+            .reviews
+            .values()
+            .filter(|review| review.user_id == user_id)
+            .map(|review| review.reviewed_at);
+        let solved = self
+            .events
+            .values()
+            .filter(|event| event.user_id == user_id && event.status == ProblemStatus::Solved)
+            .map(|event| event.occurred_at);
+        reviewed
+            .chain(solved)
+            .map(|at| local_date(at, timezone))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    pub fn list_goals(&self, user_id: i64) -> Vec<Goal> {
+        let mut goals: Vec<_> = self
+            .goals
+            .values()
+            .filter(|goal| goal.user_id == user_id)
+            .cloned()
+            .collect();
+        goals.sort_by_key(|goal| goal.id);
+        goals
+    }
+
+    pub fn upsert_goal(
+        &mut self,
+        user_id: i64,
+        metric: GoalMetric,
+        period: GoalPeriod,
+        target: i64,
+    ) -> Goal {
+        if let Some(goal) = self
+            .goals
+            .values_mut()
+            .find(|goal| goal.user_id == user_id && goal.metric == metric && goal.period == period)
+        {
+            goal.target = target;
+            return goal.clone();
+        }
+        let goal = Goal {
+            id: self.new_id(),
+            user_id,
+            metric,
+            period,
+            target,
+            created_at: Utc::now(),
+        };
+        self.goals.insert(goal.id, goal.clone());
+        goal
+    }
+
+    pub fn delete_goal(&mut self, user_id: i64, goal_id: i64) -> bool {
+        if self
+            .goals
+            .get(&goal_id)
+            .is_some_and(|goal| goal.user_id == user_id)
+        {
+            self.goals.remove(&goal_id);
+            return true;
+        }
+        false
+    }
+
+    fn flag_leech(
+        &mut self,
+        card_id: i64,
+        policy: LeechPolicy,
+        now: DateTime<Utc>,
+    ) -> Option<ProblemCard> {
+        let card = self
+            .cards
+            .get_mut(&card_id)
+            .filter(|card| card.leech_at.is_none() && card.lapses >= policy.threshold)?;
+        card.leech_at = Some(now);
+        match policy.action {
+            LeechAction::Tag => {
+                card.tags.retain(|tag| tag != LEECH_TAG);
+                card.tags.push(LEECH_TAG.to_owned());
+            }
+            LeechAction::Suspend => card.suspended_at = Some(now),
+            LeechAction::Notify => {}
+        }
+        Some(card.clone())
+    }
+
+    pub fn list_leeches(&self, user_id: i64) -> Vec<ProblemCard> {
+        let mut cards: Vec<_> = self
+            .cards
+            .values()
+            .filter(|card| card.user_id == user_id && card.leech_at.is_some())
+            .cloned()
+            .collect();
+        cards.sort_by(|a, b| {
+            b.lapses
+                .cmp(&a.lapses)
+                .then(a.leech_at.cmp(&b.leech_at))
+                .then(a.id.cmp(&b.id))
+        });
+        cards
+    }
+
+    pub fn clear_leech(&mut self, user_id: i64, card_id: i64) -> Option<ProblemCard> {
+        let card = self
+            .cards
+            .get_mut(&card_id)
+            .filter(|card| card.user_id == user_id)?;
+        card.lapses = 0;
+        card.leech_at = None;
+        card.suspended_at = None;
+        card.tags.retain(|tag| tag != LEECH_TAG);
+        Some(card.clone())
+    }
+
+    pub fn get_notification_preference(&self, user_id: i64) -> Option<NotificationPreference> {
+        self.notification_preferences.get(&user_id).cloned()
+    }
+
+    pub fn set_notification_preference(
         &mut self,
         user_id: i64,
         email_enabled: bool,
         digest_hour_utc: u8,
+        timezone: Option<&str>,
     ) -> Option<NotificationPreference> {
         let pref = self.notification_preferences.get_mut(&user_id)?;
         pref.email_enabled = email_enabled;
         pref.digest_hour_utc = digest_hour_utc.min(23);
+        if let Some(timezone) = timezone {
+            pref.timezone = timezone.to_owned();
+        }
         Some(pref.clone())
     }
 
@@ -334,6 +1361,332 @@ impl InMemoryStore {
         log
     }
 
+    pub fn upsert_push_subscription(
+        &mut self,
+        user_id: i64,
+        endpoint: String,
+        p256dh: String,
+        auth: String,
+        device_label: String,
+    ) -> PushSubscription {
+        if let Some(existing) = self
+            .push_subscriptions
+            .values_mut()
+            .find(|subscription| subscription.endpoint == endpoint)
+        {
+            existing.user_id = user_id;
+            existing.p256dh = p256dh;
+            existing.auth = auth;
+            existing.device_label = device_label;
+            return existing.clone();
+        }
+        let subscription = PushSubscription {
+            id: self.new_id(),
+            user_id,
+            endpoint,
+            p256dh,
+            auth,
+            device_label,
+            created_at: Utc::now(),
+            last_used_at: None,
+        };
+        self.push_subscriptions
+            .insert(subscription.id, subscription.clone());
+        subscription
+    }
+
+    pub fn list_push_subscriptions(&self, user_id: i64) -> Vec<PushSubscription> {
+        let mut subscriptions: Vec<_> = self
+            .push_subscriptions
+            .values()
+            .filter(|subscription| subscription.user_id == user_id)
+            .cloned()
+            .collect();
+        subscriptions.sort_by_key(|subscription| std::cmp::Reverse(subscription.created_at));
+        subscriptions
+    }
+
+    pub fn delete_push_subscription(&mut self, user_id: i64, subscription_id: i64) -> bool {
+        let owned = self
+            .push_subscriptions
+            .get(&subscription_id)
+            .is_some_and(|subscription| subscription.user_id == user_id);
+        owned && self.push_subscriptions.remove(&subscription_id).is_some()
+    }
+
+    pub fn delete_push_subscription_by_endpoint(&mut self, endpoint: &str) {
+        self.push_subscriptions
+            .retain(|_, subscription| subscription.endpoint != endpoint);
+    }
+
+    pub fn touch_push_subscription(&mut self, subscription_id: i64) {
+        if let Some(subscription) = self.push_subscriptions.get_mut(&subscription_id) {
+            subscription.last_used_at = Some(Utc::now());
+        }
+    }
+
+    pub fn list_events(&self, user_id: i64) -> Vec<ProblemEvent> {
+        let mut events = self
+            .events
+            .values()
+            .filter(|ev| ev.user_id == user_id)
+            .cloned()
+            .collect::<Vec<_>>();
+        events.sort_by_key(|ev| (ev.occurred_at, ev.id));
+        events
+    }
+
+    pub fn import_archive(&mut self, user_id: i64, import: ArchiveImport) -> ArchiveImportSummary {
+        let mut summary = ArchiveImportSummary::default();
+        let user_prefix = format!("{user_id}:");
+        if import.mode == ImportMode::Replace {
+            self.reviews.retain(|_, review| review.user_id != user_id);
+            self.cards.retain(|_, card| card.user_id != user_id);
+            self.card_index
+                .retain(|key, _| !key.starts_with(&user_prefix));
+            self.events.retain(|_, event| event.user_id != user_id);
+            self.dedup.retain(|key| !key.starts_with(&user_prefix));
+            if import.goals.is_some() {
+                self.goals.retain(|_, goal| goal.user_id != user_id);
+            }
+        }
+        if let Some(settings) = import.settings {
+            let timezone = settings
+                .timezone
+                .or_else(|| {
+                    self.notification_preferences
+                        .get(&user_id)
+                        .map(|pref| pref.timezone.clone())
+                })
+                .unwrap_or_else(|| "UTC".to_owned());
+            self.notification_preferences.insert(
+                user_id,
+                NotificationPreference {
+                    user_id,
+                    email_enabled: settings.email_enabled,
+                    digest_hour_utc: settings.digest_hour_utc,
+                    timezone,
+                },
+            );
+        }
+
+        for goal in import.goals.into_iter().flatten() {
+            let exists = self.goals.values().any(|existing| {
+                existing.user_id == user_id
+                    && existing.metric == goal.metric
+                    && existing.period == goal.period
+            });
+            if !exists {
+                self.upsert_goal(user_id, goal.metric, goal.period, goal.target);
+                summary.goals_imported += 1;
+            }
+        }
+
+        let mut card_ids = HashMap::new();
+        // Cards whose lapses the archive carried; their reviews are not
+        // counted again.
+        let mut lapses_given = HashSet::new();
+        for card in import.cards {
+            let interval_index = card.interval_index.min(self.schedule.max_index());
+            let card_key = format!("{user_id}:{}:{}", card.source, card.problem_slug);
+            let leech_state = card
+                .lapses
+                .map(|lapses| (lapses, card.leech_at, card.suspended_at));
+            let (card_id, archive_wins) = match self.card_index.get(&card_key).copied() {
+                Some(card_id) => {
+                    let last_reviewed_at = self
+                        .reviews
+                        .values()
+                        .filter(|review| review.card_id == card_id)
+                        .map(|review| review.reviewed_at)
+                        .max();
+                    let archive_wins = card.last_reviewed_at > last_reviewed_at;
+                    if archive_wins && let Some(existing) = self.cards.get_mut(&card_id) {
+                        existing.kind = card.kind;
+                        existing.title = card.title;
+                        existing.url = card.url;
+                        existing.front = card.front;
+                        existing.back = card.back;
+                        existing.tags = card.tags;
+                        existing.interval_index = interval_index;
+                        existing.next_due_at = card.next_due_at;
+                    }
+                    summary.cards_merged += 1;
+                    (card_id, archive_wins)
+                }
+                None => {
+                    let problem = match (card.kind, &card.catalog_url) {
+                        (CardKind::Problem, Some(url)) => {
+                            Some(self.catalog_problem(&card.source, &card.problem_slug, url))
+                        }
+                        (CardKind::Problem, None) => {
+                            self.catalog_entry(&card.source, &card.problem_slug)
+                        }
+                        _ => None,
+                    };
+                    let card_id = self.new_id();
+                    self.card_index.insert(card_key, card_id);
+                    self.cards.insert(
+                        card_id,
+                        ProblemCard {
+                            id: card_id,
+                            user_id,
+                            kind: card.kind,
+                            source: card.source,
+                            problem_slug: card.problem_slug,
+                            title: card.title,
+                            url: card.url,
+                            front: card.front,
+                            back: card.back,
+                            tags: card.tags,
+                            problem_id: problem.as_ref().map(|problem| problem.id),
+                            difficulty: problem.and_then(|problem| problem.difficulty),
+                            interval_index,
+                            next_due_at: card.next_due_at,
+                            created_at: card.created_at.unwrap_or_else(Utc::now),
+                            lapses: 0,
+                            leech_at: None,
+                            suspended_at: None,
+                        },
+                    );
+                    summary.cards_created += 1;
+                    (card_id, true)
+                }
+            };
+            if let Some((lapses, leech_at, suspended_at)) = leech_state {
+                lapses_given.insert(card_id);
+                if archive_wins && let Some(existing) = self.cards.get_mut(&card_id) {
+                    existing.lapses = lapses;
+                    existing.leech_at = leech_at;
+                    existing.suspended_at = suspended_at;
+                }
+            }
+            card_ids.insert(card.archive_id, card_id);
+        }
+
+        for event in import.events {
+            let dedup_key = make_event_dedup_key(
+                user_id,
+                &event.source,
+                &event.problem_slug,
+                &event.status,
+                event.occurred_at,
+            );
+            if !self.dedup.insert(dedup_key.clone()) {
+                continue;
+            }
+            let id = self.new_id();
+            self.events.insert(
+                id,
+                ProblemEvent {
+                    id,
+                    user_id,
+                    source: event.source,
+                    problem_slug: event.problem_slug,
+                    title: event.title,
+                    url: event.url,
+                    status: event.status,
+                    occurred_at: event.occurred_at,
+                    dedup_key,
+                },
+            );
+            summary.events_imported += 1;
+        }
+
+        for review in import.reviews {
+            let Some(card_id) = card_ids.get(&review.archive_card_id).copied() else {
+                continue;
+            };
+            let duplicate = self.reviews.values().any(|existing| {
+                existing.card_id == card_id
+                    && existing.grade == review.grade
+                    && existing.reviewed_at == review.reviewed_at
+            });
+            if duplicate {
+                summary.reviews_skipped += 1;
+                continue;
+            }
+            let id = self.new_id();
+            self.reviews.insert(
+                id,
+                ReviewEvent {
+                    id,
+                    card_id,
+                    user_id,
+                    grade: review.grade,
+                    reviewed_at: review.reviewed_at,
+                    next_due_at: review.next_due_at,
+                },
+            );
+            if review.grade == Grade::Again
+                && !lapses_given.contains(&card_id)
+                && let Some(card) = self.cards.get_mut(&card_id)
+            {
+                card.lapses += 1;
+            }
+            summary.reviews_imported += 1;
+        }
+        summary
+    }
+
+    pub fn claim_idempotency_key(
+        &mut self,
+        user_id: i64,
+        key: &str,
+        request_hash: &str,
+        now: DateTime<Utc>,
+        stale_before: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+    ) -> IdempotencyClaim {
+        let slot = (user_id, key.to_owned());
+        if let Some(existing) = self.idempotency_keys.get(&slot)
+            && existing.expires_at > now
+        {
+            let stale = existing.response.is_none()
+                && existing.request_hash == request_hash
+                && existing.claimed_at <= stale_before;
+            if !stale {
+                return IdempotencyClaim::Existing(existing.clone());
+            }
+        }
+        self.idempotency_keys.insert(
+            slot,
+            IdempotencyRecord {
+                user_id,
+                key: key.to_owned(),
+                request_hash: request_hash.to_owned(),
+                response: None,
+                claimed_at: now,
+                expires_at,
+            },
+        );
+        IdempotencyClaim::Claimed
+    }
+
+    pub fn complete_idempotency_key(&mut self, user_id: i64, key: &str, response: StoredResponse) {
+        if let Some(record) = self.idempotency_keys.get_mut(&(user_id, key.to_owned())) {
+            record.response = Some(response);
+        }
+    }
+
+    pub fn release_idempotency_key(&mut self, user_id: i64, key: &str) {
+        let slot = (user_id, key.to_owned());
+        if self
+            .idempotency_keys
+            .get(&slot)
+            .is_some_and(|record| record.response.is_none())
+        {
+            self.idempotency_keys.remove(&slot);
+        }
+    }
+
+    pub fn purge_expired_idempotency_keys(&mut self, now: DateTime<Utc>) -> u64 {
+        let before = self.idempotency_keys.len();
+        self.idempotency_keys
+            .retain(|_, record| record.expires_at > now);
+        (before - self.idempotency_keys.len()) as u64
+    }
+
     pub fn latest_event_for_user(&self, user_id: i64) -> Option<ProblemEvent> {
         self.events
             .values()
@@ -345,10 +1698,17 @@ impl InMemoryStore {
 
 #[cfg(test)]
 mod tests {
-    use chrono::{Duration, Utc};
+    use std::collections::BTreeSet;
+
+    use chrono::{Duration, NaiveDate, TimeZone, Utc};
 
-    use crate::models::{IngestProblemInput, ProblemStatus};
-    use crate::srs::{IntervalUnit, ScheduleProfile, SrsSchedule};
+    use crate::models::{
+        ArchiveImport, CardContent, CardKind, CatalogProblemInput, DailyActivity, GoalMetric,
+        GoalPeriod, IdempotencyClaim, ImportMode, ImportedCard, ImportedGoal, ImportedReview,
+        ImportedSettings, IngestProblemInput, LEECH_TAG, LeechAction, LeechPolicy, ProblemStatus,
+        RetentionCounts, ReviewEvent, StoredResponse,
+    };
+    use crate::srs::{Grade, IntervalUnit, ScheduleProfile, SrsSchedule};
 
     use super::InMemoryStore;
 
@@ -378,6 +1738,734 @@ mod tests {
         assert_eq!(first.id, second.id);
     }
 
+    #[test]
+    fn users_share_catalog_problems_and_aliases_resolve() {
+        let mut store = InMemoryStore::new_with_schedule(SrsSchedule::default());
+        let alice = store.get_or_create_user("alice@example.com");
+        let bob = store.get_or_create_user("bob@example.com");
+        let now = Utc::now();
+        let solve = |user_id, slug: &str, title: &str| IngestProblemInput {
+            user_id,
+            source: "leetcode".to_owned(),
+            problem_slug: slug.to_owned(),
+            title: title.to_owned(),
+            url: format!("https://leetcode.com/problems/{slug}"),
+            status: ProblemStatus::Solved,
+            occurred_at: now,
+        };
+        store.seed_problems(
+            1,
+            vec![CatalogProblemInput {
+                source: "leetcode".to_owned(),
+                slug: "two-sum".to_owned(),
+                title: "Two Sum".to_owned(),
+                url: "https://leetcode.com/problems/two-sum/".to_owned(),
+                difficulty: None,
+                tags: Vec::new(),
+                aliases: vec!["2-sum".to_owned()],
+            }],
+        );
+        let problem = store
+            .find_problem("leetcode", "two-sum")
+            .expect("catalogued");
+
+        store.ingest_event(solve(alice.id, "two-sum", "my two sum"));
+        let event = store.ingest_event(solve(bob.id, "2-sum", "2sum"));
+        assert_eq!(event.problem_slug, "two-sum");
+        assert_eq!(store.count_problem_learners(problem.id), 2);
+        assert!(
+            store
+                .cards
+                .values()
+                .all(|card| card.problem_id == Some(problem.id) && card.title == "Two Sum")
+        );
+
+        store.ingest_event(solve(alice.id, "three-sum", "Alice's notes"));
+        store.ingest_event(solve(bob.id, "three-sum", "3Sum"));
+        assert_eq!(store.problems.len(), 2);
+        let three_sum = store
+            .find_problem("leetcode", "three-sum")
+            .expect("identity entry");
+        assert_eq!(three_sum.title, None);
+        assert_eq!(store.count_problem_learners(three_sum.id), 2);
+        let titles: BTreeSet<_> = store
+            .cards
+            .values()
+            .filter(|card| card.problem_slug == "three-sum")
+            .map(|card| card.title.as_str())
+            .collect();
+        assert_eq!(titles, BTreeSet::from(["3Sum", "Alice's notes"]));
+        assert_eq!(
+            store
+                .find_problem("leetcode", "2-sum")
+                .map(|found| found.id),
+            Some(problem.id)
+        );
+    }
+
+    #[test]
+    fn seeding_the_catalog_is_idempotent_and_reports_uncatalogued_problems() {
+        let mut store = InMemoryStore::new_with_schedule(SrsSchedule::default());
+        let user = store.get_or_create_user("a@b.com");
+        for slug in ["two-sum", "design-twitter"] {
+            store.ingest_event(IngestProblemInput {
+                user_id: user.id,
+                source: "leetcode".to_owned(),
+                problem_slug: slug.to_owned(),
+                title: slug.to_owned(),
+                url: format!("https://leetcode.com/problems/{slug}/"),
+                status: ProblemStatus::Solved,
+                occurred_at: Utc::now(),
+            });
+        }
+        let dataset = vec![
+            CatalogProblemInput {
+                source: "leetcode".to_owned(),
+                slug: "two-sum".to_owned(),
+                title: "Two Sum".to_owned(),
+                url: "https://leetcode.com/problems/two-sum/".to_owned(),
+                difficulty: Some("Easy".to_owned()),
+                tags: vec!["array".to_owned()],
+                aliases: Vec::new(),
+            },
+            CatalogProblemInput {
+                source: "leetcode".to_owned(),
+                slug: "lru-cache".to_owned(),
+                title: "LRU Cache".to_owned(),
+                url: "https://leetcode.com/problems/lru-cache/".to_owned(),
+                difficulty: Some("Medium".to_owned()),
+                tags: Vec::new(),
+                aliases: Vec::new(),
+            },
+        ];
+
+        let first = store.seed_problems(1, dataset.clone());
+        assert_eq!((first.inserted, first.updated, first.unchanged), (1, 1, 0));
+        let second = store.seed_problems(1, dataset);
+        assert_eq!(
+            (second.inserted, second.updated, second.unchanged),
+            (0, 0, 2)
+        );
+
+        let card = store
+            .cards
+            .values()
+            .find(|card| card.problem_slug == "two-sum")
+            .expect("two-sum card");
+        let problem = store
+            .find_problem("leetcode", "two-sum")
+            .expect("catalogued");
+        assert_eq!(card.problem_id, Some(problem.id));
+        assert_eq!(card.title, "Two Sum");
+        assert_eq!(problem.difficulty.as_deref(), Some("Easy"));
+        assert_eq!(problem.catalog_version, Some(1));
+
+        let uncatalogued = store.uncatalogued_problems();
+        assert_eq!(uncatalogued.len(), 1);
+        assert_eq!(uncatalogued[0].slug, "design-twitter");
+        assert_eq!(uncatalogued[0].learners, 1);
+    }
+
+    #[test]
+    fn review_stats_use_each_cards_previous_review() {
+        let mut store = InMemoryStore::new_with_schedule(SrsSchedule::default());
+        let user = store.get_or_create_user("a@b.com");
+        let now = Utc::now();
+        let event = store.ingest_event(IngestProblemInput {
+            user_id: user.id,
+            source: "leetcode".to_owned(),
+            problem_slug: "two-sum".to_owned(),
+            title: "Two Sum".to_owned(),
+            url: "https://leetcode.com/problems/two-sum/".to_owned(),
+            status: ProblemStatus::Solved,
+            occurred_at: now,
+        });
+        let card_id = store
+            .cards
+            .values()
+            .find(|card| card.problem_slug == event.problem_slug)
+            .expect("card")
+            .id;
+        for (grade, reviewed, due) in [
+            (Grade::Good, 40, 30),
+            (Grade::Again, 9, 8),
+            (Grade::Good, 8, 5),
+        ] {
+            let id = store.new_id();
+            store.reviews.insert(
+                id,
+                ReviewEvent {
+                    id,
+                    card_id,
+                    user_id: user.id,
+                    grade,
+                    reviewed_at: now - Duration::days(reviewed),
+                    next_due_at: now - Duration::days(due),
+                },
+            );
+        }
+
+        let stats = store.review_stats(user.id, now - Duration::days(14), Duration::days(2));
+        assert_eq!(
+            stats.totals,
+            RetentionCounts {
+                reviews: 2,
+                mature_reviews: 1,
+                mature_passed: 0,
+                lapses: 1,
+            }
+        );
+        assert_eq!(stats.grades, [(Grade::Again, 1), (Grade::Good, 1)]);
+        assert_eq!(stats.sources[0].0, "leetcode");
+        assert_eq!(stats.interval_indexes, [(0, 1)]);
+        let gap = stats.average_review_gap_seconds.expect("gap");
+        assert!((gap - 16.0 * 86_400.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn daily_activity_counts_local_days_and_goals_upsert() {
+        let mut store = InMemoryStore::new_with_schedule(SrsSchedule::default());
+        let user = store.get_or_create_user("a@b.com");
+        // 05:00 on March 5th in Tokyo.
+        let at = Utc.with_ymd_and_hms(2026, 3, 4, 20, 0, 0).unwrap();
+        for occurred_at in [at, at + Duration::hours(1), at - Duration::days(2)] {
+            store.ingest_event(IngestProblemInput {
+                user_id: user.id,
+                source: "leetcode".to_owned(),
+                problem_slug: "two-sum".to_owned(),
+                title: "Two Sum".to_owned(),
+                url: "https://leetcode.com/problems/two-sum/".to_owned(),
+                status: ProblemStatus::Solved,
+                occurred_at,
+            });
+        }
+        let card = store.list_cards(user.id).remove(0);
+        assert_eq!(card.created_at, at - Duration::days(2));
+        let id = store.new_id();
+        store.reviews.insert(
+            id,
+            ReviewEvent {
+                id,
+                card_id: card.id,
+                user_id: user.id,
+                grade: Grade::Good,
+                reviewed_at: at,
+                next_due_at: at + Duration::days(1),
+            },
+        );
+
+        let day = |d| NaiveDate::from_ymd_opt(2026, 3, d).unwrap();
+        let tokyo = store.daily_activity(user.id, "Asia/Tokyo", day(1), day(10));
+        assert_eq!(
+            tokyo,
+            [
+                DailyActivity {
+                    date: day(3),
+                    reviews: 0,
+                    solved: 1,
+                    new_cards: 1,
+                },
+                DailyActivity {
+                    date: day(5),
+                    reviews: 1,
+                    solved: 1,
+                    new_cards: 0,
+                },
+            ]
+        );
+        assert_eq!(store.active_days(user.id, "UTC"), [day(2), day(4)]);
+        assert!(
+            store
+                .daily_activity(user.id, "UTC", day(5), day(10))
+                .is_empty()
+        );
+
+        let goal = store.upsert_goal(user.id, GoalMetric::Reviews, GoalPeriod::Day, 10);
+        let retargeted = store.upsert_goal(user.id, GoalMetric::Reviews, GoalPeriod::Day, 5);
+        assert_eq!((retargeted.id, retargeted.target), (goal.id, 5));
+        store.upsert_goal(user.id, GoalMetric::Solved, GoalPeriod::Week, 3);
+        assert_eq!(store.list_goals(user.id).len(), 2);
+        assert!(!store.delete_goal(user.id + 1, goal.id));
+        assert!(store.delete_goal(user.id, goal.id));
+        assert_eq!(store.list_goals(user.id)[0].metric, GoalMetric::Solved);
+    }
+
+    #[test]
+    fn lapses_flag_leeches_once_and_clearing_restores_the_card() {
+        let mut store = InMemoryStore::new_with_schedule(SrsSchedule::default());
+        let user = store.get_or_create_user("a@b.com");
+        let now = Utc::now();
+        let mut cards = Vec::new();
+        for slug in ["two-sum", "lru-cache"] {
+            store.ingest_event(IngestProblemInput {
+                user_id: user.id,
+                source: "leetcode".to_owned(),
+                problem_slug: slug.to_owned(),
+                title: slug.to_owned(),
+                url: format!("https://leetcode.com/problems/{slug}/"),
+                status: ProblemStatus::Solved,
+                occurred_at: now - Duration::days(3),
+            });
+            let cards_now = store.list_cards(user.id);
+            let card = cards_now.iter().find(|card| card.problem_slug == slug);
+            cards.push(card.unwrap().id);
+        }
+        let (tagged, suspended) = (cards[0], cards[1]);
+        let tag = LeechPolicy {
+            threshold: 2,
+            action: LeechAction::Tag,
+        };
+        let suspend = LeechPolicy {
+            threshold: 3,
+            action: LeechAction::Suspend,
+        };
+        for grade in [Grade::Again, Grade::Good] {
+            let graded = store.grade_card(user.id, tagged, grade, tag).unwrap();
+            assert!(graded.leech.is_none());
+            store.grade_card(user.id, suspended, grade, suspend);
+        }
+        let card = store
+            .grade_card(user.id, tagged, Grade::Again, tag)
+            .unwrap()
+            .leech
+            .expect("flagged at the threshold");
+        assert_eq!(card.lapses, 2);
+        assert!(card.leech_at.is_some());
+        assert_eq!(card.tags, [LEECH_TAG]);
+        let again = store.grade_card(user.id, tagged, Grade::Again, tag);
+        assert!(again.unwrap().leech.is_none());
+        assert_eq!(store.get_card(user.id, tagged).unwrap().tags, [LEECH_TAG]);
+
+        for _ in 0..2 {
+            store.grade_card(user.id, suspended, Grade::Again, suspend);
+        }
+        assert!(
+            store
+                .get_card(user.id, suspended)
+                .unwrap()
+                .suspended_at
+                .is_some()
+        );
+        let far_future = now + Duration::days(3650);
+        let due = store.due_cards(user.id, far_future);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].id, tagged);
+        let leeches: Vec<_> = store
+            .list_leeches(user.id)
+            .iter()
+            .map(|card| (card.id, card.lapses))
+            .collect();
+        assert_eq!(leeches, [(tagged, 3), (suspended, 3)]);
+
+        assert!(store.clear_leech(user.id + 1, suspended).is_none());
+        let cleared = store.clear_leech(user.id, suspended).unwrap();
+        assert_eq!((cleared.lapses, cleared.leech_at), (0, None));
+        assert_eq!(store.due_cards(user.id, far_future).len(), 2);
+        assert!(store.clear_leech(user.id, tagged).unwrap().tags.is_empty());
+        assert!(store.list_leeches(user.id).is_empty());
+    }
+
+    #[test]
+    fn authored_cards_are_keyed_per_user_and_protect_problem_cards() {
+        let mut store = InMemoryStore::new_with_schedule(SrsSchedule::default());
+        let user = store.get_or_create_user("a@b.com");
+        let now = Utc::now();
+        let content = CardContent {
+            kind: CardKind::Basic,
+            title: "Capital of France?".to_owned(),
+            front: "Capital of France?".to_owned(),
+            back: Some("Paris".to_owned()),
+            tags: vec!["geography".to_owned()],
+        };
+        let card = store
+            .create_card(user.id, "france", content.clone(), now)
+            .expect("card created");
+        assert_eq!(card.source, "custom");
+        assert!(
+            store
+                .create_card(user.id, "france", content.clone(), now)
+                .is_none()
+        );
+        assert_eq!(store.due_cards(user.id, now).len(), 1);
+        assert!(
+            store
+                .grade_card(user.id, card.id, Grade::Good, LeechPolicy::default())
+                .is_some()
+        );
+
+        let problem = store.ingest_event(IngestProblemInput {
+            user_id: user.id,
+            source: "leetcode".to_owned(),
+            problem_slug: "two-sum".to_owned(),
+            title: "Two Sum".to_owned(),
+            url: "https://leetcode.com/problems/two-sum".to_owned(),
+            status: ProblemStatus::Solved,
+            occurred_at: now,
+        });
+        let problem_card = store
+            .list_cards(user.id)
+            .into_iter()
+            .find(|card| card.kind == CardKind::Problem)
+            .expect("problem card");
+        assert_eq!(problem_card.problem_slug, problem.problem_slug);
+        assert!(
+            store
+                .update_card(user.id, problem_card.id, content.clone())
+                .is_none()
+        );
+
+        assert!(store.delete_card(user.id, card.id));
+        assert!(store.user_history(user.id).is_empty());
+        assert!(store.create_card(user.id, "france", content, now).is_some());
+    }
+
+    #[test]
+    fn rotated_integration_token_accepts_previous_secret_during_grace() {
+        let mut store = InMemoryStore::new_with_schedule(SrsSchedule::default());
+        let user = store.get_or_create_user("a@b.com");
+        let token = store.create_integration_token(
+            user.id,
+            "old-hash".to_owned(),
+            "cli".to_owned(),
+            vec!["cards:read".to_owned()],
+            None,
+        );
+
+        store.rotate_integration_token(
+            user.id,
+            token.id,
+            "new-hash".to_owned(),
+            Some(Utc::now() + Duration::minutes(5)),
+            None,
+        );
+        assert!(store.user_from_integration_token("old-hash").is_some());
+        assert!(store.user_from_integration_token("new-hash").is_some());
+
+        store.rotate_integration_token(user.id, token.id, "newest-hash".to_owned(), None, None);
+        assert!(store.user_from_integration_token("old-hash").is_none());
+        assert!(store.user_from_integration_token("new-hash").is_none());
+        assert!(store.user_from_integration_token("newest-hash").is_some());
+    }
+
+    #[test]
+    fn revoking_other_sessions_keeps_the_current_one() {
+        let mut store = InMemoryStore::new_with_schedule(SrsSchedule::default());
+        let user = store.get_or_create_user("a@b.com");
+        let expires_at = Utc::now() + Duration::days(1);
+        let current = store.create_session(user.id, "current".to_owned(), expires_at, None, None);
+        store.create_session(user.id, "other".to_owned(), expires_at, None, None);
+
+        assert_eq!(store.revoke_other_sessions(user.id, current.id), 1);
+        assert!(store.user_from_session("current").is_some());
+        assert!(store.user_from_session("other").is_none());
+        assert_eq!(store.list_active_sessions(user.id).len(), 1);
+    }
+
+    #[test]
+    fn oidc_auth_request_is_consumed_once_and_identity_relinks() {
+        let mut store = InMemoryStore::new_with_schedule(SrsSchedule::default());
+        store.create_oidc_auth_request(
+            "state-hash".to_owned(),
+            "nonce".to_owned(),
+            "verifier".to_owned(),
+            Utc::now() + Duration::minutes(10),
+        );
+        store.create_oidc_auth_request(
+            "expired".to_owned(),
+            "nonce".to_owned(),
+            "verifier".to_owned(),
+            Utc::now() - Duration::minutes(1),
+        );
+        assert!(store.consume_oidc_auth_request("state-hash").is_some());
+        assert!(store.consume_oidc_auth_request("state-hash").is_none());
+        assert!(store.consume_oidc_auth_request("expired").is_none());
+
+        let user = store.get_or_create_user("a@b.com");
+        let first = store.link_identity(user.id, "https://idp", "sub-1", Some("a@b.com"));
+        let again = store.link_identity(user.id, "https://idp", "sub-1", Some("new@b.com"));
+        assert_eq!(first.id, again.id);
+        assert_eq!(again.email.as_deref(), Some("new@b.com"));
+        assert_eq!(
+            store
+                .user_from_identity("https://idp", "sub-1")
+                .map(|u| u.id),
+            Some(user.id)
+        );
+        assert!(store.user_from_identity("https://other", "sub-1").is_none());
+    }
+
+    #[test]
+    fn account_deletion_requires_a_live_request_and_clears_user_rows() {
+        let mut store = InMemoryStore::new_with_schedule(SrsSchedule::default());
+        let user = store.get_or_create_user("gone@b.com");
+        let other = store.get_or_create_user("stays@b.com");
+        for user_id in [user.id, other.id] {
+            store.ingest_event(IngestProblemInput {
+                user_id,
+                source: "leetcode".to_owned(),
+                problem_slug: "two-sum".to_owned(),
+                title: "Two Sum".to_owned(),
+                url: "https://leetcode.com/problems/two-sum".to_owned(),
+                status: ProblemStatus::Solved,
+                occurred_at: Utc::now(),
+            });
+        }
+        store.create_account_deletion_request(
+            user.id,
+            "expired",
+            Utc::now() - Duration::minutes(1),
+        );
+        store.create_account_deletion_request(user.id, "live", Utc::now() + Duration::minutes(30));
+
+        assert!(!store.delete_account(user.id, "expired"));
+        assert!(!store.delete_account(other.id, "live"));
+        assert!(store.delete_account(user.id, "live"));
+
+        assert!(store.get_user_by_email("gone@b.com").is_none());
+        assert!(store.list_events(user.id).is_empty());
+        assert!(store.list_cards(user.id).is_empty());
+        assert!(store.account_deletion_requests.is_empty());
+        assert_eq!(store.list_cards(other.id).len(), 1);
+        let again = store.get_or_create_user("gone@b.com");
+        assert_ne!(again.id, user.id);
+    }
+
+    #[test]
+    fn idempotency_keys_replay_until_expiry() {
+        let mut store = InMemoryStore::new_with_schedule(SrsSchedule::default());
+        let now = Utc::now();
+        let stale = now - Duration::seconds(60);
+        let ttl = now + Duration::hours(1);
+
+        assert!(matches!(
+            store.claim_idempotency_key(1, "k", "hash-a", now, stale, ttl),
+            IdempotencyClaim::Claimed
+        ));
+        let IdempotencyClaim::Existing(pending) =
+            store.claim_idempotency_key(1, "k", "hash-a", now, stale, ttl)
+        else {
+            panic!("second claim should see the first");
+        };
+        assert!(pending.response.is_none());
+        // A claim nobody completed can be retried once its lease runs out,
+        // but only by the same request.
+        let retry_at = now + Duration::seconds(61);
+        assert!(matches!(
+            store.claim_idempotency_key(1, "k", "hash-b", retry_at, now, ttl),
+            IdempotencyClaim::Existing(_)
+        ));
+        assert!(matches!(
+            store.claim_idempotency_key(1, "k", "hash-a", retry_at, now, ttl),
+            IdempotencyClaim::Claimed
+        ));
+        // Keys are scoped per user.
+        assert!(matches!(
+            store.claim_idempotency_key(2, "k", "hash-a", now, stale, ttl),
+            IdempotencyClaim::Claimed
+        ));
+
+        store.complete_idempotency_key(
+            1,
+            "k",
+            StoredResponse {
+                status: 200,
+                content_type: Some("application/json".to_owned()),
+                body: b"{}".to_vec(),
+            },
+        );
+        store.release_idempotency_key(1, "k");
+        let IdempotencyClaim::Existing(done) =
+            store.claim_idempotency_key(1, "k", "hash-b", now, stale, ttl)
+        else {
+            panic!("completed key should replay");
+        };
+        assert_eq!(done.request_hash, "hash-a");
+        assert_eq!(done.response.map(|response| response.status), Some(200));
+
+        let later = ttl + Duration::seconds(1);
+        assert!(matches!(
+            store.claim_idempotency_key(
+                1,
+                "k",
+                "hash-b",
+                later,
+                later - Duration::seconds(60),
+                later + Duration::hours(1)
+            ),
+            IdempotencyClaim::Claimed
+        ));
+        assert_eq!(store.purge_expired_idempotency_keys(later), 1);
+    }
+
+    #[test]
+    fn archive_merge_keeps_the_more_recently_reviewed_schedule() {
+        let mut store = InMemoryStore::new_with_schedule(SrsSchedule::default());
+        let user = store.get_or_create_user("import@b.com");
+        let now = Utc::now();
+        store.ingest_event(IngestProblemInput {
+            user_id: user.id,
+            source: "leetcode".to_owned(),
+            problem_slug: "two-sum".to_owned(),
+            title: "Two Sum".to_owned(),
+            url: "https://leetcode.com/problems/two-sum".to_owned(),
+            status: ProblemStatus::Solved,
+            occurred_at: now,
+        });
+        let card = |archive_id, slug: &str, last_reviewed_at| ImportedCard {
+            archive_id,
+            kind: CardKind::Problem,
+            source: "leetcode".to_owned(),
+            problem_slug: slug.to_owned(),
+            title: slug.to_owned(),
+            url: format!("https://leetcode.com/problems/{slug}"),
+            catalog_url: None,
+            front: None,
+            back: None,
+            tags: Vec::new(),
+            interval_index: 2,
+            next_due_at: now + Duration::days(9),
+            created_at: Some(now - Duration::days(30)),
+            last_reviewed_at,
+            lapses: None,
+            leech_at: None,
+            suspended_at: None,
+        };
+        let review = |archive_card_id| ImportedReview {
+            archive_card_id,
+            grade: Grade::Good,
+            reviewed_at: now - Duration::days(1),
+            next_due_at: now + Duration::days(9),
+        };
+        let import = ArchiveImport {
+            mode: ImportMode::Merge,
+            settings: Some(ImportedSettings {
+                email_enabled: false,
+                digest_hour_utc: 7,
+                timezone: None,
+            }),
+            goals: None,
+            cards: vec![
+                card(10, "two-sum", Some(now - Duration::days(1))),
+                card(11, "climbing-stairs", None),
+            ],
+            events: Vec::new(),
+            reviews: vec![review(10)],
+        };
+
+        let summary = store.import_archive(user.id, import.clone());
+        assert_eq!(summary.cards_created, 1);
+        assert_eq!(summary.cards_merged, 1);
+        assert_eq!(summary.reviews_imported, 1);
+        let cards = store.list_cards(user.id);
+        assert_eq!(cards.len(), 2);
+        assert!(cards.iter().all(|card| card.interval_index == 2));
+        assert!(
+            !store
+                .get_notification_preference(user.id)
+                .unwrap()
+                .email_enabled
+        );
+
+        let again = store.import_archive(user.id, import.clone());
+        assert_eq!((again.cards_created, again.reviews_skipped), (0, 1));
+        assert_eq!(store.user_history(user.id).len(), 1);
+
+        let replaced = store.import_archive(
+            user.id,
+            ArchiveImport {
+                mode: ImportMode::Replace,
+                cards: vec![card(1, "valid-anagram", None)],
+                reviews: Vec::new(),
+                ..import
+            },
+        );
+        assert_eq!(replaced.cards_created, 1);
+        assert_eq!(store.list_cards(user.id).len(), 1);
+        assert!(store.user_history(user.id).is_empty());
+        assert!(store.list_events(user.id).is_empty());
+    }
+
+    #[test]
+    fn archive_import_restores_lapses_and_goals() {
+        let mut store = InMemoryStore::new_with_schedule(SrsSchedule::default());
+        let user = store.get_or_create_user("leech-import@b.com");
+        let now = Utc::now();
+        store.upsert_goal(user.id, GoalMetric::Reviews, GoalPeriod::Day, 5);
+        let goal = |metric, period, target| ImportedGoal {
+            metric,
+            period,
+            target,
+        };
+        let import = ArchiveImport {
+            mode: ImportMode::Merge,
+            settings: None,
+            goals: Some(vec![
+                goal(GoalMetric::Reviews, GoalPeriod::Day, 20),
+                goal(GoalMetric::Solved, GoalPeriod::Week, 3),
+            ]),
+            cards: vec![ImportedCard {
+                archive_id: 1,
+                kind: CardKind::Problem,
+                source: "leetcode".to_owned(),
+                problem_slug: "jump-game".to_owned(),
+                title: "Jump Game".to_owned(),
+                url: "https://leetcode.com/problems/jump-game/".to_owned(),
+                catalog_url: None,
+                front: None,
+                back: None,
+                tags: vec![LEECH_TAG.to_owned()],
+                interval_index: 0,
+                next_due_at: now,
+                created_at: None,
+                last_reviewed_at: Some(now - Duration::days(1)),
+                lapses: Some(8),
+                leech_at: Some(now - Duration::days(1)),
+                suspended_at: Some(now - Duration::days(1)),
+            }],
+            events: Vec::new(),
+            reviews: vec![ImportedReview {
+                archive_card_id: 1,
+                grade: Grade::Again,
+                reviewed_at: now - Duration::days(1),
+                next_due_at: now,
+            }],
+        };
+
+        let summary = store.import_archive(user.id, import.clone());
+        assert_eq!(summary.goals_imported, 1);
+        let targets = store
+            .list_goals(user.id)
+            .iter()
+            .map(|goal| goal.target)
+            .collect::<Vec<_>>();
+        assert_eq!(targets, [5, 3]);
+        let card = &store.list_cards(user.id)[0];
+        // The archived count already includes its own reviews.
+        assert_eq!(card.lapses, 8);
+        assert!(card.leech_at.is_some() && card.suspended_at.is_some());
+
+        let replaced = store.import_archive(
+            user.id,
+            ArchiveImport {
+                mode: ImportMode::Replace,
+                goals: Some(vec![goal(GoalMetric::NewCards, GoalPeriod::Day, 2)]),
+                ..import.clone()
+            },
+        );
+        assert_eq!(replaced.goals_imported, 1);
+        assert_eq!(store.list_goals(user.id).len(), 1);
+
+        store.import_archive(
+            user.id,
+            ArchiveImport {
+                mode: ImportMode::Replace,
+                goals: None,
+                ..import
+            },
+        );
+        assert_eq!(store.list_goals(user.id)[0].metric, GoalMetric::NewCards);
+    }
+
     #[test]
     fn verifies_magic_link_once() {
         let mut store = InMemoryStore::new_with_schedule(SrsSchedule::default());
@@ -417,4 +2505,33 @@ mod tests {
         assert!(card.next_due_at >= now + Duration::minutes(1));
         assert!(card.next_due_at < now + Duration::minutes(2));
     }
+
+    #[test]
+    fn push_subscription_upsert_reassigns_endpoint() {
+        let mut store = InMemoryStore::new_with_schedule(SrsSchedule::default());
+        let first_user = store.get_or_create_user("first@push.com");
+        let second_user = store.get_or_create_user("second@push.com");
+        let endpoint = "https://push.example.com/send/abc".to_owned();
+
+        let created = store.upsert_push_subscription(
+            first_user.id,
+            endpoint.clone(),
+            "key".to_owned(),
+            "auth".to_owned(),
+            "Laptop".to_owned(),
+        );
+        let moved = store.upsert_push_subscription(
+            second_user.id,
+            endpoint,
+            "key2".to_owned(),
+            "auth2".to_owned(),
+            "Laptop".to_owned(),
+        );
+
+        assert_eq!(created.id, moved.id);
+        assert!(store.list_push_subscriptions(first_user.id).is_empty());
+        assert_eq!(store.list_push_subscriptions(second_user.id).len(), 1);
+        assert!(!store.delete_push_subscription(first_user.id, moved.id));
+        assert!(store.delete_push_subscription(second_user.id, moved.id));
+    }
 }
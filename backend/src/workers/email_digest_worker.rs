@@ -19,5 +19,23 @@ pub async fn email_digest_worker(state: AppState) {
         {
             error!(error = %error_value, "email_digest_tick_failed");
         }
+        let span = tracing::info_span!("token_expiry_tick", requestId = %request_id);
+        if let Err(error_value) = state
+            .notification_service
+            .process_token_expiry_once(chrono::Utc::now())
+            .instrument(span)
+            .await
+        {
+            error!(error = %error_value, "token_expiry_tick_failed");
+        }
+        let span = tracing::info_span!("idempotency_purge_tick", requestId = %request_id);
+        if let Err(error_value) = state
+            .idempotency_service
+            .purge_expired(chrono::Utc::now())
+            .instrument(span)
+            .await
+        {
+            error!(error = %error_value, "idempotency_purge_tick_failed");
+        }
     }
 }
@@ -0,0 +1,23 @@
+use std::time::Duration;
+
+use crate::app::state::AppState;
+use tracing::{error, info};
+
+/// Picks up keys added to or removed from `JWT_KEYS_DIR` so rotations do not
+/// need a restart.
+pub async fn jwt_key_reload_worker(state: AppState, interval: Duration) {
+    info!(
+        requestId = "worker-jwt-key-reload",
+        interval_secs = interval.as_secs(),
+        "jwt_key_reload_worker_started"
+    );
+    let mut ticker = tokio::time::interval(interval);
+    // The keys were loaded at startup; skip the immediate first tick.
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        if let Err(error_value) = state.auth_service.reload_jwt_keys() {
+            error!(error = %error_value, "jwt_key_reload_failed");
+        }
+    }
+}
@@ -1 +1,2 @@
 pub mod email_digest_worker;
+pub mod jwt_key_reload_worker;
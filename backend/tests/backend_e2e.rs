@@ -1,20 +1,62 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
 use axum::{
-    body::Body,
-    http::{Request, StatusCode, header},
+    Form, Json, Router,
+    body::{Body, Bytes},
+    extract::{Query, State},
+    http::{HeaderMap, Request, StatusCode, header},
+    routing::{get, post},
 };
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{Timelike, Utc};
+use futures_util::future::join_all;
 use http_body_util::BodyExt;
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use p256::{
+    SecretKey,
+    ecdsa::{Signature, SigningKey, signature::Signer},
+    elliptic_curve::sec1::ToEncodedPoint,
+    pkcs8::EncodePrivateKey,
+};
+use sea_orm::{ConnectionTrait, DbBackend, Statement};
 use serde_json::{Value, json};
 use serial_test::serial;
+use sha2::{Digest, Sha256};
 use tower::ServiceExt;
+use url::Url;
 
 use srs_anything_backend::{
-    app::{bootstrap::build_state_with_schedule, routes::app_router},
+    app::{
+        bootstrap::build_state_with_schedule,
+        rate_limit::{RateLimitConfig, RateLimiter},
+        routes::app_router,
+        state::AppState,
+    },
+    catalog::CatalogFile,
+    ports::{
+        notification_sender::NotificationSender,
+        oidc::OidcConfig,
+        rate_limit_store::RateLimitRule,
+        web_push::{PushEndpoints, VapidKeys, WebPushNotificationSender},
+    },
+    repositories::{
+        postgres::PostgresRepository,
+        traits::{AuthRepository, NewPushSubscription, PushSubscriptionRepository},
+    },
     srs::{IntervalUnit, ScheduleProfile, SrsSchedule},
 };
 
 mod support;
 
 async fn test_app() -> Option<axum::Router> {
+    let state = test_state().await?;
+    Some(app_router(state))
+}
+
+async fn test_state() -> Option<AppState> {
     let schedule = SrsSchedule::from_profile(ScheduleProfile {
         unit: IntervalUnit::Minutes,
         intervals: vec![1, 3, 5],
@@ -22,7 +64,7 @@ async fn test_app() -> Option<axum::Router> {
     .expect("valid test schedule");
     let db = support::db::try_test_db().await?;
     support::db::reset_db(&db).await;
-    Some(app_router(build_state_with_schedule(schedule, db)))
+    Some(build_state_with_schedule(schedule, db))
 }
 
 async fn json_response(response: axum::response::Response) -> Value {
@@ -47,6 +89,402 @@ fn auth_cookie_from_headers(headers: &axum::http::HeaderMap) -> String {
         .to_owned()
 }
 
+async fn login_cookie(app: &axum::Router, email: &str) -> String {
+    login_cookie_from(app, email, "srs-e2e").await
+}
+
+async fn login_cookie_from(app: &axum::Router, email: &str, user_agent: &str) -> String {
+    let request_token = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/auth/request-magic-link")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(json!({ "email": email }).to_string()))
+                .expect("request"),
+        )
+        .await
+        .expect("request magic link response");
+    let token_body = json_response(request_token).await;
+    let magic_token = token_body["dev_magic_token"]
+        .as_str()
+        .expect("magic token")
+        .to_owned();
+
+    let verify = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/auth/verify-magic-link")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::USER_AGENT, user_agent)
+                .body(Body::from(json!({"token":magic_token}).to_string()))
+                .expect("request"),
+        )
+        .await
+        .expect("verify response");
+    assert_eq!(verify.status(), StatusCode::OK);
+    auth_cookie_from_headers(verify.headers())
+}
+
+async fn create_api_token(app: &axum::Router, auth_cookie: &str, body: Value) -> String {
+    let create_token = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/integrations/tokens")
+                .header(header::COOKIE, auth_cookie)
+                .header(header::ORIGIN, "http://localhost:5173")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .expect("request"),
+        )
+        .await
+        .expect("create token response");
+    assert_eq!(create_token.status(), StatusCode::OK);
+    json_response(create_token).await["token"]
+        .as_str()
+        .expect("api token")
+        .to_owned()
+}
+
+#[derive(Clone, Default)]
+struct StandInPushService {
+    received: Arc<Mutex<Vec<(String, HeaderMap, Bytes)>>>,
+}
+
+async fn accept_push(
+    State(service): State<StandInPushService>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    service
+        .received
+        .lock()
+        .expect("stand-in lock")
+        .push(("accepted".to_owned(), headers, body));
+    StatusCode::CREATED
+}
+
+async fn reject_gone_push(
+    State(service): State<StandInPushService>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    service
+        .received
+        .lock()
+        .expect("stand-in lock")
+        .push(("gone".to_owned(), headers, body));
+    StatusCode::GONE
+}
+
+/// Sends the push on to the `to` query parameter, like a compromised or
+/// misbehaving push service could.
+async fn redirect_push(
+    State(service): State<StandInPushService>,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> (StatusCode, [(header::HeaderName, String); 1]) {
+    service
+        .received
+        .lock()
+        .expect("stand-in lock")
+        .push(("redirect".to_owned(), headers, body));
+    (
+        StatusCode::TEMPORARY_REDIRECT,
+        [(header::LOCATION, query["to"].clone())],
+    )
+}
+
+async fn spawn_stand_in_push_service() -> (String, StandInPushService) {
+    let service = StandInPushService::default();
+    let router = Router::new()
+        .route("/push/accept", post(accept_push))
+        .route("/push/gone", post(reject_gone_push))
+        .route("/push/redirect", post(redirect_push))
+        .with_state(service.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind stand-in push service");
+    let addr = listener.local_addr().expect("stand-in addr");
+    tokio::spawn(async move {
+        axum::serve(listener, router)
+            .await
+            .expect("serve stand-in push service");
+    });
+    (format!("http://{addr}"), service)
+}
+
+const STAND_IN_IDP_CLIENT_ID: &str = "srs-e2e-client";
+const STAND_IN_IDP_KID: &str = "stand-in-key-1";
+
+struct PendingCode {
+    code_challenge: String,
+    claims: Value,
+}
+
+/// Minimal OpenID provider: discovery, a one-key JWKS and a token endpoint
+/// that checks PKCE before handing out an ES256-signed ID token.
+#[derive(Clone)]
+struct StandInIdp {
+    issuer: String,
+    encoding_key: EncodingKey,
+    public_jwk: Value,
+    pending: Arc<Mutex<HashMap<String, PendingCode>>>,
+}
+
+impl StandInIdp {
+    /// Plays the user approving the login at the provider: records what the
+    /// authorization URL asked for and returns the `(code, state)` redirect.
+    fn authorize(
+        &self,
+        authorization_url: &str,
+        subject: &str,
+        email: &str,
+        email_verified: bool,
+    ) -> (String, String) {
+        let url = reqwest::Url::parse(authorization_url).expect("authorization url");
+        let query = url.query_pairs().into_owned().collect::<HashMap<_, _>>();
+        assert_eq!(query["client_id"], STAND_IN_IDP_CLIENT_ID);
+        assert_eq!(query["code_challenge_method"], "S256");
+        let now = Utc::now().timestamp();
+        let code = format!("code-{subject}-{now}");
+        self.pending.lock().expect("idp lock").insert(
+            code.clone(),
+            PendingCode {
+                code_challenge: query["code_challenge"].clone(),
+                claims: json!({
+                    "iss": self.issuer,
+                    "aud": STAND_IN_IDP_CLIENT_ID,
+                    "sub": subject,
+                    "email": email,
+                    "email_verified": email_verified,
+                    "nonce": query["nonce"],
+                    "iat": now,
+                    "exp": now + 300,
+                }),
+            },
+        );
+        (code, query["state"].clone())
+    }
+}
+
+async fn idp_discovery(State(idp): State<StandInIdp>) -> Json<Value> {
+    Json(json!({
+        "issuer": idp.issuer,
+        "authorization_endpoint": format!("{}/authorize", idp.issuer),
+        "token_endpoint": format!("{}/token", idp.issuer),
+        "jwks_uri": format!("{}/jwks", idp.issuer),
+    }))
+}
+
+async fn idp_jwks(State(idp): State<StandInIdp>) -> Json<Value> {
+    Json(json!({ "keys": [idp.public_jwk] }))
+}
+
+async fn idp_token(
+    State(idp): State<StandInIdp>,
+    Form(form): Form<HashMap<String, String>>,
+) -> Result<Json<Value>, StatusCode> {
+    let pending = idp
+        .pending
+        .lock()
+        .expect("idp lock")
+        .remove(form.get("code").ok_or(StatusCode::BAD_REQUEST)?)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let verifier = form.get("code_verifier").ok_or(StatusCode::BAD_REQUEST)?;
+    if URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())) != pending.code_challenge {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let mut header = Header::new(Algorithm::ES256);
+    header.kid = Some(STAND_IN_IDP_KID.to_owned());
+    let id_token = jsonwebtoken::encode(&header, &pending.claims, &idp.encoding_key)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(json!({
+        "access_token": "stand-in-access-token",
+        "token_type": "Bearer",
+        "id_token": id_token,
+    })))
+}
+
+async fn spawn_stand_in_idp() -> StandInIdp {
+    let secret_key = random_p256_key();
+    let point = secret_key.public_key().to_encoded_point(false);
+    let der = secret_key.to_pkcs8_der().expect("pkcs8 der");
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind stand-in idp");
+    let addr = listener.local_addr().expect("stand-in idp addr");
+    let idp = StandInIdp {
+        issuer: format!("http://{addr}"),
+        encoding_key: EncodingKey::from_ec_der(der.as_bytes()),
+        public_jwk: json!({
+            "kty": "EC",
+            "crv": "P-256",
+            "kid": STAND_IN_IDP_KID,
+            "alg": "ES256",
+            "use": "sig",
+            "x": URL_SAFE_NO_PAD.encode(point.x().expect("x coordinate")),
+            "y": URL_SAFE_NO_PAD.encode(point.y().expect("y coordinate")),
+        }),
+        pending: Arc::new(Mutex::new(HashMap::new())),
+    };
+    let router = Router::new()
+        .route("/.well-known/openid-configuration", get(idp_discovery))
+        .route("/jwks", get(idp_jwks))
+        .route("/token", post(idp_token))
+        .with_state(idp.clone());
+    tokio::spawn(async move {
+        axum::serve(listener, router)
+            .await
+            .expect("serve stand-in idp");
+    });
+    idp
+}
+
+fn random_p256_key() -> SecretKey {
+    loop {
+        let bytes: [u8; 32] = rand::random();
+        if let Ok(secret_key) = SecretKey::from_slice(&bytes) {
+            return secret_key;
+        }
+    }
+}
+
+fn cbor_head(major: u8, len: usize) -> Vec<u8> {
+    match len {
+        0..=23 => vec![(major << 5) | len as u8],
+        24..=255 => vec![(major << 5) | 24, len as u8],
+        _ => {
+            let mut head = vec![(major << 5) | 25];
+            head.extend_from_slice(&(len as u16).to_be_bytes());
+            head
+        }
+    }
+}
+
+fn cbor_int(value: i64) -> Vec<u8> {
+    if value >= 0 {
+        cbor_head(0, value as usize)
+    } else {
+        cbor_head(1, (-1 - value) as usize)
+    }
+}
+
+fn cbor_bytes(bytes: &[u8]) -> Vec<u8> {
+    let mut encoded = cbor_head(2, bytes.len());
+    encoded.extend_from_slice(bytes);
+    encoded
+}
+
+fn cbor_text(text: &str) -> Vec<u8> {
+    let mut encoded = cbor_head(3, text.len());
+    encoded.extend_from_slice(text.as_bytes());
+    encoded
+}
+
+/// Software authenticator holding one ES256 passkey for the `localhost` RP.
+struct SoftPasskey {
+    signing_key: SigningKey,
+    credential_id: Vec<u8>,
+    sign_count: u32,
+}
+
+impl SoftPasskey {
+    fn new() -> Self {
+        Self {
+            signing_key: SigningKey::from(random_p256_key()),
+            credential_id: rand::random::<[u8; 16]>().to_vec(),
+            sign_count: 0,
+        }
+    }
+
+    fn credential_id(&self) -> String {
+        URL_SAFE_NO_PAD.encode(&self.credential_id)
+    }
+
+    fn client_data(kind: &str, challenge: &str) -> String {
+        let client_data = json!({
+            "type": kind,
+            "challenge": challenge,
+            "origin": "http://localhost:5173",
+            "crossOrigin": false,
+        });
+        URL_SAFE_NO_PAD.encode(client_data.to_string())
+    }
+
+    fn authenticator_data(&self, attested: bool) -> Vec<u8> {
+        let mut data = Sha256::digest(b"localhost").to_vec();
+        data.push(if attested { 0x45 } else { 0x05 });
+        data.extend_from_slice(&self.sign_count.to_be_bytes());
+        if attested {
+            let point = self.signing_key.verifying_key().to_encoded_point(false);
+            data.extend_from_slice(&[0u8; 16]);
+            data.extend_from_slice(&(self.credential_id.len() as u16).to_be_bytes());
+            data.extend_from_slice(&self.credential_id);
+            data.extend(cbor_head(5, 5));
+            for (label, value) in [
+                (1, cbor_int(2)),
+                (3, cbor_int(-7)),
+                (-1, cbor_int(1)),
+                (-2, cbor_bytes(point.x().expect("x coordinate"))),
+                (-3, cbor_bytes(point.y().expect("y coordinate"))),
+            ] {
+                data.extend(cbor_int(label));
+                data.extend(value);
+            }
+        }
+        data
+    }
+
+    fn attestation(&mut self, challenge: &str) -> Value {
+        self.sign_count += 1;
+        let mut attestation_object = cbor_head(5, 3);
+        attestation_object.extend(cbor_text("fmt"));
+        attestation_object.extend(cbor_text("none"));
+        attestation_object.extend(cbor_text("attStmt"));
+        attestation_object.extend(cbor_head(5, 0));
+        attestation_object.extend(cbor_text("authData"));
+        attestation_object.extend(cbor_bytes(&self.authenticator_data(true)));
+        json!({
+            "id": self.credential_id(),
+            "type": "public-key",
+            "response": {
+                "clientDataJSON": Self::client_data("webauthn.create", challenge),
+                "attestationObject": URL_SAFE_NO_PAD.encode(attestation_object),
+            },
+        })
+    }
+
+    fn assertion(&mut self, challenge: &str, user_handle: &str) -> Value {
+        self.sign_count += 1;
+        let client_data_json = Self::client_data("webauthn.get", challenge);
+        let authenticator_data = self.authenticator_data(false);
+        let mut signed = authenticator_data.clone();
+        signed.extend_from_slice(&Sha256::digest(
+            URL_SAFE_NO_PAD
+                .decode(&client_data_json)
+                .expect("client data"),
+        ));
+        let signature: Signature = self.signing_key.sign(&signed);
+        json!({
+            "id": self.credential_id(),
+            "type": "public-key",
+            "response": {
+                "clientDataJSON": client_data_json,
+                "authenticatorData": URL_SAFE_NO_PAD.encode(authenticator_data),
+                "signature": URL_SAFE_NO_PAD.encode(signature.to_der().as_bytes()),
+                "userHandle": user_handle,
+            },
+        })
+    }
+}
+
 #[tokio::test]
 #[serial]
 async fn auth_lifecycle_works_end_to_end() {
@@ -66,6 +504,21 @@ async fn auth_lifecycle_works_end_to_end() {
         .expect("health response");
     assert_eq!(health.status(), StatusCode::OK);
 
+    let jwks = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/.well-known/jwks.json")
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("jwks response");
+    assert_eq!(jwks.status(), StatusCode::OK);
+    assert!(jwks.headers().contains_key(header::CACHE_CONTROL));
+    // HS256 secrets are never published.
+    assert_eq!(json_response(jwks).await, json!({ "keys": [] }));
+
     let request_token = app
         .clone()
         .oneshot(
@@ -323,8 +776,32 @@ async fn ingestion_dedup_and_settings_dashboard_contracts_hold() {
     assert_eq!(dashboard.status(), StatusCode::OK);
     let dashboard_body = json_response(dashboard).await;
     assert_eq!(dashboard_body["upcoming_count"], 1);
-    assert_eq!(dashboard_body["leetcode_count"], 1);
-    assert_eq!(dashboard_body["neetcode_count"], 0);
+    let source_counts = dashboard_body["source_counts"]
+        .as_array()
+        .expect("source counts")
+        .iter()
+        .map(|entry| {
+            (
+                entry["source"].as_str().expect("source").to_owned(),
+                entry["count"].as_u64().expect("count"),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        source_counts,
+        [
+            ("leetcode".to_owned(), 1),
+            ("neetcode".to_owned(), 0),
+            ("codeforces".to_owned(), 0),
+            ("atcoder".to_owned(), 0),
+            ("hackerrank".to_owned(), 0),
+            ("exercism".to_owned(), 0),
+        ]
+    );
+    assert_eq!(
+        dashboard_body["source_counts"][0]["display_name"],
+        "LeetCode"
+    );
 
     let get_settings = app
         .clone()
@@ -419,3 +896,3029 @@ async fn csrf_rejects_mutating_cookie_requests_without_origin() {
         .expect("save settings response");
     assert_eq!(save_settings.status(), StatusCode::FORBIDDEN);
 }
+
+#[tokio::test]
+#[serial]
+async fn web_push_digest_reaches_subscribed_devices() {
+    let Some(mut state) = test_state().await else {
+        return;
+    };
+    let (push_base_url, push_service) = spawn_stand_in_push_service().await;
+    state.push_service = state
+        .push_service
+        .clone()
+        .with_endpoint_origin(&Url::parse(&push_base_url).expect("stand-in url"));
+    let app = app_router(state.clone());
+    let auth_cookie = login_cookie(&app, "push@test.com").await;
+    let api_token = create_api_token(&app, &auth_cookie, json!({"label":"push"})).await;
+
+    let vapid = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/push/vapid-public-key")
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("vapid response");
+    assert_eq!(vapid.status(), StatusCode::OK);
+    let vapid_key = json_response(vapid).await["public_key"]
+        .as_str()
+        .expect("vapid public key")
+        .to_owned();
+
+    let device_key = p256::SecretKey::from_slice(&[9_u8; 32]).expect("device key");
+    let p256dh = URL_SAFE_NO_PAD.encode(device_key.public_key().to_sec1_bytes());
+    let auth = URL_SAFE_NO_PAD.encode([3_u8; 16]);
+    let subscribe = |endpoint: String| {
+        let app = app.clone();
+        let request = Request::builder()
+            .method("POST")
+            .uri("/push/subscriptions")
+            .header(header::COOKIE, &auth_cookie)
+            .header(header::ORIGIN, "http://localhost:5173")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                json!({
+                    "endpoint": endpoint,
+                    "keys": {"p256dh": p256dh, "auth": auth},
+                    "device_label": "Test browser"
+                })
+                .to_string(),
+            ))
+            .expect("request");
+        async move { app.oneshot(request).await.expect("subscribe response") }
+    };
+    for path in ["/push/accept", "/push/gone"] {
+        let subscribed = subscribe(format!("{push_base_url}{path}")).await;
+        assert_eq!(subscribed.status(), StatusCode::CREATED);
+    }
+    for endpoint in [
+        "http://127.0.0.1:9/push/accept",
+        "https://10.0.0.1/push/accept",
+        "https://push.example.com/push/accept",
+    ] {
+        let rejected = subscribe(endpoint.to_owned()).await;
+        assert_eq!(rejected.status(), StatusCode::BAD_REQUEST);
+    }
+
+    let ingest = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/events/problem-status")
+                .header("x-api-key", &api_token)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(
+                    json!({
+                      "source":"leetcode",
+                      "problem_slug":"two-sum",
+                      "title":"Two Sum",
+                      "url":"https://leetcode.com/problems/two-sum",
+                      "status":"solved",
+                      "occurred_at":"2026-01-01T00:00:00Z"
+                    })
+                    .to_string(),
+                ))
+                .expect("request"),
+        )
+        .await
+        .expect("ingest response");
+    assert_eq!(ingest.status(), StatusCode::CREATED);
+
+    let digest_time = Utc::now().with_hour(12).expect("digest hour");
+    state
+        .notification_service
+        .process_digests_once(digest_time)
+        .await
+        .expect("process digests");
+
+    let received = push_service.received.lock().expect("stand-in lock").clone();
+    assert_eq!(received.len(), 2);
+    for (_, headers, body) in &received {
+        assert_eq!(headers["content-encoding"], "aes128gcm");
+        assert!(headers.contains_key("ttl"));
+        let authorization = headers["authorization"].to_str().expect("auth header");
+        assert!(authorization.starts_with("vapid t="));
+        assert!(authorization.ends_with(&format!("k={vapid_key}")));
+        assert_eq!(&body[16..20], &4096_u32.to_be_bytes());
+        assert_eq!(body[20], 65);
+    }
+
+    let subscriptions = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/push/subscriptions")
+                .header(header::COOKIE, &auth_cookie)
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("list subscriptions response");
+    let subscriptions = json_response(subscriptions).await;
+    let subscriptions = subscriptions.as_array().expect("subscriptions");
+    assert_eq!(subscriptions.len(), 1);
+    assert!(
+        subscriptions[0]["endpoint"]
+            .as_str()
+            .is_some_and(|endpoint| endpoint.ends_with("/push/accept"))
+    );
+    assert!(!subscriptions[0]["last_used_at"].is_null());
+
+    let unsubscribe = app
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(format!("/push/subscriptions/{}", subscriptions[0]["id"]))
+                .header(header::COOKIE, &auth_cookie)
+                .header(header::ORIGIN, "http://localhost:5173")
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("unsubscribe response");
+    assert_eq!(unsubscribe.status(), StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+#[serial]
+async fn calendar_feed_serves_ics_until_revoked() {
+    let Some(app) = test_app().await else {
+        return;
+    };
+    let auth_cookie = login_cookie(&app, "calendar@test.com").await;
+    let api_token = create_api_token(&app, &auth_cookie, json!({"label":"calendar"})).await;
+
+    let ingest = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/events/problem-status")
+                .header("x-api-key", &api_token)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(
+                    json!({
+                      "source":"leetcode",
+                      "problem_slug":"valid-anagram",
+                      "title":"Valid Anagram",
+                      "url":"https://leetcode.com/problems/valid-anagram",
+                      "status":"solved",
+                      "occurred_at":"2026-01-01T00:00:00Z"
+                    })
+                    .to_string(),
+                ))
+                .expect("request"),
+        )
+        .await
+        .expect("ingest response");
+    assert_eq!(ingest.status(), StatusCode::CREATED);
+
+    let create_feed = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/calendar/feeds")
+                .header(header::COOKIE, &auth_cookie)
+                .header(header::ORIGIN, "http://localhost:5173")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(json!({"label":"Phone calendar"}).to_string()))
+                .expect("request"),
+        )
+        .await
+        .expect("create feed response");
+    assert_eq!(create_feed.status(), StatusCode::OK);
+    let created = json_response(create_feed).await;
+    let feed_path = created["feed_path"].as_str().expect("feed path").to_owned();
+    let feed_id = created["feed_summary"]["id"].clone();
+
+    let feed = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(&feed_path)
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("calendar feed response");
+    assert_eq!(feed.status(), StatusCode::OK);
+    assert_eq!(
+        feed.headers()[header::CONTENT_TYPE],
+        "text/calendar; charset=utf-8"
+    );
+    let body = feed
+        .into_body()
+        .collect()
+        .await
+        .expect("collect body")
+        .to_bytes();
+    let calendar = String::from_utf8(body.to_vec()).expect("utf8 calendar");
+    assert!(calendar.starts_with("BEGIN:VCALENDAR\r\n"));
+    assert_eq!(calendar.matches("BEGIN:VEVENT").count(), 1);
+    assert!(calendar.contains("Valid Anagram"));
+
+    let revoke = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(format!("/calendar/feeds/{feed_id}"))
+                .header(header::COOKIE, &auth_cookie)
+                .header(header::ORIGIN, "http://localhost:5173")
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("revoke feed response");
+    assert_eq!(revoke.status(), StatusCode::NO_CONTENT);
+
+    let revoked_feed = app
+        .oneshot(
+            Request::builder()
+                .uri(&feed_path)
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("calendar feed response");
+    assert_eq!(revoked_feed.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[serial]
+async fn integration_token_scopes_are_enforced() {
+    let Some(app) = test_app().await else {
+        return;
+    };
+    let auth_cookie = login_cookie(&app, "scopes@test.com").await;
+    let ingest_token = create_api_token(&app, &auth_cookie, json!({"label":"userscript"})).await;
+    let cli_token = create_api_token(
+        &app,
+        &auth_cookie,
+        json!({"label":"cli","scopes":["reviews:read","reviews:write","cards:read"]}),
+    )
+    .await;
+
+    let unknown_scope = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/integrations/tokens")
+                .header(header::COOKIE, &auth_cookie)
+                .header(header::ORIGIN, "http://localhost:5173")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(
+                    json!({"label":"bad","scopes":["admin"]}).to_string(),
+                ))
+                .expect("request"),
+        )
+        .await
+        .expect("create token response");
+    assert_eq!(unknown_scope.status(), StatusCode::BAD_REQUEST);
+
+    let event_payload = json!({
+      "source":"leetcode",
+      "problem_slug":"climbing-stairs",
+      "title":"Climbing Stairs",
+      "url":"https://leetcode.com/problems/climbing-stairs",
+      "status":"solved",
+      "occurred_at":"2026-01-01T00:00:00Z"
+    });
+    for (token, expected) in [
+        (&cli_token, StatusCode::FORBIDDEN),
+        (&ingest_token, StatusCode::CREATED),
+    ] {
+        let ingest = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/events/problem-status")
+                    .header("x-api-key", token)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(event_payload.to_string()))
+                    .expect("request"),
+            )
+            .await
+            .expect("ingest response");
+        assert_eq!(ingest.status(), expected);
+    }
+
+    let denied_due = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/reviews/due")
+                .header("x-api-key", &ingest_token)
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("due response");
+    assert_eq!(denied_due.status(), StatusCode::FORBIDDEN);
+
+    let cards = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/cards")
+                .header("x-api-key", &cli_token)
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("cards response");
+    assert_eq!(cards.status(), StatusCode::OK);
+    let cards = json_response(cards).await;
+    let card_id = cards[0]["id"].clone();
+
+    let grade = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/reviews/{card_id}/grade"))
+                .header("x-api-key", &cli_token)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(json!({"grade":"good"}).to_string()))
+                .expect("request"),
+        )
+        .await
+        .expect("grade response");
+    assert_eq!(grade.status(), StatusCode::OK);
+
+    let history = app
+        .oneshot(
+            Request::builder()
+                .uri("/history")
+                .header("x-api-key", &cli_token)
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("history response");
+    assert_eq!(history.status(), StatusCode::OK);
+    assert_eq!(
+        json_response(history).await.as_array().map(Vec::len),
+        Some(1)
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn web_push_only_posts_to_allowed_endpoints_and_does_not_follow_redirects() {
+    let Some(db) = support::db::try_test_db().await else {
+        return;
+    };
+    support::db::reset_db(&db).await;
+    let repo = Arc::new(PostgresRepository::new(db, SrsSchedule::default()));
+    let (allowed_base_url, allowed) = spawn_stand_in_push_service().await;
+    let (other_base_url, other) = spawn_stand_in_push_service().await;
+    let endpoints = PushEndpoints::default();
+    endpoints.allow_origin(&Url::parse(&allowed_base_url).expect("stand-in url"));
+
+    // Stored directly, as rows from before the allowlist would be.
+    let user = repo
+        .get_or_create_user("push-allowlist@test.com")
+        .await
+        .expect("user");
+    let device_key = p256::SecretKey::from_slice(&[9_u8; 32]).expect("device key");
+    for endpoint in [
+        format!("{allowed_base_url}/push/redirect?to={other_base_url}/push/accept"),
+        format!("{other_base_url}/push/accept"),
+    ] {
+        repo.upsert_push_subscription(
+            user.id,
+            NewPushSubscription {
+                endpoint,
+                p256dh: URL_SAFE_NO_PAD.encode(device_key.public_key().to_sec1_bytes()),
+                auth: URL_SAFE_NO_PAD.encode([3_u8; 16]),
+                device_label: "Test browser".to_owned(),
+            },
+        )
+        .await
+        .expect("store subscription");
+    }
+
+    let sender = WebPushNotificationSender::new(
+        repo.clone(),
+        VapidKeys::generate("mailto:test@example.com"),
+        endpoints,
+    );
+    sender
+        .send_digest(user.id, "Reviews due", "1 card due")
+        .await
+        .expect("send digest");
+
+    let received = allowed.received.lock().expect("stand-in lock").clone();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].0, "redirect");
+    assert!(other.received.lock().expect("stand-in lock").is_empty());
+}
+
+#[tokio::test]
+#[serial]
+async fn integration_tokens_rotate_with_grace_and_warn_before_expiry() {
+    let Some(mut state) = test_state().await else {
+        return;
+    };
+    state.security.trust_proxy_headers = true;
+    let (push_base_url, push_service) = spawn_stand_in_push_service().await;
+    state.push_service = state
+        .push_service
+        .clone()
+        .with_endpoint_origin(&Url::parse(&push_base_url).expect("stand-in url"));
+    let app = app_router(state.clone());
+    let auth_cookie = login_cookie(&app, "rotate@test.com").await;
+    let original = create_api_token(
+        &app,
+        &auth_cookie,
+        json!({"label":"cli","scopes":["cards:read"],"expires_in_days":3}),
+    )
+    .await;
+
+    let list_cards = |token: String| {
+        let app = app.clone();
+        async move {
+            app.oneshot(
+                Request::builder()
+                    .uri("/cards")
+                    .header("x-api-key", token)
+                    .header(header::USER_AGENT, "srs-cli/1.0")
+                    .header("x-forwarded-for", "203.0.113.7, 10.0.0.1")
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("cards response")
+            .status()
+        }
+    };
+    let rotate = |token_id: i64, body: Value| {
+        let app = app.clone();
+        let auth_cookie = auth_cookie.clone();
+        async move {
+            app.oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/integrations/tokens/{token_id}/rotate"))
+                    .header(header::COOKIE, auth_cookie)
+                    .header(header::ORIGIN, "http://localhost:5173")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(body.to_string()))
+                    .expect("request"),
+            )
+            .await
+            .expect("rotate response")
+        }
+    };
+    let list_tokens = || {
+        let app = app.clone();
+        let auth_cookie = auth_cookie.clone();
+        async move {
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .uri("/integrations")
+                        .header(header::COOKIE, auth_cookie)
+                        .body(Body::empty())
+                        .expect("request"),
+                )
+                .await
+                .expect("integrations response");
+            json_response(response).await["tokens"].clone()
+        }
+    };
+
+    assert_eq!(list_cards(original.clone()).await, StatusCode::OK);
+    let token_id = list_tokens().await[0]["id"].as_i64().expect("token id");
+
+    let graced = rotate(token_id, json!({"grace_period_minutes": 30})).await;
+    assert_eq!(graced.status(), StatusCode::OK);
+    let graced = json_response(graced).await;
+    let second = graced["token"].as_str().expect("rotated token").to_owned();
+    assert_ne!(second, original);
+    assert_eq!(graced["token_summary"]["id"], token_id);
+    assert!(!graced["token_summary"]["previous_secret_expires_at"].is_null());
+    assert_eq!(list_cards(original.clone()).await, StatusCode::OK);
+    assert_eq!(list_cards(second.clone()).await, StatusCode::OK);
+
+    let immediate = rotate(token_id, json!({"expires_in_days": 30})).await;
+    assert_eq!(immediate.status(), StatusCode::OK);
+    let third = json_response(immediate).await["token"]
+        .as_str()
+        .expect("rotated token")
+        .to_owned();
+    assert_eq!(list_cards(original).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(list_cards(second).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(list_cards(third).await, StatusCode::OK);
+    assert_eq!(
+        rotate(token_id + 1000, json!({})).await.status(),
+        StatusCode::NOT_FOUND
+    );
+
+    let tokens = list_tokens().await;
+    assert_eq!(tokens[0]["last_used_ip"], "203.0.113.7");
+    assert_eq!(tokens[0]["last_used_user_agent"], "srs-cli/1.0");
+    assert!(tokens[0]["previous_secret_expires_at"].is_null());
+
+    create_api_token(
+        &app,
+        &auth_cookie,
+        json!({"label":"nightly sync","expires_in_days":2}),
+    )
+    .await;
+    let device_key = p256::SecretKey::from_slice(&[5_u8; 32]).expect("device key");
+    let subscribe = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/push/subscriptions")
+                .header(header::COOKIE, &auth_cookie)
+                .header(header::ORIGIN, "http://localhost:5173")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(
+                    json!({
+                        "endpoint": format!("{push_base_url}/push/accept"),
+                        "keys": {
+                            "p256dh": URL_SAFE_NO_PAD.encode(device_key.public_key().to_sec1_bytes()),
+                            "auth": URL_SAFE_NO_PAD.encode([4_u8; 16])
+                        }
+                    })
+                    .to_string(),
+                ))
+                .expect("request"),
+        )
+        .await
+        .expect("subscribe response");
+    assert_eq!(subscribe.status(), StatusCode::CREATED);
+
+    for _ in 0..2 {
+        state
+            .notification_service
+            .process_token_expiry_once(Utc::now())
+            .await
+            .expect("process token expiry");
+    }
+    assert_eq!(
+        push_service.received.lock().expect("stand-in lock").len(),
+        1
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn sessions_are_listed_and_revocable() {
+    let Some(app) = test_app().await else {
+        return;
+    };
+    let laptop = login_cookie_from(&app, "sessions@test.com", "Laptop Firefox").await;
+    let phone = login_cookie_from(&app, "sessions@test.com", "Phone Safari").await;
+    let tablet = login_cookie_from(&app, "sessions@test.com", "Tablet Chrome").await;
+
+    let me_status = |cookie: String| {
+        let app = app.clone();
+        async move {
+            app.oneshot(
+                Request::builder()
+                    .uri("/me")
+                    .header(header::COOKIE, cookie)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("me response")
+            .status()
+        }
+    };
+    let list_sessions = |cookie: String| {
+        let app = app.clone();
+        async move {
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .uri("/auth/sessions")
+                        .header(header::COOKIE, cookie)
+                        .body(Body::empty())
+                        .expect("request"),
+                )
+                .await
+                .expect("sessions response");
+            assert_eq!(response.status(), StatusCode::OK);
+            json_response(response).await
+        }
+    };
+
+    let sessions = list_sessions(laptop.clone()).await;
+    let sessions = sessions.as_array().expect("sessions").clone();
+    assert_eq!(sessions.len(), 3);
+    let current = sessions
+        .iter()
+        .find(|session| session["current"] == true)
+        .expect("current session");
+    assert_eq!(current["user_agent"], "Laptop Firefox");
+    let phone_session = sessions
+        .iter()
+        .find(|session| session["user_agent"] == "Phone Safari")
+        .expect("phone session");
+
+    let revoke_phone = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(format!("/auth/sessions/{}", phone_session["id"]))
+                .header(header::COOKIE, &laptop)
+                .header(header::ORIGIN, "http://localhost:5173")
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("revoke session response");
+    assert_eq!(revoke_phone.status(), StatusCode::NO_CONTENT);
+    assert_eq!(me_status(phone.clone()).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(me_status(tablet.clone()).await, StatusCode::OK);
+
+    let revoke_others = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/auth/sessions/revoke-others")
+                .header(header::COOKIE, &laptop)
+                .header(header::ORIGIN, "http://localhost:5173")
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("revoke others response");
+    assert_eq!(revoke_others.status(), StatusCode::OK);
+    assert_eq!(json_response(revoke_others).await["revoked_count"], 1);
+    assert_eq!(me_status(tablet).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        list_sessions(laptop.clone()).await.as_array().map(Vec::len),
+        Some(1)
+    );
+
+    let logout = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/auth/logout")
+                .header(header::COOKIE, &laptop)
+                .header(header::ORIGIN, "http://localhost:5173")
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("logout response");
+    assert_eq!(logout.status(), StatusCode::NO_CONTENT);
+    assert_eq!(me_status(laptop).await, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[serial]
+async fn rate_limits_reject_bursts_with_retry_after() {
+    let Some(mut state) = test_state().await else {
+        return;
+    };
+    let rule = |limit| {
+        Some(RateLimitRule {
+            limit,
+            window: std::time::Duration::from_secs(60),
+        })
+    };
+    state.security.trust_proxy_headers = true;
+    state.rate_limiter = RateLimiter::in_memory(RateLimitConfig {
+        ip: rule(2),
+        email: rule(1),
+        api_token: rule(2),
+    });
+    let app = app_router(state);
+    let auth_cookie = login_cookie(&app, "limits@test.com").await;
+    let api_token = create_api_token(&app, &auth_cookie, json!({"label":"burst"})).await;
+
+    let request_link = |email: &'static str, ip: &'static str| {
+        let app = app.clone();
+        async move {
+            app.oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/auth/request-magic-link")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .header("x-forwarded-for", ip)
+                    .body(Body::from(json!({ "email": email }).to_string()))
+                    .expect("request"),
+            )
+            .await
+            .expect("request magic link response")
+        }
+    };
+
+    assert_eq!(
+        request_link("first@test.com", "198.51.100.1")
+            .await
+            .status(),
+        StatusCode::OK
+    );
+    let same_email = request_link("First@test.com", "198.51.100.2").await;
+    assert_eq!(same_email.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = same_email.headers()[header::RETRY_AFTER]
+        .to_str()
+        .expect("retry-after")
+        .parse()
+        .expect("retry-after seconds");
+    assert!((1..=60).contains(&retry_after));
+
+    assert_eq!(
+        request_link("second@test.com", "198.51.100.1")
+            .await
+            .status(),
+        StatusCode::OK
+    );
+    let same_ip = request_link("third@test.com", "198.51.100.1").await;
+    assert_eq!(same_ip.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(same_ip.headers().contains_key(header::RETRY_AFTER));
+
+    let mut statuses = Vec::new();
+    for _ in 0..3 {
+        let ingest = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/events/problem-status")
+                    .header("x-api-key", &api_token)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(
+                        json!({
+                          "source":"leetcode",
+                          "problem_slug":"two-sum",
+                          "title":"Two Sum",
+                          "url":"https://leetcode.com/problems/two-sum",
+                          "status":"solved",
+                          "occurred_at":"2026-01-01T00:00:00Z"
+                        })
+                        .to_string(),
+                    ))
+                    .expect("request"),
+            )
+            .await
+            .expect("ingest response");
+        statuses.push(ingest.status());
+    }
+    assert_eq!(
+        statuses,
+        vec![
+            StatusCode::CREATED,
+            StatusCode::CREATED,
+            StatusCode::TOO_MANY_REQUESTS
+        ]
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn oidc_login_links_verified_email_and_issues_session_cookie() {
+    let Some(mut state) = test_state().await else {
+        return;
+    };
+    let unconfigured = app_router(state.clone())
+        .oneshot(
+            Request::builder()
+                .uri("/auth/oidc/login")
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("unconfigured login response");
+    assert_eq!(unconfigured.status(), StatusCode::NOT_FOUND);
+
+    let idp = spawn_stand_in_idp().await;
+    state.auth_service = state.auth_service.clone().with_oidc(OidcConfig {
+        issuer_url: idp.issuer.clone(),
+        client_id: STAND_IN_IDP_CLIENT_ID.to_owned(),
+        client_secret: Some("stand-in-secret".to_owned()),
+        redirect_url: "http://localhost:3000/auth/oidc/callback".to_owned(),
+        scopes: "openid email".to_owned(),
+        post_login_redirect: "http://localhost:5173/".to_owned(),
+    });
+    let app = app_router(state);
+
+    let magic_link_cookie = login_cookie(&app, "sso@example.com").await;
+    let me = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/me")
+                .header(header::COOKIE, &magic_link_cookie)
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("me response");
+    let magic_link_user_id = json_response(me).await["id"].as_i64().expect("user id");
+
+    let start_login = |app: axum::Router| async move {
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/auth/oidc/login")
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("oidc login response");
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let location = response.headers()[header::LOCATION]
+            .to_str()
+            .expect("location")
+            .to_owned();
+        (location, auth_cookie_from_headers(response.headers()))
+    };
+    let callback = |app: axum::Router, code: String, state: String, cookie: String| async move {
+        app.oneshot(
+            Request::builder()
+                .uri(format!("/auth/oidc/callback?code={code}&state={state}"))
+                .header(header::COOKIE, cookie)
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("oidc callback response")
+    };
+
+    let (location, state_cookie) = start_login(app.clone()).await;
+    assert!(location.starts_with(&format!("{}/authorize?", idp.issuer)));
+    assert!(state_cookie.starts_with("srs_oidc_state="));
+    let (code, oidc_state) = idp.authorize(&location, "subject-1", "SSO@example.com", true);
+
+    let forged = callback(
+        app.clone(),
+        code.clone(),
+        oidc_state.clone(),
+        "srs_oidc_state=forged".to_owned(),
+    )
+    .await;
+    assert_eq!(forged.status(), StatusCode::UNAUTHORIZED);
+
+    let completed = callback(
+        app.clone(),
+        code.clone(),
+        oidc_state.clone(),
+        state_cookie.clone(),
+    )
+    .await;
+    assert_eq!(completed.status(), StatusCode::SEE_OTHER);
+    assert_eq!(
+        completed.headers()[header::LOCATION],
+        "http://localhost:5173/"
+    );
+    let set_cookies = completed
+        .headers()
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .collect::<Vec<_>>();
+    assert!(
+        set_cookies
+            .iter()
+            .any(|cookie| cookie.starts_with("srs_oidc_state=;") && cookie.contains("Max-Age=0"))
+    );
+    let sso_cookie = set_cookies
+        .iter()
+        .find(|cookie| cookie.starts_with("srs_auth="))
+        .and_then(|cookie| cookie.split(';').next())
+        .expect("auth cookie")
+        .to_owned();
+    let me = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/me")
+                .header(header::COOKIE, &sso_cookie)
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("me response");
+    assert_eq!(me.status(), StatusCode::OK);
+    assert_eq!(
+        json_response(me).await["id"].as_i64(),
+        Some(magic_link_user_id)
+    );
+
+    let replayed = callback(app.clone(), code, oidc_state, state_cookie).await;
+    assert_eq!(replayed.status(), StatusCode::UNAUTHORIZED);
+
+    let (location, state_cookie) = start_login(app.clone()).await;
+    let (code, oidc_state) = idp.authorize(&location, "subject-2", "unverified@example.com", false);
+    let unverified = callback(app.clone(), code, oidc_state, state_cookie).await;
+    assert_eq!(unverified.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[serial]
+async fn passkeys_register_after_magic_link_and_log_in() {
+    let Some(app) = test_app().await else {
+        return;
+    };
+    let auth_cookie = login_cookie(&app, "passkey@example.com").await;
+    let post_json = |uri: &'static str, cookie: Option<&str>, body: Value| {
+        let mut request = Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header(header::ORIGIN, "http://localhost:5173")
+            .header(header::CONTENT_TYPE, "application/json");
+        if let Some(cookie) = cookie {
+            request = request.header(header::COOKIE, cookie);
+        }
+        app.clone()
+            .oneshot(request.body(Body::from(body.to_string())).expect("request"))
+    };
+
+    let options = post_json(
+        "/auth/passkeys/register/options",
+        Some(&auth_cookie),
+        json!({}),
+    )
+    .await
+    .expect("registration options response");
+    assert_eq!(options.status(), StatusCode::OK);
+    let options = json_response(options).await;
+    assert_eq!(options["rp"]["id"], "localhost");
+    assert_eq!(options["attestation"], "none");
+    let user_handle = options["user"]["id"]
+        .as_str()
+        .expect("user handle")
+        .to_owned();
+    let challenge = options["challenge"].as_str().expect("challenge").to_owned();
+
+    let mut authenticator = SoftPasskey::new();
+    let registration = authenticator.attestation(&challenge);
+    let registered = post_json(
+        "/auth/passkeys/register",
+        Some(&auth_cookie),
+        json!({ "label": "Laptop", "credential": registration }),
+    )
+    .await
+    .expect("register response");
+    assert_eq!(registered.status(), StatusCode::OK);
+    let passkey_id = json_response(registered).await["id"]
+        .as_i64()
+        .expect("passkey id");
+
+    let replayed = post_json(
+        "/auth/passkeys/register",
+        Some(&auth_cookie),
+        json!({ "credential": registration }),
+    )
+    .await
+    .expect("replayed register response");
+    assert_eq!(replayed.status(), StatusCode::UNAUTHORIZED);
+
+    let listed = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/auth/passkeys")
+                .header(header::COOKIE, &auth_cookie)
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("list passkeys response");
+    let listed = json_response(listed).await;
+    assert_eq!(listed.as_array().map(Vec::len), Some(1));
+    assert_eq!(listed[0]["label"], "Laptop");
+
+    let login_challenge = || async {
+        let options = post_json("/auth/passkeys/login/options", None, json!({}))
+            .await
+            .expect("login options response");
+        assert_eq!(options.status(), StatusCode::OK);
+        json_response(options).await["challenge"]
+            .as_str()
+            .expect("challenge")
+            .to_owned()
+    };
+
+    let assertion = authenticator.assertion(&login_challenge().await, &user_handle);
+    let login = post_json("/auth/passkeys/login", None, assertion.clone())
+        .await
+        .expect("passkey login response");
+    assert_eq!(login.status(), StatusCode::OK);
+    let passkey_cookie = auth_cookie_from_headers(login.headers());
+    assert_eq!(
+        json_response(login).await["user"]["email"],
+        "passkey@example.com"
+    );
+    let me = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/me")
+                .header(header::COOKIE, &passkey_cookie)
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("me response");
+    assert_eq!(me.status(), StatusCode::OK);
+
+    // A cloned authenticator replays an old counter value.
+    authenticator.sign_count -= 1;
+    let cloned = authenticator.assertion(&login_challenge().await, &user_handle);
+    let rejected = post_json("/auth/passkeys/login", None, cloned)
+        .await
+        .expect("cloned login response");
+    assert_eq!(rejected.status(), StatusCode::UNAUTHORIZED);
+
+    let deleted = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(format!("/auth/passkeys/{passkey_id}"))
+                .header(header::COOKIE, &auth_cookie)
+                .header(header::ORIGIN, "http://localhost:5173")
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("delete passkey response");
+    assert_eq!(deleted.status(), StatusCode::NO_CONTENT);
+    let after_delete = authenticator.assertion(&login_challenge().await, &user_handle);
+    let rejected = post_json("/auth/passkeys/login", None, after_delete)
+        .await
+        .expect("deleted passkey login response");
+    assert_eq!(rejected.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[serial]
+async fn account_export_streams_archive_and_deletion_needs_confirmation() {
+    let Some(mut state) = test_state().await else {
+        return;
+    };
+    let (push_base_url, push_service) = spawn_stand_in_push_service().await;
+    state.push_service = state
+        .push_service
+        .clone()
+        .with_endpoint_origin(&Url::parse(&push_base_url).expect("stand-in url"));
+    let app = app_router(state);
+    let auth_cookie = login_cookie(&app, "leaving@test.com").await;
+    let device_key = p256::SecretKey::from_slice(&[9_u8; 32]).expect("device key");
+    let subscribed = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/push/subscriptions")
+                .header(header::COOKIE, &auth_cookie)
+                .header(header::ORIGIN, "http://localhost:5173")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(
+                    json!({
+                        "endpoint": format!("{push_base_url}/push/accept"),
+                        "keys": {
+                            "p256dh": URL_SAFE_NO_PAD.encode(device_key.public_key().to_sec1_bytes()),
+                            "auth": URL_SAFE_NO_PAD.encode([3_u8; 16])
+                        },
+                        "device_label": "Phone"
+                    })
+                    .to_string(),
+                ))
+                .expect("request"),
+        )
+        .await
+        .expect("subscribe response");
+    assert_eq!(subscribed.status(), StatusCode::CREATED);
+    let bystander_cookie = login_cookie(&app, "staying@test.com").await;
+    let ingest_token = create_api_token(&app, &auth_cookie, json!({"label":"userscript"})).await;
+    let export_token = create_api_token(
+        &app,
+        &auth_cookie,
+        json!({"label":"backup","scopes":["export:read"]}),
+    )
+    .await;
+    let bystander_token =
+        create_api_token(&app, &bystander_cookie, json!({"label":"userscript"})).await;
+    for token in [&ingest_token, &bystander_token] {
+        let ingest = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/events/problem-status")
+                    .header("x-api-key", token)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(
+                        json!({
+                          "source":"leetcode",
+                          "problem_slug":"two-sum",
+                          "title":"Two Sum",
+                          "url":"https://leetcode.com/problems/two-sum",
+                          "status":"solved",
+                          "occurred_at":"2026-01-01T00:00:00Z"
+                        })
+                        .to_string(),
+                    ))
+                    .expect("request"),
+            )
+            .await
+            .expect("ingest response");
+        assert_eq!(ingest.status(), StatusCode::CREATED);
+    }
+
+    let export = |credential: (header::HeaderName, String)| {
+        let app = app.clone();
+        async move {
+            app.oneshot(
+                Request::builder()
+                    .uri("/me/export")
+                    .header(credential.0, credential.1)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("export response")
+        }
+    };
+    let denied = export((
+        header::HeaderName::from_static("x-api-key"),
+        ingest_token.clone(),
+    ))
+    .await;
+    assert_eq!(denied.status(), StatusCode::FORBIDDEN);
+    let by_token = export((header::HeaderName::from_static("x-api-key"), export_token)).await;
+    assert_eq!(by_token.status(), StatusCode::OK);
+    let archive = export((header::COOKIE, auth_cookie.clone())).await;
+    assert_eq!(archive.status(), StatusCode::OK);
+    assert!(
+        archive.headers()[header::CONTENT_DISPOSITION]
+            .to_str()
+            .expect("disposition")
+            .starts_with("attachment; filename=\"srs-anything-export-")
+    );
+    let archive = json_response(archive).await;
+    assert_eq!(archive["format"], "srs-anything-export");
+    assert_eq!(archive["version"], 1);
+    assert_eq!(archive["user"]["email"], "leaving@test.com");
+    assert_eq!(archive["settings"]["digest_hour_utc"], 12);
+    assert_eq!(archive["cards"][0]["problem_slug"], "two-sum");
+    assert_eq!(archive["problem_events"].as_array().map(Vec::len), Some(1));
+    assert_eq!(archive["reviews"], json!([]));
+    let tokens = archive["integration_tokens"].as_array().expect("tokens");
+    assert_eq!(tokens.len(), 2);
+    assert!(tokens.iter().all(|token| token.get("token_hash").is_none()));
+
+    let delete_account = |token: &str| {
+        let app = app.clone();
+        let auth_cookie = auth_cookie.clone();
+        let body = json!({ "confirmation_token": token }).to_string();
+        async move {
+            app.oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri("/me")
+                    .header(header::COOKIE, auth_cookie)
+                    .header(header::ORIGIN, "http://localhost:5173")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(body))
+                    .expect("request"),
+            )
+            .await
+            .expect("delete account response")
+        }
+    };
+    assert_eq!(
+        delete_account("not-requested").await.status(),
+        StatusCode::BAD_REQUEST
+    );
+
+    let deletion_request = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/me/deletion-request")
+                .header(header::COOKIE, &auth_cookie)
+                .header(header::ORIGIN, "http://localhost:5173")
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("deletion request response");
+    assert_eq!(deletion_request.status(), StatusCode::OK);
+    let confirmation_token = json_response(deletion_request).await["dev_confirmation_token"]
+        .as_str()
+        .expect("confirmation token")
+        .to_owned();
+    let db = support::db::try_test_db().await.expect("test db");
+    let sent = db
+        .query_all(Statement::from_string(
+            DbBackend::Postgres,
+            "SELECT subject, body FROM email_delivery_logs".to_owned(),
+        ))
+        .await
+        .expect("email log query");
+    let sent = sent
+        .iter()
+        .map(|row| {
+            (
+                row.try_get::<String>("", "subject").expect("subject"),
+                row.try_get::<String>("", "body").expect("body"),
+            )
+        })
+        .filter(|(_, body)| body.contains(&confirmation_token))
+        .collect::<Vec<_>>();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].0, "Confirm deleting your account");
+    assert!(
+        push_service
+            .received
+            .lock()
+            .expect("stand-in lock")
+            .is_empty()
+    );
+
+    let deleted = delete_account(&confirmation_token).await;
+    assert_eq!(deleted.status(), StatusCode::NO_CONTENT);
+    assert!(
+        deleted.headers()[header::SET_COOKIE]
+            .to_str()
+            .expect("cookie")
+            .contains("Max-Age=0")
+    );
+    assert_eq!(
+        export((header::COOKIE, auth_cookie.clone())).await.status(),
+        StatusCode::UNAUTHORIZED
+    );
+    let token_after_delete = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/reviews/due")
+                .header("x-api-key", &ingest_token)
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("due response");
+    assert_eq!(token_after_delete.status(), StatusCode::UNAUTHORIZED);
+
+    let bystander_archive = json_response(export((header::COOKIE, bystander_cookie)).await).await;
+    assert_eq!(bystander_archive["cards"].as_array().map(Vec::len), Some(1));
+
+    let returning_cookie = login_cookie(&app, "leaving@test.com").await;
+    let fresh_archive = json_response(export((header::COOKIE, returning_cookie)).await).await;
+    assert_eq!(fresh_archive["cards"], json!([]));
+    assert_eq!(fresh_archive["integration_tokens"], json!([]));
+}
+
+#[tokio::test]
+#[serial]
+async fn archive_import_restores_into_another_account_atomically() {
+    let Some(app) = test_app().await else {
+        return;
+    };
+    let source_cookie = login_cookie(&app, "old-instance@test.com").await;
+    let token = create_api_token(
+        &app,
+        &source_cookie,
+        json!({"label":"cli","scopes":["events:write","reviews:write","cards:read"]}),
+    )
+    .await;
+    for slug in ["two-sum", "valid-anagram"] {
+        let ingest = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/events/problem-status")
+                    .header("x-api-key", &token)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(
+                        json!({
+                          "source":"leetcode",
+                          "problem_slug":slug,
+                          "title":slug,
+                          "url":format!("https://leetcode.com/problems/{slug}"),
+                          "status":"solved",
+                          "occurred_at":"2026-01-01T00:00:00Z"
+                        })
+                        .to_string(),
+                    ))
+                    .expect("request"),
+            )
+            .await
+            .expect("ingest response");
+        assert_eq!(ingest.status(), StatusCode::CREATED);
+    }
+    let export = |cookie: String| {
+        let app = app.clone();
+        async move {
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .uri("/me/export")
+                        .header(header::COOKIE, cookie)
+                        .body(Body::empty())
+                        .expect("request"),
+                )
+                .await
+                .expect("export response");
+            assert_eq!(response.status(), StatusCode::OK);
+            json_response(response).await
+        }
+    };
+    let card_id = export(source_cookie.clone()).await["cards"][0]["id"].clone();
+    let grade = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/reviews/{card_id}/grade"))
+                .header("x-api-key", &token)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(json!({"grade":"good"}).to_string()))
+                .expect("request"),
+        )
+        .await
+        .expect("grade response");
+    assert_eq!(grade.status(), StatusCode::OK);
+    let archive = export(source_cookie).await;
+
+    let target_cookie = login_cookie(&app, "new-instance@test.com").await;
+    let import = |mode: &str, body: Value| {
+        let app = app.clone();
+        let cookie = target_cookie.clone();
+        let uri = format!("/me/import?mode={mode}");
+        async move {
+            app.oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(uri)
+                    .header(header::COOKIE, cookie)
+                    .header(header::ORIGIN, "http://localhost:5173")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(body.to_string()))
+                    .expect("request"),
+            )
+            .await
+            .expect("import response")
+        }
+    };
+
+    let replaced = import("replace", archive.clone()).await;
+    assert_eq!(replaced.status(), StatusCode::OK);
+    assert_eq!(
+        json_response(replaced).await,
+        json!({
+            "cards_created": 2,
+            "cards_merged": 0,
+            "events_imported": 2,
+            "reviews_imported": 1,
+            "reviews_skipped": 0,
+            "goals_imported": 0
+        })
+    );
+    let merged = import("merge", archive.clone()).await;
+    let merged = json_response(merged).await;
+    assert_eq!(merged["cards_merged"], 2);
+    assert_eq!(merged["reviews_skipped"], 1);
+
+    let restored = export(target_cookie.clone()).await;
+    assert_eq!(restored["user"]["email"], "new-instance@test.com");
+    assert_eq!(restored["reviews"].as_array().map(Vec::len), Some(1));
+    let find_card = |archive: &Value, key: &str, value: &Value| {
+        archive["cards"]
+            .as_array()
+            .expect("cards")
+            .iter()
+            .find(|card| &card[key] == value)
+            .expect("card")
+            .clone()
+    };
+    let reviewed_card = find_card(&archive, "id", &archive["reviews"][0]["card_id"]);
+    let restored_card = find_card(&restored, "problem_slug", &reviewed_card["problem_slug"]);
+    assert_eq!(
+        restored_card["interval_index"],
+        reviewed_card["interval_index"]
+    );
+    assert_eq!(restored["reviews"][0]["card_id"], restored_card["id"]);
+
+    let mut dangling = archive.clone();
+    dangling["reviews"][0]["card_id"] = json!(-1);
+    assert_eq!(
+        import("replace", dangling).await.status(),
+        StatusCode::BAD_REQUEST
+    );
+    let mut future_version = archive.clone();
+    future_version["version"] = json!(99);
+    assert_eq!(
+        import("replace", future_version).await.status(),
+        StatusCode::BAD_REQUEST
+    );
+
+    // Postgres refuses NUL bytes in text, so the last card fails after the
+    // replace has already cleared the account; the rollback must undo that.
+    let mut poisoned = archive.clone();
+    let mut bad_card = poisoned["cards"][0].clone();
+    bad_card["id"] = json!(987_654);
+    bad_card["problem_slug"] = json!("bad-card");
+    bad_card["title"] = json!("nul\u{0}byte");
+    poisoned["cards"]
+        .as_array_mut()
+        .expect("cards")
+        .push(bad_card);
+    assert_eq!(
+        import("replace", poisoned).await.status(),
+        StatusCode::INTERNAL_SERVER_ERROR
+    );
+    let untouched = export(target_cookie).await;
+    assert_eq!(untouched["cards"], restored["cards"]);
+    assert_eq!(untouched["reviews"], restored["reviews"]);
+}
+
+#[tokio::test]
+#[serial]
+async fn anki_package_imports_and_exports_round_trip() {
+    let Some(app) = test_app().await else {
+        return;
+    };
+    let import = |cookie: String, mode: &str, package: Vec<u8>| {
+        let app = app.clone();
+        let uri = format!("/me/import/anki?mode={mode}");
+        async move {
+            app.oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(uri)
+                    .header(header::COOKIE, cookie)
+                    .header(header::ORIGIN, "http://localhost:5173")
+                    .header(header::CONTENT_TYPE, "application/octet-stream")
+                    .body(Body::from(package))
+                    .expect("request"),
+            )
+            .await
+            .expect("import response")
+        }
+    };
+    let get = |cookie: String, uri: &'static str| {
+        let app = app.clone();
+        async move {
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .uri(uri)
+                        .header(header::COOKIE, cookie)
+                        .body(Body::empty())
+                        .expect("request"),
+                )
+                .await
+                .expect("export response");
+            assert_eq!(response.status(), StatusCode::OK);
+            response
+        }
+    };
+    let fixture = include_bytes!("fixtures/anki/basic_leetcode.apkg").to_vec();
+
+    let anki_cookie = login_cookie(&app, "anki-user@test.com").await;
+    let imported = import(anki_cookie.clone(), "merge", fixture.clone()).await;
+    assert_eq!(imported.status(), StatusCode::OK);
+    assert_eq!(
+        json_response(imported).await,
+        json!({
+            "cards_created": 3,
+            "cards_merged": 0,
+            "events_imported": 0,
+            "reviews_imported": 4,
+            "reviews_skipped": 0,
+            "goals_imported": 0
+        })
+    );
+    let archive = json_response(get(anki_cookie.clone(), "/me/export").await).await;
+    let mut cards = archive["cards"]
+        .as_array()
+        .expect("cards")
+        .iter()
+        .map(|card| {
+            (
+                format!("{}/{}", card["source"], card["problem_slug"]).replace('"', ""),
+                card.clone(),
+            )
+        })
+        .collect::<Vec<_>>();
+    cards.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        cards
+            .iter()
+            .map(|(key, _)| key.as_str())
+            .collect::<Vec<_>>(),
+        [
+            "anki/binary-search",
+            "leetcode/lru-cache",
+            "leetcode/two-sum"
+        ]
+    );
+    let two_sum = &cards[2].1;
+    assert_eq!(two_sum["title"], "Two Sum");
+    assert_eq!(two_sum["url"], "https://leetcode.com/problems/two-sum/");
+    // A 7 day Anki interval lands on the last step of the test schedule.
+    assert_eq!(two_sum["interval_index"], 2);
+    assert_eq!(two_sum["next_due_at"], "2026-02-10T00:00:00Z");
+    assert_eq!(cards[0].1["title"], "Binary Search");
+    assert_eq!(archive["settings"]["digest_hour_utc"], 12);
+
+    let export = get(anki_cookie, "/me/export/anki").await;
+    assert_eq!(
+        export.headers()[header::CONTENT_TYPE],
+        "application/octet-stream"
+    );
+    assert!(
+        export.headers()[header::CONTENT_DISPOSITION]
+            .to_str()
+            .expect("disposition")
+            .ends_with(".apkg\"")
+    );
+    let package = export
+        .into_body()
+        .collect()
+        .await
+        .expect("body")
+        .to_bytes()
+        .to_vec();
+
+    let other_cookie = login_cookie(&app, "anki-restore@test.com").await;
+    let restored =
+        json_response(import(other_cookie.clone(), "merge", package.clone()).await).await;
+    assert_eq!(restored["cards_created"], 3);
+    assert_eq!(restored["reviews_imported"], 4);
+    let again = json_response(import(other_cookie.clone(), "merge", package).await).await;
+    assert_eq!(again["cards_merged"], 3);
+    assert_eq!(again["reviews_skipped"], 4);
+    let restored = json_response(get(other_cookie.clone(), "/me/export").await).await;
+    let restored_two_sum = restored["cards"]
+        .as_array()
+        .expect("cards")
+        .iter()
+        .find(|card| card["problem_slug"] == "two-sum")
+        .expect("two-sum");
+    assert_eq!(restored_two_sum["interval_index"], 2);
+    assert_eq!(restored["reviews"].as_array().map(Vec::len), Some(4));
+
+    let newer_format = include_bytes!("fixtures/anki/anki21b_only.apkg").to_vec();
+    assert_eq!(
+        import(other_cookie.clone(), "replace", newer_format)
+            .await
+            .status(),
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        import(other_cookie, "replace", b"not a zip".to_vec())
+            .await
+            .status(),
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn leetcode_history_import_schedules_cards_and_is_idempotent() {
+    let Some(app) = test_app().await else {
+        return;
+    };
+    let cookie = login_cookie(&app, "leetcode-history@test.com").await;
+    let import = |body: String| {
+        let app = app.clone();
+        let cookie = cookie.clone();
+        async move {
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/me/import/leetcode")
+                        .header(header::COOKIE, cookie)
+                        .header(header::ORIGIN, "http://localhost:5173")
+                        .body(Body::from(body))
+                        .expect("request"),
+                )
+                .await
+                .expect("import response");
+            let status = response.status();
+            (status, json_response(response).await)
+        }
+    };
+    let now = Utc::now().timestamp();
+    let submission = |slug: &str, status: &str, seconds_ago: i64| {
+        json!({
+            "title": slug.replace('-', " "),
+            "title_slug": slug,
+            "status_display": status,
+            "timestamp": now - seconds_ago,
+        })
+    };
+    let dump = json!({
+        "submissions_dump": [
+            submission("two-sum", "Accepted", 120),
+            submission("two-sum", "Accepted", 2 * 86_400),
+            submission("two-sum", "Wrong Answer", 2 * 86_400 + 60),
+            submission("two-sum", "Accepted", 9 * 86_400),
+            submission("lru-cache", "Accepted", 400 * 86_400),
+            submission("valid-anagram", "Time Limit Exceeded", 3 * 86_400),
+        ],
+        "has_next": false
+    })
+    .to_string();
+
+    let (status, summary) = import(dump.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(summary["cards_created"], 3);
+    assert_eq!(summary["events_imported"], 6);
+
+    let (_, again) = import(dump).await;
+    assert_eq!(again["cards_created"], 0);
+    assert_eq!(again["cards_merged"], 3);
+    assert_eq!(again["events_imported"], 0);
+
+    let csv = format!(
+        "title,title_slug,status_display,timestamp\n\
+         Contains Duplicate,contains-duplicate,Accepted,{}\n\
+         two sum,two-sum,Accepted,{}\n",
+        now - 60,
+        now - 120
+    );
+    let (status, summary) = import(csv).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(summary["cards_created"], 1);
+    assert_eq!(summary["cards_merged"], 1);
+    assert_eq!(summary["events_imported"], 1);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/me/export")
+                .header(header::COOKIE, cookie.clone())
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("export response");
+    let archive = json_response(response).await;
+    let card = |slug: &str| {
+        archive["cards"]
+            .as_array()
+            .expect("cards")
+            .iter()
+            .find(|card| card["problem_slug"] == slug)
+            .expect("card")
+            .clone()
+    };
+    // Solved on three separate days and recently: last step of the schedule.
+    assert_eq!(card("two-sum")["interval_index"], 2);
+    assert_eq!(
+        card("two-sum")["url"],
+        "https://leetcode.com/problems/two-sum/"
+    );
+    assert_eq!(card("lru-cache")["interval_index"], 0);
+    assert_eq!(card("valid-anagram")["interval_index"], 0);
+    let unsolved = archive["problem_events"]
+        .as_array()
+        .expect("events")
+        .iter()
+        .filter(|event| event["status"] == "unsolved")
+        .count();
+    assert_eq!(unsolved, 2);
+
+    let (status, _) = import("title,timestamp\nTwo Sum,1767225600\n".to_owned()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+#[serial]
+async fn event_batch_reports_per_item_status() {
+    let Some(app) = test_app().await else {
+        return;
+    };
+    let cookie = login_cookie(&app, "batch-user@test.com").await;
+    let token = create_api_token(&app, &cookie, json!({"label":"queue"})).await;
+    let event = |slug: &str| {
+        json!({
+            "source": "LeetCode",
+            "problem_slug": slug,
+            "title": slug,
+            "url": format!("https://leetcode.com/problems/{slug}"),
+            "status": "solved",
+            "occurred_at": "2026-01-01T00:00:00Z"
+        })
+    };
+    let send = |events: Value| {
+        let app = app.clone();
+        let token = token.clone();
+        async move {
+            app.oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/events/batch")
+                    .header("x-api-key", token)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(json!({ "events": events }).to_string()))
+                    .expect("request"),
+            )
+            .await
+            .expect("batch response")
+        }
+    };
+
+    let mut blank_slug = event("ignored");
+    blank_slug["problem_slug"] = json!(" ");
+    let response = send(json!([
+        event("two-sum"),
+        event("two-sum"),
+        {"problem_slug": "missing-fields"},
+        blank_slug,
+        event("valid-anagram"),
+    ]))
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = json_response(response).await;
+    assert_eq!(body["created"], 2);
+    assert_eq!(body["deduplicated"], 1);
+    assert_eq!(body["rejected"], 2);
+    let statuses = body["results"]
+        .as_array()
+        .expect("results")
+        .iter()
+        .map(|result| result["status"].as_str().expect("status").to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        statuses,
+        ["created", "deduplicated", "rejected", "rejected", "created"]
+    );
+    assert_eq!(body["results"][0]["event"]["source"], "leetcode");
+    assert_eq!(
+        body["results"][0]["event"]["id"],
+        body["results"][1]["event"]["id"]
+    );
+    assert!(
+        body["results"][2]["reason"]
+            .as_str()
+            .expect("reason")
+            .contains("missing field")
+    );
+    assert_eq!(body["results"][3]["index"], 3);
+
+    let replay = json_response(send(json!([event("two-sum")])).await).await;
+    assert_eq!(replay["results"][0]["status"], "deduplicated");
+
+    let too_many = (0..201).map(|_| event("two-sum")).collect::<Vec<_>>();
+    assert_eq!(
+        send(Value::Array(too_many)).await.status(),
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn idempotency_key_replays_grades_and_rejects_reuse() {
+    let Some(app) = test_app().await else {
+        return;
+    };
+    let cookie = login_cookie(&app, "idempotent@test.com").await;
+    let token = create_api_token(
+        &app,
+        &cookie,
+        json!({"label":"mobile","scopes":["events:write","reviews:read","reviews:write","cards:read"]}),
+    )
+    .await;
+    let ingest = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/events/problem-status")
+                .header("x-api-key", &token)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(
+                    json!({
+                      "source":"leetcode",
+                      "problem_slug":"house-robber",
+                      "title":"House Robber",
+                      "url":"https://leetcode.com/problems/house-robber",
+                      "status":"solved",
+                      "occurred_at":"2026-01-01T00:00:00Z"
+                    })
+                    .to_string(),
+                ))
+                .expect("request"),
+        )
+        .await
+        .expect("ingest response");
+    assert_eq!(ingest.status(), StatusCode::CREATED);
+    let cards = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/cards")
+                .header("x-api-key", &token)
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("cards response");
+    let card_id = json_response(cards).await[0]["id"].clone();
+
+    let grade = |key: &str, grade: &str| {
+        let app = app.clone();
+        let request = Request::builder()
+            .method("POST")
+            .uri(format!("/reviews/{card_id}/grade"))
+            .header("x-api-key", &token)
+            .header("idempotency-key", key)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(json!({ "grade": grade }).to_string()))
+            .expect("request");
+        async move { app.oneshot(request).await.expect("grade response") }
+    };
+
+    let first = grade("grade-retry-1", "good").await;
+    assert_eq!(first.status(), StatusCode::OK);
+    assert!(first.headers().get("idempotent-replayed").is_none());
+    let first = json_response(first).await;
+
+    let retry = grade("grade-retry-1", "good").await;
+    assert_eq!(retry.status(), StatusCode::OK);
+    assert_eq!(
+        retry
+            .headers()
+            .get("idempotent-replayed")
+            .and_then(|value| value.to_str().ok()),
+        Some("true")
+    );
+    assert_eq!(json_response(retry).await, first);
+
+    let reused = grade("grade-retry-1", "again").await;
+    assert_eq!(reused.status(), StatusCode::CONFLICT);
+
+    // Racing retries with one key run the grade once; the rest wait or replay.
+    let racing = join_all((0..8).map(|_| grade("grade-race", "hard"))).await;
+    let mut ran = 0;
+    for response in &racing {
+        match response.status() {
+            StatusCode::OK if response.headers().get("idempotent-replayed").is_none() => ran += 1,
+            StatusCode::OK => {}
+            StatusCode::CONFLICT => assert_eq!(response.headers()[header::RETRY_AFTER], "1"),
+            status => panic!("racing grade answered {status}"),
+        }
+    }
+    assert_eq!(ran, 1);
+    assert_eq!(
+        grade("bad key", "good").await.status(),
+        StatusCode::BAD_REQUEST
+    );
+    // Only the import routes let a keyed request past axum's 2 MB default.
+    let oversized = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/reviews/{card_id}/grade"))
+                .header("x-api-key", &token)
+                .header("idempotency-key", "grade-retry-2")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(vec![b' '; 3 * 1024 * 1024]))
+                .expect("request"),
+        )
+        .await
+        .expect("oversized grade response");
+    assert_eq!(oversized.status(), StatusCode::BAD_REQUEST);
+
+    let history = app
+        .oneshot(
+            Request::builder()
+                .uri("/history")
+                .header("x-api-key", &token)
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("history response");
+    assert_eq!(
+        json_response(history).await.as_array().map(Vec::len),
+        Some(2)
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn ingestion_normalizes_events_against_the_source_registry() {
+    let Some(app) = test_app().await else {
+        return;
+    };
+    let cookie = login_cookie(&app, "sources@test.com").await;
+    let token = create_api_token(&app, &cookie, json!({"label":"userscript"})).await;
+    let ingest = |body: Value| {
+        let app = app.clone();
+        let request = Request::builder()
+            .method("POST")
+            .uri("/events/problem-status")
+            .header("x-api-key", &token)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .expect("request");
+        async move { app.oneshot(request).await.expect("ingest response") }
+    };
+    let event = |source: &str, slug: &str, url: &str| {
+        json!({
+            "source": source,
+            "problem_slug": slug,
+            "title": "Two Sum",
+            "url": url,
+            "status": "solved",
+            "occurred_at": "2026-01-01T00:00:00Z"
+        })
+    };
+
+    let scraped = ingest(event(
+        "LeetCode",
+        "description",
+        "https://leetcode.com/problems/two-sum/description/",
+    ))
+    .await;
+    assert_eq!(scraped.status(), StatusCode::CREATED);
+    let scraped = json_response(scraped).await;
+    assert_eq!(scraped["source"], "leetcode");
+    assert_eq!(scraped["problem_slug"], "two-sum");
+    assert_eq!(scraped["url"], "https://leetcode.com/problems/two-sum/");
+
+    let canonical = json_response(
+        ingest(event(
+            "leetcode",
+            "two-sum",
+            "https://leetcode.com/problems/two-sum",
+        ))
+        .await,
+    )
+    .await;
+    assert_eq!(canonical["id"], scraped["id"]);
+
+    for rejected in [
+        event(
+            "codewars",
+            "two-sum",
+            "https://www.codewars.com/kata/two-sum",
+        ),
+        event(
+            "leetcode",
+            "two-sum",
+            "https://neetcode.io/problems/two-sum",
+        ),
+        event("neetcode", "two sum", ""),
+    ] {
+        assert_eq!(ingest(rejected).await.status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn authored_cards_are_scheduled_with_problem_cards() {
+    let Some(app) = test_app().await else {
+        return;
+    };
+    let cookie = login_cookie(&app, "cards@test.com").await;
+    let token = create_api_token(
+        &app,
+        &cookie,
+        json!({"label":"notes","scopes":["cards:read","cards:write","reviews:read","reviews:write"]}),
+    )
+    .await;
+    let send = |method: &str, uri: &str, body: Option<Value>| {
+        let app = app.clone();
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("x-api-key", &token);
+        if body.is_some() {
+            request = request.header(header::CONTENT_TYPE, "application/json");
+        }
+        let request = request
+            .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+            .expect("request");
+        async move { app.oneshot(request).await.expect("cards response") }
+    };
+
+    let basic = send(
+        "POST",
+        "/cards",
+        Some(json!({"front":"What does `?` do?","back":"Propagates the error","key":"rust-question-mark"})),
+    )
+    .await;
+    assert_eq!(basic.status(), StatusCode::CREATED);
+    let basic = json_response(basic).await;
+    assert_eq!(basic["kind"], "basic");
+    assert_eq!(basic["source"], "custom");
+    assert_eq!(basic["problem_slug"], "rust-question-mark");
+    assert_eq!(basic["title"], "What does `?` do?");
+
+    let cloze = json_response(
+        send(
+            "POST",
+            "/cards",
+            Some(json!({"kind":"cloze","front":"A {{c1::binary heap}} backs Rust's BinaryHeap"})),
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(cloze["title"], "A [...] backs Rust's BinaryHeap");
+    assert!(
+        cloze["problem_slug"]
+            .as_str()
+            .expect("generated key")
+            .starts_with("card-")
+    );
+
+    let duplicate = send(
+        "POST",
+        "/cards",
+        Some(json!({"front":"Again?","back":"Yes","key":"rust-question-mark"})),
+    )
+    .await;
+    assert_eq!(duplicate.status(), StatusCode::CONFLICT);
+    for invalid in [
+        json!({"front":"No back"}),
+        json!({"kind":"cloze","front":"No deletions"}),
+        json!({"kind":"problem","front":"Two Sum","back":"x"}),
+    ] {
+        assert_eq!(
+            send("POST", "/cards", Some(invalid)).await.status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    let due = json_response(send("GET", "/reviews/due", None).await).await;
+    let due_ids = due
+        .as_array()
+        .expect("due cards")
+        .iter()
+        .map(|card| card["id"].clone())
+        .collect::<Vec<_>>();
+    assert!(due_ids.contains(&basic["id"]) && due_ids.contains(&cloze["id"]));
+
+    let graded = send(
+        "POST",
+        &format!("/reviews/{}/grade", basic["id"]),
+        Some(json!({"grade":"good"})),
+    )
+    .await;
+    assert_eq!(graded.status(), StatusCode::OK);
+    let due = json_response(send("GET", "/reviews/due", None).await).await;
+    assert_eq!(due.as_array().expect("due cards").len(), 1);
+
+    let updated = send(
+        "PATCH",
+        &format!("/cards/{}", basic["id"]),
+        Some(json!({"back":"Returns early with the error"})),
+    )
+    .await;
+    assert_eq!(updated.status(), StatusCode::OK);
+    let updated = json_response(updated).await;
+    assert_eq!(updated["front"], "What does `?` do?");
+    assert_eq!(updated["back"], "Returns early with the error");
+    assert_eq!(
+        updated["next_due_at"],
+        json_response(graded).await["next_due_at"]
+    );
+
+    let fetched = json_response(send("GET", &format!("/cards/{}", basic["id"]), None).await).await;
+    assert_eq!(fetched["back"], "Returns early with the error");
+
+    assert_eq!(
+        send("DELETE", &format!("/cards/{}", basic["id"]), None)
+            .await
+            .status(),
+        StatusCode::NO_CONTENT
+    );
+    assert_eq!(
+        send("GET", &format!("/cards/{}", basic["id"]), None)
+            .await
+            .status(),
+        StatusCode::NOT_FOUND
+    );
+    let history = json_response(send("GET", "/history", None).await).await;
+    assert!(history.as_array().expect("history").is_empty());
+
+    let other_cookie = login_cookie(&app, "other-cards@test.com").await;
+    let other_token = create_api_token(
+        &app,
+        &other_cookie,
+        json!({"label":"notes","scopes":["cards:write"]}),
+    )
+    .await;
+    let foreign = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(format!("/cards/{}", cloze["id"]))
+                .header("x-api-key", &other_token)
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("delete response");
+    assert_eq!(foreign.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+#[serial]
+async fn markdown_and_csv_notes_import_as_cards_with_dry_run() {
+    let Some(app) = test_app().await else {
+        return;
+    };
+    let cookie = login_cookie(&app, "notes-import@test.com").await;
+    let token = create_api_token(
+        &app,
+        &cookie,
+        json!({"label":"notes","scopes":["cards:read","cards:write"]}),
+    )
+    .await;
+    let import = |uri: &str, body: &str| {
+        let app = app.clone();
+        let request = Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("x-api-key", &token)
+            .header(header::CONTENT_TYPE, "text/plain")
+            .body(Body::from(body.to_owned()))
+            .expect("request");
+        async move { app.oneshot(request).await.expect("import response") }
+    };
+    let notes = "---\ntags: [rust]\n---\n\
+                 # Rust\n\
+                 ## What does `?` do?\nPropagates the error.\n\
+                 ## A {{c1::trait object}} uses dynamic dispatch\n";
+
+    let preview = import("/cards/import/markdown?dry_run=true", notes).await;
+    assert_eq!(preview.status(), StatusCode::OK);
+    let preview = json_response(preview).await;
+    assert_eq!(preview["dry_run"], true);
+    assert_eq!(
+        (
+            &preview["created"],
+            &preview["updated"],
+            &preview["skipped"]
+        ),
+        (&json!(2), &json!(0), &json!(1))
+    );
+    assert_eq!(preview["results"][0]["status"], "skipped");
+    assert_eq!(preview["results"][0]["line"], 4);
+    assert_eq!(preview["results"][1]["key"], "what-does-do");
+    assert!(preview["results"][1].get("card_id").is_none());
+    let cards = || {
+        let app = app.clone();
+        let request = Request::builder()
+            .uri("/cards")
+            .header("x-api-key", &token)
+            .body(Body::empty())
+            .expect("request");
+        async move { json_response(app.oneshot(request).await.expect("cards response")).await }
+    };
+    assert!(cards().await.as_array().expect("cards").is_empty());
+
+    let imported = json_response(import("/cards/import/markdown", notes).await).await;
+    assert_eq!(imported["created"], 2);
+    let listed = cards().await;
+    assert_eq!(listed.as_array().expect("cards").len(), 2);
+    let cloze = listed
+        .as_array()
+        .expect("cards")
+        .iter()
+        .find(|card| card["kind"] == "cloze")
+        .expect("cloze card");
+    assert_eq!(cloze["tags"], json!(["rust"]));
+    assert_eq!(cloze["title"], "A [...] uses dynamic dispatch");
+
+    let edited = notes.replace("Propagates the error.", "Returns early with the error.");
+    let reimported = json_response(import("/cards/import/markdown", &edited).await).await;
+    assert_eq!(
+        (
+            &reimported["created"],
+            &reimported["updated"],
+            &reimported["skipped"]
+        ),
+        (&json!(0), &json!(1), &json!(2))
+    );
+    assert_eq!(reimported["results"][2]["reason"], "unchanged");
+    assert_eq!(cards().await.as_array().expect("cards").len(), 2);
+
+    let csv = "Question,Answer,Labels\n\
+               What is a lifetime?,How long a reference is valid,rust borrowck\n\
+               What is a lifetime?,Duplicate,rust\n\
+               No answer,,\n";
+    let csv_import = json_response(
+        import(
+            "/cards/import/csv?front=Question&back=Answer&tags=Labels",
+            csv,
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(
+        (&csv_import["created"], &csv_import["skipped"]),
+        (&json!(1), &json!(2))
+    );
+    assert_eq!(
+        csv_import["results"][1]["reason"],
+        "key what-is-a-lifetime appears earlier in this file"
+    );
+    assert_eq!(
+        csv_import["results"][2]["reason"],
+        "basic cards need a back"
+    );
+
+    let unmapped = import("/cards/import/csv", csv).await;
+    assert_eq!(unmapped.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+#[serial]
+async fn users_share_catalog_problems_resolved_through_aliases() {
+    let Some(app) = test_app().await else {
+        return;
+    };
+    let db = support::db::try_test_db().await.expect("test database");
+    let mut tokens = Vec::new();
+    for email in ["alice@test.com", "bob@test.com"] {
+        let cookie = login_cookie(&app, email).await;
+        tokens.push(
+            create_api_token(
+                &app,
+                &cookie,
+                json!({"label":"catalog","scopes":["events:write","cards:read"]}),
+            )
+            .await,
+        );
+    }
+    let send = |token: &str, method: &str, uri: &str, body: Option<Value>| {
+        let app = app.clone();
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("x-api-key", token);
+        if body.is_some() {
+            request = request.header(header::CONTENT_TYPE, "application/json");
+        }
+        let request = request
+            .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+            .expect("request");
+        async move { app.oneshot(request).await.expect("catalog response") }
+    };
+    let solved = |slug: &str, title: &str| {
+        json!({
+            "source": "leetcode",
+            "problem_slug": slug,
+            "title": title,
+            "url": format!("https://leetcode.com/problems/{slug}/"),
+            "status": "solved",
+            "occurred_at": "2026-01-01T00:00:00Z"
+        })
+    };
+
+    db.execute(Statement::from_string(
+        DbBackend::Postgres,
+        "INSERT INTO problems (source, slug, title, url, difficulty, tags, aliases, catalog_version) \
+         VALUES ('leetcode', 'two-sum', 'Two Sum', 'https://leetcode.com/problems/two-sum/', \
+         'easy', ARRAY['array', 'hash-table'], ARRAY['2-sum'], 1)"
+            .to_owned(),
+    ))
+    .await
+    .expect("seed catalog entry");
+    let created = send(
+        &tokens[0],
+        "POST",
+        "/events/problem-status",
+        Some(solved("two-sum", "my two sum")),
+    )
+    .await;
+    assert_eq!(created.status(), StatusCode::CREATED);
+
+    let aliased = json_response(
+        send(
+            &tokens[1],
+            "POST",
+            "/events/problem-status",
+            Some(solved("2-sum", "2sum")),
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(aliased["problem_slug"], "two-sum");
+
+    let problem = send(&tokens[1], "GET", "/problems/leetcode/2-sum", None).await;
+    assert_eq!(problem.status(), StatusCode::OK);
+    let problem = json_response(problem).await;
+    assert_eq!(problem["slug"], "two-sum");
+    assert_eq!(problem["difficulty"], "easy");
+    assert_eq!(problem["learners"], 2);
+
+    let cards = json_response(send(&tokens[1], "GET", "/cards", None).await).await;
+    let card = &cards.as_array().expect("cards")[0];
+    assert_eq!(card["problem_id"], problem["id"]);
+    assert_eq!(card["title"], "Two Sum");
+    assert_eq!(card["difficulty"], "easy");
+    assert_eq!(card["tags"], json!(["array", "hash-table"]));
+}
+
+#[tokio::test]
+#[serial]
+async fn users_ingesting_an_uncatalogued_problem_share_one_catalog_entry() {
+    let Some(app) = test_app().await else {
+        return;
+    };
+    let db = support::db::try_test_db().await.expect("test database");
+    let mut tokens = Vec::new();
+    for email in ["alice@test.com", "bob@test.com"] {
+        let cookie = login_cookie(&app, email).await;
+        tokens.push(
+            create_api_token(
+                &app,
+                &cookie,
+                json!({"label":"catalog","scopes":["events:write","cards:read"]}),
+            )
+            .await,
+        );
+    }
+    let send = |token: &str, method: &str, uri: &str, body: Option<Value>| {
+        let app = app.clone();
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("x-api-key", token);
+        if body.is_some() {
+            request = request.header(header::CONTENT_TYPE, "application/json");
+        }
+        let request = request
+            .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+            .expect("request");
+        async move { app.oneshot(request).await.expect("catalog response") }
+    };
+
+    for (token, title) in tokens.iter().zip(["Alice's 3sum notes", "3Sum"]) {
+        let created = send(
+            token,
+            "POST",
+            "/events/problem-status",
+            Some(json!({
+                "source": "leetcode",
+                "problem_slug": "three-sum",
+                "title": title,
+                "url": "https://leetcode.com/problems/three-sum/description/",
+                "status": "solved",
+                "occurred_at": "2026-01-01T00:00:00Z"
+            })),
+        )
+        .await;
+        assert_eq!(created.status(), StatusCode::CREATED);
+    }
+
+    let rows = db
+        .query_all(Statement::from_string(
+            DbBackend::Postgres,
+            "SELECT id, title, url FROM problems WHERE source = 'leetcode' AND slug = 'three-sum'"
+                .to_owned(),
+        ))
+        .await
+        .expect("catalog rows");
+    assert_eq!(rows.len(), 1);
+    let problem_id: i64 = rows[0].try_get("", "id").expect("problem id");
+    let title: Option<String> = rows[0].try_get("", "title").expect("problem title");
+    let url: String = rows[0].try_get("", "url").expect("problem url");
+    assert_eq!(title, None);
+    assert_eq!(url, "https://leetcode.com/problems/three-sum/");
+
+    for (token, title) in tokens.iter().zip(["Alice's 3sum notes", "3Sum"]) {
+        let cards = json_response(send(token, "GET", "/cards", None).await).await;
+        let card = &cards.as_array().expect("cards")[0];
+        assert_eq!(card["problem_id"], problem_id);
+        assert_eq!(card["title"], title);
+    }
+    let problem = send(&tokens[0], "GET", "/problems/leetcode/three-sum", None).await;
+    assert_eq!(problem.status(), StatusCode::OK);
+    let problem = json_response(problem).await;
+    assert_eq!(problem["title"], Value::Null);
+    assert_eq!(problem["learners"], 2);
+}
+
+#[tokio::test]
+#[serial]
+async fn seeding_the_catalog_enriches_cards_and_reports_unknown_slugs() {
+    let Some(state) = test_state().await else {
+        return;
+    };
+    let app = app_router(state.clone());
+    let cookie = login_cookie(&app, "catalog@test.com").await;
+    let token = create_api_token(
+        &app,
+        &cookie,
+        json!({"label":"catalog","scopes":["events:write","cards:read"]}),
+    )
+    .await;
+    for slug in ["two-sum", "design-twitter"] {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/events/problem-status")
+                    .header("x-api-key", &token)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(
+                        json!({
+                            "source": "leetcode",
+                            "problem_slug": slug,
+                            "title": slug,
+                            "url": format!("https://leetcode.com/problems/{slug}/"),
+                            "status": "solved",
+                            "occurred_at": "2026-01-01T00:00:00Z"
+                        })
+                        .to_string(),
+                    ))
+                    .expect("request"),
+            )
+            .await
+            .expect("ingest response");
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    let catalog: CatalogFile = serde_yaml::from_str(
+        r#"
+version: 4
+problems:
+  - source: leetcode
+    slug: two-sum
+    title: Two Sum
+    difficulty: Easy
+    tags: [array, hash-table]
+    aliases: [2-sum]
+  - source: leetcode
+    slug: lru-cache
+    title: LRU Cache
+  - source: kattis
+    slug: hello
+    title: Hello World!
+"#,
+    )
+    .expect("catalog yaml");
+    let report = state
+        .problem_service
+        .seed_catalog(&catalog)
+        .await
+        .expect("seed catalog");
+    assert_eq!(report.summary.inserted, 1);
+    assert_eq!(report.summary.updated, 1);
+    assert_eq!(report.rejected.len(), 1);
+    assert_eq!(report.uncatalogued.len(), 1);
+    assert_eq!(report.uncatalogued[0].slug, "design-twitter");
+
+    let again = state
+        .problem_service
+        .seed_catalog(&catalog)
+        .await
+        .expect("seed catalog again");
+    assert_eq!(again.summary.unchanged, 2);
+    assert_eq!(again.summary.inserted + again.summary.updated, 0);
+
+    let cards = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/cards")
+                .header("x-api-key", &token)
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("cards response");
+    let cards = json_response(cards).await;
+    let two_sum = cards
+        .as_array()
+        .expect("cards")
+        .iter()
+        .find(|card| card["problem_slug"] == "two-sum")
+        .expect("two-sum card");
+    assert_eq!(two_sum["title"], "Two Sum");
+    assert_eq!(two_sum["difficulty"], "Easy");
+    assert_eq!(two_sum["tags"], json!(["array", "hash-table"]));
+
+    let alias = app
+        .oneshot(
+            Request::builder()
+                .uri("/problems/leetcode/2-sum")
+                .header("x-api-key", &token)
+                .body(Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("problem response");
+    let alias = json_response(alias).await;
+    assert_eq!(alias["slug"], "two-sum");
+    assert_eq!(alias["catalog_version"], 4);
+}
+
+#[tokio::test]
+#[serial]
+async fn stats_report_retention_lapses_and_breakdowns() {
+    let Some(app) = test_app().await else {
+        return;
+    };
+    let cookie = login_cookie(&app, "stats@test.com").await;
+    let now = Utc::now();
+    let at = |days: i64| (now - chrono::Duration::days(days)).to_rfc3339();
+    let review = |card_id: i64, grade: &str, reviewed: i64, due: i64| json!({"card_id": card_id, "grade": grade, "reviewed_at": at(reviewed), "next_due_at": at(due)});
+    let archive = json!({
+        "format": "srs-anything-export",
+        "version": 1,
+        "exported_at": now.to_rfc3339(),
+        "user": {"email": "stats@test.com", "created_at": at(30)},
+        "settings": {"email_enabled": false, "digest_hour_utc": 12},
+        "cards": [
+            {"id": 1, "kind": "basic", "source": "custom", "problem_slug": "borrowing",
+             "title": "Borrowing", "url": "", "front": "Borrowing", "back": "Shared or mutable",
+             "tags": ["rust"], "interval_index": 2, "next_due_at": at(-5)},
+            {"id": 2, "source": "leetcode", "problem_slug": "two-sum", "title": "Two Sum",
+             "url": "https://leetcode.com/problems/two-sum/", "interval_index": 0,
+             "next_due_at": at(-1)}
+        ],
+        "problem_events": [],
+        "reviews": [
+            review(1, "good", 10, 6),
+            review(1, "again", 6, 5),
+            review(1, "good", 5, 2),
+            review(1, "easy", 2, -5),
+            review(2, "hard", 1, -1)
+        ]
+    });
+    let imported = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/me/import?mode=replace")
+                .header(header::COOKIE, &cookie)
+                .header(header::ORIGIN, "http://localhost:5173")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(archive.to_string()))
+                .expect("request"),
+        )
+        .await
+        .expect("import response");
+    assert_eq!(imported.status(), StatusCode::OK);
+
+    let get_stats = |uri: &str| {
+        app.clone().oneshot(
+            Request::builder()
+                .uri(uri)
+                .header(header::COOKIE, &cookie)
+                .body(Body::empty())
+                .expect("request"),
+        )
+    };
+    let response = get_stats("/stats?weeks=4&mature_days=2")
+        .await
+        .expect("stats response");
+    assert_eq!(response.status(), StatusCode::OK);
+    let stats = json_response(response).await;
+    assert_eq!(stats["reviews"], 5);
+    assert_eq!(stats["mature_reviews"], 2);
+    assert_eq!(stats["mature_passed"], 1);
+    assert_eq!(stats["retention"], 0.5);
+    assert_eq!(stats["lapses"], 1);
+    let gap = stats["average_review_gap_seconds"]
+        .as_f64()
+        .expect("average gap");
+    assert!((gap - 8.0 * 86_400.0 / 3.0).abs() < 1.0);
+    assert_eq!(
+        stats["grades"],
+        json!({"again": 1, "hard": 1, "good": 2, "easy": 1})
+    );
+    assert_eq!(
+        stats["interval_histogram"],
+        json!([{"interval_index": 0, "cards": 1}, {"interval_index": 2, "cards": 1}])
+    );
+    let weekly = stats["weekly"].as_array().expect("weekly");
+    assert_eq!(weekly.len(), 4);
+    assert_eq!(
+        weekly
+            .iter()
+            .map(|week| week["reviews"].as_i64().expect("reviews"))
+            .sum::<i64>(),
+        5
+    );
+    assert_eq!(stats["sources"][0]["source"], "custom");
+    assert_eq!(stats["sources"][0]["reviews"], 4);
+    assert_eq!(stats["sources"][1]["source"], "leetcode");
+    assert_eq!(stats["sources"][1]["retention"], Value::Null);
+    assert_eq!(
+        stats["tags"],
+        json!([{"tag": "rust", "reviews": 4, "mature_reviews": 2, "mature_passed": 1,
+                "retention": 0.5, "lapses": 1}])
+    );
+
+    assert_eq!(
+        get_stats("/stats?weeks=0")
+            .await
+            .expect("stats response")
+            .status(),
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn forecast_buckets_due_cards_and_simulates_follow_ups() {
+    let Some(app) = test_app().await else {
+        return;
+    };
+    let cookie = login_cookie(&app, "forecast@test.com").await;
+    let token = create_api_token(
+        &app,
+        &cookie,
+        json!({"label":"forecast","scopes":["events:write","reviews:read"]}),
+    )
+    .await;
+    let send = |method: &str, uri: &str, body: Option<Value>| {
+        let app = app.clone();
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("x-api-key", &token);
+        if body.is_some() {
+            request = request.header(header::CONTENT_TYPE, "application/json");
+        }
+        let request = request
+            .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+            .expect("request");
+        async move { app.oneshot(request).await.expect("forecast response") }
+    };
+    for slug in ["two-sum", "lru-cache"] {
+        let ingested = send(
+            "POST",
+            "/events/problem-status",
+            Some(json!({
+                "source": "leetcode",
+                "problem_slug": slug,
+                "title": slug,
+                "url": format!("https://leetcode.com/problems/{slug}/"),
+                "status": "solved",
+                "occurred_at": "2026-01-01T00:00:00Z"
+            })),
+        )
+        .await;
+        assert_eq!(ingested.status(), StatusCode::CREATED);
+    }
+
+    let plain =
+        json_response(send("GET", "/forecast?days=7&utc_offset_minutes=840", None).await).await;
+    assert_eq!(plain["overdue"], 2);
+    assert_eq!(plain["pass_rate"], Value::Null);
+    let days = plain["forecast"].as_array().expect("forecast days");
+    assert_eq!(days.len(), 7);
+    assert_eq!(
+        days[0]["date"],
+        (Utc::now() + chrono::Duration::minutes(840))
+            .date_naive()
+            .to_string()
+    );
+    assert_eq!(days[0]["due"], 2);
+    assert!(days[1..].iter().all(|day| day["due"] == 0));
+    assert!(days[0].get("projected").is_none());
+
+    // The test schedule's intervals are minutes, so a card that keeps
+    // passing comes back every day.
+    let simulated =
+        json_response(send("GET", "/forecast?days=3&simulate=true&pass_rate=1", None).await).await;
+    assert_eq!(simulated["pass_rate"], 1.0);
+    assert_eq!(
+        simulated["forecast"]
+            .as_array()
+            .expect("forecast days")
+            .iter()
+            .map(|day| day["projected"].as_f64().expect("projected"))
+            .collect::<Vec<_>>(),
+        vec![2.0, 2.0, 2.0]
+    );
+
+    for invalid in [
+        "/forecast?days=0",
+        "/forecast?utc_offset_minutes=900",
+        "/forecast?simulate=true&pass_rate=1.5",
+    ] {
+        assert_eq!(
+            send("GET", invalid, None).await.status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn activity_streaks_and_goals_follow_the_user_timezone() {
+    let Some(state) = test_state().await else {
+        return;
+    };
+    let app = app_router(state.clone());
+    let db = support::db::try_test_db().await.expect("test database");
+    let cookie = login_cookie(&app, "activity@test.com").await;
+    let send = |method: &str, uri: &str, body: Option<Value>| {
+        let app = app.clone();
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::COOKIE, &cookie)
+            .header(header::ORIGIN, "http://localhost:5173");
+        if body.is_some() {
+            request = request.header(header::CONTENT_TYPE, "application/json");
+        }
+        let request = request
+            .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+            .expect("request");
+        async move { app.oneshot(request).await.expect("activity response") }
+    };
+
+    let now = Utc::now();
+    let at = |days: i64| (now - chrono::Duration::days(days)).to_rfc3339();
+    let today = (now + chrono::Duration::hours(9)).date_naive();
+    let review = |reviewed: i64| json!({"card_id": 1, "grade": "good", "reviewed_at": at(reviewed), "next_due_at": at(-1)});
+    let archive = json!({
+        "format": "srs-anything-export",
+        "version": 1,
+        "exported_at": now.to_rfc3339(),
+        "user": {"email": "activity@test.com", "created_at": at(30)},
+        "settings": {"email_enabled": true, "digest_hour_utc": now.hour(), "timezone": "Asia/Tokyo"},
+        "cards": [
+            {"id": 1, "source": "leetcode", "problem_slug": "two-sum", "title": "Two Sum",
+             "url": "https://leetcode.com/problems/two-sum/", "interval_index": 1,
+             "next_due_at": at(-1), "created_at": at(6)},
+            {"id": 2, "source": "leetcode", "problem_slug": "lru-cache", "title": "LRU Cache",
+             "url": "https://leetcode.com/problems/lru-cache/", "interval_index": 0,
+             "next_due_at": at(-1)}
+        ],
+        "problem_events": [
+            {"source": "leetcode", "problem_slug": "two-sum", "title": "Two Sum",
+             "url": "https://leetcode.com/problems/two-sum/", "status": "solved",
+             "occurred_at": at(6)}
+        ],
+        "reviews": [review(0), review(1), review(2), review(5)]
+    });
+    let imported = send("POST", "/me/import?mode=replace", Some(archive)).await;
+    assert_eq!(imported.status(), StatusCode::OK);
+
+    let settings = json_response(send("GET", "/settings", None).await).await;
+    assert_eq!(settings["timezone"], "Asia/Tokyo");
+    let rejected = send(
+        "POST",
+        "/settings",
+        Some(json!({"email_enabled": true, "digest_hour_utc": 12, "timezone": "Mars/Olympus"})),
+    )
+    .await;
+    assert_eq!(rejected.status(), StatusCode::BAD_REQUEST);
+
+    let heatmap = json_response(send("GET", "/activity/heatmap", None).await).await;
+    assert_eq!(heatmap["timezone"], "Asia/Tokyo");
+    assert_eq!(heatmap["to"], today.to_string());
+    let days = heatmap["days"].as_array().expect("heatmap days");
+    assert_eq!(days.len(), 365);
+    assert_eq!(days[364]["reviews"], 1);
+    assert_eq!(days[364]["new_cards"], 1);
+    assert_eq!(days[358]["solved"], 1);
+    assert_eq!(
+        heatmap["totals"],
+        json!({"reviews": 4, "solved": 1, "new_cards": 2, "active_days": 5})
+    );
+    assert_eq!(heatmap["streak"]["current"], 3);
+    assert_eq!(heatmap["streak"]["longest"], 3);
+    assert_eq!(
+        send("GET", "/activity/heatmap?year=9999", None)
+            .await
+            .status(),
+        StatusCode::BAD_REQUEST
+    );
+
+    let goal = json_response(
+        send(
+            "POST",
+            "/goals",
+            Some(json!({"metric": "reviews", "period": "day", "target": 2})),
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(
+        (goal["progress"].clone(), goal["met"].clone()),
+        (json!(1), json!(false))
+    );
+    let retargeted = json_response(
+        send(
+            "POST",
+            "/goals",
+            Some(json!({"metric": "reviews", "period": "day", "target": 1})),
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(retargeted["id"], goal["id"]);
+    assert_eq!(retargeted["met"], true);
+    let weekly = send(
+        "POST",
+        "/goals",
+        Some(json!({"metric": "solved", "period": "week", "target": 5})),
+    )
+    .await;
+    assert_eq!(weekly.status(), StatusCode::OK);
+    assert_eq!(
+        send(
+            "POST",
+            "/goals",
+            Some(json!({"metric": "reviews", "period": "day", "target": 0})),
+        )
+        .await
+        .status(),
+        StatusCode::BAD_REQUEST
+    );
+    let goals = json_response(send("GET", "/goals", None).await).await;
+    assert_eq!(goals.as_array().expect("goals").len(), 2);
+
+    let dashboard = json_response(send("GET", "/dashboard", None).await).await;
+    assert_eq!(dashboard["activity"]["streak"]["current"], 3);
+    assert_eq!(dashboard["activity"]["goals"][1]["metric"], "solved");
+
+    state
+        .notification_service
+        .process_digests_once(now)
+        .await
+        .expect("process digests");
+    let body: String = db
+        .query_one(Statement::from_string(
+            DbBackend::Postgres,
+            "SELECT body FROM email_delivery_logs".to_owned(),
+        ))
+        .await
+        .expect("digest query")
+        .expect("digest sent")
+        .try_get("", "body")
+        .expect("digest body");
+    assert!(body.contains("Current streak: 3 days"));
+    assert!(body.contains("- reviews today: 1/1 (met)"));
+    assert!(body.contains("- problems solved this week:"));
+
+    let goal_uri = format!("/goals/{}", goal["id"]);
+    assert_eq!(
+        send("DELETE", &goal_uri, None).await.status(),
+        StatusCode::NO_CONTENT
+    );
+    assert_eq!(
+        send("DELETE", &goal_uri, None).await.status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn repeated_lapses_flag_a_leech_that_shows_in_the_digest() {
+    let Some(state) = test_state().await else {
+        return;
+    };
+    let app = app_router(state.clone());
+    let db = support::db::try_test_db().await.expect("test database");
+    let cookie = login_cookie(&app, "leech@test.com").await;
+    let send = |method: &str, uri: &str, body: Option<Value>| {
+        let app = app.clone();
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::COOKIE, &cookie)
+            .header(header::ORIGIN, "http://localhost:5173");
+        if body.is_some() {
+            request = request.header(header::CONTENT_TYPE, "application/json");
+        }
+        let request = request
+            .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+            .expect("request");
+        async move { app.oneshot(request).await.expect("leech response") }
+    };
+
+    let now = Utc::now();
+    let at = |hours: i64| (now - chrono::Duration::hours(hours)).to_rfc3339();
+    let lapses: Vec<Value> = (1..=7)
+        .map(|hours| json!({"card_id": 1, "grade": "again", "reviewed_at": at(hours), "next_due_at": at(hours - 1)}))
+        .collect();
+    let archive = json!({
+        "format": "srs-anything-export",
+        "version": 1,
+        "exported_at": now.to_rfc3339(),
+        "user": {"email": "leech@test.com", "created_at": at(48)},
+        "settings": {"email_enabled": true, "digest_hour_utc": now.hour()},
+        "cards": [
+            {"id": 1, "source": "leetcode", "problem_slug": "two-sum", "title": "Two Sum",
+             "url": "https://leetcode.com/problems/two-sum/", "interval_index": 0,
+             "next_due_at": at(1)},
+            {"id": 2, "source": "leetcode", "problem_slug": "lru-cache", "title": "LRU Cache",
+             "url": "https://leetcode.com/problems/lru-cache/", "interval_index": 0,
+             "next_due_at": at(1), "lapses": 3}
+        ],
+        "goals": [{"metric": "reviews", "period": "day", "target": 4}],
+        "problem_events": [],
+        "reviews": lapses
+    });
+    let imported =
+        json_response(send("POST", "/me/import?mode=replace", Some(archive)).await).await;
+    assert_eq!(imported["goals_imported"], 1);
+    let goals = json_response(send("GET", "/goals", None).await).await;
+    assert_eq!(goals[0]["target"], 4);
+
+    let cards = json_response(send("GET", "/cards", None).await).await;
+    let card = cards
+        .as_array()
+        .expect("cards")
+        .iter()
+        .find(|card| card["problem_slug"] == "two-sum")
+        .expect("two-sum card")
+        .clone();
+    assert_eq!(card["lapses"], 7);
+    assert_eq!(card["leech_at"], Value::Null);
+    let lru = cards
+        .as_array()
+        .expect("cards")
+        .iter()
+        .find(|card| card["problem_slug"] == "lru-cache")
+        .expect("lru-cache card");
+    assert_eq!(lru["lapses"], 3);
+
+    let grade_uri = format!("/reviews/{}/grade", card["id"]);
+    let graded = send("POST", &grade_uri, Some(json!({"grade": "again"}))).await;
+    assert_eq!(graded.status(), StatusCode::OK);
+
+    let leeches = json_response(send("GET", "/cards/leeches", None).await).await;
+    assert_eq!(leeches["threshold"], 8);
+    assert_eq!(leeches["action"], "tag");
+    let listed = leeches["cards"].as_array().expect("leech cards");
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0]["id"], card["id"]);
+    assert_eq!(listed[0]["lapses"], 8);
+    assert_eq!(listed[0]["tags"], json!(["leech"]));
+    assert!(listed[0]["leech_at"].is_string());
+
+    state
+        .notification_service
+        .process_digests_once(now)
+        .await
+        .expect("process digests");
+    let body: String = db
+        .query_one(Statement::from_string(
+            DbBackend::Postgres,
+            "SELECT body FROM email_delivery_logs".to_owned(),
+        ))
+        .await
+        .expect("digest query")
+        .expect("digest sent")
+        .try_get("", "body")
+        .expect("digest body");
+    assert!(body.contains("Leeches to restudy:\n- Two Sum (8 lapses)"));
+
+    let export = json_response(send("GET", "/me/export", None).await).await;
+    assert_eq!(
+        export["goals"],
+        json!([{"metric": "reviews", "period": "day", "target": 4}])
+    );
+    let exported = export["cards"]
+        .as_array()
+        .expect("exported cards")
+        .iter()
+        .find(|card| card["problem_slug"] == "two-sum")
+        .expect("exported two-sum");
+    assert_eq!(exported["lapses"], 8);
+    assert!(exported["leech_at"].is_string());
+
+    let clear_uri = format!("/cards/{}/leech", card["id"]);
+    let cleared = json_response(send("DELETE", &clear_uri, None).await).await;
+    assert_eq!(cleared["lapses"], 0);
+    assert_eq!(cleared["tags"], json!([]));
+    assert_eq!(cleared["leech_at"], Value::Null);
+    let leeches = json_response(send("GET", "/cards/leeches", None).await).await;
+    assert_eq!(leeches["cards"], json!([]));
+    assert_eq!(
+        send("DELETE", "/cards/999999/leech", None).await.status(),
+        StatusCode::NOT_FOUND
+    );
+}
@@ -0,0 +1,108 @@
+"""Regenerates the Anki fixtures used by the backend tests.
+
+Run from this directory with `python3 generate.py`. The packages are
+committed, so tests never need Anki or this script.
+"""
+
+import json
+import os
+import sqlite3
+import tempfile
+import zipfile
+
+CRT = 1_767_225_600  # 2026-01-01T00:00:00Z
+DAY = 86_400
+BASIC_MODEL_ID = 1_342_697_561_419
+
+SCHEMA = """
+create table col (id integer primary key, crt integer not null, mod integer not null, scm integer not null, ver integer not null, dty integer not null, usn integer not null, ls integer not null, conf text not null, models text not null, decks text not null, dconf text not null, tags text not null);
+create table notes (id integer primary key, guid text not null, mid integer not null, mod integer not null, usn integer not null, tags text not null, flds text not null, sfld integer not null, csum integer not null, flags integer not null, data text not null);
+create table cards (id integer primary key, nid integer not null, did integer not null, ord integer not null, mod integer not null, usn integer not null, type integer not null, queue integer not null, due integer not null, ivl integer not null, factor integer not null, reps integer not null, lapses integer not null, left integer not null, odue integer not null, odid integer not null, flags integer not null, data text not null);
+create table revlog (id integer primary key, cid integer not null, usn integer not null, ease integer not null, ivl integer not null, lastIvl integer not null, factor integer not null, time integer not null, type integer not null);
+create table graves (usn integer not null, oid integer not null, type integer not null);
+"""
+
+MODELS = {
+    str(BASIC_MODEL_ID): {
+        "id": BASIC_MODEL_ID,
+        "name": "Basic",
+        "type": 0,
+        "flds": [{"name": "Front", "ord": 0}, {"name": "Back", "ord": 1}],
+        "tmpls": [{"name": "Card 1", "ord": 0, "qfmt": "{{Front}}", "afmt": "{{Back}}"}],
+    }
+}
+
+# (note id, fields, card (type, queue, due, ivl, reps, lapses), revlog [(ms, ease, ivl, type)])
+NOTES = [
+    (
+        1_767_225_600_001,
+        ["Two Sum", "https://leetcode.com/problems/two-sum/ - use a hash map"],
+        (2, 2, 40, 7, 3, 1),
+        [
+            ((CRT + 20 * DAY) * 1000, 1, -600, 0),
+            ((CRT + 21 * DAY) * 1000, 3, 3, 1),
+            ((CRT + 33 * DAY) * 1000, 3, 7, 1),
+        ],
+    ),
+    (
+        1_767_225_600_002,
+        ["<b>Binary&nbsp;Search</b>", "Halve the range each step"],
+        (1, 1, CRT + 34 * DAY, 0, 1, 0),
+        [((CRT + 34 * DAY) * 1000, 3, -600, 0)],
+    ),
+    (
+        1_767_225_600_003,
+        ["LRU Cache", '<a href="https://leetcode.com/problems/lru-cache/">problem</a>'],
+        (0, 0, 3, 0, 0, 0),
+        [],
+    ),
+]
+
+
+def build_collection(path):
+    db = sqlite3.connect(path)
+    db.executescript(SCHEMA)
+    db.execute(
+        "insert into col values (1, ?, ?, ?, 11, 0, 0, 0, '{}', ?, '{}', '{}', '{}')",
+        (CRT, CRT * 1000, CRT * 1000, json.dumps(MODELS)),
+    )
+    for index, (note_id, fields, card, revlog) in enumerate(NOTES):
+        db.execute(
+            "insert into notes values (?, ?, ?, ?, 0, '', ?, ?, 0, 0, '')",
+            (note_id, f"fixture{index}", BASIC_MODEL_ID, CRT, "\x1f".join(fields), fields[0]),
+        )
+        card_id = note_id + 1000
+        card_type, queue, due, ivl, reps, lapses = card
+        db.execute(
+            "insert into cards values (?, ?, 1, 0, ?, 0, ?, ?, ?, ?, 2500, ?, ?, 0, 0, 0, 0, '')",
+            (card_id, note_id, CRT, card_type, queue, due, ivl, reps, lapses),
+        )
+        for review_ms, ease, review_ivl, review_type in revlog:
+            db.execute(
+                "insert into revlog values (?, ?, 0, ?, ?, 0, 2500, 8000, ?)",
+                (review_ms, card_id, ease, review_ivl, review_type),
+            )
+    db.commit()
+    db.close()
+
+
+def write_package(name, entries):
+    # Fixed timestamps keep regenerated packages byte-identical.
+    with zipfile.ZipFile(name, "w") as package:
+        for entry_name, data in entries.items():
+            info = zipfile.ZipInfo(entry_name, date_time=(2026, 1, 1, 0, 0, 0))
+            info.compress_type = zipfile.ZIP_DEFLATED
+            package.writestr(info, data)
+
+
+def main():
+    with tempfile.TemporaryDirectory() as tmp:
+        collection = os.path.join(tmp, "collection.anki2")
+        build_collection(collection)
+        with open(collection, "rb") as handle:
+            write_package("basic_leetcode.apkg", {"collection.anki2": handle.read(), "media": b"{}"})
+        write_package("anki21b_only.apkg", {"collection.anki21b": b"\x28\xb5\x2f\xfd", "media": b""})
+
+
+if __name__ == "__main__":
+    main()
@@ -55,11 +55,23 @@ async fn settings_contract_clamps_digest_hour() {
         .await
         .expect("user");
     let updated = settings_repo
-        .set_notification_preference(user.id, true, 250)
+        .set_notification_preference(user.id, true, 250, None)
         .await
         .expect("set settings")
         .expect("settings");
     assert_eq!(updated.digest_hour_utc, 23);
+    assert_eq!(updated.timezone, "UTC");
+
+    settings_repo
+        .set_notification_preference(user.id, true, 8, Some("Asia/Tokyo"))
+        .await
+        .expect("set timezone");
+    let kept = settings_repo
+        .set_notification_preference(user.id, false, 8, None)
+        .await
+        .expect("set settings")
+        .expect("settings");
+    assert_eq!(kept.timezone, "Asia/Tokyo");
 }
 
 #[tokio::test]
@@ -106,3 +118,58 @@ async fn event_and_review_contracts_round_trip() {
         .expect("upcoming cards");
     assert!(!upcoming.is_empty());
 }
+
+#[tokio::test]
+#[serial]
+async fn event_batch_contract_reports_created_and_deduplicated() {
+    let Some((auth_repo, event_repo, review_repo, _)) = postgres_repos().await else {
+        return;
+    };
+    let user = auth_repo
+        .get_or_create_user("batch@test.com")
+        .await
+        .expect("user");
+    let now = chrono::Utc::now();
+    let payload = |slug: &str| IngestProblemInput {
+        user_id: user.id,
+        source: "leetcode".to_owned(),
+        problem_slug: slug.to_owned(),
+        title: slug.to_owned(),
+        url: format!("https://leetcode.com/problems/{slug}"),
+        status: ProblemStatus::Solved,
+        occurred_at: now,
+    };
+    let existing = event_repo
+        .ingest_event(payload("contains-duplicate"))
+        .await
+        .expect("ingest");
+
+    let results = event_repo
+        .ingest_events(vec![
+            payload("two-sum"),
+            payload("two-sum"),
+            payload("valid-anagram"),
+            payload("contains-duplicate"),
+        ])
+        .await
+        .expect("ingest batch");
+    assert_eq!(
+        results
+            .iter()
+            .map(|result| result.created)
+            .collect::<Vec<_>>(),
+        [true, false, true, false]
+    );
+    assert_eq!(results[0].event.id, results[1].event.id);
+    assert_eq!(results[3].event.id, existing.id);
+    assert!(
+        event_repo
+            .ingest_events(Vec::new())
+            .await
+            .expect("empty batch")
+            .is_empty()
+    );
+
+    let cards = review_repo.list_cards(user.id).await.expect("cards");
+    assert_eq!(cards.len(), 3);
+}
@@ -16,6 +16,16 @@ pub async fn reset_db(db: &DatabaseConnection) {
         DbBackend::Postgres,
         r#"
         TRUNCATE TABLE
+          goals,
+          idempotency_keys,
+          problems,
+          account_deletion_requests,
+          passkey_credentials,
+          webauthn_challenges,
+          user_identities,
+          oidc_auth_requests,
+          push_subscriptions,
+          calendar_feed_tokens,
           review_events,
           problem_cards,
           problem_events,
@@ -15,15 +15,27 @@ export type ProblemEvent = {
   occurred_at: string;
 };
 
+export type CardKind = 'problem' | 'basic' | 'cloze';
+
 export type ProblemCard = {
   id: number;
   user_id: number;
+  kind: CardKind;
   source: string;
   problem_slug: string;
   title: string;
   url: string;
+  front: string | null;
+  back: string | null;
+  tags: string[];
+  problem_id: number | null;
+  difficulty: string | null;
   interval_index: number;
   next_due_at: string;
+  created_at: string;
+  lapses: number;
+  leech_at: string | null;
+  suspended_at: string | null;
 };
 
 export type ReviewEvent = {
@@ -38,15 +50,46 @@ export type ReviewEvent = {
 export type Dashboard = {
   due_count: number;
   upcoming_count: number;
-  leetcode_count: number;
-  neetcode_count: number;
+  source_counts: SourceCount[];
   latest_ingestion: ProblemEvent | null;
+  activity: ActivitySummary;
+};
+
+export type Streak = {
+  current: number;
+  longest: number;
+  last_active_on: string | null;
+};
+
+export type GoalProgress = {
+  id: number;
+  user_id: number;
+  metric: 'reviews' | 'solved' | 'new_cards';
+  period: 'day' | 'week';
+  target: number;
+  created_at: string;
+  period_start: string;
+  period_end: string;
+  progress: number;
+  met: boolean;
+};
+
+export type ActivitySummary = {
+  streak: Streak;
+  goals: GoalProgress[];
+};
+
+export type SourceCount = {
+  source: string;
+  display_name: string;
+  count: number;
 };
 
 export type Settings = {
   user_id: number;
   email_enabled: boolean;
   digest_hour_utc: number;
+  timezone: string;
 };
 
 export type Integrations = {
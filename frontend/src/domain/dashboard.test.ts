@@ -1,12 +1,15 @@
 import { describe, expect, it } from 'vitest';
-import { hasDueReviews, sourceBreakdown } from './dashboard';
+import { goalLabel, hasDueReviews, sourceBreakdown } from './dashboard';
 
 const dashboard = {
   due_count: 2,
   upcoming_count: 4,
-  leetcode_count: 3,
-  neetcode_count: 1,
+  source_counts: [
+    { source: 'leetcode', display_name: 'LeetCode', count: 3 },
+    { source: 'neetcode', display_name: 'NeetCode', count: 1 },
+  ],
   latest_ingestion: null,
+  activity: { streak: { current: 0, longest: 0, last_active_on: null }, goals: [] },
 };
 
 describe('dashboard selectors', () => {
@@ -18,8 +21,25 @@ describe('dashboard selectors', () => {
   it('returns source breakdown', () => {
     const rows = sourceBreakdown(dashboard);
     expect(rows).toEqual([
-      { source: 'leetcode', total: 3 },
-      { source: 'neetcode', total: 1 },
+      { source: 'LeetCode', total: 3 },
+      { source: 'NeetCode', total: 1 },
     ]);
   });
+
+  it('labels goals by metric and period', () => {
+    const goal = {
+      id: 1,
+      user_id: 1,
+      metric: 'solved' as const,
+      period: 'week' as const,
+      target: 5,
+      created_at: '2026-03-02T00:00:00Z',
+      period_start: '2026-03-02',
+      period_end: '2026-03-08',
+      progress: 2,
+      met: false,
+    };
+    expect(goalLabel(goal)).toBe('Problems solved this week');
+    expect(goalLabel({ ...goal, metric: 'reviews', period: 'day' })).toBe('Reviews today');
+  });
 });
@@ -1,8 +1,15 @@
-import type { Dashboard } from '../api/types';
+import type { Dashboard, GoalProgress } from '../api/types';
 
-export const sourceBreakdown = (dashboard: Dashboard): Array<{ source: string; total: number }> => [
-  { source: 'leetcode', total: dashboard.leetcode_count },
-  { source: 'neetcode', total: dashboard.neetcode_count },
-];
+export const sourceBreakdown = (dashboard: Dashboard): Array<{ source: string; total: number }> =>
+  dashboard.source_counts.map((entry) => ({ source: entry.display_name, total: entry.count }));
 
 export const hasDueReviews = (dashboard: Dashboard): boolean => dashboard.due_count > 0;
+
+const goalMetricLabels: Record<GoalProgress['metric'], string> = {
+  reviews: 'Reviews',
+  solved: 'Problems solved',
+  new_cards: 'New cards',
+};
+
+export const goalLabel = (goal: GoalProgress): string =>
+  `${goalMetricLabels[goal.metric]} ${goal.period === 'day' ? 'today' : 'this week'}`;
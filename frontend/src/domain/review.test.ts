@@ -5,12 +5,22 @@ const sampleQueue = [
   {
     id: 1,
     user_id: 1,
+    kind: 'problem' as const,
     source: 'leetcode',
     problem_slug: 'two-sum',
     title: 'Two Sum',
     url: 'https://leetcode.com/problems/two-sum',
+    front: null,
+    back: null,
+    tags: [],
+    problem_id: null,
+    difficulty: null,
     interval_index: 0,
     next_due_at: new Date().toISOString(),
+    created_at: new Date().toISOString(),
+    lapses: 0,
+    leech_at: null,
+    suspended_at: null,
   },
 ];
 
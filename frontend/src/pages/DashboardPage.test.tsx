@@ -10,9 +10,12 @@ describe('DashboardPage', () => {
         JSON.stringify({
           due_count: 0,
           upcoming_count: 2,
-          leetcode_count: 1,
-          neetcode_count: 1,
+          source_counts: [
+            { source: 'leetcode', display_name: 'LeetCode', count: 1 },
+            { source: 'neetcode', display_name: 'NeetCode', count: 1 },
+          ],
           latest_ingestion: null,
+          activity: { streak: { current: 0, longest: 0, last_active_on: null }, goals: [] },
         }),
         {
           status: 200,
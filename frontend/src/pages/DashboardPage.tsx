@@ -3,7 +3,7 @@ import { useQuery } from '@tanstack/react-query';
 import { api } from '../api/client';
 import { EmptyState, ErrorState, LoadingState } from '../components/ui/AsyncState';
 import { PageLayout, SectionCard } from '../components/ui/PageLayout';
-import { hasDueReviews, sourceBreakdown } from '../domain/dashboard';
+import { goalLabel, hasDueReviews, sourceBreakdown } from '../domain/dashboard';
 
 export const DashboardPage = () => {
   const { data, isLoading, isError } = useQuery({
@@ -29,11 +29,25 @@ export const DashboardPage = () => {
         </SectionCard>
         <SectionCard title="Streak">
           <Text size="xl" fw={700}>
-            0
+            {data.activity.streak.current}
+          </Text>
+          <Text size="sm" c="dimmed">
+            Longest: {data.activity.streak.longest}
           </Text>
         </SectionCard>
       </SimpleGrid>
 
+      {data.activity.goals.length > 0 ? (
+        <SectionCard title="Goals">
+          {data.activity.goals.map((goal) => (
+            <Text key={goal.id} size="sm">
+              {goalLabel(goal)}: {goal.progress}/{goal.target}
+              {goal.met ? ' (met)' : ''}
+            </Text>
+          ))}
+        </SectionCard>
+      ) : null}
+
       <SectionCard title="Source split">
         <Group gap="md">
           {sourceBreakdown(data).map((item) => (
@@ -13,6 +13,7 @@ describe('SettingsPage', () => {
             user_id: 1,
             email_enabled: true,
             digest_hour_utc: 12,
+            timezone: 'UTC',
           }),
           { status: 200, headers: { 'Content-Type': 'application/json' } },
         ),